@@ -0,0 +1,45 @@
+//! Checks the paired-single math routines against their scalar versions on the console.
+
+#![no_std]
+#![no_main]
+#![feature(asm_experimental_arch, alloc_error_handler)]
+
+extern crate alloc;
+
+use core::{alloc::Layout, panic::PanicInfo};
+
+use rosalina::{math::ps, os::OS, println};
+
+#[no_mangle]
+extern "C" fn main() -> ! {
+    let _os = OS::init();
+
+    match ps::check_paired() {
+        Ok(()) => println!("Paired-single routines match their scalar versions"),
+        Err(routine) => println!("Paired-single {routine} doesn't match its scalar version"),
+    }
+
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[panic_handler]
+fn panic_handler(info: &PanicInfo) -> ! {
+    println!("{}", info);
+    loop {
+        core::hint::spin_loop();
+    }
+}
+
+#[alloc_error_handler]
+fn alloc_handler(layout: Layout) -> ! {
+    println!(
+        "Failed to allocate item with \n Size: {}\n, Align: {}\n",
+        layout.size(),
+        layout.align()
+    );
+    loop {
+        core::hint::spin_loop();
+    }
+}
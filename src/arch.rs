@@ -171,21 +171,26 @@ impl MachineStateRegister {
 
     pub fn read() -> Self {
         let reg;
+        #[cfg(target_arch = "powerpc")]
         unsafe {
             core::arch::asm!(
                 "mfmsr {reg}",
                 reg = out(reg) reg,
             );
         }
+        #[cfg(not(target_arch = "powerpc"))]
+        {
+            reg = 0;
+        }
         Self(reg)
     }
 
     pub fn write(self) {
-        let reg = self.0;
+        #[cfg(target_arch = "powerpc")]
         unsafe {
             core::arch::asm!(
                 "mtmsr {reg}",
-                reg = in(reg) reg,
+                reg = in(reg) self.0,
             );
         }
     }
@@ -536,3 +541,171 @@ bitflags::bitflags! {
         const POW = 1 << 18;
     }
 }
+
+/// How `psq_l`/`psq_st` convert between memory and paired singles.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(u32)]
+pub enum QuantizeType {
+    Float = 0,
+    U8 = 4,
+    U16 = 5,
+    I8 = 6,
+    I16 = 7,
+}
+
+#[derive(Debug)]
+pub struct InvalidQuantizeTypeError;
+
+impl TryFrom<u32> for QuantizeType {
+    type Error = InvalidQuantizeTypeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Float),
+            4 => Ok(Self::U8),
+            5 => Ok(Self::U16),
+            6 => Ok(Self::I8),
+            7 => Ok(Self::I16),
+            _ => Err(InvalidQuantizeTypeError),
+        }
+    }
+}
+
+impl From<QuantizeType> for u32 {
+    fn from(value: QuantizeType) -> Self {
+        value as Self
+    }
+}
+
+/// One of the eight graphics quantization registers (GQR0-7).
+///
+/// Loads scale by 2^-scale after converting, stores by 2^scale before converting, so the
+/// scale is the number of fractional bits in the integer format.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[repr(transparent)]
+pub struct GraphicsQuantizationRegister(u32);
+
+impl GraphicsQuantizationRegister {
+    /// Plain f32 loads and stores, what GQR0 is expected to hold.
+    pub const FLOAT: Self = Self(0);
+
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn read(index: u8) -> Self {
+        let reg: u32;
+        #[cfg(target_arch = "powerpc")]
+        unsafe {
+            match index {
+                0 => core::arch::asm!("mfspr {reg},912", reg = out(reg) reg),
+                1 => core::arch::asm!("mfspr {reg},913", reg = out(reg) reg),
+                2 => core::arch::asm!("mfspr {reg},914", reg = out(reg) reg),
+                3 => core::arch::asm!("mfspr {reg},915", reg = out(reg) reg),
+                4 => core::arch::asm!("mfspr {reg},916", reg = out(reg) reg),
+                5 => core::arch::asm!("mfspr {reg},917", reg = out(reg) reg),
+                6 => core::arch::asm!("mfspr {reg},918", reg = out(reg) reg),
+                7 => core::arch::asm!("mfspr {reg},919", reg = out(reg) reg),
+                _ => panic!("There are only 8 quantization registers"),
+            }
+        }
+        #[cfg(not(target_arch = "powerpc"))]
+        {
+            assert!(index < 8, "There are only 8 quantization registers");
+            reg = 0;
+        }
+        Self(reg)
+    }
+
+    pub fn write(self, index: u8) {
+        #[cfg(target_arch = "powerpc")]
+        unsafe {
+            let reg = self.0;
+            match index {
+                0 => core::arch::asm!("mtspr 912,{reg}", reg = in(reg) reg),
+                1 => core::arch::asm!("mtspr 913,{reg}", reg = in(reg) reg),
+                2 => core::arch::asm!("mtspr 914,{reg}", reg = in(reg) reg),
+                3 => core::arch::asm!("mtspr 915,{reg}", reg = in(reg) reg),
+                4 => core::arch::asm!("mtspr 916,{reg}", reg = in(reg) reg),
+                5 => core::arch::asm!("mtspr 917,{reg}", reg = in(reg) reg),
+                6 => core::arch::asm!("mtspr 918,{reg}", reg = in(reg) reg),
+                7 => core::arch::asm!("mtspr 919,{reg}", reg = in(reg) reg),
+                _ => panic!("There are only 8 quantization registers"),
+            }
+            core::arch::asm!("isync");
+        }
+        #[cfg(not(target_arch = "powerpc"))]
+        {
+            assert!(index < 8, "There are only 8 quantization registers");
+        }
+    }
+
+    pub fn load_type(&self) -> QuantizeType {
+        self.0.get_bits(16..=18).try_into().unwrap()
+    }
+
+    pub fn with_load_type(&mut self, ty: QuantizeType) -> &mut Self {
+        self.0.set_bits(16..=18, ty.into());
+        self
+    }
+
+    pub fn load_scale(&self) -> i8 {
+        decode_quantize_scale(self.0.get_bits(24..=29))
+    }
+
+    pub fn with_load_scale(&mut self, scale: i8) -> &mut Self {
+        self.0.set_bits(24..=29, encode_quantize_scale(scale));
+        self
+    }
+
+    pub fn store_type(&self) -> QuantizeType {
+        self.0.get_bits(0..=2).try_into().unwrap()
+    }
+
+    pub fn with_store_type(&mut self, ty: QuantizeType) -> &mut Self {
+        self.0.set_bits(0..=2, ty.into());
+        self
+    }
+
+    pub fn store_scale(&self) -> i8 {
+        decode_quantize_scale(self.0.get_bits(8..=13))
+    }
+
+    pub fn with_store_scale(&mut self, scale: i8) -> &mut Self {
+        self.0.set_bits(8..=13, encode_quantize_scale(scale));
+        self
+    }
+}
+
+impl Default for GraphicsQuantizationRegister {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for GraphicsQuantizationRegister {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<GraphicsQuantizationRegister> for u32 {
+    fn from(value: GraphicsQuantizationRegister) -> Self {
+        value.0
+    }
+}
+
+/// Scales are 6 bit two's complement, -32..=31.
+#[allow(clippy::cast_sign_loss)]
+fn encode_quantize_scale(scale: i8) -> u32 {
+    assert!(
+        (-32..32).contains(&scale),
+        "Quantization scale out of range"
+    );
+    (scale as u32) & 0x3F
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
+const fn decode_quantize_scale(bits: u32) -> i8 {
+    ((bits << 2) as i8) >> 2
+}
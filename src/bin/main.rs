@@ -17,7 +17,7 @@ use rosalina::{
     exception::{decrementer_set, Exception},
     exi::ExternalInterface,
     gfx,
-    gx::Fifo,
+    gx::{Fifo, GxCommands},
     interrupts, isfs,
     mmio::si::SiChannel,
     os::OS,
@@ -53,6 +53,7 @@ extern "C" fn main() -> ! {
     if fifo.link_cpu_gpu() {
         interrupts::disable();
         fifo.set_interrupts();
+        gfx::init();
         interrupts::enable();
         gfx::enable_write_gather_pipe();
         println!("GXFIFO linked!");
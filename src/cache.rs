@@ -1,3 +1,4 @@
+#[cfg(target_arch = "powerpc")]
 #[no_mangle]
 #[naked]
 pub(crate) unsafe extern "C" fn __init_cache() -> ! {
@@ -20,6 +21,7 @@ pub(crate) unsafe extern "C" fn __init_cache() -> ! {
     )
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn ic_flash_invalidate() -> ! {
     unsafe {
@@ -35,6 +37,7 @@ pub extern "C" fn ic_flash_invalidate() -> ! {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn ic_enabled() -> ! {
     unsafe {
@@ -53,6 +56,7 @@ pub extern "C" fn ic_enabled() -> ! {
         )
     }
 }
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn ic_enable() -> ! {
     unsafe {
@@ -68,6 +72,7 @@ pub extern "C" fn ic_enable() -> ! {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn ic_invalidate_range(ptr: *mut u8, len: usize) {
     unsafe {
@@ -93,6 +98,7 @@ pub extern "C" fn ic_invalidate_range(ptr: *mut u8, len: usize) {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn dc_enabled() -> ! {
     unsafe {
@@ -114,6 +120,7 @@ pub extern "C" fn dc_enabled() -> ! {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn dc_enable() -> ! {
     unsafe {
@@ -129,6 +136,7 @@ pub extern "C" fn dc_enable() -> ! {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn dc_flush_range_no_sync(ptr: *mut u8, len: usize) {
     unsafe {
@@ -152,6 +160,7 @@ pub extern "C" fn dc_flush_range_no_sync(ptr: *mut u8, len: usize) {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn dc_flush_range(ptr: *const u8, len: usize) {
     unsafe {
@@ -176,6 +185,7 @@ pub extern "C" fn dc_flush_range(ptr: *const u8, len: usize) {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn dc_invalidate_range(ptr: *mut u8, len: usize) {
     unsafe {
@@ -199,6 +209,7 @@ pub extern "C" fn dc_invalidate_range(ptr: *mut u8, len: usize) {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn l2_enabled() -> ! {
     unsafe {
@@ -213,6 +224,7 @@ pub extern "C" fn l2_enabled() -> ! {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn l2_init() -> ! {
     unsafe {
@@ -242,6 +254,7 @@ pub extern "C" fn l2_init() -> ! {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn l2_enable() -> ! {
     unsafe {
@@ -259,6 +272,7 @@ pub extern "C" fn l2_enable() -> ! {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn l2_disable() -> ! {
     unsafe {
@@ -275,6 +289,7 @@ pub extern "C" fn l2_disable() -> ! {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 pub extern "C" fn l2_global_invalidate() -> ! {
     unsafe {
@@ -309,3 +324,18 @@ pub extern "C" fn l2_global_invalidate() -> ! {
         )
     }
 }
+
+// Off the console there are no caches to keep coherent with the hardware, so the range
+// operations do nothing. This lets code that flushes its buffers build and run in host tests.
+
+#[cfg(not(target_arch = "powerpc"))]
+pub const extern "C" fn ic_invalidate_range(_ptr: *mut u8, _len: usize) {}
+
+#[cfg(not(target_arch = "powerpc"))]
+pub const extern "C" fn dc_flush_range_no_sync(_ptr: *mut u8, _len: usize) {}
+
+#[cfg(not(target_arch = "powerpc"))]
+pub const extern "C" fn dc_flush_range(_ptr: *const u8, _len: usize) {}
+
+#[cfg(not(target_arch = "powerpc"))]
+pub const extern "C" fn dc_invalidate_range(_ptr: *mut u8, _len: usize) {}
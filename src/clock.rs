@@ -8,19 +8,25 @@ pub struct Instant {
 }
 
 impl Instant {
+    /// Reads the time base. Off the console there is none and the time stands still at zero.
     pub fn now() -> Self {
-        let mut time1 = 0u32;
+        let time1: u32;
         let time2: u32;
 
         let mut instant = 0u64;
+        #[cfg(target_arch = "powerpc")]
         unsafe {
             core::arch::asm!(
                 "1: mftbu {time1}",
                 "mftb {time2}",
-                time1 = inout(reg) time1,
+                time1 = out(reg) time1,
                 time2 = out(reg) time2,
             );
         }
+        #[cfg(not(target_arch = "powerpc"))]
+        {
+            (time1, time2) = (0, 0);
+        }
 
         instant = bitfrob::u64_with_value(0, 31, instant, time2.into());
         instant = bitfrob::u64_with_value(32, 63, instant, time1.into());
@@ -71,9 +77,11 @@ impl Sub for &Instant {
     }
 }
 
+#[cfg_attr(not(target_arch = "powerpc"), allow(unused_variables))]
 pub fn set_time(time: u64) {
     let time_upper: u32 = time.get_bits(32..64).try_into().unwrap();
     let time_lower: u32 = time.get_bits(0..32).try_into().unwrap();
+    #[cfg(target_arch = "powerpc")]
     unsafe {
         core::arch::asm!(
             "mttbu {time_upper}",
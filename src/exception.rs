@@ -1,5 +1,5 @@
 use core::fmt::Display;
-use core::{arch::asm, fmt::Write};
+use core::fmt::Write;
 
 use alloc::boxed::Box;
 use spin::RwLock;
@@ -237,6 +237,7 @@ impl Exception {
         core::ptr::copy_nonoverlapping(asm_start, addr_ptr.cast::<u8>(), asm_len);
         dc_flush_range_no_sync(addr_ptr.cast::<u8>(), asm_len);
         ic_invalidate_range(addr_ptr.cast::<u8>(), asm_len);
+        #[cfg(target_arch = "powerpc")]
         core::arch::asm!("sync");
     }
 
@@ -353,11 +354,17 @@ pub fn default_exception_handler(
     Err("An Unrecoverable exception occured!")
 }
 
+#[cfg_attr(not(target_arch = "powerpc"), allow(unused_variables))]
 fn mfspr(spr: i32) -> i32 {
     let outspr;
 
+    #[cfg(target_arch = "powerpc")]
     unsafe {
-        asm!("mfspr {0},{1}", out(reg)  outspr, in(reg)spr);
+        core::arch::asm!("mfspr {0},{1}", out(reg)  outspr, in(reg)spr);
+    }
+    #[cfg(not(target_arch = "powerpc"))]
+    {
+        outspr = 0;
     }
 
     outspr
@@ -385,6 +392,7 @@ extern "C" {
     static EXCEPTION_HANDLER_END: LinkerSymbol;
 }
 
+#[cfg(target_arch = "powerpc")]
 #[naked]
 #[allow(named_asm_labels)]
 pub extern "C" fn systemcall_handler() {
@@ -414,8 +422,12 @@ pub extern "C" fn systemcall_handler() {
     }
 }
 
+#[cfg_attr(not(target_arch = "powerpc"), allow(unused_variables))]
 pub fn decrementer_set(ticks: usize) {
-    unsafe { core::arch::asm!("mtdec {ticks}", ticks = in(reg) ticks,) }
+    #[cfg(target_arch = "powerpc")]
+    unsafe {
+        core::arch::asm!("mtdec {ticks}", ticks = in(reg) ticks,);
+    }
 }
 
 #[repr(C)]
@@ -535,12 +547,14 @@ impl SpecialPurposeRegisters {
     }
 }
 
+#[cfg(target_arch = "powerpc")]
 static EXCEPTION_CONTEXT: Context = Context::new();
 
 /// # Safety
 ///
 /// Must be called by an the calling of an exceptions
 /// **DO NOT CALL THIS DIRECTLY EVER**
+#[cfg(target_arch = "powerpc")]
 #[no_mangle]
 #[naked]
 #[allow(named_asm_labels)]
@@ -602,6 +616,7 @@ pub unsafe extern "C" fn exception_handler_shim() -> ! {
     )
 }
 
+#[cfg(target_arch = "powerpc")]
 const fn handler_flags() -> usize {
     const MSR_DR: usize = 0x10;
     const MSR_IR: usize = 0x20;
@@ -612,6 +627,7 @@ const fn handler_flags() -> usize {
 /// # Safety
 ///
 /// MUST BE CALLED FORM `exception_handler_shim`
+#[cfg(target_arch = "powerpc")]
 #[no_mangle]
 #[naked]
 pub unsafe extern "C" fn de_exception_handler() -> ! {
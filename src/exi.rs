@@ -9,9 +9,13 @@ use crate::{
         },
         pi::{InterruptState, Mask},
     },
+    os::Align32,
     sram::Sram,
 };
 
+/// Bytes of IPL ROM read per DMA.
+const ROM_CHUNK: usize = 256;
+
 pub struct ExternalInterface;
 
 impl ExternalInterface {
@@ -164,4 +168,53 @@ impl ExternalInterface {
 
         Sram { buffer }
     }
+
+    /// Reads the IPL ROM from `address` until `buffer` is full.
+    ///
+    /// The ROM sits behind device 1 of channel 0 like the RTC and SRAM, and is read 256 bytes at a
+    /// time. Only the fonts are readable once the IPL has turned the descrambler off.
+    pub fn read_rom(address: u32, buffer: &mut [u8]) {
+        let mut chunk = Align32([0u8; ROM_CHUNK]);
+
+        for (index, out) in buffer.chunks_mut(ROM_CHUNK).enumerate() {
+            let offset = u32::try_from(index * ROM_CHUNK).unwrap();
+            dc_invalidate_range(chunk.0.as_mut_ptr(), ROM_CHUNK);
+
+            ExiParams::read_zero()
+                .with_device_select(ExiDevice::Device1)
+                .with_clock(ExiClock::EightMegahertz)
+                .with_exi_interrupt_mask(Mask::Enabled)
+                .with_transfer_complete_mask(Mask::Enabled)
+                .with_external_insertion_mask(Mask::Enabled)
+                .write_zero();
+
+            EXI_CHANNEL_0_IMM_DATA.write((address + offset) << 6);
+
+            ExiControl::read_zero()
+                .with_dma_mode(DmaMode::Immediate)
+                .with_read_write_mode(ReadWriteMode::Write)
+                .with_transfer_length(4)
+                .with_dma_start(DmaStart::Start)
+                .write_zero();
+
+            while ExiControl::read_zero().dma_start() == DmaStart::Start {}
+
+            EXI_CHANNEL_0_DMA_START.write(chunk.0.as_ptr().addr());
+            EXI_CHANNEL_0_DMA_LENGTH.write(ROM_CHUNK);
+
+            ExiControl::read_zero()
+                .with_dma_mode(DmaMode::Dma)
+                .with_read_write_mode(ReadWriteMode::Read)
+                .with_dma_start(DmaStart::Start)
+                .write_zero();
+
+            while ExiControl::read_zero().dma_start() == DmaStart::Start {}
+
+            ExiParams::read_zero()
+                .with_device_select(ExiDevice::None)
+                .write_zero();
+
+            out.copy_from_slice(&chunk.0[..out.len()]);
+        }
+    }
 }
@@ -0,0 +1,433 @@
+//! Text rendering with bitmap fonts.
+//!
+//! A `Font` maps characters to glyphs, rectangles on one of its texture pages, and lays text out
+//! from them. The same layout is either queued into a `SpriteBatch` as one quad per glyph, or
+//! blended straight into an XFB on the CPU for drawing without the GP, like a console before GX
+//! is set up. Two kinds of fonts are provided: `bmfont::BmFont` reads the fonts made by `BMFont`
+//! and compatible tools, `ipl::IplFont` unpacks the ANSI and Shift-JIS fonts in the IPL ROM.
+
+use alloc::{vec, vec::Vec};
+use bytemuck::{Pod, Zeroable};
+
+use crate::{
+    cache::dc_flush_range,
+    gx::{
+        sprite::{Sprite, SpriteBatch},
+        texture::{codec, TexObj, TextureFormat},
+    },
+    vi::ViFramebuffer,
+    yay0,
+};
+
+pub mod bmfont;
+pub mod ipl;
+pub mod sjis;
+
+#[derive(Debug)]
+pub enum ParseError {
+    UnexpectedEnd,
+    BadMagic,
+    UnsupportedVersion(u8),
+    /// An offset in the font points outside of it.
+    InvalidOffset {
+        offset: usize,
+    },
+    /// A line of a text descriptor missing a value or with one that doesn't parse, counted
+    /// from 1.
+    InvalidLine(usize),
+    InvalidDimensions {
+        width: u16,
+        height: u16,
+    },
+    UnknownEncoding(u16),
+    /// The last character of an IPL font comes before the first or has no glyph.
+    InvalidCharacterRange {
+        first: u16,
+        last: u16,
+    },
+    Compression(yay0::Error),
+    Texture(codec::Error),
+}
+
+/// Where a character is on its page and how it sits on the line.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Glyph {
+    pub page: u16,
+    /// Left, top, width and height of the glyph on its page.
+    pub source: [u16; 4],
+    /// Top left corner of the glyph relative to the pen, which is at the top of the line.
+    pub offset: [i16; 2],
+    /// How far the pen moves on after the glyph.
+    pub advance: i16,
+}
+
+/// One texture of a font, with its coverage kept on the CPU for drawing into an XFB.
+pub struct Page {
+    texture: TexObj,
+    /// Backs the texture, 32 byte aligned. Moving the `Vec` leaves the texels where they are.
+    _texels: Vec<Block>,
+    /// Alpha of every texel, row by row.
+    coverage: Vec<u8>,
+}
+
+#[repr(C, align(32))]
+#[derive(Copy, Clone)]
+struct Block([u8; 32]);
+
+// Safety: a plain array of bytes with no padding, the alignment makes the size a multiple of 32.
+unsafe impl Zeroable for Block {}
+unsafe impl Pod for Block {}
+
+impl Page {
+    /// Copies already encoded texture data into a page. Glyphs are drawn with the alpha of the
+    /// texture, which is the intensity for `I4` and `I8`.
+    ///
+    /// # Errors
+    ///
+    /// Fails on color indexed formats and data shorter than the texture.
+    pub fn new(
+        data: &[u8],
+        width: u16,
+        height: u16,
+        format: TextureFormat,
+    ) -> Result<Self, codec::Error> {
+        let coverage = codec::decode(data, width.into(), height.into(), format)?
+            .chunks_exact(4)
+            .map(|rgba| rgba[3])
+            .collect();
+
+        let size = format.size(width.into(), height.into());
+        let mut texels = vec![Block([0; 32]); size.div_ceil(32)];
+        bytemuck::cast_slice_mut::<_, u8>(&mut texels)[..size].copy_from_slice(&data[..size]);
+        let texture = TexObj::new(bytemuck::cast_slice(&texels), width, height, format);
+
+        Ok(Self {
+            texture,
+            _texels: texels,
+            coverage,
+        })
+    }
+
+    /// Encodes an RGBA8 image into a page of `format`.
+    ///
+    /// # Errors
+    ///
+    /// See `codec::encode`.
+    pub fn from_rgba(
+        rgba: &[u8],
+        width: u16,
+        height: u16,
+        format: TextureFormat,
+    ) -> Result<Self, codec::Error> {
+        let data = codec::encode(rgba, width.into(), height.into(), format)?;
+        Self::new(&data, width, height, format)
+    }
+
+    pub const fn texture(&self) -> &TexObj {
+        &self.texture
+    }
+
+    /// Alpha of the texel at `x`, `y`, zero outside of the page.
+    pub fn coverage(&self, x: u16, y: u16) -> u8 {
+        if x >= self.texture.width() {
+            return 0;
+        }
+        let index = usize::from(y) * usize::from(self.texture.width()) + usize::from(x);
+        self.coverage.get(index).copied().unwrap_or(0)
+    }
+}
+
+/// A bitmap font, laying text out line by line from the top left. Lines are separated by `\n`
+/// and other control characters are skipped.
+pub trait Font {
+    fn glyph(&self, character: char) -> Option<Glyph>;
+
+    /// Glyph drawn for characters the font doesn't have.
+    fn fallback(&self) -> Option<Glyph> {
+        self.glyph('?')
+    }
+
+    /// Distance from the top of one line to the top of the next.
+    fn line_height(&self) -> u16;
+
+    /// Extra space between `left` and `right` when they follow each other.
+    fn kerning(&self, _left: char, _right: char) -> i16 {
+        0
+    }
+
+    fn page(&self, index: u16) -> Option<&Page>;
+
+    /// Width of the widest line of `text` and height of all its lines, in pixels.
+    fn measure(&self, text: &str) -> [u16; 2] {
+        let mut width = 0;
+        let mut height = 0;
+        for line in text.split('\n') {
+            let mut pen = Pen::default();
+            for character in line.chars() {
+                pen.advance(self, character);
+            }
+            width = width.max(pen.x);
+            height += i32::from(self.line_height());
+        }
+        [clamp(width), clamp(height)]
+    }
+
+    /// Splits `text` into lines no wider than `max_width`.
+    ///
+    /// Lines break at spaces, which are dropped at the break along with those starting a line
+    /// that has to break, and in the middle of words that don't fit a line on their own, like
+    /// text without spaces. Every line holds at least one character, even one wider than
+    /// `max_width`.
+    fn wrap<'a>(&self, text: &'a str, max_width: u16) -> Vec<&'a str> {
+        let max_width = i32::from(max_width);
+        let mut lines = Vec::new();
+
+        for mut rest in text.split('\n') {
+            loop {
+                let mut pen = Pen::default();
+                let mut space = None;
+                let mut overflow = None;
+                for (index, character) in rest.char_indices() {
+                    if character == ' ' {
+                        space = Some(index);
+                    }
+                    pen.advance(self, character);
+                    if pen.x > max_width && index > 0 {
+                        overflow = Some(index);
+                        break;
+                    }
+                }
+
+                let Some(overflow) = overflow else {
+                    lines.push(rest);
+                    break;
+                };
+                let split = space.filter(|&space| space > 0).unwrap_or(overflow);
+                let line = rest[..split].trim_end_matches(' ');
+                rest = rest[split..].trim_start_matches(' ');
+                // Breaking in the spaces a line starts with would leave nothing before the break.
+                if !line.is_empty() || rest.is_empty() {
+                    lines.push(line);
+                }
+                if rest.is_empty() {
+                    break;
+                }
+            }
+        }
+
+        lines
+    }
+
+    /// Queues `text` into `batch` with its top left corner at `position`, tinted by `color`.
+    #[allow(clippy::cast_precision_loss)]
+    fn draw(&self, batch: &mut SpriteBatch, text: &str, position: [f32; 2], color: [u8; 4]) {
+        lay_out(self, text, |glyph, [x, y]| {
+            let [_, _, width, height] = glyph.source;
+            let Some(page) = self.page(glyph.page) else {
+                return;
+            };
+            if width > 0 && height > 0 {
+                let sprite = Sprite::new(
+                    [position[0] + x as f32, position[1] + y as f32],
+                    glyph.source,
+                )
+                .with_color(color);
+                batch.draw(page.texture(), sprite);
+            }
+        });
+    }
+
+    /// Blends `text` in `color` into `framebuffer` with its top left corner at `position`, and
+    /// flushes the framebuffer out of the data cache. Glyphs are cut off at the edges.
+    ///
+    /// The two pixels of a YUV2 pair share their chroma, so colored text gets soft edges
+    /// sideways.
+    fn draw_xfb(
+        &self,
+        framebuffer: &mut ViFramebuffer,
+        text: &str,
+        position: [i32; 2],
+        color: [u8; 3],
+    ) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let data: &mut [u8] = &mut framebuffer.data;
+        let color = ycbcr(color);
+
+        lay_out(self, text, |glyph, [x, y]| {
+            let [left, top, glyph_width, glyph_height] = glyph.source;
+            let Some(page) = self.page(glyph.page) else {
+                return;
+            };
+
+            for row in 0..glyph_height {
+                let Ok(pixel_y) = usize::try_from(position[1] + y + i32::from(row)) else {
+                    continue;
+                };
+                for column in 0..glyph_width {
+                    let Ok(pixel_x) = usize::try_from(position[0] + x + i32::from(column)) else {
+                        continue;
+                    };
+                    let coverage = page.coverage(left + column, top + row);
+                    if coverage > 0 && pixel_x < width && pixel_y < height {
+                        blend_xfb(data, width, pixel_x, pixel_y, color, coverage);
+                    }
+                }
+            }
+        });
+
+        dc_flush_range(data.as_ptr(), data.len());
+    }
+}
+
+/// Calls `place` with every glyph of `text` and the position of its top left corner, relative to
+/// the top left of the text.
+fn lay_out<F: Font + ?Sized>(font: &F, text: &str, mut place: impl FnMut(Glyph, [i32; 2])) {
+    let mut y = 0;
+    for line in text.split('\n') {
+        let mut pen = Pen::default();
+        for character in line.chars() {
+            if let Some((glyph, x)) = pen.advance(font, character) {
+                let [offset_x, offset_y] = glyph.offset.map(i32::from);
+                place(glyph, [x + offset_x, y + offset_y]);
+            }
+        }
+        y += i32::from(font.line_height());
+    }
+}
+
+/// Where the next glyph of a line goes, shared by layout, measuring and wrapping so they agree.
+#[derive(Default)]
+struct Pen {
+    x: i32,
+    /// Last character that got a glyph, the one the next is kerned against.
+    previous: Option<char>,
+}
+
+impl Pen {
+    /// Moves the pen over `character` and returns its glyph with the pen position it is drawn at.
+    /// Characters without a glyph, like control characters, leave the pen as it is.
+    fn advance<F: Font + ?Sized>(&mut self, font: &F, character: char) -> Option<(Glyph, i32)> {
+        let glyph = glyph_or_fallback(font, character)?;
+        if let Some(previous) = self.previous.replace(character) {
+            self.x += i32::from(font.kerning(previous, character));
+        }
+        let x = self.x;
+        self.x += i32::from(glyph.advance);
+        Some((glyph, x))
+    }
+}
+
+fn glyph_or_fallback<F: Font + ?Sized>(font: &F, character: char) -> Option<Glyph> {
+    if character.is_control() {
+        return None;
+    }
+    font.glyph(character).or_else(|| font.fallback())
+}
+
+fn clamp(value: i32) -> u16 {
+    u16::try_from(value.max(0)).unwrap_or(u16::MAX)
+}
+
+/// BT.601 studio swing Y, Cb and Cr of an RGB color, what the VI expects in an XFB.
+fn ycbcr(color: [u8; 3]) -> [u8; 3] {
+    let [red, green, blue] = color.map(i32::from);
+    let y = 16 + ((66 * red + 129 * green + 25 * blue + 128) >> 8);
+    let cb = 128 + ((-38 * red - 74 * green + 112 * blue + 128) >> 8);
+    let cr = 128 + ((112 * red - 94 * green - 18 * blue + 128) >> 8);
+    [y, cb, cr].map(|value| u8::try_from(value).unwrap())
+}
+
+/// Blends `color` over the pixel at `x`, `y` of a YUV2 framebuffer `width` pixels wide. Each
+/// pixel of a pair blends the shared chroma at half strength.
+fn blend_xfb(data: &mut [u8], width: usize, x: usize, y: usize, color: [u8; 3], coverage: u8) {
+    let [luma, cb, cr] = color;
+    let mix = |old: u8, new: u8, weight: u32| {
+        let mixed = (u32::from(old) * (255 - weight) + u32::from(new) * weight + 127) / 255;
+        u8::try_from(mixed).unwrap()
+    };
+
+    let pair = (y * width + x) / 2 * 4;
+    let Some(pixels) = data.get_mut(pair..pair + 4) else {
+        return;
+    };
+    let weight = u32::from(coverage);
+    pixels[x % 2 * 2] = mix(pixels[x % 2 * 2], luma, weight);
+    pixels[1] = mix(pixels[1], cb, weight / 2);
+    pixels[3] = mix(pixels[3], cr, weight / 2);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every printable character 10 pixels wide, with `A` and `V` kerned together.
+    struct Fixed;
+
+    impl Font for Fixed {
+        fn glyph(&self, _character: char) -> Option<Glyph> {
+            Some(Glyph {
+                page: 0,
+                source: [0, 0, 10, 16],
+                offset: [0, 0],
+                advance: 10,
+            })
+        }
+
+        fn line_height(&self) -> u16 {
+            16
+        }
+
+        fn kerning(&self, left: char, right: char) -> i16 {
+            if (left, right) == ('A', 'V') {
+                -3
+            } else {
+                0
+            }
+        }
+
+        fn page(&self, _index: u16) -> Option<&Page> {
+            None
+        }
+    }
+
+    fn positions(text: &str) -> Vec<[i32; 2]> {
+        let mut positions = Vec::new();
+        lay_out(&Fixed, text, |_, position| positions.push(position));
+        positions
+    }
+
+    #[test]
+    fn control_characters_keep_kerning() {
+        assert_eq!(positions("AV"), [[0, 0], [7, 0]]);
+        assert_eq!(positions("A\rV"), positions("AV"));
+        assert_eq!(Fixed.measure("A\rV"), [17, 16]);
+        assert_eq!(Fixed.measure("A\rV"), Fixed.measure("AV"));
+    }
+
+    #[test]
+    fn measure_matches_layout() {
+        let text = "AVA\nVA AV";
+        let right = positions(text)
+            .into_iter()
+            .map(|[x, _]| x + 10)
+            .max()
+            .unwrap();
+        assert_eq!(Fixed.measure(text), [u16::try_from(right).unwrap(), 32]);
+    }
+
+    #[test]
+    fn wrap_breaks_at_kerned_width() {
+        // Kerned, "AVA" is 27 pixels wide and fits, "AAA" needs 30.
+        assert_eq!(Fixed.wrap("AVA", 27), ["AVA"]);
+        assert_eq!(Fixed.wrap("A\rVA", 27), ["A\rVA"]);
+        assert_eq!(Fixed.wrap("AAA", 27), ["AA", "A"]);
+    }
+
+    #[test]
+    fn wrap_drops_leading_spaces_at_a_break() {
+        assert_eq!(Fixed.wrap("  AAAA", 30), ["AAA", "A"]);
+        assert_eq!(Fixed.wrap("  AA", 40), ["  AA"]);
+        assert_eq!(Fixed.wrap("AA   AA", 30), ["AA", "AA"]);
+        assert_eq!(Fixed.wrap("A\n     \nA", 30), ["A", "", "A"]);
+    }
+}
@@ -0,0 +1,294 @@
+//! Reader for `BMFont` descriptors.
+//!
+//! A descriptor lists where every character sits on a set of page textures, the line metrics and
+//! kerning pairs. Both the text format and version 3 of the binary format are read; the pages are
+//! image files named by the descriptor, which have to be converted to a GX texture format and
+//! handed over with `BmFont::set_page` before their glyphs show up.
+
+use alloc::{
+    collections::BTreeMap,
+    string::{String, ToString},
+    vec::Vec,
+};
+use core::str::FromStr;
+
+use super::{Font, Glyph, Page, ParseError};
+
+const BINARY_MAGIC: [u8; 3] = *b"BMF";
+
+pub struct BmFont {
+    line_height: u16,
+    base: u16,
+    glyphs: BTreeMap<char, Glyph>,
+    kerning: BTreeMap<(char, char), i16>,
+    page_files: Vec<String>,
+    pages: Vec<Option<Page>>,
+}
+
+impl BmFont {
+    /// Reads a text or binary descriptor, telling them apart by the binary magic.
+    ///
+    /// # Errors
+    ///
+    /// Fails on text descriptors that aren't UTF-8 or have lines with missing or malformed
+    /// values, and on binary descriptors of another version or with blocks cut short.
+    pub fn parse(descriptor: &[u8]) -> Result<Self, ParseError> {
+        if descriptor.starts_with(&BINARY_MAGIC) {
+            Self::parse_binary(descriptor)
+        } else {
+            let text = core::str::from_utf8(descriptor).map_err(|_| ParseError::BadMagic)?;
+            Self::parse_text(text)
+        }
+    }
+
+    /// Distance from the top of the line to the baseline.
+    pub const fn base(&self) -> u16 {
+        self.base
+    }
+
+    /// Number of characters with a glyph.
+    pub fn len(&self) -> usize {
+        self.glyphs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.glyphs.is_empty()
+    }
+
+    /// File name of each page, indexed by page id.
+    pub fn page_files(&self) -> impl ExactSizeIterator<Item = &str> {
+        self.page_files.iter().map(String::as_str)
+    }
+
+    /// Sets the texture of page `id`. Glyphs on pages without one are skipped when drawing.
+    pub fn set_page(&mut self, id: u16, page: Page) {
+        let index = usize::from(id);
+        if self.pages.len() <= index {
+            self.pages.resize_with(index + 1, || None);
+        }
+        self.pages[index] = Some(page);
+    }
+
+    #[must_use]
+    pub fn with_page(mut self, id: u16, page: Page) -> Self {
+        self.set_page(id, page);
+        self
+    }
+
+    const fn empty() -> Self {
+        Self {
+            line_height: 0,
+            base: 0,
+            glyphs: BTreeMap::new(),
+            kerning: BTreeMap::new(),
+            page_files: Vec::new(),
+            pages: Vec::new(),
+        }
+    }
+
+    fn set_page_file(&mut self, id: u16, file: &str) {
+        let index = usize::from(id);
+        if self.page_files.len() <= index {
+            self.page_files.resize(index + 1, String::new());
+        }
+        self.page_files[index] = file.to_string();
+    }
+
+    fn parse_text(text: &str) -> Result<Self, ParseError> {
+        let mut font = Self::empty();
+
+        for (index, line) in text.lines().enumerate() {
+            let number = index + 1;
+            let (tag, rest) = line
+                .trim_start()
+                .split_once(char::is_whitespace)
+                .unwrap_or((line.trim(), ""));
+            let fields = Fields::new(rest, number);
+
+            match tag {
+                "common" => {
+                    font.line_height = fields.get("lineHeight")?;
+                    font.base = fields.get("base")?;
+                }
+                "page" => {
+                    font.set_page_file(fields.get("id")?, fields.text("file")?);
+                }
+                "char" => {
+                    let id: u32 = fields.get("id")?;
+                    let character = char::from_u32(id).ok_or(ParseError::InvalidLine(number))?;
+                    let glyph = Glyph {
+                        page: fields.get("page")?,
+                        source: [
+                            fields.get("x")?,
+                            fields.get("y")?,
+                            fields.get("width")?,
+                            fields.get("height")?,
+                        ],
+                        offset: [fields.get("xoffset")?, fields.get("yoffset")?],
+                        advance: fields.get("xadvance")?,
+                    };
+                    font.glyphs.insert(character, glyph);
+                }
+                "kerning" => {
+                    let pair = [fields.get("first")?, fields.get("second")?].map(char::from_u32);
+                    let [Some(first), Some(second)] = pair else {
+                        return Err(ParseError::InvalidLine(number));
+                    };
+                    font.kerning.insert((first, second), fields.get("amount")?);
+                }
+                _ => {}
+            }
+        }
+
+        Ok(font)
+    }
+
+    fn parse_binary(data: &[u8]) -> Result<Self, ParseError> {
+        let version = read_u8(data, 3)?;
+        if version != 3 {
+            return Err(ParseError::UnsupportedVersion(version));
+        }
+
+        let mut font = Self::empty();
+        let mut offset = 4;
+        while offset < data.len() {
+            let kind = read_u8(data, offset)?;
+            let size = usize::try_from(read_u32(data, offset + 1)?).unwrap();
+            let block = slice(data, offset + 5, size)?;
+            offset += 5 + size;
+
+            match kind {
+                2 => {
+                    font.line_height = read_u16(block, 0)?;
+                    font.base = read_u16(block, 2)?;
+                }
+                3 => {
+                    let files = block
+                        .split(|&byte| byte == 0)
+                        .filter(|file| !file.is_empty());
+                    for (id, file) in (0..).zip(files) {
+                        let file = core::str::from_utf8(file).map_err(|_| ParseError::BadMagic)?;
+                        font.set_page_file(id, file);
+                    }
+                }
+                4 => {
+                    for entry in block.chunks_exact(20) {
+                        let Some(character) = char::from_u32(read_u32(entry, 0)?) else {
+                            continue;
+                        };
+                        let glyph = Glyph {
+                            page: read_u8(entry, 18)?.into(),
+                            source: [
+                                read_u16(entry, 4)?,
+                                read_u16(entry, 6)?,
+                                read_u16(entry, 8)?,
+                                read_u16(entry, 10)?,
+                            ],
+                            offset: [read_i16(entry, 12)?, read_i16(entry, 14)?],
+                            advance: read_i16(entry, 16)?,
+                        };
+                        font.glyphs.insert(character, glyph);
+                    }
+                }
+                5 => {
+                    for entry in block.chunks_exact(10) {
+                        let first = char::from_u32(read_u32(entry, 0)?);
+                        let second = char::from_u32(read_u32(entry, 4)?);
+                        if let (Some(first), Some(second)) = (first, second) {
+                            font.kerning.insert((first, second), read_i16(entry, 8)?);
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(font)
+    }
+}
+
+impl Font for BmFont {
+    fn glyph(&self, character: char) -> Option<Glyph> {
+        self.glyphs.get(&character).copied()
+    }
+
+    fn line_height(&self) -> u16 {
+        self.line_height
+    }
+
+    fn kerning(&self, left: char, right: char) -> i16 {
+        self.kerning.get(&(left, right)).copied().unwrap_or(0)
+    }
+
+    fn page(&self, index: u16) -> Option<&Page> {
+        self.pages.get(usize::from(index))?.as_ref()
+    }
+}
+
+/// The `key=value` pairs of a text descriptor line, values in quotes may contain spaces.
+struct Fields<'a> {
+    pairs: Vec<(&'a str, &'a str)>,
+    line: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn new(mut rest: &'a str, line: usize) -> Self {
+        let mut pairs = Vec::new();
+        while let Some((key, after)) = rest.split_once('=') {
+            let key = key.split_whitespace().last().unwrap_or_default();
+            let (value, next) = after.strip_prefix('"').map_or_else(
+                || after.split_once(char::is_whitespace).unwrap_or((after, "")),
+                |quoted| quoted.split_once('"').unwrap_or((quoted, "")),
+            );
+            pairs.push((key, value));
+            rest = next;
+        }
+        Self { pairs, line }
+    }
+
+    fn text(&self, key: &str) -> Result<&'a str, ParseError> {
+        self.pairs
+            .iter()
+            .find(|(name, _)| *name == key)
+            .map(|(_, value)| *value)
+            .ok_or(ParseError::InvalidLine(self.line))
+    }
+
+    fn get<T: FromStr>(&self, key: &str) -> Result<T, ParseError> {
+        self.text(key)?
+            .parse()
+            .map_err(|_| ParseError::InvalidLine(self.line))
+    }
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8], ParseError> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(ParseError::InvalidOffset { offset })
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Result<u8, ParseError> {
+    data.get(offset).copied().ok_or(ParseError::UnexpectedEnd)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ParseError> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or(ParseError::UnexpectedEnd)?;
+    Ok(u16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_i16(data: &[u8], offset: usize) -> Result<i16, ParseError> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or(ParseError::UnexpectedEnd)?;
+    Ok(i16::from_le_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ParseError> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(ParseError::UnexpectedEnd)?;
+    Ok(u32::from_le_bytes(bytes.try_into().unwrap()))
+}
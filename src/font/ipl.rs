@@ -0,0 +1,368 @@
+//! The fonts built into the IPL ROM.
+//!
+//! The ROM holds a Yay0 compressed ANSI font and a Shift-JIS one with the level 1 kanji. Once
+//! decompressed, a font is a header, a table with the width of every glyph and sheets of glyph
+//! cells at 2 bits per texel, laid out in 8x8 tiles like `I4` is. The sheets are expanded to `I4`
+//! with the 4 intensity levels from the header, which is how the SDK uses them too.
+
+use alloc::{vec, vec::Vec};
+
+use super::{sjis, Font, Glyph, Page, ParseError};
+use crate::{exi::ExternalInterface, gx::texture::TextureFormat, yay0};
+
+/// Glyph of the first halfwidth character in the Shift-JIS font, after the 524 characters of JIS
+/// X 0208 rows 1 to 8.
+const HALFWIDTH_GLYPH: u16 = 0x20C;
+
+/// Glyph of the first level 1 kanji in the Shift-JIS font.
+const KANJI_GLYPH: u16 = 0x2BE;
+
+/// Cells of JIS X 0208 rows 1 to 8 that hold a character, which are the only ones with glyphs.
+const ASSIGNED_CELLS: [&[(u8, u8)]; 8] = [
+    &[(1, 94)],
+    &[(1, 14), (26, 33), (42, 48), (60, 74), (82, 89), (94, 94)],
+    &[(16, 25), (33, 58), (65, 90)],
+    &[(1, 83)],
+    &[(1, 86)],
+    &[(1, 24), (33, 56)],
+    &[(1, 33), (49, 81)],
+    &[(1, 32)],
+];
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Encoding {
+    Ansi,
+    ShiftJis,
+}
+
+impl Encoding {
+    /// Where the compressed font starts in the ROM.
+    pub const fn rom_address(self) -> u32 {
+        match self {
+            Self::Ansi => 0x1F_CF00,
+            Self::ShiftJis => 0x1A_FF00,
+        }
+    }
+
+    /// Room the compressed font has in the ROM.
+    pub const fn rom_size(self) -> usize {
+        match self {
+            Self::Ansi => 0x3000,
+            Self::ShiftJis => 0x4_D000,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidEncodingError;
+
+impl TryFrom<u16> for Encoding {
+    type Error = InvalidEncodingError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Ansi),
+            1 => Ok(Self::ShiftJis),
+            _ => Err(InvalidEncodingError),
+        }
+    }
+}
+
+impl From<Encoding> for u16 {
+    fn from(value: Encoding) -> Self {
+        match value {
+            Encoding::Ansi => 0,
+            Encoding::ShiftJis => 1,
+        }
+    }
+}
+
+/// Header at the start of a decompressed IPL font. Character codes are bytes for the ANSI font
+/// and Shift-JIS codes for the other one.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Header {
+    pub encoding: Encoding,
+    pub first_char: u16,
+    pub last_char: u16,
+    /// Character drawn for codes the font doesn't have.
+    pub invalid_char: u16,
+    pub ascent: u16,
+    pub descent: u16,
+    /// Width of the widest glyph.
+    pub width: u16,
+    /// Distance from the top of one line to the top of the next.
+    pub leading: u16,
+    pub cell_width: u16,
+    pub cell_height: u16,
+    pub sheet_columns: u16,
+    pub sheet_rows: u16,
+    pub sheet_width: u16,
+    pub sheet_height: u16,
+    /// Offset of the glyph widths, one byte each.
+    pub width_table: u16,
+    /// Offset of the 2 bit sheets.
+    pub sheet_image: u32,
+    /// Intensity of each of the 4 texel values.
+    pub levels: [u8; 4],
+}
+
+impl Header {
+    pub const LENGTH: usize = 0x30;
+
+    fn from_bytes(data: &[u8]) -> Result<Self, ParseError> {
+        let font_type = read_u16(data, 0x00)?;
+        let header = Self {
+            encoding: font_type
+                .try_into()
+                .map_err(|_| ParseError::UnknownEncoding(font_type))?,
+            first_char: read_u16(data, 0x02)?,
+            last_char: read_u16(data, 0x04)?,
+            invalid_char: read_u16(data, 0x06)?,
+            ascent: read_u16(data, 0x08)?,
+            descent: read_u16(data, 0x0A)?,
+            width: read_u16(data, 0x0C)?,
+            leading: read_u16(data, 0x0E)?,
+            cell_width: read_u16(data, 0x10)?,
+            cell_height: read_u16(data, 0x12)?,
+            sheet_columns: read_u16(data, 0x1A)?,
+            sheet_rows: read_u16(data, 0x1C)?,
+            sheet_width: read_u16(data, 0x1E)?,
+            sheet_height: read_u16(data, 0x20)?,
+            width_table: read_u16(data, 0x22)?,
+            sheet_image: read_u32(data, 0x24)?,
+            levels: data
+                .get(0x2C..Self::LENGTH)
+                .ok_or(ParseError::UnexpectedEnd)?
+                .try_into()
+                .unwrap(),
+        };
+
+        let (width, height) = (header.sheet_width, header.sheet_height);
+        let fits = |cells: u16, cell: u16, size: u16| {
+            cells > 0 && cell > 0 && u32::from(cells) * u32::from(cell) <= u32::from(size)
+        };
+        if !(1..=1024).contains(&width)
+            || !(1..=1024).contains(&height)
+            || width % 8 != 0
+            || height % 8 != 0
+            || !fits(header.sheet_columns, header.cell_width, width)
+            || !fits(header.sheet_rows, header.cell_height, height)
+        {
+            return Err(ParseError::InvalidDimensions { width, height });
+        }
+        Ok(header)
+    }
+
+    /// Number of glyphs in the font.
+    pub fn glyph_count(&self) -> Option<usize> {
+        let last = match self.encoding {
+            Encoding::Ansi => self.last_char.checked_sub(self.first_char)?,
+            Encoding::ShiftJis => sjis_glyph_index(self.last_char)?,
+        };
+        Some(usize::from(last) + 1)
+    }
+
+    pub const fn glyphs_per_sheet(&self) -> usize {
+        self.sheet_columns as usize * self.sheet_rows as usize
+    }
+}
+
+/// One of the IPL ROM fonts, unpacked into `I4` pages.
+pub struct IplFont {
+    header: Header,
+    widths: Vec<u8>,
+    pages: Vec<Page>,
+}
+
+impl IplFont {
+    /// Reads the font with `encoding` out of the IPL ROM and unpacks it. `ExternalInterface::init`
+    /// has to have run.
+    ///
+    /// # Errors
+    ///
+    /// See `from_yay0`.
+    pub fn load(encoding: Encoding) -> Result<Self, ParseError> {
+        let mut compressed = vec![0; encoding.rom_size()];
+        ExternalInterface::read_rom(encoding.rom_address(), &mut compressed);
+        Self::from_yay0(&compressed)
+    }
+
+    /// Unpacks a Yay0 compressed font, like a copy of the ROM's.
+    ///
+    /// # Errors
+    ///
+    /// Fails on broken compressed data and anything `new` fails on.
+    pub fn from_yay0(data: &[u8]) -> Result<Self, ParseError> {
+        Self::new(&yay0::decompress(data).map_err(ParseError::Compression)?)
+    }
+
+    /// Unpacks a decompressed font.
+    ///
+    /// # Errors
+    ///
+    /// Fails on unknown encodings, sheets that don't fit a texture or their cells, and tables
+    /// outside of `data`.
+    pub fn new(data: &[u8]) -> Result<Self, ParseError> {
+        let header = Header::from_bytes(data)?;
+        let count = header
+            .glyph_count()
+            .ok_or(ParseError::InvalidCharacterRange {
+                first: header.first_char,
+                last: header.last_char,
+            })?;
+        let widths = slice(data, header.width_table.into(), count)?.to_vec();
+
+        let (width, height) = (header.sheet_width, header.sheet_height);
+        let sheet_size = usize::from(width) * usize::from(height) / 4;
+        let sheets = count.div_ceil(header.glyphs_per_sheet());
+        let image = slice(
+            data,
+            header.sheet_image.try_into().unwrap(),
+            sheets * sheet_size,
+        )?;
+
+        let pages = image
+            .chunks_exact(sheet_size)
+            .map(|sheet| {
+                Page::new(
+                    &expand_sheet(sheet, header.levels),
+                    width,
+                    height,
+                    TextureFormat::I4,
+                )
+            })
+            .collect::<Result<_, _>>()
+            .map_err(ParseError::Texture)?;
+
+        Ok(Self {
+            header,
+            widths,
+            pages,
+        })
+    }
+
+    pub const fn header(&self) -> &Header {
+        &self.header
+    }
+
+    pub const fn encoding(&self) -> Encoding {
+        self.header.encoding
+    }
+
+    /// Glyph of character `code`, a byte for the ANSI font and a Shift-JIS code for the other.
+    pub fn glyph_for_code(&self, code: u16) -> Option<Glyph> {
+        let index = match self.header.encoding {
+            Encoding::Ansi => code.checked_sub(self.header.first_char)?,
+            Encoding::ShiftJis => sjis_glyph_index(code)?,
+        };
+        let index = usize::from(index);
+        let width = *self.widths.get(index)?;
+
+        // The header is checked to fit every cell into a sheet, so none of this overflows.
+        let per_sheet = self.header.glyphs_per_sheet();
+        let cell = index % per_sheet;
+        let column = u16::try_from(cell % usize::from(self.header.sheet_columns)).unwrap();
+        let row = u16::try_from(cell / usize::from(self.header.sheet_columns)).unwrap();
+        Some(Glyph {
+            page: u16::try_from(index / per_sheet).unwrap(),
+            source: [
+                column * self.header.cell_width,
+                row * self.header.cell_height,
+                width.into(),
+                self.header.cell_height,
+            ],
+            offset: [0, 0],
+            advance: width.into(),
+        })
+    }
+}
+
+impl Font for IplFont {
+    /// Characters are mapped to Latin-1 for the ANSI font and through `sjis::from_char` for the
+    /// Shift-JIS one.
+    fn glyph(&self, character: char) -> Option<Glyph> {
+        let code = match self.header.encoding {
+            Encoding::Ansi => u8::try_from(character).ok()?.into(),
+            Encoding::ShiftJis => sjis::from_char(character)?,
+        };
+        self.glyph_for_code(code)
+    }
+
+    fn fallback(&self) -> Option<Glyph> {
+        self.glyph_for_code(self.header.invalid_char)
+    }
+
+    fn line_height(&self) -> u16 {
+        self.header.leading
+    }
+
+    fn page(&self, index: u16) -> Option<&Page> {
+        self.pages.get(usize::from(index))
+    }
+}
+
+/// Index of the glyph for Shift-JIS `code` in the Shift-JIS font: the assigned characters of JIS
+/// rows 1 to 8, then halfwidth characters, then level 1 kanji in JIS order.
+fn sjis_glyph_index(code: u16) -> Option<u16> {
+    match code {
+        0x20..=0x7E => return Some(HALFWIDTH_GLYPH + code - 0x20),
+        0xA1..=0xDF => return Some(HALFWIDTH_GLYPH + 95 + code - 0xA1),
+        _ => {}
+    }
+
+    let (row, cell) = sjis::to_jis(code)?;
+    match row {
+        1..=8 => {
+            let mut index = 0;
+            for (assigned_row, ranges) in (1..).zip(ASSIGNED_CELLS) {
+                for &(first, last) in ranges {
+                    if assigned_row == row && (first..=last).contains(&cell) {
+                        return Some(index + u16::from(cell - first));
+                    }
+                    index += u16::from(last - first + 1);
+                }
+            }
+            None
+        }
+        // Level 1 kanji end at row 47 cell 51.
+        16..=47 if row < 47 || cell <= 51 => {
+            Some(KANJI_GLYPH + (u16::from(row) - 16) * 94 + u16::from(cell) - 1)
+        }
+        _ => None,
+    }
+}
+
+/// Expands a 2 bit sheet to `I4`, each byte of 4 texels turning into 2 bytes.
+fn expand_sheet(sheet: &[u8], levels: [u8; 4]) -> Vec<u8> {
+    let level = |byte: u8, shift: u32| levels[usize::from(byte >> shift & 0x3)] >> 4;
+    sheet
+        .iter()
+        .flat_map(|&byte| {
+            [
+                level(byte, 6) << 4 | level(byte, 4),
+                level(byte, 2) << 4 | level(byte, 0),
+            ]
+        })
+        .collect()
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8], ParseError> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(ParseError::InvalidOffset { offset })
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ParseError> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or(ParseError::UnexpectedEnd)?;
+    Ok(u16::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ParseError> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(ParseError::UnexpectedEnd)?;
+    Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
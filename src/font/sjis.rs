@@ -0,0 +1,119 @@
+//! Mapping from Unicode to Shift-JIS.
+//!
+//! Covers everything that maps without a full JIS X 0208 table: ASCII, halfwidth katakana, the
+//! symbols of rows 1 and 2, fullwidth digits and Latin letters, kana, Greek and Cyrillic. Kanji
+//! codes follow no pattern, so kanji have to be given as Shift-JIS already and drawn with
+//! `IplFont::glyph_for_code`.
+
+use alloc::vec::Vec;
+
+/// Symbols from 0x8140 on, skipping trail byte 0x7F, up to the end of those in JIS row 2.
+const SYMBOLS: [char; 108] = [
+    '\u{3000}', '\u{3001}', '\u{3002}', '\u{FF0C}', '\u{FF0E}', '\u{30FB}', '\u{FF1A}', '\u{FF1B}',
+    '\u{FF1F}', '\u{FF01}', '\u{309B}', '\u{309C}', '\u{00B4}', '\u{FF40}', '\u{00A8}', '\u{FF3E}',
+    '\u{FFE3}', '\u{FF3F}', '\u{30FD}', '\u{30FE}', '\u{309D}', '\u{309E}', '\u{3003}', '\u{4EDD}',
+    '\u{3005}', '\u{3006}', '\u{3007}', '\u{30FC}', '\u{2015}', '\u{2010}', '\u{FF0F}', '\u{FF3C}',
+    '\u{301C}', '\u{2016}', '\u{FF5C}', '\u{2026}', '\u{2025}', '\u{2018}', '\u{2019}', '\u{201C}',
+    '\u{201D}', '\u{FF08}', '\u{FF09}', '\u{3014}', '\u{3015}', '\u{FF3B}', '\u{FF3D}', '\u{FF5B}',
+    '\u{FF5D}', '\u{3008}', '\u{3009}', '\u{300A}', '\u{300B}', '\u{300C}', '\u{300D}', '\u{300E}',
+    '\u{300F}', '\u{3010}', '\u{3011}', '\u{FF0B}', '\u{2212}', '\u{00B1}', '\u{00D7}', '\u{00F7}',
+    '\u{FF1D}', '\u{2260}', '\u{FF1C}', '\u{FF1E}', '\u{2266}', '\u{2267}', '\u{221E}', '\u{2234}',
+    '\u{2642}', '\u{2640}', '\u{00B0}', '\u{2032}', '\u{2033}', '\u{2103}', '\u{FFE5}', '\u{FF04}',
+    '\u{00A2}', '\u{00A3}', '\u{FF05}', '\u{FF03}', '\u{FF06}', '\u{FF0A}', '\u{FF20}', '\u{00A7}',
+    '\u{2606}', '\u{2605}', '\u{25CB}', '\u{25CF}', '\u{25CE}', '\u{25C7}', '\u{25C6}', '\u{25A1}',
+    '\u{25A0}', '\u{25B3}', '\u{25B2}', '\u{25BD}', '\u{25BC}', '\u{203B}', '\u{3012}', '\u{2192}',
+    '\u{2190}', '\u{2191}', '\u{2193}', '\u{3013}',
+];
+
+/// Shift-JIS code of `character`, a single byte below 0x100.
+///
+/// ASCII maps to itself, so `\` and `~` stay as they are rather than turning into the yen sign
+/// and overline of JIS X 0201.
+pub fn from_char(character: char) -> Option<u16> {
+    let value = u32::from(character);
+    let index = |first: u32| u16::try_from(value - first).unwrap();
+
+    match character {
+        ' '..='~' => Some(index(0)),
+        '\u{FF61}'..='\u{FF9F}' => Some(0xA1 + index(0xFF61)),
+        '\u{FF10}'..='\u{FF19}' => Some(0x824F + index(0xFF10)),
+        '\u{FF21}'..='\u{FF3A}' => Some(0x8260 + index(0xFF21)),
+        '\u{FF41}'..='\u{FF5A}' => Some(0x8281 + index(0xFF41)),
+        '\u{3041}'..='\u{3093}' => Some(0x829F + index(0x3041)),
+        '\u{30A1}'..='\u{30F6}' => Some(cell(0x8340, index(0x30A1))),
+        // Greek has no final sigma in JIS, the letters after it move up one.
+        '\u{0391}'..='\u{03A9}' if value != 0x03A2 => {
+            Some(0x839F + index(0x0391) - u16::from(value > 0x03A2))
+        }
+        '\u{03B1}'..='\u{03C9}' if value != 0x03C2 => {
+            Some(0x83BF + index(0x03B1) - u16::from(value > 0x03C2))
+        }
+        // JIS puts Ё and ё after Е and е.
+        '\u{0401}' => Some(0x8446),
+        '\u{0451}' => Some(0x8476),
+        '\u{0410}'..='\u{042F}' => Some(0x8440 + index(0x0410) + u16::from(value >= 0x0416)),
+        '\u{0430}'..='\u{044F}' => Some(cell(0x8470, index(0x0430) + u16::from(value >= 0x0436))),
+        _ => SYMBOLS
+            .iter()
+            .position(|&symbol| symbol == character)
+            .map(|position| cell(0x8140, u16::try_from(position).unwrap())),
+    }
+}
+
+/// Encodes `text` as Shift-JIS bytes, with `?` for characters `from_char` doesn't map.
+pub fn encode(text: &str) -> Vec<u8> {
+    let mut bytes = Vec::with_capacity(text.len());
+    for character in text.chars() {
+        match from_char(character) {
+            Some(code @ 0x100..) => bytes.extend_from_slice(&code.to_be_bytes()),
+            Some(code) => bytes.push(u8::try_from(code).unwrap()),
+            None => bytes.push(b'?'),
+        }
+    }
+    bytes
+}
+
+/// Whether `byte` starts a double byte code.
+pub const fn is_lead_byte(byte: u8) -> bool {
+    matches!(byte, 0x81..=0x9F | 0xE0..=0xFC)
+}
+
+/// Splits Shift-JIS bytes into codes. A lead byte at the very end comes out on its own.
+pub fn codes(bytes: &[u8]) -> impl Iterator<Item = u16> + '_ {
+    let mut bytes = bytes.iter().copied();
+    core::iter::from_fn(move || {
+        let first = bytes.next()?;
+        if is_lead_byte(first) {
+            if let Some(second) = bytes.next() {
+                return Some(u16::from_be_bytes([first, second]));
+            }
+        }
+        Some(u16::from(first))
+    })
+}
+
+/// JIS X 0208 row and cell, both counted from 1, of a double byte code.
+pub const fn to_jis(code: u16) -> Option<(u8, u8)> {
+    let [lead, trail] = code.to_be_bytes();
+    let pair = match lead {
+        0x81..=0x9F => lead - 0x81,
+        0xE0..=0xEF => lead - 0xC1,
+        _ => return None,
+    };
+    match trail {
+        0x40..=0x7E => Some((pair * 2 + 1, trail - 0x3F)),
+        0x80..=0x9E => Some((pair * 2 + 1, trail - 0x40)),
+        0x9F..=0xFC => Some((pair * 2 + 2, trail - 0x9E)),
+        _ => None,
+    }
+}
+
+/// The code `index` characters after `first` within one lead byte, stepping over trail byte 0x7F.
+const fn cell(first: u16, index: u16) -> u16 {
+    let code = first + index;
+    if first & 0xFF < 0x7F && code & 0xFF >= 0x7F {
+        code + 1
+    } else {
+        code
+    }
+}
@@ -1,9 +1,10 @@
-use core::{alloc::Layout, pin::Pin};
+use core::{alloc::Layout, pin::Pin, sync::atomic::Ordering};
 
 use alloc::boxed::Box;
 use bit_field::BitField;
 
 use crate::{
+    gx::{BREAKPOINT_CALLBACK, FIFO_STALLED},
     interrupts::Interrupt,
     mmio::{
         cp::{
@@ -19,28 +20,38 @@ use crate::{
     },
 };
 
+/// Installs the CP interrupt handler, which stalls CPU writes to the `gx::Fifo` between its high
+/// and low watermarks and runs the breakpoint callback.
 pub fn init() {
     Interrupt::set_interrupt_handler(Interrupt::CommandProcessor, |_| {
         let status = CommandStatus::read();
+        let control = CommandControl::read();
 
-        if status.fifo_underflow() {
+        // Over the high watermark, hold off writes until the GPU drains down to the low one.
+        if status.fifo_overflow() && control.fifo_overflow_interrupt() == InterruptState::Happened {
+            FIFO_STALLED.store(true, Ordering::Release);
             CommandControl::read()
+                .with_fifo_overflow_interrupt(InterruptState::Idle)
                 .with_fifo_underflow_interrupt(InterruptState::Happened)
                 .write();
-            CommandClear::read().with_fifo_underflow(true).write();
+            CommandClear::read().with_fifo_overflow(true).write();
         }
 
-        if status.fifo_overflow() {
-            CommandControl::read()
-                .with_fifo_overflow_interrupt(InterruptState::Happened)
-                .write();
-            CommandClear::read().with_fifo_overflow(true).write();
+        if status.fifo_underflow() && control.fifo_underflow_interrupt() == InterruptState::Happened
+        {
+            end_fifo_stall();
         }
 
-        if status.fifo_breakpoint_interrupt().into() {
+        if status.fifo_breakpoint_interrupt() == InterruptState::Happened
+            && control.fifo_breakpoint_interrupt() == InterruptState::Happened
+        {
             CommandControl::read()
-                .with_fifo_breakpoint_interrupt(InterruptState::Happened)
+                .with_fifo_breakpoint_interrupt(InterruptState::Idle)
                 .write();
+
+            if let Some(callback) = BREAKPOINT_CALLBACK.read().as_ref() {
+                callback();
+            }
         }
 
         Ok(())
@@ -51,6 +62,17 @@ pub fn init() {
         .write();
 }
 
+/// Lets CPU writes carry on once the GPU drained the FIFO to the low watermark, and arms the
+/// high watermark again.
+pub(crate) fn end_fifo_stall() {
+    FIFO_STALLED.store(false, Ordering::Release);
+    CommandControl::read()
+        .with_fifo_underflow_interrupt(InterruptState::Idle)
+        .with_fifo_overflow_interrupt(InterruptState::Happened)
+        .write();
+    CommandClear::read().with_fifo_underflow(true).write();
+}
+
 #[repr(C, align(32))]
 pub struct Fifo<const SIZE: usize> {
     buf: Pin<Box<[u8; SIZE]>>,
@@ -109,7 +131,10 @@ impl<const SIZE: usize> Fifo<SIZE> {
         CP_FIFO_READ_PTR_LO.write(buf_read_addr.get_bits(0..=15).try_into().unwrap());
         CP_FIFO_READ_PTR_HI.write(buf_read_addr.get_bits(16..=31).try_into().unwrap());
 
-        unsafe { core::arch::asm!("sc") };
+        #[cfg(target_arch = "powerpc")]
+        unsafe {
+            core::arch::asm!("sc");
+        }
     }
 
     pub fn confirm_link(&self) {
@@ -131,8 +156,10 @@ impl<const SIZE: usize> Default for Fifo<SIZE> {
     }
 }
 
+#[cfg_attr(not(target_arch = "powerpc"), allow(unused_variables))]
 pub fn enable_write_gather_pipe() {
     let val: usize = 0x0C00_8000;
+    #[cfg(target_arch = "powerpc")]
     unsafe {
         core::arch::asm!(
             "mtspr 921,{val}",
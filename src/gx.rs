@@ -1,23 +1,92 @@
-use core::alloc::Layout;
+use core::{
+    alloc::Layout,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
 
+use alloc::boxed::Box;
 use bit_field::BitField;
+use spin::RwLock;
+use voladdress::{Safe, VolAddress};
 
 use crate::{
+    arch::MachineStateRegister,
+    cache::{dc_flush_range, dc_invalidate_range},
+    clock::Instant,
+    gfx,
+    gx::{
+        bp::{
+            AlphaCompare, BlendControl, BpMask, BpRegister, ConstantAlpha, CopyClearAr,
+            CopyClearGb, CopyClearZ, CopyFilter, CullMode, EfbCopyControl, EfbCopyDestination,
+            EfbCopySourceSize, EfbCopySourceTopLeft, EfbCopyStride, EfbCopyYScale, GenMode,
+            PeControl, PeDone, PeToken, PeTokenInterrupt, RasIndOrder, RasIndScale, SamplePattern,
+            ScissorBottomRight, ScissorTopLeft, SuLpSize, TevAlphaEnv, TevColorBg, TevColorRa,
+            TevKSel, TevStageOrders, TexCacheInvalidate, TlutLoadDestination, TlutLoadSource,
+            ZMode,
+        },
+        command::{CommandSink, Opcode},
+        cp::{ArrayBase, ArrayStride, CpRegister},
+        display_list::DisplayList,
+        efb::Efb,
+        indirect::{IndStage, IndTexMatrix, TevIndirect},
+        light::{light_offset, Channel, ChannelConfig, LightObj},
+        pe::{
+            AlphaOp, BlendFactor, BlendMode, CompareFn, DisplayCopy, Fog, FogRangeTable, LogicOp,
+            PixelFormat, SamplePositions, TexCopy, VerticalFilter, AA_EFB_HEIGHT, AA_SAMPLES,
+            AA_VERTICAL_FILTER, CENTERED_SAMPLES, DEFAULT_VERTICAL_FILTER,
+        },
+        shadow::{BpLoad, ShadowStats, StateShadow, StateSnapshot},
+        tev::{
+            AlphaCombiner, ColorCombiner, KonstSelect, SwapTable, TevOrder, TevPreset, TevRegister,
+        },
+        texgen::{TexCoordGen, POST_IDENTITY_ROW},
+        texture::{TexObj, TexRegion, TlutObj, TlutRegion},
+        vertex::{Attribute, Primitive, PrimitiveBuilder, VertexLayout},
+        xf::{
+            AmbientColor, DualTex, MaterialColor, NumColors, NumTexGens, ProjectionType, Viewport,
+            XfMemory, XfRegister,
+        },
+    },
     interrupts::Interrupt,
+    math::{Mtx34, Mtx44},
     mmio::{
         cp::{
-            CommandClear, CommandControl, CP_FIFO_BASE_HI, CP_FIFO_BASE_LO, CP_FIFO_END_HI,
-            CP_FIFO_END_LO, CP_FIFO_HIGH_MARK_HI, CP_FIFO_HIGH_MARK_LO, CP_FIFO_LO_MARK_HI,
-            CP_FIFO_LO_MARK_LO, CP_FIFO_READ_PTR_HI, CP_FIFO_READ_PTR_LO,
-            CP_FIFO_READ_WRITE_DST_HI, CP_FIFO_READ_WRITE_DST_LO, CP_FIFO_WRITE_PTR_HI,
-            CP_FIFO_WRITE_PTR_LO,
+            CommandClear, CommandControl, CommandStatus, CP_FIFO_BASE_HI, CP_FIFO_BASE_LO,
+            CP_FIFO_BP_PTR_HI, CP_FIFO_BP_PTR_LO, CP_FIFO_END_HI, CP_FIFO_END_LO,
+            CP_FIFO_HIGH_MARK_HI, CP_FIFO_HIGH_MARK_LO, CP_FIFO_LO_MARK_HI, CP_FIFO_LO_MARK_LO,
+            CP_FIFO_READ_PTR_HI, CP_FIFO_READ_PTR_LO, CP_FIFO_READ_WRITE_DST_HI,
+            CP_FIFO_READ_WRITE_DST_LO, CP_FIFO_WRITE_PTR_HI, CP_FIFO_WRITE_PTR_LO,
+        },
+        pe::{InterruptControl, PE_TOKEN},
+        pi::{
+            InterruptMask, InterruptState, Mask, FIFO_BASE, FIFO_END, FIFO_RESET, FIFO_WRITE_PTR,
         },
-        pi::{InterruptMask, InterruptState, Mask, FIFO_BASE, FIFO_END, FIFO_WRITE_PTR},
         vi::Enabled,
+        Physical,
     },
+    utils::WriteGatherPipe,
     vi::ViFramebuffer,
 };
 
+pub mod bp;
+pub mod command;
+pub mod cp;
+pub mod disasm;
+pub mod display_list;
+pub mod efb;
+pub mod indirect;
+pub mod light;
+pub mod pe;
+pub mod perf;
+pub mod shadow;
+pub mod sprite;
+pub mod tev;
+pub mod texgen;
+pub mod texture;
+pub mod vertex;
+pub mod watchdog;
+pub mod xf;
+
 pub struct Fifo {
     buf_start: *mut u8,
     buf_end: *mut u8,
@@ -29,7 +98,9 @@ pub struct Fifo {
     read_write_distance: u32,
     cpu_ready: bool,
     gpu_ready: bool,
-    _padding: [u8; 93],
+    copy_control: EfbCopyControl,
+    pe_control: PeControl,
+    shadow: StateShadow,
 }
 
 #[derive(Debug)]
@@ -39,8 +110,21 @@ pub enum Error {
 
 impl Fifo {
     pub fn new() -> Self {
-        // Safety: an all zero bit pattern is valid for a fifo.
-        unsafe { core::mem::zeroed() }
+        Self {
+            buf_start: core::ptr::null_mut(),
+            buf_end: core::ptr::null_mut(),
+            size: 0,
+            hi_mark: 0,
+            lo_mark: 0,
+            read_ptr: core::ptr::null_mut(),
+            write_ptr: core::ptr::null_mut(),
+            read_write_distance: 0,
+            cpu_ready: false,
+            gpu_ready: false,
+            copy_control: DisplayCopy::new().register(),
+            pe_control: DisplayCopy::new().pe_control(),
+            shadow: StateShadow::new(),
+        }
     }
     /// # Errors
     /// `WrongLayout`:  Somehow the layout provided is wrong, this should never happen
@@ -73,8 +157,10 @@ impl Fifo {
         Ok(self)
     }
 
+    /// Points the CPU writes at this FIFO, carrying on from its saved write pointer.
     pub fn set_as_cpu_fifo(&mut self) {
         self.cpu_ready = true;
+        CPU_FIFO.store(self.buf_start.addr(), Ordering::Relaxed);
 
         CommandControl::read()
             .with_fifo_underflow_interrupt(InterruptState::Idle)
@@ -90,6 +176,7 @@ impl Fifo {
         sync();
     }
 
+    /// Points the GPU reads at this FIFO, carrying on from its saved read pointer.
     pub fn set_as_gpu_fifo(&mut self) {
         CommandControl::read()
             .with_gp_fifo_read_enable(Enabled::Disabled)
@@ -98,6 +185,7 @@ impl Fifo {
             .write();
 
         self.gpu_ready = true;
+        GPU_FIFO.store(self.buf_start.addr(), Ordering::Relaxed);
 
         let buf_start_addr = u32::try_from(self.buf_start.addr()).unwrap();
         let buf_end_addr = u32::try_from(self.buf_end.addr()).unwrap();
@@ -148,14 +236,153 @@ impl Fifo {
         self.cpu_ready && self.gpu_ready
     }
 
+    /// Reads back how far the CPU has written and the GPU has read into this FIFO, so a later
+    /// `set_as_cpu_fifo` or `set_as_gpu_fifo` picks up from there.
+    pub fn save_pointers(&mut self) {
+        let start = self.buf_start.addr();
+
+        if CPU_FIFO.load(Ordering::Relaxed) == start {
+            // The top bits of the PI write pointer flag that it wrapped around.
+            let base = FIFO_BASE.read() & 0x1FFF_FFE0;
+            let write = FIFO_WRITE_PTR.read() & 0x1FFF_FFE0;
+            self.write_ptr = self.buf_start.wrapping_add(write - base);
+        }
+
+        if GPU_FIFO.load(Ordering::Relaxed) == start {
+            let base = read_split(CP_FIFO_BASE_LO, CP_FIFO_BASE_HI);
+            let read = read_split(CP_FIFO_READ_PTR_LO, CP_FIFO_READ_PTR_HI);
+            self.read_ptr = self.buf_start.wrapping_add((read - base) as usize);
+            self.read_write_distance =
+                read_split(CP_FIFO_READ_WRITE_DST_LO, CP_FIFO_READ_WRITE_DST_HI);
+        } else {
+            let read = self.read_ptr.addr() - start;
+            let write = self.write_ptr.addr() - start;
+            let distance = if write >= read {
+                write - read
+            } else {
+                write + self.size as usize - read
+            };
+            self.read_write_distance = u32::try_from(distance).unwrap();
+        }
+    }
+
+    pub const fn read_ptr(&self) -> *mut u8 {
+        self.read_ptr
+    }
+
+    pub const fn write_ptr(&self) -> *mut u8 {
+        self.write_ptr
+    }
+
+    /// Bytes written but not read yet, as of the last `save_pointers`.
+    pub const fn read_write_distance(&self) -> u32 {
+        self.read_write_distance
+    }
+
+    /// Throws away everything the GPU hasn't read yet and starts this FIFO over empty as both the
+    /// CPU and GPU FIFO, for carrying on after the GPU hung on a bad command. Returns whether CPU
+    /// and GPU could be linked again.
+    pub fn reset(&mut self) -> bool {
+        CommandControl::read()
+            .with_gp_fifo_read_enable(Enabled::Disabled)
+            .with_fifo_link_enable(Enabled::Disabled)
+            .with_fifo_overflow_interrupt(InterruptState::Idle)
+            .with_fifo_underflow_interrupt(InterruptState::Idle)
+            .with_fifo_breakpoint_interrupt(InterruptState::Idle)
+            .write();
+
+        // Nothing is going to drain the FIFO below the low watermark anymore.
+        FIFO_STALLED.store(false, Ordering::Release);
+        self.flush();
+
+        FIFO_RESET.write(1);
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_micros(50) {}
+        FIFO_RESET.write(0);
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_micros(5) {}
+
+        self.read_ptr = self.buf_start;
+        self.write_ptr = self.buf_start;
+        self.read_write_distance = 0;
+        // Whatever the GPU didn't get to never made it into its registers.
+        self.invalidate_state();
+
+        self.set_as_cpu_fifo();
+        self.set_as_gpu_fifo();
+        self.link_cpu_gpu()
+    }
+
+    /// Moves the CPU writes over from `current` to this FIFO, flushing and saving where `current`
+    /// was left. The GPU keeps reading whichever FIFO it was on, CPU and GPU are only linked
+    /// again if this is also the GPU FIFO.
+    ///
+    /// The register shadow of this FIFO is dropped, `current` may have changed the registers
+    /// since this one was last written.
+    pub fn switch_cpu_fifo(&mut self, current: &mut Self) {
+        current.flush();
+        current.save_pointers();
+        self.invalidate_state();
+
+        self.set_as_cpu_fifo();
+        if GPU_FIFO.load(Ordering::Relaxed) == self.buf_start.addr() {
+            self.link_cpu_gpu();
+        } else {
+            CommandControl::read()
+                .with_fifo_link_enable(Enabled::Disabled)
+                .with_fifo_overflow_interrupt(InterruptState::Idle)
+                .with_fifo_underflow_interrupt(InterruptState::Idle)
+                .write();
+            // Watermarks only mean anything while the CPU writes the FIFO the GPU reads.
+            FIFO_STALLED.store(false, Ordering::Release);
+        }
+    }
+
+    /// Has the GPU stop reading this FIFO once it gets to `ptr`, which calls the breakpoint
+    /// callback from the `gfx::init` CP interrupt handler. The handler turns the breakpoint off
+    /// again, letting the GPU carry on.
+    pub fn enable_breakpoint(&self, ptr: *const u8) {
+        assert!(
+            (self.buf_start.addr()..=self.buf_end.addr()).contains(&ptr.addr()),
+            "Breakpoints have to be inside the FIFO"
+        );
+        let offset = u32::try_from(ptr.addr() - self.buf_start.addr()).unwrap();
+        let addr = read_split(CP_FIFO_BASE_LO, CP_FIFO_BASE_HI) + offset;
+
+        CP_FIFO_BP_PTR_LO.write(addr.get_bits(0..=15).try_into().unwrap());
+        CP_FIFO_BP_PTR_HI.write(addr.get_bits(16..=31).try_into().unwrap());
+
+        CommandControl::read()
+            .with_command_processor_interrupt(InterruptState::Happened)
+            .with_fifo_breakpoint_interrupt(InterruptState::Happened)
+            .write();
+    }
+
+    pub fn disable_breakpoint(&self) {
+        CommandControl::read()
+            .with_command_processor_interrupt(InterruptState::Idle)
+            .with_fifo_breakpoint_interrupt(InterruptState::Idle)
+            .write();
+    }
+
+    /// Called from the CP interrupt whenever the GPU hits the breakpoint.
+    pub fn set_breakpoint_callback(&self, callback: impl Fn() + Send + Sync + 'static) {
+        *BREAKPOINT_CALLBACK.write() = Some(Box::new(callback));
+    }
+
+    /// Installs the PE token and finish interrupt handlers that `wait_draw_done`, `read_draw_sync`
+    /// and the draw callbacks rely on.
     pub fn set_interrupts(&self) {
         Interrupt::set_interrupt_handler(Interrupt::PixelEngineToken, |_| {
-            let status = 0xCC00_100A as *mut u16;
-            let mut val = unsafe { status.read_volatile() };
-            if val.get_bit(2) {
-                val.set_bit(2, true);
-                unsafe {
-                    status.write_volatile(val);
+            if InterruptControl::read().token() == InterruptState::Happened {
+                let token = PE_TOKEN.read();
+                InterruptControl::read()
+                    .with_token(InterruptState::Happened)
+                    .with_finish(InterruptState::Idle)
+                    .write();
+
+                if let Some(callback) = DRAW_SYNC_CALLBACK.read().as_ref() {
+                    callback(token);
                 }
             }
 
@@ -163,27 +390,25 @@ impl Fifo {
         });
 
         Interrupt::set_interrupt_handler(Interrupt::PixelEngineFinish, |_| {
-            let status = 0xCC00_100A as *mut u16;
-            let mut val = unsafe { status.read_volatile() };
-
-            if val.get_bit(3) {
-                val.set_bit(3, true);
-                unsafe {
-                    status.write_volatile(val);
+            if InterruptControl::read().finish() == InterruptState::Happened {
+                InterruptControl::read()
+                    .with_token(InterruptState::Idle)
+                    .with_finish(InterruptState::Happened)
+                    .write();
+                DRAW_DONE.store(true, Ordering::Release);
+
+                if let Some(callback) = DRAW_DONE_CALLBACK.read().as_ref() {
+                    callback();
                 }
             }
 
             Ok(())
         });
 
-        let status = 0xCC00_100A as *mut u16;
-        let mut val = unsafe { status.read_volatile() };
-
-        val.set_bit(0, true);
-        val.set_bit(1, true);
-        unsafe {
-            status.write_volatile(val);
-        }
+        InterruptControl::read()
+            .with_token_enable(Enabled::Enabled)
+            .with_finish_enable(Enabled::Enabled)
+            .write();
 
         InterruptMask::read()
             .with_pixel_engine_token(Mask::Enabled)
@@ -191,29 +416,318 @@ impl Fifo {
             .write();
     }
 
-    pub fn set_copy_clear(&mut self, color: [u8; 4], z_value: u32) {
-        let [r, g, b, a] = color;
-        let z_value = z_value.min(0x00_FF_FF_FF);
+    /// Has the PE raise its finish interrupt once everything sent so far has been drawn.
+    pub fn set_draw_done(&mut self) {
+        DRAW_DONE.store(false, Ordering::Release);
+        PeDone::new().with_finish(true).write(self);
+        self.flush();
+    }
 
-        let mut ra = 0u16;
-        ra.set_bits(0..8, r.into()).set_bits(8..16, a.into());
-        let mut gb = 0u16;
-        gb.set_bits(0..8, b.into()).set_bits(8..16, g.into());
+    /// Spins until the PE finish interrupt asked for by the last `set_draw_done` has happened.
+    pub fn wait_draw_done(&self) {
+        while !DRAW_DONE.load(Ordering::Acquire) {
+            core::hint::spin_loop();
+        }
+    }
 
-        let pipe_u8 = 0xCC00_8000 as *mut u8;
-        let pipe_u32 = 0xCC00_8000 as *mut u32;
+    /// Has the PE latch `token` once everything sent so far has been drawn, raising its token
+    /// interrupt. The latest one reached can be polled with `read_draw_sync`.
+    pub fn set_draw_sync(&mut self, token: u16) {
+        PeTokenInterrupt::new().with_token(token).write(self);
+        PeToken::new().with_token(token).write(self);
+        self.flush();
+    }
 
-        unsafe {
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x4F00_0000u32 | u32::from(ra));
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x5000_0000u32 | u32::from(gb));
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x5100_0000u32 | z_value);
+    /// The last token the PE has drawn past.
+    pub fn read_draw_sync(&self) -> u16 {
+        PE_TOKEN.read()
+    }
+
+    /// Called from the PE finish interrupt, after every `set_draw_done`.
+    pub fn set_draw_done_callback(&self, callback: impl Fn() + Send + Sync + 'static) {
+        *DRAW_DONE_CALLBACK.write() = Some(Box::new(callback));
+    }
+
+    /// Called from the PE token interrupt with the token that was reached.
+    pub fn set_draw_sync_callback(&self, callback: impl Fn(u16) + Send + Sync + 'static) {
+        *DRAW_SYNC_CALLBACK.write() = Some(Box::new(callback));
+    }
+
+    pub fn clear_draw_callbacks(&self) {
+        *DRAW_DONE_CALLBACK.write() = None;
+        *DRAW_SYNC_CALLBACK.write() = None;
+    }
+
+    /// Sets up how `copy_display` copies the EFB out. The pixel format is written right away,
+    /// the rest is held on to since writing the copy control register starts a copy.
+    pub fn set_copy_display_control(&mut self, copy: &DisplayCopy) {
+        self.copy_control = copy.register();
+        self.pe_control = copy.pe_control();
+        self.write_bp_masked(0x3F, self.pe_control);
+    }
+
+    /// CPU access to the EFB, laid out in the pixel format last set by
+    /// `set_copy_display_control`.
+    pub fn efb(&self) -> Efb {
+        Efb::from_control(self.pe_control)
+    }
+
+    /// Switches the EFB to the multisampled `Rgb565Z16` format, sampling at `AA_SAMPLES` and
+    /// copying out through `AA_VERTICAL_FILTER`, or back to `Rgb8Z24` with the defaults.
+    ///
+    /// `set_copy_display_control` sets the pixel format too, so call this after it. The
+    /// multisampled EFB only holds `AA_EFB_HEIGHT` lines, a 480 line frame is drawn as two
+    /// halves with a matching viewport and scissor, each copied out with `copy_display_strip`.
+    pub fn set_anti_aliasing(&mut self, enable: bool) {
+        let format = if enable {
+            PixelFormat::Rgb565Z16
+        } else {
+            PixelFormat::Rgb8Z24
+        };
+        self.pe_control.with_pixel_format(format);
+        let pe_control = self.pe_control;
+        self.write_bp_masked(0x3F, pe_control);
+
+        self.set_multisampling(enable);
+        if enable {
+            self.set_copy_filter(&AA_SAMPLES, &AA_VERTICAL_FILTER);
+        } else {
+            self.set_copy_filter_default();
         }
     }
 
-    pub fn set_viewport(
+    /// Copies the whole EFB out to `framebuffer` and waits for the pixel engine to be done.
+    ///
+    /// The wait is on the PE finish interrupt, so `set_interrupts` has to have been called.
+    pub fn copy_display(&mut self, framebuffer: &ViFramebuffer) {
+        self.copy_display_strip(framebuffer, 0, framebuffer.height);
+    }
+
+    /// Copies the top `height` lines of the EFB out to the lines of `framebuffer` starting at
+    /// `top`, for frames drawn in several strips, and waits like `copy_display`.
+    pub fn copy_display_strip(&mut self, framebuffer: &ViFramebuffer, top: usize, height: usize) {
+        assert!(
+            top + height <= framebuffer.height,
+            "Strip is outside of the framebuffer"
+        );
+        if self.pe_control.pixel_format() == PixelFormat::Rgb565Z16 {
+            assert!(
+                height <= AA_EFB_HEIGHT,
+                "The multisampled EFB is only 264 lines tall"
+            );
+        }
+
+        self.set_copy_display_source(0, 0, framebuffer.width, height);
+        self.set_copy_display_distance(framebuffer.width);
+        let offset = top * framebuffer.width * core::mem::size_of::<u16>();
+        EfbCopyDestination::new()
+            .with_addr(Physical::new(
+                framebuffer.data[offset..].as_ptr().cast_mut(),
+            ))
+            .write(self);
+
+        let control = self.copy_control;
+        control.write(self);
+
+        self.set_draw_done();
+        self.wait_draw_done();
+    }
+
+    /// Copies a rectangle of the EFB into `dst`, returning it as a texture ready to bind.
+    ///
+    /// `dst` has to be 32 byte aligned, hold at least `copy.buffer_size()` bytes and stay alive
+    /// for as long as the GP may sample the texture. Its cache lines are invalidated and TMEM is
+    /// invalidated after the copy, so stale data can't end up in the texture or be sampled.
+    pub fn copy_tex(&mut self, copy: &TexCopy, dst: &mut [u8]) -> TexObj {
+        assert!(
+            dst.len() >= copy.buffer_size(),
+            "Copy destination is smaller than the copied texture"
+        );
+        assert!(
+            !copy.mipmap || (copy.width % 2 == 0 && copy.height % 2 == 0),
+            "Mipmap copies need an even sized source"
+        );
+        let (width, height) = copy.texture_size();
+        let format = copy.format.texture_format();
+
+        // Dirty lines written back after the copy would overwrite it.
+        dc_invalidate_range(dst.as_mut_ptr(), dst.len());
+
+        EfbCopySourceTopLeft::new()
+            .with_left(copy.left)
+            .with_top(copy.top)
+            .write(self);
+        EfbCopySourceSize::new()
+            .with_width(copy.width)
+            .with_height(copy.height)
+            .write(self);
+        EfbCopyStride::new()
+            .with_stride(format.size(width.into(), 1))
+            .write(self);
+        EfbCopyDestination::new()
+            .with_addr(Physical::new(dst.as_mut_ptr()))
+            .write(self);
+
+        // Z only comes out of the EFB while it is set up as Z24.
+        if copy.format.is_z() {
+            let mut z_only = PeControl::new();
+            z_only.with_pixel_format(PixelFormat::Z24);
+            self.write_bp_masked(0x7, z_only);
+        }
+
+        copy.register().write(self);
+
+        // Writing the pixel format back also keeps drawing from starting before the copy is done.
+        let pe_control = self.pe_control;
+        self.write_bp_masked(0x3F, pe_control);
+        self.invalidate_texture_cache();
+
+        TexObj::new(dst, width, height, format)
+    }
+
+    /// Forgets the shadowed BP, CP and XF registers so the next write to each of them goes out,
+    /// after the GP changed them by itself, for example by running a display list.
+    pub fn invalidate_state(&mut self) {
+        self.shadow.invalidate();
+    }
+
+    /// Register writes emitted and dropped as redundant since the last `reset_shadow_stats`.
+    pub const fn shadow_stats(&self) -> ShadowStats {
+        self.shadow.stats()
+    }
+
+    pub fn reset_shadow_stats(&mut self) {
+        self.shadow.reset_stats();
+    }
+
+    /// Copies the shadowed register state, for `restore_state` to go back to.
+    pub fn save_state(&self) -> StateSnapshot {
+        StateSnapshot(Box::new(self.shadow.clone()))
+    }
+
+    /// Writes back every register that changed since `saved` was taken. Only registers written
+    /// through this FIFO before then are known, and matrix and light memory isn't shadowed.
+    pub fn restore_state(&mut self, saved: &StateSnapshot) {
+        for address in 0..=u8::MAX {
+            if let Some((mask, word)) = self.shadow.restore_bp(&saved.0, address) {
+                self.load_bp_reg(BpMask::new().with_mask(mask).raw());
+                self.load_bp_reg(word);
+            }
+            if let Some(value) = self.shadow.restore_cp(&saved.0, address) {
+                self.load_cp_reg(address, value);
+            }
+        }
+        for address in XfMemory::Register.range() {
+            if let Some(value) = self.shadow.restore_xf(&saved.0, address) {
+                self.load_xf_regs(address, &[value]);
+            }
+        }
+    }
+
+    /// Pushes whatever is still sitting in the write gather pipe out to the FIFO.
+    pub fn flush(&mut self) {
+        for _ in 0..8 {
+            self.write_u32(0);
+        }
+    }
+
+    /// Runs `list`. Registers it loads aren't seen by the register shadow, so follow this with
+    /// `invalidate_state` unless the list only draws.
+    pub fn call_display_list(&mut self, list: &DisplayList) {
+        let addr = Physical::new(list.as_ptr().cast_mut()).addr();
+        self.call_display_list_at(
+            u32::try_from(addr).unwrap(),
+            u32::try_from(list.len()).unwrap(),
+        );
+    }
+}
+
+impl CommandSink for Fifo {
+    fn write_u8(&mut self, value: u8) {
+        wait_for_fifo_room();
+        WriteGatherPipe::new().write_u8(value);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        wait_for_fifo_room();
+        WriteGatherPipe::new().write_u16(value);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        wait_for_fifo_room();
+        WriteGatherPipe::new().write_u32(value);
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        wait_for_fifo_room();
+        WriteGatherPipe::new().write_f32(value);
+    }
+
+    fn load_bp_reg(&mut self, word: u32) {
+        match self.shadow.load_bp(word) {
+            BpLoad::Skip => return,
+            BpLoad::Emit => {}
+            BpLoad::EmitMasked(mask) => {
+                self.write_u8(Opcode::LoadBpReg.into());
+                self.write_u32(BpMask::new().with_mask(mask).raw());
+            }
+        }
+        self.write_u8(Opcode::LoadBpReg.into());
+        self.write_u32(word);
+    }
+
+    fn load_cp_reg(&mut self, address: u8, value: u32) {
+        if self.shadow.load_cp(address, value) {
+            self.write_u8(Opcode::LoadCpReg.into());
+            self.write_u8(address);
+            self.write_u32(value);
+        }
+    }
+
+    fn load_xf_regs(&mut self, address: u16, values: &[u32]) {
+        assert!(
+            (1..=16).contains(&values.len()),
+            "XF loads must be between 1 and 16 words"
+        );
+        if !self.shadow.load_xf(address, values) {
+            return;
+        }
+
+        let count = u32::try_from(values.len() - 1).unwrap();
+        self.write_u8(Opcode::LoadXfReg.into());
+        self.write_u32(count << 16 | u32::from(address));
+        for value in values {
+            self.write_u32(*value);
+        }
+    }
+
+    fn load_xf_regs_f32(&mut self, address: u16, values: &[f32]) {
+        assert!(
+            (1..=16).contains(&values.len()),
+            "XF loads must be between 1 and 16 words"
+        );
+        let mut words = [0; 16];
+        for (word, value) in words.iter_mut().zip(values) {
+            *word = value.to_bits();
+        }
+        self.load_xf_regs(address, &words[..values.len()]);
+    }
+}
+
+/// The GX calls that only emit commands, so they can go live through a `Fifo` or be recorded
+/// into a `DisplayListBuilder`.
+pub trait GxCommands: CommandSink + Sized {
+    fn set_copy_clear(&mut self, color: [u8; 4], z_value: u32) {
+        let [r, g, b, a] = color;
+
+        CopyClearAr::new().with_red(r).with_alpha(a).write(self);
+        CopyClearGb::new().with_green(g).with_blue(b).write(self);
+        CopyClearZ::new()
+            .with_z(z_value.min(0x00_FF_FF_FF))
+            .write(self);
+    }
+
+    fn set_viewport(
         &mut self,
         x_origin: f32,
         y_origin: f32,
@@ -234,204 +748,792 @@ impl Fifo {
         let far = Z_FACTOR * far_plane;
         let z = far - near;
 
-        let pipe_u8 = 0xCC00_8000 as *mut u8;
+        Viewport {
+            scale: [x_0, y_0, z],
+            offset: [x_1, y_1, far],
+        }
+        .write(self);
+    }
 
-        let pipe_u32 = 0xCC00_8000 as *mut u32;
-        let pipe_f32 = 0xCC00_8000 as *mut f32;
+    /// Limits drawing to the `width` x `height` rectangle at `x_origin`, `y_origin`. Coordinates
+    /// past what the 11 bit fields hold are clamped.
+    fn set_scissor(&mut self, x_origin: u32, y_origin: u32, width: u32, height: u32) {
+        assert!(
+            width > 0 && height > 0,
+            "The scissor rectangle can't be empty"
+        );
+        // Scissor coordinates are offset by 342, like the viewport.
+        let clamp = |value: u32| u16::try_from(value.min(0x7FF)).unwrap();
+        let left = x_origin.saturating_add(342);
+        let top = y_origin.saturating_add(342);
+
+        ScissorTopLeft::new()
+            .with_top(clamp(top))
+            .with_left(clamp(left))
+            .write(self);
+        ScissorBottomRight::new()
+            .with_bottom(clamp(top.saturating_add(height - 1)))
+            .with_right(clamp(left.saturating_add(width - 1)))
+            .write(self);
+    }
 
-        unsafe {
-            let array = [x_0, y_0, z, x_1, y_1, far];
-            for n in 0..6 {
-                pipe_u8.write_volatile(0x10);
-                pipe_u32.write_volatile(0x101a + n);
-                pipe_f32.write_volatile(array[n as usize]);
-            }
-        }
+    fn set_copy_display_source(&mut self, left: usize, top: usize, width: usize, height: usize) {
+        EfbCopySourceTopLeft::new()
+            .with_left(left.try_into().unwrap())
+            .with_top(top.try_into().unwrap())
+            .write(self);
+        EfbCopySourceSize::new()
+            .with_width(width.try_into().unwrap())
+            .with_height(height.try_into().unwrap())
+            .write(self);
     }
 
-    pub fn set_scissor(&mut self, x_origin: u32, y_origin: u32, width: u32, height: u32) {
-        let xo = (x_origin + 0x156).min(0x7FF);
-        let yo = (y_origin + 0x156).min(0x7FF);
-        let nwd = xo + (width - 1).min(0x7FF);
-        let nht = yo + (height - 1).min(0xFFF);
+    fn set_copy_display_destination(&mut self, framebuffer: &ViFramebuffer) {
+        EfbCopyDestination::new()
+            .with_addr(Physical::new(framebuffer.data.as_ptr().cast_mut()))
+            .write(self);
+    }
 
-        let mut top_left = 0u32;
-        let mut bottom_right = 0u32;
+    /// `width` is in pixels, the XFB stores two bytes per pixel.
+    fn set_copy_display_distance(&mut self, width: usize) {
+        EfbCopyStride::new()
+            .with_stride(width * core::mem::size_of::<u16>())
+            .write(self);
+    }
 
-        top_left.set_bits(0..12, yo).set_bits(12..22, xo);
-        bottom_right.set_bits(0..12, nht).set_bits(12..22, nwd);
+    /// Stretches display copies vertically by `y_scale`, which is stored as `256 / y_scale` in a
+    /// 9 bit field and so can't go below 1.
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    fn set_y_scale(&mut self, y_scale: f32) {
+        assert!(
+            (1.0..=256.0).contains(&y_scale),
+            "The display copy Y scale has to be between 1 and 256"
+        );
+        let value = (256.0 / y_scale) as u16;
 
-        let pipe_u8 = 0xCC00_8000 as *mut u8;
-        let pipe_u32 = 0xCC00_8000 as *mut u32;
+        EfbCopyYScale::new().with_y_scale(value).write(self);
+    }
+
+    fn set_copy_filter_default(&mut self) {
+        self.set_copy_filter(&CENTERED_SAMPLES, &DEFAULT_VERTICAL_FILTER);
+    }
+
+    /// Loads the multisample positions into SU and the vertical filter used by display copies.
+    fn set_copy_filter(&mut self, samples: &SamplePositions, vertical: &VerticalFilter) {
+        for (index, pixel) in (0..4).zip(samples) {
+            let mut pattern = SamplePattern::new(index);
+            for (slot, (x, y)) in pixel.iter().enumerate() {
+                assert!(*x < 16 && *y < 16, "Sample positions are 4 bits each");
+                pattern.with_sample(slot, *x, *y);
+            }
+            pattern.write(self);
+        }
 
-        unsafe {
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x2000_0000 | top_left);
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x2100_0000 | bottom_right);
+        let mut filter = CopyFilter::new(0);
+        for (slot, coefficient) in vertical[0..4].iter().enumerate() {
+            filter.with_coefficient(slot, *coefficient);
         }
+        filter.write(self);
+
+        let mut filter = CopyFilter::new(1);
+        for (slot, coefficient) in vertical[4..7].iter().enumerate() {
+            filter.with_coefficient(slot, *coefficient);
+        }
+        filter.write(self);
     }
 
-    pub fn set_copy_display_source(
+    /// Changes only the multisampling bit of the gen mode register.
+    fn set_multisampling(&mut self, enable: bool) {
+        let mut gen_mode = GenMode::new();
+        gen_mode.with_multisampling(enable);
+        self.write_bp_masked(1 << 9, gen_mode);
+    }
+
+    fn set_su_lpsize(
         &mut self,
-        left: usize,
-        top: usize,
-        width: usize,
-        height: usize,
+        line_size: u8,
+        point_size: u8,
+        line_offset: u8,
+        point_offset: u8,
+        line_aspect_ratio: bool,
+    ) {
+        SuLpSize::new()
+            .with_line_width(line_size)
+            .with_point_size(point_size)
+            .with_line_offset(line_offset)
+            .with_point_offset(point_offset)
+            .with_line_aspect_ratio(line_aspect_ratio)
+            .write(self);
+    }
+
+    /// Sets every field of the gen mode register at once. `num_tex_stages` is one less than the
+    /// number of TEV stages, as the register stores it. Nothing is written to XF.
+    fn set_gen_mode(
+        &mut self,
+        num_tex_coords: u8,
+        num_colors: u8,
+        ms_en: bool,
+        num_tex_stages: u8,
+        cull_mode: CullMode,
+        num_bump_maps: u8,
+        z_freeze: bool,
     ) {
-        let top_left = left << 10 | top;
-        let width_height = (width - 1) << 10 | (height - 1);
-
-        let pipe_u8 = 0xCC00_8000 as *mut u8;
-        let pipe_u32 = 0xCC00_8000 as *mut u32;
-
-        unsafe {
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x4900_0000u32 | u32::try_from(top_left).unwrap());
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x4A00_0000 | u32::try_from(width_height).unwrap());
-        }
-    }
-
-    pub fn set_copy_display_destination(&mut self, framebuffer: &ViFramebuffer) {
-        let mut dest = bitfrob::u32_with_value(24, 31, 0u32, 0x4b);
-        dest = bitfrob::u32_with_value(
-            0,
-            24,
-            dest,
-            framebuffer
-                .data
-                .as_ptr()
-                .map_addr(|addr| addr - 0x8000_0000)
-                .addr()
-                .try_into()
-                .unwrap(),
+        GenMode::new()
+            .with_num_tex_gens(num_tex_coords)
+            .with_num_color_channels(num_colors)
+            .with_multisampling(ms_en)
+            .with_num_tev_stages(num_tex_stages.saturating_add(1))
+            .with_cull_mode(cull_mode)
+            .with_num_indirect_stages(num_bump_maps)
+            .with_z_freeze(z_freeze)
+            .write(self);
+    }
+
+    fn set_vertex_layout(&mut self, layout: &VertexLayout) {
+        let (lo, hi) = layout.descriptor.registers();
+        lo.write(self);
+        hi.write(self);
+
+        let (a, b, c) = layout.format.registers();
+        a.write_at(layout.index, self);
+        b.write_at(layout.index, self);
+        c.write_at(layout.index, self);
+
+        layout.in_vertex_spec().write(self);
+    }
+
+    /// Points the CP array for an indexed `attribute` at `data`, flushing it out of the cache.
+    fn set_array<T>(&mut self, attribute: Attribute, data: &[T], stride: u8) {
+        let ptr = data.as_ptr().cast::<u8>();
+        dc_flush_range(ptr, core::mem::size_of_val(data));
+
+        let index = attribute.array_index();
+        ArrayBase::new()
+            .with_addr(Physical::new(ptr.cast_mut()))
+            .write_at(index, self);
+        ArrayStride::new().with_stride(stride).write_at(index, self);
+    }
+
+    fn begin(
+        &mut self,
+        primitive: Primitive,
+        layout: &VertexLayout,
+        count: u16,
+    ) -> PrimitiveBuilder<'_, Self> {
+        PrimitiveBuilder::new(self, primitive, layout, count)
+    }
+
+    /// Changes only the cull mode of the gen mode register.
+    fn set_cull_mode(&mut self, mode: CullMode) {
+        let mut gen_mode = GenMode::new();
+        gen_mode.with_cull_mode(mode);
+        self.write_bp_masked(0x3 << 14, gen_mode);
+    }
+
+    /// Changes only the TEV stage count of the gen mode register.
+    fn set_num_tev_stages(&mut self, count: u8) {
+        let mut gen_mode = GenMode::new();
+        gen_mode.with_num_tev_stages(count);
+        self.write_bp_masked(0xF << 10, gen_mode);
+    }
+
+    fn set_tev_color_combiner(&mut self, stage: u8, combiner: &ColorCombiner) {
+        combiner.register(stage).write(self);
+    }
+
+    /// Leaves the swap table selections of the stage untouched.
+    fn set_tev_alpha_combiner(&mut self, stage: u8, combiner: &AlphaCombiner) {
+        self.write_bp_masked(0xFF_FFF0, combiner.register(stage));
+    }
+
+    fn set_tev_op(&mut self, stage: u8, preset: TevPreset) {
+        let (color, alpha) = preset.combiners(stage);
+        self.set_tev_color_combiner(stage, &color);
+        self.set_tev_alpha_combiner(stage, &alpha);
+    }
+
+    fn set_tev_order(&mut self, stage: u8, order: &TevOrder) {
+        assert!(stage < 16, "There are only 16 TEV stages");
+        let mut reg = TevStageOrders::new(stage / 2);
+        order.apply(stage, &mut reg);
+        self.write_bp_masked(0xFFF << (12 * (stage % 2)), reg);
+    }
+
+    /// Selects the swap tables applied to the rasterized color and the texture color of `stage`.
+    fn set_tev_swap_mode(&mut self, stage: u8, ras_table: u8, tex_table: u8) {
+        assert!(
+            ras_table < 4 && tex_table < 4,
+            "There are only 4 swap tables"
         );
+        let mut reg = TevAlphaEnv::new(stage);
+        reg.with_ras_swap(ras_table).with_tex_swap(tex_table);
+        self.write_bp_masked(0xF, reg);
+    }
 
-        unsafe {
-            let pipe_u8 = 0xCC00_8000 as *mut u8;
-            let pipe_u32 = 0xCC00_8000 as *mut u32;
+    fn set_tev_swap_table(&mut self, table: u8, swap: &SwapTable) {
+        let (rg, ba) = swap.registers(table);
+        self.write_bp_masked(0xF, rg);
+        self.write_bp_masked(0xF, ba);
+    }
 
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(dest);
-        }
+    fn set_tev_konst_color_select(&mut self, stage: u8, select: KonstSelect) {
+        assert!(stage < 16, "There are only 16 TEV stages");
+        let odd = stage % 2 == 1;
+        let mut reg = TevKSel::new(stage / 2);
+        reg.with_konst_color(odd, select);
+        self.write_bp_masked(0x1F << (4 + 10 * u32::from(odd)), reg);
     }
 
-    pub fn set_copy_display_distance(&mut self, width: usize) {
-        unsafe {
-            (0xCC00_8000 as *mut u8).write_volatile(0x61);
-            (0xCC00_8000 as *mut u32)
-                .write_volatile(0x4D00_0000 | u32::try_from(width & 0x3FF).unwrap());
+    fn set_tev_konst_alpha_select(&mut self, stage: u8, select: KonstSelect) {
+        assert!(stage < 16, "There are only 16 TEV stages");
+        let odd = stage % 2 == 1;
+        let mut reg = TevKSel::new(stage / 2);
+        reg.with_konst_alpha(odd, select);
+        self.write_bp_masked(0x1F << (9 + 10 * u32::from(odd)), reg);
+    }
+
+    /// Sets a TEV register to an RGBA color with 10 bit signed channels.
+    fn set_tev_color(&mut self, register: TevRegister, color: [i16; 4]) {
+        let [red, green, blue, alpha] = color;
+        let mut ra = TevColorRa::new(register.index());
+        ra.with_red(red).with_alpha(alpha).write(self);
+
+        // The blue/green half only sticks reliably when loaded three times.
+        let mut bg = TevColorBg::new(register.index());
+        bg.with_blue(blue).with_green(green);
+        for _ in 0..3 {
+            bg.write(self);
         }
     }
 
-    pub fn set_copy_display_control(
-        &mut self,
-        _left_right_clamp: u8,
-        _top_bottom_clamp: u8,
-        _gamma: u8,
-        _needs_y_scale: bool,
-        _clear_framebuffer: bool,
-        _frame_2_field: u8,
-        _dest: u8,
-    ) {
+    /// Loads a position matrix into slot `slot` (0..10) of XF matrix memory.
+    fn load_position_matrix(&mut self, matrix: &Mtx34, slot: u8) {
+        assert!(slot < 10, "There are only 10 position matrix slots");
+        XfMemory::PositionMatrix.load(u16::from(slot) * 12, &matrix.to_words(), self);
+    }
+
+    /// Loads the 3x3 part of `matrix` as the normal matrix paired with position slot `slot`.
+    ///
+    /// Pass the inverse transpose of the position matrix unless it has no non-uniform scale.
+    fn load_normal_matrix(&mut self, matrix: &Mtx34, slot: u8) {
+        assert!(slot < 10, "There are only 10 normal matrix slots");
+        XfMemory::NormalMatrix.load(u16::from(slot) * 9, &matrix.to_normal_words(), self);
     }
 
-    pub fn set_y_scale(&mut self, y_scale: f32) {
-        let val = 256.0 / y_scale;
+    /// Loads a texture matrix into slot `slot` (0..10), which sits after the position matrices.
+    fn load_tex_matrix(&mut self, matrix: &Mtx34, slot: u8) {
+        assert!(slot < 10, "There are only 10 texture matrix slots");
+        XfMemory::PositionMatrix.load(
+            u16::from(TEX_MATRIX_ROW + slot * 3) * 4,
+            &matrix.to_words(),
+            self,
+        );
+    }
 
-        let pipe_u8 = 0xCC00_8000 as *mut u8;
-        let pipe_u32 = 0xCC00_8000 as *mut u32;
+    /// Loads a post-transform texture matrix into slot `slot` (0..20).
+    fn load_post_tex_matrix(&mut self, matrix: &Mtx34, slot: u8) {
+        assert!(slot < 20, "There are only 20 post-transform matrix slots");
+        XfMemory::PostMatrix.load(u16::from(slot) * 12, &matrix.to_words(), self);
+    }
 
-        unsafe {
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x4E00_0000u32 | val as u32);
+    /// Loads the identity matrices texture coordinates and post-transforms fall back to, once
+    /// after init is enough.
+    fn load_identity_matrix(&mut self) {
+        XfMemory::PositionMatrix.load(
+            u16::from(IDENTITY_MATRIX_ROW) * 4,
+            &Mtx34::IDENTITY.to_words(),
+            self,
+        );
+        XfMemory::PostMatrix.load(
+            u16::from(POST_IDENTITY_ROW) * 4,
+            &Mtx34::IDENTITY.to_words(),
+            self,
+        );
+    }
+
+    /// Selects the position and normal matrix used for the following vertices.
+    ///
+    /// Texture coordinates go back to the matrix loaded by `load_identity_matrix`.
+    fn set_current_matrix(&mut self, slot: u8) {
+        self.set_current_matrices(slot, &[None; 8]);
+    }
+
+    /// Selects the position and normal matrix along with the texture matrix slot each generated
+    /// texture coordinate is transformed by, `None` meaning the identity matrix.
+    fn set_current_matrices(&mut self, slot: u8, tex_slots: &[Option<u8>; 8]) {
+        assert!(slot < 10, "There are only 10 position matrix slots");
+        let row = slot * 3;
+        let tex_rows = tex_slots.map(|tex_slot| {
+            tex_slot.map_or(IDENTITY_MATRIX_ROW, |tex_slot| {
+                assert!(tex_slot < 10, "There are only 10 texture matrix slots");
+                TEX_MATRIX_ROW + tex_slot * 3
+            })
+        });
+
+        let mut cp_a = cp::MatrixIndexA::new();
+        let mut xf_a = xf::MatrixIndexA::new();
+        cp_a.with_position_normal(row);
+        xf_a.with_position_normal(row);
+        for (coord, tex_row) in tex_rows.iter().enumerate().take(4) {
+            cp_a.with_tex_coord(coord, *tex_row);
+            xf_a.with_tex_coord(coord, *tex_row);
         }
+        let mut cp_b = cp::MatrixIndexB::new();
+        let mut xf_b = xf::MatrixIndexB::new();
+        for (coord, tex_row) in tex_rows.iter().enumerate().skip(4) {
+            cp_b.with_tex_coord(coord, *tex_row);
+            xf_b.with_tex_coord(coord, *tex_row);
+        }
+
+        cp_a.write(self);
+        cp_b.write(self);
+        xf_a.write(self);
+        xf_b.write(self);
     }
 
-    pub fn set_copy_filter_default(&mut self) {
-        let pipe_u8 = 0xCC00_8000 as *mut u8;
-        let pipe_u32 = 0xCC00_8000 as *mut u32;
+    /// Sets how many texture coordinates are generated, in both XF and the gen mode register.
+    fn set_num_tex_gens(&mut self, count: u8) {
+        NumTexGens::new().with_count(count).write(self);
 
-        unsafe {
-            for i in 1..=4 {
-                pipe_u8.write_volatile(0x61);
-                pipe_u32.write_volatile(i << 24 | 0x66_66_66);
-            }
+        let mut gen_mode = GenMode::new();
+        gen_mode.with_num_tex_gens(count);
+        self.write_bp_masked(0xF, gen_mode);
+    }
+
+    fn set_tex_coord_gen(&mut self, coord: u8, tex_gen: &TexCoordGen) {
+        assert!(coord < 8, "There are only 8 texture coordinate generators");
+        let (info, post) = tex_gen.registers();
+        info.write_at(coord, self);
+        post.write_at(coord, self);
+    }
+
+    /// Post-transform matrices and normalization only apply while this is on.
+    fn set_dual_tex(&mut self, enable: bool) {
+        DualTex::new().with_enable(enable).write(self);
+    }
+
+    /// Sets up generators 0.. from `tex_gens` and the generated coordinate count to match,
+    /// turning dual texturing on only when one of them needs it.
+    fn set_tex_coord_gens(&mut self, tex_gens: &[TexCoordGen]) {
+        let count = u8::try_from(tex_gens.len())
+            .ok()
+            .filter(|count| *count <= 8)
+            .expect("There are only 8 texture coordinate generators");
+        for (coord, tex_gen) in (0..count).zip(tex_gens) {
+            self.set_tex_coord_gen(coord, tex_gen);
         }
+        self.set_dual_tex(tex_gens.iter().any(TexCoordGen::is_dual));
+        self.set_num_tex_gens(count);
+    }
+
+    fn set_projection(&mut self, matrix: &Mtx44, ty: ProjectionType) {
+        matrix.to_projection(ty).write(self);
+    }
+
+    /// Sets how many color channels reach the TEV, in both XF and the gen mode register.
+    fn set_num_channels(&mut self, count: u8) {
+        NumColors::new().with_count(count).write(self);
 
-        unsafe {
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x53595000);
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(0x54000015);
+        let mut gen_mode = GenMode::new();
+        gen_mode.with_num_color_channels(count);
+        self.write_bp_masked(0x7 << 4, gen_mode);
+    }
+
+    fn set_channel(&mut self, channel: Channel, config: &ChannelConfig) {
+        let control = config.register();
+        for index in channel.control_indices() {
+            control.write_at(*index, self);
         }
     }
 
-    pub fn set_su_lpsize(
+    /// Sets the ambient color of color channel `index`, its alpha goes to the alpha channel.
+    fn set_ambient_color(&mut self, index: u8, color: [u8; 4]) {
+        assert!(index < 2, "XF only has 2 color channels");
+        AmbientColor::new().with_color(color).write_at(index, self);
+    }
+
+    /// Sets the material color of color channel `index`, its alpha goes to the alpha channel.
+    fn set_material_color(&mut self, index: u8, color: [u8; 4]) {
+        assert!(index < 2, "XF only has 2 color channels");
+        MaterialColor::new().with_color(color).write_at(index, self);
+    }
+
+    /// Loads `light` into light slot `slot` (0..8).
+    fn load_light(&mut self, light: &LightObj, slot: u8) {
+        let address = XfMemory::Light.address(light_offset(slot));
+        self.load_xf_regs(address, &light.to_words());
+    }
+
+    /// Sets how new pixels are combined with the EFB, leaving the update masks and dither alone.
+    fn set_blend_mode(
         &mut self,
-        line_size: u8,
-        point_size: u8,
-        line_offset: u8,
-        point_offset: u8,
-        line_aspect_ratio: bool,
+        mode: BlendMode,
+        src_factor: BlendFactor,
+        dst_factor: BlendFactor,
+        logic_op: LogicOp,
     ) {
-        let mut su_lpsize = bitfrob::u32_with_value(0, 7, 0u32, line_size.into());
-        su_lpsize = bitfrob::u32_with_value(8, 15, su_lpsize, point_size.into());
-        su_lpsize = bitfrob::u32_with_value(16, 18, su_lpsize, line_offset.into());
-        su_lpsize = bitfrob::u32_with_value(19, 21, su_lpsize, point_offset.into());
-        su_lpsize = bitfrob::u32_with_bit(22, su_lpsize, line_aspect_ratio);
-        su_lpsize = bitfrob::u32_with_value(24, 31, su_lpsize, 0x22);
+        let mut blend = BlendControl::new();
+        blend
+            .with_blend(matches!(mode, BlendMode::Blend | BlendMode::Subtract))
+            .with_logic(mode == BlendMode::Logic)
+            .with_subtract(mode == BlendMode::Subtract)
+            .with_src_factor(src_factor)
+            .with_dst_factor(dst_factor)
+            .with_logic_op(logic_op);
+        self.write_bp_masked(0xFFE3, blend);
+    }
 
-        unsafe {
-            let pipe_u8 = 0xCC00_8000 as *mut u8;
-            let pipe_u32 = 0xCC00_8000 as *mut u32;
+    fn set_color_update(&mut self, enable: bool) {
+        let mut blend = BlendControl::new();
+        blend.with_color_update(enable);
+        self.write_bp_masked(1 << 3, blend);
+    }
 
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(su_lpsize);
-        }
+    fn set_alpha_update(&mut self, enable: bool) {
+        let mut blend = BlendControl::new();
+        blend.with_alpha_update(enable);
+        self.write_bp_masked(1 << 4, blend);
     }
 
-    pub fn set_gen_mode(
+    fn set_dither(&mut self, enable: bool) {
+        let mut blend = BlendControl::new();
+        blend.with_dither(enable);
+        self.write_bp_masked(1 << 2, blend);
+    }
+
+    fn set_z_mode(&mut self, enable: bool, function: CompareFn, update: bool) {
+        ZMode::new()
+            .with_enable(enable)
+            .with_function(function)
+            .with_update(update)
+            .write(self);
+    }
+
+    /// Runs the Z test before texturing when set, which is only correct without alpha test.
+    fn set_z_compare_location(&mut self, before_texture: bool) {
+        let mut control = PeControl::new();
+        control.with_z_compare_before_texture(before_texture);
+        self.write_bp_masked(1 << 6, control);
+    }
+
+    /// Pixels pass when `alpha comp0 ref0` combined by `op` with `alpha comp1 ref1` holds.
+    fn set_alpha_compare(
         &mut self,
-        num_tex_coords: u8,
-        num_colors: u8,
-        ms_en: bool,
-        num_tex_stages: u8,
-        cull_mode: u8,
-        num_bump_maps: u8,
-        z_freeze: u8,
+        comp0: CompareFn,
+        ref0: u8,
+        op: AlphaOp,
+        comp1: CompareFn,
+        ref1: u8,
     ) {
-        let mut gen_mode = bitfrob::u32_with_value(0, 3, 0u32, num_tex_coords.into());
-        gen_mode = bitfrob::u32_with_value(4, 8, gen_mode, num_colors.into());
-        gen_mode = bitfrob::u32_with_bit(9, gen_mode, ms_en);
-        gen_mode = bitfrob::u32_with_value(10, 13, gen_mode, num_tex_stages.into());
-        gen_mode = bitfrob::u32_with_value(14, 15, gen_mode, cull_mode.into());
-        gen_mode = bitfrob::u32_with_value(16, 18, gen_mode, num_bump_maps.into());
-        gen_mode = bitfrob::u32_with_value(19, 23, gen_mode, z_freeze.into());
+        AlphaCompare::new()
+            .with_comp0(comp0)
+            .with_ref0(ref0)
+            .with_op(op)
+            .with_comp1(comp1)
+            .with_ref1(ref1)
+            .write(self);
+    }
+
+    /// Writes `alpha` into the EFB instead of the pixel alpha, when enabled.
+    fn set_dst_alpha(&mut self, enable: bool, alpha: u8) {
+        ConstantAlpha::new()
+            .with_enable(enable)
+            .with_alpha(alpha)
+            .write(self);
+    }
 
-        unsafe {
-            let pipe_u8 = 0xCC00_8000 as *mut u8;
-            let pipe_u32 = 0xCC00_8000 as *mut u32;
+    fn set_fog(&mut self, fog: &Fog) {
+        let (param0, param1, param2, param3, color) = fog.registers();
+        param0.write(self);
+        param1.write(self);
+        param2.write(self);
+        param3.write(self);
+        color.write(self);
+    }
 
-            pipe_u8.write_volatile(0x61);
-            pipe_u32.write_volatile(gen_mode);
+    /// Makes fog follow the distance to the eye across the screen instead of only Z, `center`
+    /// is the screen column of the eye. The table is only loaded when enabling.
+    fn set_fog_range_adjust(&mut self, enable: bool, center: u16, table: &FogRangeTable) {
+        if enable {
+            for register in table.registers() {
+                register.write(self);
+            }
         }
+        FogRangeTable::range_register(center, enable).write(self);
     }
 
-    pub fn copy_display(&mut self, framebuffer: &ViFramebuffer) {
-        self.set_copy_display_source(0, 0, framebuffer.width, framebuffer.height);
-        self.set_copy_display_distance(framebuffer.width);
-        self.set_copy_display_destination(framebuffer);
+    /// Binds `texture` to `map`, cached in the fixed TMEM region of that map.
+    fn load_tex_obj(&mut self, texture: &TexObj, map: u8) {
+        self.load_tex_obj_in(texture, map, &TexRegion::for_map(map));
+    }
+
+    fn load_tex_obj_in(&mut self, texture: &TexObj, map: u8, region: &TexRegion) {
+        assert!(
+            !texture.format().is_indexed() || texture.tlut().is_some(),
+            "Color indexed textures need a TLUT"
+        );
+
+        let (mode0, mode1, image0, image1, image2, image3) = texture.registers(map, region);
+        mode0.write(self);
+        mode1.write(self);
+        image0.write(self);
+        image1.write(self);
+        image2.write(self);
+        image3.write(self);
+        if let Some(tlut) = texture.tlut_register(map) {
+            tlut.write(self);
+        }
+    }
+
+    /// Copies a palette from main memory into TMEM.
+    fn load_tlut(&mut self, tlut: &TlutObj, region: TlutRegion) {
+        assert!(
+            tlut.entries() <= region.entries,
+            "The TLUT doesn't fit in its region"
+        );
+
+        TlutLoadSource::new().with_addr(tlut.addr()).write(self);
+        TlutLoadDestination::new()
+            .with_tmem_addr(region.tmem_addr)
+            .with_lines(tlut.entries() / 16)
+            .write(self);
+    }
+
+    /// Drops everything the texture cache holds, needed after texture data changes in memory.
+    fn invalidate_texture_cache(&mut self) {
+        for line in [0x000, 0x100] {
+            TexCacheInvalidate::new()
+                .with_line(line)
+                .with_size(8)
+                .write(self);
+        }
+    }
+
+    fn set_tev_konst_color(&mut self, index: u8, color: [u8; 4]) {
+        let [red, green, blue, alpha] = color.map(i16::from);
+        TevColorRa::new(index)
+            .with_red(red)
+            .with_alpha(alpha)
+            .with_konst(true)
+            .write(self);
+        TevColorBg::new(index)
+            .with_blue(blue)
+            .with_green(green)
+            .with_konst(true)
+            .write(self);
+    }
+
+    /// Changes only the indirect stage count of the gen mode register.
+    fn set_num_ind_stages(&mut self, count: u8) {
+        assert!(count <= 4, "There are only 4 indirect stages");
+        let mut gen_mode = GenMode::new();
+        gen_mode.with_num_indirect_stages(count);
+        self.write_bp_masked(0x7 << 16, gen_mode);
+    }
+
+    /// Sets the texture sampled by `ind_stage` and the scale applied to its coordinate.
+    fn set_ind_stage(&mut self, ind_stage: u8, stage: &IndStage) {
+        assert!(ind_stage < 4, "There are only 4 indirect stages");
+        let mut order = RasIndOrder::new();
+        stage.apply_order(ind_stage, &mut order);
+        self.write_bp_masked(0x3F << (6 * u32::from(ind_stage)), order);
+
+        let mut scale = RasIndScale::new(ind_stage / 2);
+        stage.apply_scale(ind_stage, &mut scale);
+        self.write_bp_masked(0xFF << (8 * u32::from(ind_stage % 2)), scale);
+    }
+
+    fn set_ind_tex_matrix(&mut self, index: u8, matrix: &IndTexMatrix) {
+        for reg in matrix.registers(index) {
+            reg.write(self);
+        }
+    }
+
+    fn set_tev_indirect(&mut self, stage: u8, indirect: &TevIndirect) {
+        indirect.register(stage).write(self);
+    }
+
+    /// Turns indirect offsets off for `stage`.
+    fn set_tev_direct(&mut self, stage: u8) {
+        self.set_tev_indirect(stage, &TevIndirect::DIRECT);
+    }
+
+    /// Sets up the three stages from `stage` on for a bump map lookup in S and T, see
+    /// `TevIndirect::bump_st`.
+    fn set_tev_ind_bump_st(&mut self, stage: u8, ind_stage: u8, matrix: u8) {
+        for (offset, indirect) in (0..).zip(TevIndirect::bump_st(ind_stage, matrix)) {
+            self.set_tev_indirect(stage + offset, &indirect);
+        }
+    }
+
+    /// Warps what `stage` samples by the signed texels of `ind_stage`, up to `strength` texture
+    /// coordinate units in each direction. Slowly scrolling the indirect texture gives a heat
+    /// haze, a static one a fixed distortion like glass.
+    fn set_tev_ind_warp(&mut self, stage: u8, ind_stage: u8, matrix: u8, strength: [f32; 2]) {
+        let [s, t] = strength;
+        self.set_ind_tex_matrix(matrix, &IndTexMatrix::scale(s, t));
+        self.set_tev_indirect(stage, &TevIndirect::warp(ind_stage, matrix, true, false));
+    }
+
+    /// A weak, noisy warp for heat haze over `stage`, with `ind_stage` sampling a noise texture.
+    fn set_tev_ind_heat_haze(&mut self, stage: u8, ind_stage: u8, matrix: u8, strength: f32) {
+        self.set_tev_ind_warp(stage, ind_stage, matrix, [strength, strength]);
     }
 }
 
+impl<T: CommandSink> GxCommands for T {}
+
+/// Texture matrices share XF matrix memory with the position matrices, starting at row 30.
+const TEX_MATRIX_ROW: u8 = 30;
+
+/// Row after the texture matrices, where the SDK keeps an identity matrix.
+const IDENTITY_MATRIX_ROW: u8 = 60;
+
 impl Default for Fifo {
     fn default() -> Self {
         Self::new()
     }
 }
 
+/// Set by the PE finish interrupt.
+static DRAW_DONE: AtomicBool = AtomicBool::new(false);
+
+/// `buf_start` of the FIFOs the CPU writes and the GPU reads.
+static CPU_FIFO: AtomicUsize = AtomicUsize::new(0);
+static GPU_FIFO: AtomicUsize = AtomicUsize::new(0);
+
+/// Set by the CP interrupt between the FIFO going over its high watermark and back under its
+/// low one, CPU writes wait it out.
+pub(crate) static FIFO_STALLED: AtomicBool = AtomicBool::new(false);
+
+pub(crate) static BREAKPOINT_CALLBACK: RwLock<Option<Box<BreakpointCallback>>> = RwLock::new(None);
+
+type BreakpointCallback = dyn Fn() + Send + Sync + 'static;
+
+type DrawDoneCallback = dyn Fn() + Send + Sync + 'static;
+
+type DrawSyncCallback = dyn Fn(u16) + Send + Sync + 'static;
+
+static DRAW_DONE_CALLBACK: RwLock<Option<Box<DrawDoneCallback>>> = RwLock::new(None);
+
+static DRAW_SYNC_CALLBACK: RwLock<Option<Box<DrawSyncCallback>>> = RwLock::new(None);
+
+/// The CP interrupt handler lifts the stall, but it can't run while interrupts are disabled. Then
+/// the underflow status is polled instead and the stall lifted here.
+fn wait_for_fifo_room() {
+    while FIFO_STALLED.load(Ordering::Acquire) {
+        let interrupts = MachineStateRegister::read().external_interrupt_enabled();
+        if matches!(interrupts, Enabled::Disabled) && CommandStatus::read().fifo_underflow() {
+            gfx::end_fifo_stall();
+        }
+        core::hint::spin_loop();
+    }
+}
+
+fn read_split(lo: VolAddress<u16, Safe, Safe>, hi: VolAddress<u16, Safe, Safe>) -> u32 {
+    u32::from(lo.read()) | u32::from(hi.read()) << 16
+}
+
 fn sync() {
-    unsafe { core::arch::asm!("sc") }
+    #[cfg(target_arch = "powerpc")]
+    unsafe {
+        core::arch::asm!("sc");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn set_gen_mode_fills_the_typed_fields() {
+        let mut stream = Vec::new();
+        stream.set_gen_mode(1, 1, false, 0, CullMode::None, 0, false);
+        stream.set_gen_mode(8, 2, true, 15, CullMode::All, 4, true);
+        assert_eq!(
+            stream,
+            [0x61, 0x00, 0x00, 0x00, 0x11, 0x61, 0x00, 0x0C, 0xFE, 0x28]
+        );
+
+        let mut gen_mode = GenMode::from(0x000C_FE28);
+        assert_eq!(gen_mode.num_tex_gens(), 8);
+        assert_eq!(gen_mode.num_color_channels(), 2);
+        assert!(gen_mode.multisampling());
+        assert_eq!(gen_mode.num_tev_stages(), 16);
+        assert_eq!(gen_mode.cull_mode(), CullMode::All);
+        assert_eq!(gen_mode.num_indirect_stages(), 4);
+        assert!(gen_mode.z_freeze());
+        assert_eq!(gen_mode.with_z_freeze(false).raw(), 0x0004_FE28);
+    }
+
+    #[test]
+    #[should_panic]
+    fn set_gen_mode_rejects_values_past_their_field() {
+        Vec::new().set_gen_mode(1, 8, false, 0, CullMode::None, 0, false);
+    }
+
+    #[test]
+    fn set_y_scale_stores_the_reciprocal() {
+        let mut stream = Vec::new();
+        stream.set_y_scale(1.0);
+        stream.set_y_scale(2.0);
+        stream.set_y_scale(256.0);
+        assert_eq!(
+            stream,
+            [
+                0x61, 0x4E, 0x00, 0x01, 0x00, 0x61, 0x4E, 0x00, 0x00, 0x80, 0x61, 0x4E, 0x00, 0x00,
+                0x01
+            ]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The display copy Y scale has to be between 1 and 256")]
+    fn set_y_scale_rejects_shrinking() {
+        Vec::new().set_y_scale(0.4);
+    }
+
+    #[test]
+    #[should_panic(expected = "The display copy Y scale has to be between 1 and 256")]
+    fn set_y_scale_rejects_nan() {
+        Vec::new().set_y_scale(f32::NAN);
+    }
+
+    #[test]
+    fn set_scissor_offsets_by_342() {
+        let mut stream = Vec::new();
+        stream.set_scissor(0, 0, 640, 480);
+        assert_eq!(
+            stream,
+            [0x61, 0x20, 0x15, 0x61, 0x56, 0x61, 0x21, 0x3D, 0x53, 0x35]
+        );
+    }
+
+    #[test]
+    fn set_scissor_fits_a_full_width_rectangle_at_an_origin() {
+        let mut stream = Vec::new();
+        stream.set_scissor(100, 20, 640, 480);
+        assert_eq!(
+            stream,
+            [0x61, 0x20, 0x1B, 0xA1, 0x6A, 0x61, 0x21, 0x43, 0x93, 0x49]
+        );
+    }
+
+    #[test]
+    fn set_scissor_clamps_to_the_field() {
+        let mut stream = Vec::new();
+        stream.set_scissor(2000, 0, 10, 4000);
+        assert_eq!(
+            stream,
+            [0x61, 0x20, 0x7F, 0xF1, 0x56, 0x61, 0x21, 0x7F, 0xF7, 0xFF]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "The scissor rectangle can't be empty")]
+    fn set_scissor_rejects_an_empty_rectangle() {
+        Vec::new().set_scissor(0, 0, 0, 480);
+    }
 }
@@ -0,0 +1,3696 @@
+use bit_field::BitField;
+
+use crate::mmio::Physical;
+
+use super::{
+    command::CommandSink,
+    indirect::{IndAlpha, IndBias, IndFormat, IndMatrixSelect, IndScale, IndWrap},
+    pe::{AlphaOp, BlendFactor, CompareFn, FogType, LogicOp, PixelFormat, ZFormat},
+    tev::{
+        AlphaInput, Bias, ColorInput, CompareOp, KonstSelect, RasChannel, Scale, SwapChannel,
+        TevOp, TevRegister,
+    },
+    texture::{Anisotropy, CacheSize, TextureFilter, TextureFormat, TlutFormat, WrapMode},
+    xf::ProjectionType,
+};
+
+/// A blitting processor register.
+///
+/// BP loads carry the register address in the top byte of the word, so every register here keeps
+/// it in bits 24..=31 and `raw` is exactly what goes down the pipe.
+pub trait BpRegister: Copy {
+    fn raw(self) -> u32;
+
+    fn address(self) -> u8 {
+        self.raw().get_bits(24..=31).try_into().unwrap()
+    }
+
+    fn write(self, sink: &mut impl CommandSink) {
+        sink.write_bp(self);
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum CullMode {
+    None,
+    Back,
+    Front,
+    All,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidCullModeError;
+
+impl TryFrom<u32> for CullMode {
+    type Error = InvalidCullModeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Back),
+            2 => Ok(Self::Front),
+            3 => Ok(Self::All),
+            _ => Err(InvalidCullModeError),
+        }
+    }
+}
+
+impl From<CullMode> for u32 {
+    fn from(value: CullMode) -> Self {
+        match value {
+            CullMode::None => 0,
+            CullMode::Back => 1,
+            CullMode::Front => 2,
+            CullMode::All => 3,
+        }
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct GenMode(u32);
+
+impl GenMode {
+    pub const ADDRESS: u8 = 0x00;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn num_tex_gens(&self) -> u8 {
+        self.0.get_bits(0..=3).try_into().unwrap()
+    }
+
+    pub fn with_num_tex_gens(&mut self, count: u8) -> &mut Self {
+        self.0.set_bits(0..=3, count.into());
+        self
+    }
+
+    pub fn num_color_channels(&self) -> u8 {
+        self.0.get_bits(4..=6).try_into().unwrap()
+    }
+
+    pub fn with_num_color_channels(&mut self, count: u8) -> &mut Self {
+        self.0.set_bits(4..=6, count.into());
+        self
+    }
+
+    pub fn multisampling(&self) -> bool {
+        self.0.get_bit(9)
+    }
+
+    pub fn with_multisampling(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(9, enable);
+        self
+    }
+
+    /// Number of active TEV stages, the register itself stores `count - 1`.
+    pub fn num_tev_stages(&self) -> u8 {
+        u8::try_from(self.0.get_bits(10..=13)).unwrap() + 1
+    }
+
+    pub fn with_num_tev_stages(&mut self, count: u8) -> &mut Self {
+        assert!(
+            (1..=16).contains(&count),
+            "GX has between 1 and 16 TEV stages"
+        );
+        self.0.set_bits(10..=13, u32::from(count - 1));
+        self
+    }
+
+    pub fn cull_mode(&self) -> CullMode {
+        self.0.get_bits(14..=15).try_into().unwrap()
+    }
+
+    pub fn with_cull_mode(&mut self, mode: CullMode) -> &mut Self {
+        self.0.set_bits(14..=15, mode.into());
+        self
+    }
+
+    pub fn num_indirect_stages(&self) -> u8 {
+        self.0.get_bits(16..=18).try_into().unwrap()
+    }
+
+    pub fn with_num_indirect_stages(&mut self, count: u8) -> &mut Self {
+        self.0.set_bits(16..=18, count.into());
+        self
+    }
+
+    pub fn z_freeze(&self) -> bool {
+        self.0.get_bit(19)
+    }
+
+    pub fn with_z_freeze(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(19, enable);
+        self
+    }
+}
+
+impl Default for GenMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for GenMode {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<GenMode> for u32 {
+    fn from(value: GenMode) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for GenMode {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// One of the four registers holding the 12 multisample locations, three per register.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct SamplePattern(u32);
+
+impl SamplePattern {
+    pub const ADDRESS: u8 = 0x01;
+
+    pub fn new(index: u8) -> Self {
+        assert!(index < 4, "There are only 4 sample pattern registers");
+        Self(u32::from(Self::ADDRESS + index) << 24)
+    }
+
+    pub fn sample(&self, slot: usize) -> (u8, u8) {
+        assert!(slot < 3, "Each sample pattern register holds 3 samples");
+        let base = slot * 8;
+        (
+            self.0.get_bits(base..base + 4).try_into().unwrap(),
+            self.0.get_bits(base + 4..base + 8).try_into().unwrap(),
+        )
+    }
+
+    pub fn with_sample(&mut self, slot: usize, x: u8, y: u8) -> &mut Self {
+        assert!(slot < 3, "Each sample pattern register holds 3 samples");
+        let base = slot * 8;
+        self.0.set_bits(base..base + 4, x.into());
+        self.0.set_bits(base + 4..base + 8, y.into());
+        self
+    }
+}
+
+impl From<u32> for SamplePattern {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<SamplePattern> for u32 {
+    fn from(value: SamplePattern) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for SamplePattern {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ScissorTopLeft(u32);
+
+impl ScissorTopLeft {
+    pub const ADDRESS: u8 = 0x20;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn top(&self) -> u16 {
+        self.0.get_bits(0..=10).try_into().unwrap()
+    }
+
+    pub fn with_top(&mut self, top: u16) -> &mut Self {
+        self.0.set_bits(0..=10, top.into());
+        self
+    }
+
+    pub fn left(&self) -> u16 {
+        self.0.get_bits(12..=22).try_into().unwrap()
+    }
+
+    pub fn with_left(&mut self, left: u16) -> &mut Self {
+        self.0.set_bits(12..=22, left.into());
+        self
+    }
+}
+
+impl Default for ScissorTopLeft {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for ScissorTopLeft {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ScissorTopLeft> for u32 {
+    fn from(value: ScissorTopLeft) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for ScissorTopLeft {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ScissorBottomRight(u32);
+
+impl ScissorBottomRight {
+    pub const ADDRESS: u8 = 0x21;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn bottom(&self) -> u16 {
+        self.0.get_bits(0..=10).try_into().unwrap()
+    }
+
+    pub fn with_bottom(&mut self, bottom: u16) -> &mut Self {
+        self.0.set_bits(0..=10, bottom.into());
+        self
+    }
+
+    pub fn right(&self) -> u16 {
+        self.0.get_bits(12..=22).try_into().unwrap()
+    }
+
+    pub fn with_right(&mut self, right: u16) -> &mut Self {
+        self.0.set_bits(12..=22, right.into());
+        self
+    }
+}
+
+impl Default for ScissorBottomRight {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for ScissorBottomRight {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ScissorBottomRight> for u32 {
+    fn from(value: ScissorBottomRight) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for ScissorBottomRight {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct SuLpSize(u32);
+
+impl SuLpSize {
+    pub const ADDRESS: u8 = 0x22;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn line_width(&self) -> u8 {
+        self.0.get_bits(0..=7).try_into().unwrap()
+    }
+
+    pub fn with_line_width(&mut self, width: u8) -> &mut Self {
+        self.0.set_bits(0..=7, width.into());
+        self
+    }
+
+    pub fn point_size(&self) -> u8 {
+        self.0.get_bits(8..=15).try_into().unwrap()
+    }
+
+    pub fn with_point_size(&mut self, size: u8) -> &mut Self {
+        self.0.set_bits(8..=15, size.into());
+        self
+    }
+
+    pub fn line_offset(&self) -> u8 {
+        self.0.get_bits(16..=18).try_into().unwrap()
+    }
+
+    pub fn with_line_offset(&mut self, offset: u8) -> &mut Self {
+        self.0.set_bits(16..=18, offset.into());
+        self
+    }
+
+    pub fn point_offset(&self) -> u8 {
+        self.0.get_bits(19..=21).try_into().unwrap()
+    }
+
+    pub fn with_point_offset(&mut self, offset: u8) -> &mut Self {
+        self.0.set_bits(19..=21, offset.into());
+        self
+    }
+
+    pub fn line_aspect_ratio(&self) -> bool {
+        self.0.get_bit(22)
+    }
+
+    pub fn with_line_aspect_ratio(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(22, enable);
+        self
+    }
+}
+
+impl Default for SuLpSize {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for SuLpSize {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<SuLpSize> for u32 {
+    fn from(value: SuLpSize) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for SuLpSize {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct EfbCopySourceTopLeft(u32);
+
+impl EfbCopySourceTopLeft {
+    pub const ADDRESS: u8 = 0x49;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn left(&self) -> u16 {
+        self.0.get_bits(0..=9).try_into().unwrap()
+    }
+
+    pub fn with_left(&mut self, left: u16) -> &mut Self {
+        self.0.set_bits(0..=9, left.into());
+        self
+    }
+
+    pub fn top(&self) -> u16 {
+        self.0.get_bits(10..=19).try_into().unwrap()
+    }
+
+    pub fn with_top(&mut self, top: u16) -> &mut Self {
+        self.0.set_bits(10..=19, top.into());
+        self
+    }
+}
+
+impl Default for EfbCopySourceTopLeft {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for EfbCopySourceTopLeft {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<EfbCopySourceTopLeft> for u32 {
+    fn from(value: EfbCopySourceTopLeft) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for EfbCopySourceTopLeft {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Size of the EFB copy source rectangle, the register itself stores `size - 1`.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct EfbCopySourceSize(u32);
+
+impl EfbCopySourceSize {
+    pub const ADDRESS: u8 = 0x4A;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn width(&self) -> u16 {
+        u16::try_from(self.0.get_bits(0..=9)).unwrap() + 1
+    }
+
+    pub fn with_width(&mut self, width: u16) -> &mut Self {
+        assert!(
+            (1..=1024).contains(&width),
+            "EFB copies are 1 to 1024 pixels wide"
+        );
+        self.0.set_bits(0..=9, u32::from(width - 1));
+        self
+    }
+
+    pub fn height(&self) -> u16 {
+        u16::try_from(self.0.get_bits(10..=19)).unwrap() + 1
+    }
+
+    pub fn with_height(&mut self, height: u16) -> &mut Self {
+        assert!(
+            (1..=1024).contains(&height),
+            "EFB copies are 1 to 1024 pixels high"
+        );
+        self.0.set_bits(10..=19, u32::from(height - 1));
+        self
+    }
+}
+
+impl Default for EfbCopySourceSize {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for EfbCopySourceSize {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<EfbCopySourceSize> for u32 {
+    fn from(value: EfbCopySourceSize) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for EfbCopySourceSize {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct EfbCopyDestination(u32);
+
+impl EfbCopyDestination {
+    pub const ADDRESS: u8 = 0x4B;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    /// Physical address of the copy destination.
+    pub fn addr(&self) -> usize {
+        usize::try_from(self.0.get_bits(0..=23)).unwrap() << 5
+    }
+
+    pub fn with_addr(&mut self, physical_addr: Physical<u8>) -> &mut Self {
+        let addr = u32::try_from(physical_addr.addr()).unwrap();
+        assert!(
+            addr % 32 == 0,
+            "EFB copy destinations must be 32 byte aligned"
+        );
+        self.0.set_bits(0..=23, addr >> 5);
+        self
+    }
+}
+
+impl Default for EfbCopyDestination {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for EfbCopyDestination {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<EfbCopyDestination> for u32 {
+    fn from(value: EfbCopyDestination) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for EfbCopyDestination {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Distance between destination lines of an EFB copy, stored in 32 byte units.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct EfbCopyStride(u32);
+
+impl EfbCopyStride {
+    pub const ADDRESS: u8 = 0x4D;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn stride(&self) -> usize {
+        usize::try_from(self.0.get_bits(0..=9)).unwrap() << 5
+    }
+
+    pub fn with_stride(&mut self, bytes: usize) -> &mut Self {
+        self.0.set_bits(0..=9, u32::try_from(bytes >> 5).unwrap());
+        self
+    }
+}
+
+impl Default for EfbCopyStride {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for EfbCopyStride {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<EfbCopyStride> for u32 {
+    fn from(value: EfbCopyStride) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for EfbCopyStride {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Vertical scale applied to display copies, as `256 / scale` in 1.8 fixed point.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct EfbCopyYScale(u32);
+
+impl EfbCopyYScale {
+    pub const ADDRESS: u8 = 0x4E;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn y_scale(&self) -> u16 {
+        self.0.get_bits(0..=8).try_into().unwrap()
+    }
+
+    pub fn with_y_scale(&mut self, y_scale: u16) -> &mut Self {
+        self.0.set_bits(0..=8, y_scale.into());
+        self
+    }
+}
+
+impl Default for EfbCopyYScale {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for EfbCopyYScale {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<EfbCopyYScale> for u32 {
+    fn from(value: EfbCopyYScale) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for EfbCopyYScale {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct CopyClearAr(u32);
+
+impl CopyClearAr {
+    pub const ADDRESS: u8 = 0x4F;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn red(&self) -> u8 {
+        self.0.get_bits(0..=7).try_into().unwrap()
+    }
+
+    pub fn with_red(&mut self, red: u8) -> &mut Self {
+        self.0.set_bits(0..=7, red.into());
+        self
+    }
+
+    pub fn alpha(&self) -> u8 {
+        self.0.get_bits(8..=15).try_into().unwrap()
+    }
+
+    pub fn with_alpha(&mut self, alpha: u8) -> &mut Self {
+        self.0.set_bits(8..=15, alpha.into());
+        self
+    }
+}
+
+impl Default for CopyClearAr {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for CopyClearAr {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<CopyClearAr> for u32 {
+    fn from(value: CopyClearAr) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for CopyClearAr {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct CopyClearGb(u32);
+
+impl CopyClearGb {
+    pub const ADDRESS: u8 = 0x50;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn blue(&self) -> u8 {
+        self.0.get_bits(0..=7).try_into().unwrap()
+    }
+
+    pub fn with_blue(&mut self, blue: u8) -> &mut Self {
+        self.0.set_bits(0..=7, blue.into());
+        self
+    }
+
+    pub fn green(&self) -> u8 {
+        self.0.get_bits(8..=15).try_into().unwrap()
+    }
+
+    pub fn with_green(&mut self, green: u8) -> &mut Self {
+        self.0.set_bits(8..=15, green.into());
+        self
+    }
+}
+
+impl Default for CopyClearGb {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for CopyClearGb {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<CopyClearGb> for u32 {
+    fn from(value: CopyClearGb) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for CopyClearGb {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct CopyClearZ(u32);
+
+impl CopyClearZ {
+    pub const ADDRESS: u8 = 0x51;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn z(&self) -> u32 {
+        self.0.get_bits(0..=23)
+    }
+
+    pub fn with_z(&mut self, z: u32) -> &mut Self {
+        self.0.set_bits(0..=23, z);
+        self
+    }
+}
+
+impl Default for CopyClearZ {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for CopyClearZ {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<CopyClearZ> for u32 {
+    fn from(value: CopyClearZ) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for CopyClearZ {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Gamma {
+    One,
+    OnePointSeven,
+    TwoPointTwo,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidGammaError;
+
+impl TryFrom<u32> for Gamma {
+    type Error = InvalidGammaError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::One),
+            1 => Ok(Self::OnePointSeven),
+            2 => Ok(Self::TwoPointTwo),
+            _ => Err(InvalidGammaError),
+        }
+    }
+}
+
+impl From<Gamma> for u32 {
+    fn from(value: Gamma) -> Self {
+        match value {
+            Gamma::One => 0,
+            Gamma::OnePointSeven => 1,
+            Gamma::TwoPointTwo => 2,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum FrameToField {
+    Progressive,
+    InterlacedEven,
+    InterlacedOdd,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidFrameToFieldError;
+
+impl TryFrom<u32> for FrameToField {
+    type Error = InvalidFrameToFieldError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Progressive),
+            2 => Ok(Self::InterlacedEven),
+            3 => Ok(Self::InterlacedOdd),
+            _ => Err(InvalidFrameToFieldError),
+        }
+    }
+}
+
+impl From<FrameToField> for u32 {
+    fn from(value: FrameToField) -> Self {
+        match value {
+            FrameToField::Progressive => 0,
+            FrameToField::InterlacedEven => 2,
+            FrameToField::InterlacedOdd => 3,
+        }
+    }
+}
+
+/// Writing this register is what actually starts an EFB copy.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct EfbCopyControl(u32);
+
+impl EfbCopyControl {
+    pub const ADDRESS: u8 = 0x52;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn clamp_top(&self) -> bool {
+        self.0.get_bit(0)
+    }
+
+    pub fn with_clamp_top(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(0, enable);
+        self
+    }
+
+    pub fn clamp_bottom(&self) -> bool {
+        self.0.get_bit(1)
+    }
+
+    pub fn with_clamp_bottom(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(1, enable);
+        self
+    }
+
+    /// Destination texture format, unswizzled from the way the hardware stores it.
+    pub fn texture_format(&self) -> u8 {
+        let raw: u8 = self.0.get_bits(3..=6).try_into().unwrap();
+        (raw & 1) << 3 | raw >> 1
+    }
+
+    pub fn with_texture_format(&mut self, format: u8) -> &mut Self {
+        assert!(format < 16, "Copy texture formats are 4 bits");
+        let raw = (format & 0x7) << 1 | format >> 3;
+        self.0.set_bits(3..=6, raw.into());
+        self
+    }
+
+    pub fn gamma(&self) -> Gamma {
+        self.0.get_bits(7..=8).try_into().unwrap()
+    }
+
+    pub fn with_gamma(&mut self, gamma: Gamma) -> &mut Self {
+        self.0.set_bits(7..=8, gamma.into());
+        self
+    }
+
+    pub fn half_scale(&self) -> bool {
+        self.0.get_bit(9)
+    }
+
+    pub fn with_half_scale(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(9, enable);
+        self
+    }
+
+    pub fn y_scale(&self) -> bool {
+        self.0.get_bit(10)
+    }
+
+    pub fn with_y_scale(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(10, enable);
+        self
+    }
+
+    pub fn clear(&self) -> bool {
+        self.0.get_bit(11)
+    }
+
+    pub fn with_clear(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(11, enable);
+        self
+    }
+
+    pub fn frame_to_field(&self) -> FrameToField {
+        self.0.get_bits(12..=13).try_into().unwrap()
+    }
+
+    pub fn with_frame_to_field(&mut self, mode: FrameToField) -> &mut Self {
+        self.0.set_bits(12..=13, mode.into());
+        self
+    }
+
+    pub fn copy_to_xfb(&self) -> bool {
+        self.0.get_bit(14)
+    }
+
+    pub fn with_copy_to_xfb(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(14, enable);
+        self
+    }
+
+    pub fn intensity_format(&self) -> bool {
+        self.0.get_bit(15)
+    }
+
+    pub fn with_intensity_format(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(15, enable);
+        self
+    }
+
+    pub fn auto_convert(&self) -> bool {
+        self.0.get_bit(16)
+    }
+
+    pub fn with_auto_convert(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(16, enable);
+        self
+    }
+}
+
+impl Default for EfbCopyControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for EfbCopyControl {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<EfbCopyControl> for u32 {
+    fn from(value: EfbCopyControl) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for EfbCopyControl {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Vertical filter coefficients used by display copies, 0..=3 live in the first register and
+/// 4..=6 in the second.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct CopyFilter(u32);
+
+impl CopyFilter {
+    pub const ADDRESS: u8 = 0x53;
+
+    pub fn new(index: u8) -> Self {
+        assert!(index < 2, "There are only 2 copy filter registers");
+        Self(u32::from(Self::ADDRESS + index) << 24)
+    }
+
+    pub fn coefficient(&self, slot: usize) -> u8 {
+        assert!(slot < 4, "Each copy filter register holds 4 coefficients");
+        self.0.get_bits(slot * 6..slot * 6 + 6).try_into().unwrap()
+    }
+
+    pub fn with_coefficient(&mut self, slot: usize, coefficient: u8) -> &mut Self {
+        assert!(slot < 4, "Each copy filter register holds 4 coefficients");
+        self.0.set_bits(slot * 6..slot * 6 + 6, coefficient.into());
+        self
+    }
+}
+
+impl From<u32> for CopyFilter {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<CopyFilter> for u32 {
+    fn from(value: CopyFilter) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for CopyFilter {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Screen offset added to the scissor box, stored in 2 pixel units.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ScissorOffset(u32);
+
+impl ScissorOffset {
+    pub const ADDRESS: u8 = 0x59;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn x(&self) -> u16 {
+        self.0.get_bits(0..=9).try_into().unwrap()
+    }
+
+    pub fn with_x(&mut self, x: u16) -> &mut Self {
+        self.0.set_bits(0..=9, x.into());
+        self
+    }
+
+    pub fn y(&self) -> u16 {
+        self.0.get_bits(10..=19).try_into().unwrap()
+    }
+
+    pub fn with_y(&mut self, y: u16) -> &mut Self {
+        self.0.set_bits(10..=19, y.into());
+        self
+    }
+}
+
+impl Default for ScissorOffset {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for ScissorOffset {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ScissorOffset> for u32 {
+    fn from(value: ScissorOffset) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for ScissorOffset {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Only the bits set here are changed by the next BP load, it resets to all ones after that load.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct BpMask(u32);
+
+impl BpMask {
+    pub const ADDRESS: u8 = 0xFE;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn mask(&self) -> u32 {
+        self.0.get_bits(0..=23)
+    }
+
+    pub fn with_mask(&mut self, mask: u32) -> &mut Self {
+        self.0.set_bits(0..=23, mask);
+        self
+    }
+}
+
+impl Default for BpMask {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for BpMask {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<BpMask> for u32 {
+    fn from(value: BpMask) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for BpMask {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Texture map, texture coordinate and rasterized color used by a pair of TEV stages.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TevStageOrders(u32);
+
+impl TevStageOrders {
+    pub const ADDRESS: u8 = 0x28;
+
+    /// The register holding the orders of stages `2 * index` and `2 * index + 1`.
+    pub fn new(index: u8) -> Self {
+        assert!(index < 8, "There are only 8 TEV order registers");
+        Self(u32::from(Self::ADDRESS + index) << 24)
+    }
+
+    pub fn tex_map(&self, odd: bool) -> u8 {
+        let base = usize::from(odd) * 12;
+        self.0.get_bits(base..base + 3).try_into().unwrap()
+    }
+
+    pub fn with_tex_map(&mut self, odd: bool, map: u8) -> &mut Self {
+        let base = usize::from(odd) * 12;
+        self.0.set_bits(base..base + 3, map.into());
+        self
+    }
+
+    pub fn tex_coord(&self, odd: bool) -> u8 {
+        let base = usize::from(odd) * 12 + 3;
+        self.0.get_bits(base..base + 3).try_into().unwrap()
+    }
+
+    pub fn with_tex_coord(&mut self, odd: bool, coord: u8) -> &mut Self {
+        let base = usize::from(odd) * 12 + 3;
+        self.0.set_bits(base..base + 3, coord.into());
+        self
+    }
+
+    pub fn tex_enable(&self, odd: bool) -> bool {
+        self.0.get_bit(usize::from(odd) * 12 + 6)
+    }
+
+    pub fn with_tex_enable(&mut self, odd: bool, enable: bool) -> &mut Self {
+        self.0.set_bit(usize::from(odd) * 12 + 6, enable);
+        self
+    }
+
+    pub fn channel(&self, odd: bool) -> RasChannel {
+        let base = usize::from(odd) * 12 + 7;
+        self.0.get_bits(base..base + 3).try_into().unwrap()
+    }
+
+    pub fn with_channel(&mut self, odd: bool, channel: RasChannel) -> &mut Self {
+        let base = usize::from(odd) * 12 + 7;
+        self.0.set_bits(base..base + 3, channel.into());
+        self
+    }
+}
+
+impl From<u32> for TevStageOrders {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TevStageOrders> for u32 {
+    fn from(value: TevStageOrders) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TevStageOrders {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TevColorEnv(u32);
+
+impl TevColorEnv {
+    pub const ADDRESS: u8 = 0xC0;
+
+    pub fn new(stage: u8) -> Self {
+        assert!(stage < 16, "There are only 16 TEV stages");
+        Self(u32::from(Self::ADDRESS + stage * 2) << 24)
+    }
+
+    pub fn d(&self) -> ColorInput {
+        self.0.get_bits(0..=3).try_into().unwrap()
+    }
+
+    pub fn with_d(&mut self, input: ColorInput) -> &mut Self {
+        self.0.set_bits(0..=3, input.into());
+        self
+    }
+
+    pub fn c(&self) -> ColorInput {
+        self.0.get_bits(4..=7).try_into().unwrap()
+    }
+
+    pub fn with_c(&mut self, input: ColorInput) -> &mut Self {
+        self.0.set_bits(4..=7, input.into());
+        self
+    }
+
+    pub fn b(&self) -> ColorInput {
+        self.0.get_bits(8..=11).try_into().unwrap()
+    }
+
+    pub fn with_b(&mut self, input: ColorInput) -> &mut Self {
+        self.0.set_bits(8..=11, input.into());
+        self
+    }
+
+    pub fn a(&self) -> ColorInput {
+        self.0.get_bits(12..=15).try_into().unwrap()
+    }
+
+    pub fn with_a(&mut self, input: ColorInput) -> &mut Self {
+        self.0.set_bits(12..=15, input.into());
+        self
+    }
+
+    /// Bias, subtract and scale together, which is how compare mode is encoded.
+    pub fn op(&self) -> (TevOp, Bias, Scale) {
+        decode_tev_op(self.0)
+    }
+
+    pub fn with_op(&mut self, op: TevOp, bias: Bias, scale: Scale) -> &mut Self {
+        encode_tev_op(&mut self.0, op, bias, scale);
+        self
+    }
+
+    pub fn clamp(&self) -> bool {
+        self.0.get_bit(19)
+    }
+
+    pub fn with_clamp(&mut self, clamp: bool) -> &mut Self {
+        self.0.set_bit(19, clamp);
+        self
+    }
+
+    pub fn dest(&self) -> TevRegister {
+        self.0.get_bits(22..=23).try_into().unwrap()
+    }
+
+    pub fn with_dest(&mut self, dest: TevRegister) -> &mut Self {
+        self.0.set_bits(22..=23, dest.into());
+        self
+    }
+}
+
+impl From<u32> for TevColorEnv {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TevColorEnv> for u32 {
+    fn from(value: TevColorEnv) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TevColorEnv {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TevAlphaEnv(u32);
+
+impl TevAlphaEnv {
+    pub const ADDRESS: u8 = 0xC1;
+
+    pub fn new(stage: u8) -> Self {
+        assert!(stage < 16, "There are only 16 TEV stages");
+        Self(u32::from(Self::ADDRESS + stage * 2) << 24)
+    }
+
+    pub fn ras_swap(&self) -> u8 {
+        self.0.get_bits(0..=1).try_into().unwrap()
+    }
+
+    pub fn with_ras_swap(&mut self, table: u8) -> &mut Self {
+        self.0.set_bits(0..=1, table.into());
+        self
+    }
+
+    pub fn tex_swap(&self) -> u8 {
+        self.0.get_bits(2..=3).try_into().unwrap()
+    }
+
+    pub fn with_tex_swap(&mut self, table: u8) -> &mut Self {
+        self.0.set_bits(2..=3, table.into());
+        self
+    }
+
+    pub fn d(&self) -> AlphaInput {
+        self.0.get_bits(4..=6).try_into().unwrap()
+    }
+
+    pub fn with_d(&mut self, input: AlphaInput) -> &mut Self {
+        self.0.set_bits(4..=6, input.into());
+        self
+    }
+
+    pub fn c(&self) -> AlphaInput {
+        self.0.get_bits(7..=9).try_into().unwrap()
+    }
+
+    pub fn with_c(&mut self, input: AlphaInput) -> &mut Self {
+        self.0.set_bits(7..=9, input.into());
+        self
+    }
+
+    pub fn b(&self) -> AlphaInput {
+        self.0.get_bits(10..=12).try_into().unwrap()
+    }
+
+    pub fn with_b(&mut self, input: AlphaInput) -> &mut Self {
+        self.0.set_bits(10..=12, input.into());
+        self
+    }
+
+    pub fn a(&self) -> AlphaInput {
+        self.0.get_bits(13..=15).try_into().unwrap()
+    }
+
+    pub fn with_a(&mut self, input: AlphaInput) -> &mut Self {
+        self.0.set_bits(13..=15, input.into());
+        self
+    }
+
+    /// Bias, subtract and scale together, which is how compare mode is encoded.
+    pub fn op(&self) -> (TevOp, Bias, Scale) {
+        decode_tev_op(self.0)
+    }
+
+    pub fn with_op(&mut self, op: TevOp, bias: Bias, scale: Scale) -> &mut Self {
+        encode_tev_op(&mut self.0, op, bias, scale);
+        self
+    }
+
+    pub fn clamp(&self) -> bool {
+        self.0.get_bit(19)
+    }
+
+    pub fn with_clamp(&mut self, clamp: bool) -> &mut Self {
+        self.0.set_bit(19, clamp);
+        self
+    }
+
+    pub fn dest(&self) -> TevRegister {
+        self.0.get_bits(22..=23).try_into().unwrap()
+    }
+
+    pub fn with_dest(&mut self, dest: TevRegister) -> &mut Self {
+        self.0.set_bits(22..=23, dest.into());
+        self
+    }
+}
+
+impl From<u32> for TevAlphaEnv {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TevAlphaEnv> for u32 {
+    fn from(value: TevAlphaEnv) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TevAlphaEnv {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+fn encode_tev_op(raw: &mut u32, op: TevOp, bias: Bias, scale: Scale) {
+    match op {
+        TevOp::Add | TevOp::Subtract => {
+            raw.set_bits(16..=17, bias.into());
+            raw.set_bit(18, op == TevOp::Subtract);
+            raw.set_bits(20..=21, scale.into());
+        }
+        TevOp::Compare(target, compare) => {
+            raw.set_bits(16..=17, 3);
+            raw.set_bit(18, compare == CompareOp::Equal);
+            raw.set_bits(20..=21, target.into());
+        }
+    }
+}
+
+fn decode_tev_op(raw: u32) -> (TevOp, Bias, Scale) {
+    let subtract = raw.get_bit(18);
+    let scale = raw.get_bits(20..=21);
+    match raw.get_bits(16..=17) {
+        3 => {
+            let compare = if subtract {
+                CompareOp::Equal
+            } else {
+                CompareOp::Greater
+            };
+            (
+                TevOp::Compare(scale.try_into().unwrap(), compare),
+                Bias::Zero,
+                Scale::One,
+            )
+        }
+        bias => {
+            let op = if subtract {
+                TevOp::Subtract
+            } else {
+                TevOp::Add
+            };
+            (op, bias.try_into().unwrap(), scale.try_into().unwrap())
+        }
+    }
+}
+
+/// Red and alpha of a TEV color register, or of a konst color when `konst` is set.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TevColorRa(u32);
+
+impl TevColorRa {
+    pub const ADDRESS: u8 = 0xE0;
+
+    pub fn new(index: u8) -> Self {
+        assert!(index < 4, "There are only 4 TEV color registers");
+        Self(u32::from(Self::ADDRESS + index * 2) << 24)
+    }
+
+    pub fn red(&self) -> i16 {
+        sign_extend_11(self.0.get_bits(0..=10))
+    }
+
+    pub fn with_red(&mut self, red: i16) -> &mut Self {
+        self.0.set_bits(0..=10, truncate_11(red));
+        self
+    }
+
+    pub fn alpha(&self) -> i16 {
+        sign_extend_11(self.0.get_bits(12..=22))
+    }
+
+    pub fn with_alpha(&mut self, alpha: i16) -> &mut Self {
+        self.0.set_bits(12..=22, truncate_11(alpha));
+        self
+    }
+
+    pub fn konst(&self) -> bool {
+        self.0.get_bit(23)
+    }
+
+    pub fn with_konst(&mut self, konst: bool) -> &mut Self {
+        self.0.set_bit(23, konst);
+        self
+    }
+}
+
+impl From<u32> for TevColorRa {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TevColorRa> for u32 {
+    fn from(value: TevColorRa) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TevColorRa {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Blue and green of a TEV color register, or of a konst color when `konst` is set.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TevColorBg(u32);
+
+impl TevColorBg {
+    pub const ADDRESS: u8 = 0xE1;
+
+    pub fn new(index: u8) -> Self {
+        assert!(index < 4, "There are only 4 TEV color registers");
+        Self(u32::from(Self::ADDRESS + index * 2) << 24)
+    }
+
+    pub fn blue(&self) -> i16 {
+        sign_extend_11(self.0.get_bits(0..=10))
+    }
+
+    pub fn with_blue(&mut self, blue: i16) -> &mut Self {
+        self.0.set_bits(0..=10, truncate_11(blue));
+        self
+    }
+
+    pub fn green(&self) -> i16 {
+        sign_extend_11(self.0.get_bits(12..=22))
+    }
+
+    pub fn with_green(&mut self, green: i16) -> &mut Self {
+        self.0.set_bits(12..=22, truncate_11(green));
+        self
+    }
+
+    pub fn konst(&self) -> bool {
+        self.0.get_bit(23)
+    }
+
+    pub fn with_konst(&mut self, konst: bool) -> &mut Self {
+        self.0.set_bit(23, konst);
+        self
+    }
+}
+
+impl From<u32> for TevColorBg {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TevColorBg> for u32 {
+    fn from(value: TevColorBg) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TevColorBg {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+fn truncate_11(value: i16) -> u32 {
+    assert!(
+        (-1024..=1023).contains(&value),
+        "TEV colors are 11 bit signed values"
+    );
+    u32::from(u16::from_be_bytes(value.to_be_bytes())) & 0x7FF
+}
+
+fn sign_extend_11(value: u32) -> i16 {
+    let value = i16::try_from(value).unwrap();
+    (value << 5) >> 5
+}
+
+/// Konst selections for a pair of TEV stages plus half of a swap table.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TevKSel(u32);
+
+impl TevKSel {
+    pub const ADDRESS: u8 = 0xF6;
+
+    pub fn new(index: u8) -> Self {
+        assert!(index < 8, "There are only 8 TEV konst selection registers");
+        Self(u32::from(Self::ADDRESS + index) << 24)
+    }
+
+    /// Red (or blue, in odd registers) entry of the swap table this register belongs to.
+    pub fn swap_rb(&self) -> SwapChannel {
+        self.0.get_bits(0..=1).try_into().unwrap()
+    }
+
+    pub fn with_swap_rb(&mut self, channel: SwapChannel) -> &mut Self {
+        self.0.set_bits(0..=1, channel.into());
+        self
+    }
+
+    /// Green (or alpha, in odd registers) entry of the swap table this register belongs to.
+    pub fn swap_ga(&self) -> SwapChannel {
+        self.0.get_bits(2..=3).try_into().unwrap()
+    }
+
+    pub fn with_swap_ga(&mut self, channel: SwapChannel) -> &mut Self {
+        self.0.set_bits(2..=3, channel.into());
+        self
+    }
+
+    pub fn konst_color(&self, odd: bool) -> KonstSelect {
+        let base = 4 + usize::from(odd) * 10;
+        self.0.get_bits(base..base + 5).try_into().unwrap()
+    }
+
+    pub fn with_konst_color(&mut self, odd: bool, select: KonstSelect) -> &mut Self {
+        let base = 4 + usize::from(odd) * 10;
+        self.0.set_bits(base..base + 5, select.into());
+        self
+    }
+
+    pub fn konst_alpha(&self, odd: bool) -> KonstSelect {
+        let base = 9 + usize::from(odd) * 10;
+        self.0.get_bits(base..base + 5).try_into().unwrap()
+    }
+
+    pub fn with_konst_alpha(&mut self, odd: bool, select: KonstSelect) -> &mut Self {
+        assert!(
+            !matches!(select, KonstSelect::Color(_)),
+            "Alpha can't select a whole konst color"
+        );
+        let base = 9 + usize::from(odd) * 10;
+        self.0.set_bits(base..base + 5, select.into());
+        self
+    }
+}
+
+impl From<u32> for TevKSel {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TevKSel> for u32 {
+    fn from(value: TevKSel) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TevKSel {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Texture registers come in two banks of four, maps 4..8 live 0x20 above maps 0..4.
+fn tex_map_address(base: u8, map: u8) -> u8 {
+    assert!(map < 8, "There are only 8 texture maps");
+    base + map % 4 + (map / 4) * 0x20
+}
+
+/// Main memory address of a TLUT about to be loaded into TMEM.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TlutLoadSource(u32);
+
+impl TlutLoadSource {
+    pub const ADDRESS: u8 = 0x64;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn addr(&self) -> usize {
+        usize::try_from(self.0.get_bits(0..=23)).unwrap() << 5
+    }
+
+    pub fn with_addr(&mut self, physical_addr: u32) -> &mut Self {
+        assert!(physical_addr % 32 == 0, "TLUTs must be 32 byte aligned");
+        self.0.set_bits(0..=23, physical_addr >> 5);
+        self
+    }
+}
+
+impl Default for TlutLoadSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for TlutLoadSource {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TlutLoadSource> for u32 {
+    fn from(value: TlutLoadSource) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TlutLoadSource {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Writing this register starts a TLUT load from the address in `TlutLoadSource`.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TlutLoadDestination(u32);
+
+impl TlutLoadDestination {
+    pub const ADDRESS: u8 = 0x65;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    /// TMEM address, TLUTs always live in the upper half of TMEM.
+    pub fn tmem_addr(&self) -> u32 {
+        (self.0.get_bits(0..=9) << 9) + 0x80000
+    }
+
+    pub fn with_tmem_addr(&mut self, tmem_addr: u32) -> &mut Self {
+        assert!(
+            tmem_addr >= 0x80000 && tmem_addr % 512 == 0,
+            "TLUTs must be 512 byte aligned in the upper half of TMEM"
+        );
+        self.0.set_bits(0..=9, (tmem_addr - 0x80000) >> 9);
+        self
+    }
+
+    /// Number of 16 entry lines to load.
+    pub fn lines(&self) -> u16 {
+        self.0.get_bits(10..=20).try_into().unwrap()
+    }
+
+    pub fn with_lines(&mut self, lines: u16) -> &mut Self {
+        self.0.set_bits(10..=20, lines.into());
+        self
+    }
+}
+
+impl Default for TlutLoadDestination {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for TlutLoadDestination {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TlutLoadDestination> for u32 {
+    fn from(value: TlutLoadDestination) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TlutLoadDestination {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Invalidates `2^size` lines of the texture cache starting at `line`.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexCacheInvalidate(u32);
+
+impl TexCacheInvalidate {
+    pub const ADDRESS: u8 = 0x66;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn line(&self) -> u16 {
+        self.0.get_bits(0..=8).try_into().unwrap()
+    }
+
+    pub fn with_line(&mut self, line: u16) -> &mut Self {
+        self.0.set_bits(0..=8, line.into());
+        self
+    }
+
+    pub fn size(&self) -> u8 {
+        self.0.get_bits(9..=12).try_into().unwrap()
+    }
+
+    pub fn with_size(&mut self, size: u8) -> &mut Self {
+        self.0.set_bits(9..=12, size.into());
+        self
+    }
+}
+
+impl Default for TexCacheInvalidate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for TexCacheInvalidate {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TexCacheInvalidate> for u32 {
+    fn from(value: TexCacheInvalidate) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TexCacheInvalidate {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Wrapping and filtering of a texture map.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexMode0(u32);
+
+impl TexMode0 {
+    pub const ADDRESS: u8 = 0x80;
+
+    pub fn new(map: u8) -> Self {
+        Self(u32::from(tex_map_address(Self::ADDRESS, map)) << 24)
+    }
+
+    pub fn wrap_s(&self) -> WrapMode {
+        self.0.get_bits(0..=1).try_into().unwrap()
+    }
+
+    pub fn with_wrap_s(&mut self, wrap: WrapMode) -> &mut Self {
+        self.0.set_bits(0..=1, wrap.into());
+        self
+    }
+
+    pub fn wrap_t(&self) -> WrapMode {
+        self.0.get_bits(2..=3).try_into().unwrap()
+    }
+
+    pub fn with_wrap_t(&mut self, wrap: WrapMode) -> &mut Self {
+        self.0.set_bits(2..=3, wrap.into());
+        self
+    }
+
+    pub fn mag_filter(&self) -> TextureFilter {
+        if self.0.get_bit(4) {
+            TextureFilter::Linear
+        } else {
+            TextureFilter::Near
+        }
+    }
+
+    /// Magnification never uses mipmaps, only `Near` and `Linear` are valid.
+    pub fn with_mag_filter(&mut self, filter: TextureFilter) -> &mut Self {
+        assert!(
+            matches!(filter, TextureFilter::Near | TextureFilter::Linear),
+            "Magnification can't use mipmaps"
+        );
+        self.0.set_bit(4, filter == TextureFilter::Linear);
+        self
+    }
+
+    pub fn min_filter(&self) -> TextureFilter {
+        self.0.get_bits(5..=7).try_into().unwrap()
+    }
+
+    pub fn with_min_filter(&mut self, filter: TextureFilter) -> &mut Self {
+        self.0.set_bits(5..=7, filter.into());
+        self
+    }
+
+    pub fn diagonal_lod(&self) -> bool {
+        self.0.get_bit(8)
+    }
+
+    pub fn with_diagonal_lod(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(8, enable);
+        self
+    }
+
+    /// LOD bias in 1/32 steps.
+    pub fn lod_bias(&self) -> i8 {
+        i8::from_be_bytes([self.0.get_bits(9..=16).try_into().unwrap()])
+    }
+
+    pub fn with_lod_bias(&mut self, bias: i8) -> &mut Self {
+        self.0
+            .set_bits(9..=16, u8::from_be_bytes(bias.to_be_bytes()).into());
+        self
+    }
+
+    pub fn max_anisotropy(&self) -> Anisotropy {
+        self.0.get_bits(19..=20).try_into().unwrap()
+    }
+
+    pub fn with_max_anisotropy(&mut self, anisotropy: Anisotropy) -> &mut Self {
+        self.0.set_bits(19..=20, anisotropy.into());
+        self
+    }
+
+    pub fn bias_clamp(&self) -> bool {
+        self.0.get_bit(21)
+    }
+
+    pub fn with_bias_clamp(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(21, enable);
+        self
+    }
+}
+
+impl From<u32> for TexMode0 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TexMode0> for u32 {
+    fn from(value: TexMode0) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TexMode0 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// LOD range of a texture map, both ends in 1/16 steps.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexMode1(u32);
+
+impl TexMode1 {
+    pub const ADDRESS: u8 = 0x84;
+
+    pub fn new(map: u8) -> Self {
+        Self(u32::from(tex_map_address(Self::ADDRESS, map)) << 24)
+    }
+
+    pub fn min_lod(&self) -> u8 {
+        self.0.get_bits(0..=7).try_into().unwrap()
+    }
+
+    pub fn with_min_lod(&mut self, lod: u8) -> &mut Self {
+        self.0.set_bits(0..=7, lod.into());
+        self
+    }
+
+    pub fn max_lod(&self) -> u8 {
+        self.0.get_bits(8..=15).try_into().unwrap()
+    }
+
+    pub fn with_max_lod(&mut self, lod: u8) -> &mut Self {
+        self.0.set_bits(8..=15, lod.into());
+        self
+    }
+}
+
+impl From<u32> for TexMode1 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TexMode1> for u32 {
+    fn from(value: TexMode1) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TexMode1 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Size and format of a texture map.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexImage0(u32);
+
+impl TexImage0 {
+    pub const ADDRESS: u8 = 0x88;
+
+    pub fn new(map: u8) -> Self {
+        Self(u32::from(tex_map_address(Self::ADDRESS, map)) << 24)
+    }
+
+    pub fn width(&self) -> u16 {
+        u16::try_from(self.0.get_bits(0..=9)).unwrap() + 1
+    }
+
+    pub fn with_width(&mut self, width: u16) -> &mut Self {
+        assert!(
+            (1..=1024).contains(&width),
+            "Textures are 1 to 1024 texels wide"
+        );
+        self.0.set_bits(0..=9, (width - 1).into());
+        self
+    }
+
+    pub fn height(&self) -> u16 {
+        u16::try_from(self.0.get_bits(10..=19)).unwrap() + 1
+    }
+
+    pub fn with_height(&mut self, height: u16) -> &mut Self {
+        assert!(
+            (1..=1024).contains(&height),
+            "Textures are 1 to 1024 texels high"
+        );
+        self.0.set_bits(10..=19, (height - 1).into());
+        self
+    }
+
+    pub fn format(&self) -> TextureFormat {
+        self.0.get_bits(20..=23).try_into().unwrap()
+    }
+
+    pub fn with_format(&mut self, format: TextureFormat) -> &mut Self {
+        self.0.set_bits(20..=23, format.into());
+        self
+    }
+}
+
+impl From<u32> for TexImage0 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TexImage0> for u32 {
+    fn from(value: TexImage0) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TexImage0 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Even LOD cache region of a texture map.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexImage1(u32);
+
+impl TexImage1 {
+    pub const ADDRESS: u8 = 0x8C;
+
+    pub fn new(map: u8) -> Self {
+        Self(u32::from(tex_map_address(Self::ADDRESS, map)) << 24)
+    }
+
+    pub fn tmem_addr(&self) -> u32 {
+        self.0.get_bits(0..=14) << 5
+    }
+
+    pub fn with_tmem_addr(&mut self, tmem_addr: u32) -> &mut Self {
+        assert!(tmem_addr % 32 == 0, "TMEM regions must be 32 byte aligned");
+        self.0.set_bits(0..=14, tmem_addr >> 5);
+        self
+    }
+
+    pub fn cache_size(&self) -> Option<CacheSize> {
+        self.0.get_bits(15..=17).try_into().ok()
+    }
+
+    /// Sets both the cache width and height, which are always equal.
+    pub fn with_cache_size(&mut self, size: CacheSize) -> &mut Self {
+        self.0.set_bits(15..=17, size.into());
+        self.0.set_bits(18..=20, size.into());
+        self
+    }
+
+    pub fn preloaded(&self) -> bool {
+        self.0.get_bit(21)
+    }
+
+    pub fn with_preloaded(&mut self, preloaded: bool) -> &mut Self {
+        self.0.set_bit(21, preloaded);
+        self
+    }
+}
+
+impl From<u32> for TexImage1 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TexImage1> for u32 {
+    fn from(value: TexImage1) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TexImage1 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Odd LOD cache region of a texture map.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexImage2(u32);
+
+impl TexImage2 {
+    pub const ADDRESS: u8 = 0x90;
+
+    pub fn new(map: u8) -> Self {
+        Self(u32::from(tex_map_address(Self::ADDRESS, map)) << 24)
+    }
+
+    pub fn tmem_addr(&self) -> u32 {
+        self.0.get_bits(0..=14) << 5
+    }
+
+    pub fn with_tmem_addr(&mut self, tmem_addr: u32) -> &mut Self {
+        assert!(tmem_addr % 32 == 0, "TMEM regions must be 32 byte aligned");
+        self.0.set_bits(0..=14, tmem_addr >> 5);
+        self
+    }
+
+    pub fn cache_size(&self) -> Option<CacheSize> {
+        self.0.get_bits(15..=17).try_into().ok()
+    }
+
+    /// Sets both the cache width and height, which are always equal.
+    pub fn with_cache_size(&mut self, size: CacheSize) -> &mut Self {
+        self.0.set_bits(15..=17, size.into());
+        self.0.set_bits(18..=20, size.into());
+        self
+    }
+}
+
+impl From<u32> for TexImage2 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TexImage2> for u32 {
+    fn from(value: TexImage2) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TexImage2 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Main memory address of a texture map.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexImage3(u32);
+
+impl TexImage3 {
+    pub const ADDRESS: u8 = 0x94;
+
+    pub fn new(map: u8) -> Self {
+        Self(u32::from(tex_map_address(Self::ADDRESS, map)) << 24)
+    }
+
+    pub fn addr(&self) -> usize {
+        usize::try_from(self.0.get_bits(0..=23)).unwrap() << 5
+    }
+
+    pub fn with_addr(&mut self, physical_addr: u32) -> &mut Self {
+        assert!(physical_addr % 32 == 0, "Textures must be 32 byte aligned");
+        self.0.set_bits(0..=23, physical_addr >> 5);
+        self
+    }
+}
+
+impl From<u32> for TexImage3 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TexImage3> for u32 {
+    fn from(value: TexImage3) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TexImage3 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Palette used by a color indexed texture map.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexTlut(u32);
+
+impl TexTlut {
+    pub const ADDRESS: u8 = 0x98;
+
+    pub fn new(map: u8) -> Self {
+        Self(u32::from(tex_map_address(Self::ADDRESS, map)) << 24)
+    }
+
+    pub fn tmem_addr(&self) -> u32 {
+        (self.0.get_bits(0..=9) << 9) + 0x80000
+    }
+
+    pub fn with_tmem_addr(&mut self, tmem_addr: u32) -> &mut Self {
+        assert!(
+            tmem_addr >= 0x80000 && tmem_addr % 512 == 0,
+            "TLUTs must be 512 byte aligned in the upper half of TMEM"
+        );
+        self.0.set_bits(0..=9, (tmem_addr - 0x80000) >> 9);
+        self
+    }
+
+    pub fn format(&self) -> TlutFormat {
+        self.0.get_bits(10..=11).try_into().unwrap()
+    }
+
+    pub fn with_format(&mut self, format: TlutFormat) -> &mut Self {
+        self.0.set_bits(10..=11, format.into());
+        self
+    }
+}
+
+impl From<u32> for TexTlut {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TexTlut> for u32 {
+    fn from(value: TexTlut) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TexTlut {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ZMode(u32);
+
+impl ZMode {
+    pub const ADDRESS: u8 = 0x40;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn enable(&self) -> bool {
+        self.0.get_bit(0)
+    }
+
+    pub fn with_enable(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(0, enable);
+        self
+    }
+
+    pub fn function(&self) -> CompareFn {
+        self.0.get_bits(1..=3).try_into().unwrap()
+    }
+
+    pub fn with_function(&mut self, function: CompareFn) -> &mut Self {
+        self.0.set_bits(1..=3, function.into());
+        self
+    }
+
+    pub fn update(&self) -> bool {
+        self.0.get_bit(4)
+    }
+
+    pub fn with_update(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(4, enable);
+        self
+    }
+}
+
+impl Default for ZMode {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for ZMode {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ZMode> for u32 {
+    fn from(value: ZMode) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for ZMode {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Blending, logic op and dithering, plus which EFB channels get written.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct BlendControl(u32);
+
+impl BlendControl {
+    pub const ADDRESS: u8 = 0x41;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn blend(&self) -> bool {
+        self.0.get_bit(0)
+    }
+
+    pub fn with_blend(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(0, enable);
+        self
+    }
+
+    pub fn logic(&self) -> bool {
+        self.0.get_bit(1)
+    }
+
+    pub fn with_logic(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(1, enable);
+        self
+    }
+
+    pub fn dither(&self) -> bool {
+        self.0.get_bit(2)
+    }
+
+    pub fn with_dither(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(2, enable);
+        self
+    }
+
+    pub fn color_update(&self) -> bool {
+        self.0.get_bit(3)
+    }
+
+    pub fn with_color_update(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(3, enable);
+        self
+    }
+
+    pub fn alpha_update(&self) -> bool {
+        self.0.get_bit(4)
+    }
+
+    pub fn with_alpha_update(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(4, enable);
+        self
+    }
+
+    pub fn dst_factor(&self) -> BlendFactor {
+        self.0.get_bits(5..=7).try_into().unwrap()
+    }
+
+    pub fn with_dst_factor(&mut self, factor: BlendFactor) -> &mut Self {
+        self.0.set_bits(5..=7, factor.into());
+        self
+    }
+
+    pub fn src_factor(&self) -> BlendFactor {
+        self.0.get_bits(8..=10).try_into().unwrap()
+    }
+
+    pub fn with_src_factor(&mut self, factor: BlendFactor) -> &mut Self {
+        self.0.set_bits(8..=10, factor.into());
+        self
+    }
+
+    pub fn subtract(&self) -> bool {
+        self.0.get_bit(11)
+    }
+
+    pub fn with_subtract(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(11, enable);
+        self
+    }
+
+    pub fn logic_op(&self) -> LogicOp {
+        self.0.get_bits(12..=15).try_into().unwrap()
+    }
+
+    pub fn with_logic_op(&mut self, op: LogicOp) -> &mut Self {
+        self.0.set_bits(12..=15, op.into());
+        self
+    }
+}
+
+impl Default for BlendControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for BlendControl {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<BlendControl> for u32 {
+    fn from(value: BlendControl) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for BlendControl {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Replaces the alpha written to the EFB when enabled.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ConstantAlpha(u32);
+
+impl ConstantAlpha {
+    pub const ADDRESS: u8 = 0x42;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn alpha(&self) -> u8 {
+        self.0.get_bits(0..=7).try_into().unwrap()
+    }
+
+    pub fn with_alpha(&mut self, alpha: u8) -> &mut Self {
+        self.0.set_bits(0..=7, alpha.into());
+        self
+    }
+
+    pub fn enable(&self) -> bool {
+        self.0.get_bit(8)
+    }
+
+    pub fn with_enable(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(8, enable);
+        self
+    }
+}
+
+impl Default for ConstantAlpha {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for ConstantAlpha {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ConstantAlpha> for u32 {
+    fn from(value: ConstantAlpha) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for ConstantAlpha {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// EFB pixel format and when the Z test runs.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct PeControl(u32);
+
+impl PeControl {
+    pub const ADDRESS: u8 = 0x43;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn pixel_format(&self) -> PixelFormat {
+        self.0.get_bits(0..=2).try_into().unwrap()
+    }
+
+    pub fn with_pixel_format(&mut self, format: PixelFormat) -> &mut Self {
+        self.0.set_bits(0..=2, format.into());
+        self
+    }
+
+    pub fn z_format(&self) -> ZFormat {
+        self.0.get_bits(3..=5).try_into().unwrap()
+    }
+
+    pub fn with_z_format(&mut self, format: ZFormat) -> &mut Self {
+        self.0.set_bits(3..=5, format.into());
+        self
+    }
+
+    /// Early Z, only correct when no alpha test can discard pixels.
+    pub fn z_compare_before_texture(&self) -> bool {
+        self.0.get_bit(6)
+    }
+
+    pub fn with_z_compare_before_texture(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(6, enable);
+        self
+    }
+}
+
+impl Default for PeControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for PeControl {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PeControl> for u32 {
+    fn from(value: PeControl) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for PeControl {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Raises the PE finish interrupt once everything before it has been drawn.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct PeDone(u32);
+
+impl PeDone {
+    pub const ADDRESS: u8 = 0x45;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn finish(&self) -> bool {
+        self.0.get_bit(1)
+    }
+
+    pub fn with_finish(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(1, enable);
+        self
+    }
+}
+
+impl Default for PeDone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for PeDone {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PeDone> for u32 {
+    fn from(value: PeDone) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for PeDone {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Token the PE latches into its token register once everything before it has been drawn.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct PeToken(u32);
+
+impl PeToken {
+    pub const ADDRESS: u8 = 0x47;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn token(&self) -> u16 {
+        self.0.get_bits(0..=15).try_into().unwrap()
+    }
+
+    pub fn with_token(&mut self, token: u16) -> &mut Self {
+        self.0.set_bits(0..=15, token.into());
+        self
+    }
+}
+
+impl Default for PeToken {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for PeToken {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PeToken> for u32 {
+    fn from(value: PeToken) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for PeToken {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Same as `PeToken`, but also raises the PE token interrupt.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct PeTokenInterrupt(u32);
+
+impl PeTokenInterrupt {
+    pub const ADDRESS: u8 = 0x48;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn token(&self) -> u16 {
+        self.0.get_bits(0..=15).try_into().unwrap()
+    }
+
+    pub fn with_token(&mut self, token: u16) -> &mut Self {
+        self.0.set_bits(0..=15, token.into());
+        self
+    }
+}
+
+impl Default for PeTokenInterrupt {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for PeTokenInterrupt {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PeTokenInterrupt> for u32 {
+    fn from(value: PeTokenInterrupt) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for PeTokenInterrupt {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Range adjustment center and enable.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct FogRange(u32);
+
+impl FogRange {
+    pub const ADDRESS: u8 = 0xE8;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    /// Screen column the adjustment table is centered on. The register stores it offset by 342,
+    /// so a raw value below that comes out negative.
+    pub fn center(&self) -> i16 {
+        i16::try_from(self.0.get_bits(0..=9)).unwrap() - 342
+    }
+
+    pub fn with_center(&mut self, center: u16) -> &mut Self {
+        assert!(
+            center <= 0x3FF - 342,
+            "The fog range center has to be at most 681"
+        );
+        self.0.set_bits(0..=9, u32::from(center + 342));
+        self
+    }
+
+    pub fn enable(&self) -> bool {
+        self.0.get_bit(10)
+    }
+
+    pub fn with_enable(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(10, enable);
+        self
+    }
+}
+
+impl Default for FogRange {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for FogRange {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<FogRange> for u32 {
+    fn from(value: FogRange) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for FogRange {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Two 12 bit entries of the fog range adjustment table.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct FogRangeK(u32);
+
+impl FogRangeK {
+    pub const ADDRESS: u8 = 0xE9;
+
+    pub fn new(index: u8) -> Self {
+        assert!(index < 5, "There are only 5 fog range table registers");
+        Self(u32::from(Self::ADDRESS + index) << 24)
+    }
+
+    pub fn low(&self) -> u16 {
+        self.0.get_bits(0..=11).try_into().unwrap()
+    }
+
+    pub fn with_low(&mut self, entry: u16) -> &mut Self {
+        self.0.set_bits(0..=11, entry.into());
+        self
+    }
+
+    pub fn high(&self) -> u16 {
+        self.0.get_bits(12..=23).try_into().unwrap()
+    }
+
+    pub fn with_high(&mut self, entry: u16) -> &mut Self {
+        self.0.set_bits(12..=23, entry.into());
+        self
+    }
+}
+
+impl From<u32> for FogRangeK {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<FogRangeK> for u32 {
+    fn from(value: FogRangeK) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for FogRangeK {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Fog parameter A, a float with the low 12 mantissa bits dropped.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct FogParam0(u32);
+
+impl FogParam0 {
+    pub const ADDRESS: u8 = 0xEE;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn a(&self) -> f32 {
+        f32::from_bits(self.0.get_bits(0..=19) << 12)
+    }
+
+    pub fn with_a(&mut self, a: f32) -> &mut Self {
+        self.0.set_bits(0..=19, a.to_bits() >> 12);
+        self
+    }
+}
+
+impl Default for FogParam0 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for FogParam0 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<FogParam0> for u32 {
+    fn from(value: FogParam0) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for FogParam0 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Mantissa of fog parameter B.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct FogParam1(u32);
+
+impl FogParam1 {
+    pub const ADDRESS: u8 = 0xEF;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn b_magnitude(&self) -> u32 {
+        self.0.get_bits(0..=23)
+    }
+
+    pub fn with_b_magnitude(&mut self, magnitude: u32) -> &mut Self {
+        self.0.set_bits(0..=23, magnitude);
+        self
+    }
+}
+
+impl Default for FogParam1 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for FogParam1 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<FogParam1> for u32 {
+    fn from(value: FogParam1) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for FogParam1 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Exponent of fog parameter B.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct FogParam2(u32);
+
+impl FogParam2 {
+    pub const ADDRESS: u8 = 0xF0;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn b_shift(&self) -> u8 {
+        self.0.get_bits(0..=4).try_into().unwrap()
+    }
+
+    pub fn with_b_shift(&mut self, shift: u8) -> &mut Self {
+        self.0.set_bits(0..=4, shift.into());
+        self
+    }
+}
+
+impl Default for FogParam2 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for FogParam2 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<FogParam2> for u32 {
+    fn from(value: FogParam2) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for FogParam2 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Fog parameter C, plus the projection and fog type.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct FogParam3(u32);
+
+impl FogParam3 {
+    pub const ADDRESS: u8 = 0xF1;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn c(&self) -> f32 {
+        f32::from_bits(self.0.get_bits(0..=19) << 12)
+    }
+
+    pub fn with_c(&mut self, c: f32) -> &mut Self {
+        self.0.set_bits(0..=19, c.to_bits() >> 12);
+        self
+    }
+
+    pub fn projection(&self) -> ProjectionType {
+        u32::from(self.0.get_bit(20)).into()
+    }
+
+    pub fn with_projection(&mut self, projection: ProjectionType) -> &mut Self {
+        self.0.set_bit(20, u32::from(projection) != 0);
+        self
+    }
+
+    pub fn fog_type(&self) -> FogType {
+        self.0.get_bits(21..=23).try_into().unwrap()
+    }
+
+    pub fn with_fog_type(&mut self, ty: FogType) -> &mut Self {
+        self.0.set_bits(21..=23, ty.into());
+        self
+    }
+}
+
+impl Default for FogParam3 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for FogParam3 {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<FogParam3> for u32 {
+    fn from(value: FogParam3) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for FogParam3 {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct FogColor(u32);
+
+impl FogColor {
+    pub const ADDRESS: u8 = 0xF2;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub const fn color(&self) -> [u8; 3] {
+        let [_, red, green, blue] = self.0.to_be_bytes();
+        [red, green, blue]
+    }
+
+    pub fn with_color(&mut self, color: [u8; 3]) -> &mut Self {
+        let [red, green, blue] = color;
+        self.0
+            .set_bits(0..=23, u32::from_be_bytes([0, red, green, blue]));
+        self
+    }
+}
+
+impl Default for FogColor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for FogColor {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<FogColor> for u32 {
+    fn from(value: FogColor) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for FogColor {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Two alpha tests against fixed references, combined by `op`.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct AlphaCompare(u32);
+
+impl AlphaCompare {
+    pub const ADDRESS: u8 = 0xF3;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn ref0(&self) -> u8 {
+        self.0.get_bits(0..=7).try_into().unwrap()
+    }
+
+    pub fn with_ref0(&mut self, reference: u8) -> &mut Self {
+        self.0.set_bits(0..=7, reference.into());
+        self
+    }
+
+    pub fn ref1(&self) -> u8 {
+        self.0.get_bits(8..=15).try_into().unwrap()
+    }
+
+    pub fn with_ref1(&mut self, reference: u8) -> &mut Self {
+        self.0.set_bits(8..=15, reference.into());
+        self
+    }
+
+    pub fn comp0(&self) -> CompareFn {
+        self.0.get_bits(16..=18).try_into().unwrap()
+    }
+
+    pub fn with_comp0(&mut self, function: CompareFn) -> &mut Self {
+        self.0.set_bits(16..=18, function.into());
+        self
+    }
+
+    pub fn comp1(&self) -> CompareFn {
+        self.0.get_bits(19..=21).try_into().unwrap()
+    }
+
+    pub fn with_comp1(&mut self, function: CompareFn) -> &mut Self {
+        self.0.set_bits(19..=21, function.into());
+        self
+    }
+
+    pub fn op(&self) -> AlphaOp {
+        self.0.get_bits(22..=23).try_into().unwrap()
+    }
+
+    pub fn with_op(&mut self, op: AlphaOp) -> &mut Self {
+        self.0.set_bits(22..=23, op.into());
+        self
+    }
+}
+
+impl Default for AlphaCompare {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for AlphaCompare {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<AlphaCompare> for u32 {
+    fn from(value: AlphaCompare) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for AlphaCompare {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Selects what the setup unit performance counter counts, read back through CP counter 0.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct SuPerfSelect(u32);
+
+impl SuPerfSelect {
+    pub const ADDRESS: u8 = 0x23;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn select(&self) -> u32 {
+        self.0.get_bits(0..=23)
+    }
+
+    pub fn with_select(&mut self, select: u32) -> &mut Self {
+        self.0.set_bits(0..=23, select);
+        self
+    }
+}
+
+impl Default for SuPerfSelect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for SuPerfSelect {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<SuPerfSelect> for u32 {
+    fn from(value: SuPerfSelect) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for SuPerfSelect {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Selects what the rasterizer performance counter counts, read back through CP counter 0.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct RasPerfSelect(u32);
+
+impl RasPerfSelect {
+    pub const ADDRESS: u8 = 0x24;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn select(&self) -> u32 {
+        self.0.get_bits(0..=23)
+    }
+
+    pub fn with_select(&mut self, select: u32) -> &mut Self {
+        self.0.set_bits(0..=23, select);
+        self
+    }
+}
+
+impl Default for RasPerfSelect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for RasPerfSelect {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<RasPerfSelect> for u32 {
+    fn from(value: RasPerfSelect) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for RasPerfSelect {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Writing 0 and then `0xAAA` clears the PE pixel counters.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct PePerfControl(u32);
+
+impl PePerfControl {
+    pub const ADDRESS: u8 = 0x57;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn control(&self) -> u32 {
+        self.0.get_bits(0..=23)
+    }
+
+    pub fn with_control(&mut self, control: u32) -> &mut Self {
+        self.0.set_bits(0..=23, control);
+        self
+    }
+}
+
+impl Default for PePerfControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for PePerfControl {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PePerfControl> for u32 {
+    fn from(value: PePerfControl) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for PePerfControl {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Selects what the texture unit performance counter counts, read back through CP counter 1.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TxPerfSelect(u32);
+
+impl TxPerfSelect {
+    pub const ADDRESS: u8 = 0x67;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn select(&self) -> u32 {
+        self.0.get_bits(0..=23)
+    }
+
+    pub fn with_select(&mut self, select: u32) -> &mut Self {
+        self.0.set_bits(0..=23, select);
+        self
+    }
+}
+
+impl Default for TxPerfSelect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for TxPerfSelect {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TxPerfSelect> for u32 {
+    fn from(value: TxPerfSelect) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for TxPerfSelect {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// One column of an indirect matrix, with two bits of its scale exponent.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct IndMtx(u32);
+
+impl IndMtx {
+    pub const ADDRESS: u8 = 0x06;
+
+    /// The register holding column `column` of indirect matrix `matrix`.
+    pub fn new(matrix: u8, column: u8) -> Self {
+        assert!(matrix < 3, "There are only 3 indirect matrices");
+        assert!(column < 3, "Indirect matrices only have 3 columns");
+        Self(u32::from(Self::ADDRESS + matrix * 3 + column) << 24)
+    }
+
+    /// Signed 1.10 fixed point element of the first row.
+    pub fn row0(&self) -> i16 {
+        sign_extend_11(self.0.get_bits(0..=10))
+    }
+
+    pub fn with_row0(&mut self, element: i16) -> &mut Self {
+        self.0.set_bits(
+            0..=10,
+            u32::from(u16::from_be_bytes(element.to_be_bytes())) & 0x7FF,
+        );
+        self
+    }
+
+    pub fn row1(&self) -> i16 {
+        sign_extend_11(self.0.get_bits(11..=21))
+    }
+
+    pub fn with_row1(&mut self, element: i16) -> &mut Self {
+        self.0.set_bits(
+            11..=21,
+            u32::from(u16::from_be_bytes(element.to_be_bytes())) & 0x7FF,
+        );
+        self
+    }
+
+    /// Bits `2 * column..2 * column + 2` of the scale exponent plus 17.
+    pub fn scale_bits(&self) -> u8 {
+        self.0.get_bits(22..=23).try_into().unwrap()
+    }
+
+    pub fn with_scale_bits(&mut self, bits: u8) -> &mut Self {
+        self.0.set_bits(22..=23, bits.into());
+        self
+    }
+}
+
+impl From<u32> for IndMtx {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<IndMtx> for u32 {
+    fn from(value: IndMtx) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for IndMtx {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Indirect settings of a TEV stage.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct IndCmd(u32);
+
+impl IndCmd {
+    pub const ADDRESS: u8 = 0x10;
+
+    pub fn new(stage: u8) -> Self {
+        assert!(stage < 16, "There are only 16 TEV stages");
+        Self(u32::from(Self::ADDRESS + stage) << 24)
+    }
+
+    pub fn ind_stage(&self) -> u8 {
+        self.0.get_bits(0..=1).try_into().unwrap()
+    }
+
+    pub fn with_ind_stage(&mut self, ind_stage: u8) -> &mut Self {
+        self.0.set_bits(0..=1, ind_stage.into());
+        self
+    }
+
+    pub fn format(&self) -> IndFormat {
+        self.0.get_bits(2..=3).try_into().unwrap()
+    }
+
+    pub fn with_format(&mut self, format: IndFormat) -> &mut Self {
+        self.0.set_bits(2..=3, format.into());
+        self
+    }
+
+    pub fn bias(&self) -> IndBias {
+        self.0.get_bits(4..=6).try_into().unwrap()
+    }
+
+    pub fn with_bias(&mut self, bias: IndBias) -> &mut Self {
+        self.0.set_bits(4..=6, bias.into());
+        self
+    }
+
+    pub fn alpha(&self) -> IndAlpha {
+        self.0.get_bits(7..=8).try_into().unwrap()
+    }
+
+    pub fn with_alpha(&mut self, alpha: IndAlpha) -> &mut Self {
+        self.0.set_bits(7..=8, alpha.into());
+        self
+    }
+
+    pub fn matrix(&self) -> IndMatrixSelect {
+        self.0.get_bits(9..=12).try_into().unwrap()
+    }
+
+    pub fn with_matrix(&mut self, matrix: IndMatrixSelect) -> &mut Self {
+        self.0.set_bits(9..=12, matrix.into());
+        self
+    }
+
+    pub fn wrap_s(&self) -> IndWrap {
+        self.0.get_bits(13..=15).try_into().unwrap()
+    }
+
+    pub fn with_wrap_s(&mut self, wrap: IndWrap) -> &mut Self {
+        self.0.set_bits(13..=15, wrap.into());
+        self
+    }
+
+    pub fn wrap_t(&self) -> IndWrap {
+        self.0.get_bits(16..=18).try_into().unwrap()
+    }
+
+    pub fn with_wrap_t(&mut self, wrap: IndWrap) -> &mut Self {
+        self.0.set_bits(16..=18, wrap.into());
+        self
+    }
+
+    pub fn unmodified_lod(&self) -> bool {
+        self.0.get_bit(19)
+    }
+
+    pub fn with_unmodified_lod(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(19, enable);
+        self
+    }
+
+    pub fn add_prev(&self) -> bool {
+        self.0.get_bit(20)
+    }
+
+    pub fn with_add_prev(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(20, enable);
+        self
+    }
+}
+
+impl From<u32> for IndCmd {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<IndCmd> for u32 {
+    fn from(value: IndCmd) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for IndCmd {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Texture coordinate scales of a pair of indirect stages.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct RasIndScale(u32);
+
+impl RasIndScale {
+    pub const ADDRESS: u8 = 0x25;
+
+    /// The register holding the scales of indirect stages `2 * index` and `2 * index + 1`.
+    pub fn new(index: u8) -> Self {
+        assert!(index < 2, "There are only 2 indirect scale registers");
+        Self(u32::from(Self::ADDRESS + index) << 24)
+    }
+
+    pub fn scale_s(&self, odd: bool) -> IndScale {
+        let base = usize::from(odd) * 8;
+        self.0.get_bits(base..base + 4).try_into().unwrap()
+    }
+
+    pub fn with_scale_s(&mut self, odd: bool, scale: IndScale) -> &mut Self {
+        let base = usize::from(odd) * 8;
+        self.0.set_bits(base..base + 4, scale.into());
+        self
+    }
+
+    pub fn scale_t(&self, odd: bool) -> IndScale {
+        let base = usize::from(odd) * 8 + 4;
+        self.0.get_bits(base..base + 4).try_into().unwrap()
+    }
+
+    pub fn with_scale_t(&mut self, odd: bool, scale: IndScale) -> &mut Self {
+        let base = usize::from(odd) * 8 + 4;
+        self.0.set_bits(base..base + 4, scale.into());
+        self
+    }
+}
+
+impl From<u32> for RasIndScale {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<RasIndScale> for u32 {
+    fn from(value: RasIndScale) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for RasIndScale {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+/// Texture map and coordinate sampled by each of the 4 indirect stages.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct RasIndOrder(u32);
+
+impl RasIndOrder {
+    pub const ADDRESS: u8 = 0x27;
+
+    pub const fn new() -> Self {
+        Self((Self::ADDRESS as u32) << 24)
+    }
+
+    pub fn tex_map(&self, ind_stage: u8) -> u8 {
+        let base = usize::from(ind_stage) * 6;
+        self.0.get_bits(base..base + 3).try_into().unwrap()
+    }
+
+    pub fn with_tex_map(&mut self, ind_stage: u8, map: u8) -> &mut Self {
+        assert!(ind_stage < 4, "There are only 4 indirect stages");
+        let base = usize::from(ind_stage) * 6;
+        self.0.set_bits(base..base + 3, map.into());
+        self
+    }
+
+    pub fn tex_coord(&self, ind_stage: u8) -> u8 {
+        let base = usize::from(ind_stage) * 6 + 3;
+        self.0.get_bits(base..base + 3).try_into().unwrap()
+    }
+
+    pub fn with_tex_coord(&mut self, ind_stage: u8, coord: u8) -> &mut Self {
+        assert!(ind_stage < 4, "There are only 4 indirect stages");
+        let base = usize::from(ind_stage) * 6 + 3;
+        self.0.set_bits(base..base + 3, coord.into());
+        self
+    }
+}
+
+impl Default for RasIndOrder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for RasIndOrder {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<RasIndOrder> for u32 {
+    fn from(value: RasIndOrder) -> Self {
+        value.0
+    }
+}
+
+impl BpRegister for RasIndOrder {
+    fn raw(self) -> u32 {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fog_range_center_is_offset_by_342() {
+        let mut range = FogRange::new();
+        assert_eq!(range.center(), -342);
+        assert_eq!(range.with_center(320).raw(), 0xE800_0296);
+        assert_eq!(range.center(), 320);
+        assert_eq!(range.with_center(681).center(), 681);
+    }
+
+    #[test]
+    #[should_panic(expected = "The fog range center has to be at most 681")]
+    fn fog_range_center_has_to_fit() {
+        FogRange::new().with_center(682);
+    }
+}
@@ -0,0 +1,276 @@
+use alloc::vec::Vec;
+
+use crate::utils::WriteGatherPipe;
+
+use super::{
+    bp::{BpMask, BpRegister},
+    cp::CpRegister,
+    xf::XfRegister,
+};
+
+/// First byte of every command packet in a GX command stream.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+#[repr(u8)]
+pub enum Opcode {
+    Nop = 0x00,
+    LoadCpReg = 0x08,
+    LoadXfReg = 0x10,
+    LoadIndexA = 0x20,
+    LoadIndexB = 0x28,
+    LoadIndexC = 0x30,
+    LoadIndexD = 0x38,
+    CallDisplayList = 0x40,
+    InvalidateVertexCache = 0x48,
+    LoadBpReg = 0x61,
+}
+
+impl From<Opcode> for u8 {
+    fn from(value: Opcode) -> Self {
+        value as Self
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidOpcodeError;
+
+impl TryFrom<u8> for Opcode {
+    type Error = InvalidOpcodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(Self::Nop),
+            0x08 => Ok(Self::LoadCpReg),
+            0x10 => Ok(Self::LoadXfReg),
+            0x20 => Ok(Self::LoadIndexA),
+            0x28 => Ok(Self::LoadIndexB),
+            0x30 => Ok(Self::LoadIndexC),
+            0x38 => Ok(Self::LoadIndexD),
+            0x40 => Ok(Self::CallDisplayList),
+            0x48 => Ok(Self::InvalidateVertexCache),
+            0x61 => Ok(Self::LoadBpReg),
+            _ => Err(InvalidOpcodeError),
+        }
+    }
+}
+
+/// Anything that can take a stream of GX commands.
+///
+/// Only the raw big-endian writes have to be provided, every register load is encoded on top of
+/// them so the write-gather pipe and an in-memory buffer produce the exact same bytes.
+pub trait CommandSink {
+    fn write_u8(&mut self, value: u8);
+
+    fn write_u16(&mut self, value: u16);
+
+    fn write_u32(&mut self, value: u32);
+
+    fn write_f32(&mut self, value: f32) {
+        self.write_u32(value.to_bits());
+    }
+
+    fn nop(&mut self) {
+        self.write_u8(Opcode::Nop.into());
+    }
+
+    fn invalidate_vertex_cache(&mut self) {
+        self.write_u8(Opcode::InvalidateVertexCache.into());
+    }
+
+    /// Makes the GP read `size` bytes of commands from `physical_addr` before continuing.
+    fn call_display_list_at(&mut self, physical_addr: u32, size: u32) {
+        self.write_u8(Opcode::CallDisplayList.into());
+        self.write_u32(physical_addr);
+        self.write_u32(size);
+    }
+
+    /// Raw BP load, the register address lives in bits 24..=31 of `word`.
+    fn load_bp_reg(&mut self, word: u32) {
+        self.write_u8(Opcode::LoadBpReg.into());
+        self.write_u32(word);
+    }
+
+    fn load_cp_reg(&mut self, address: u8, value: u32) {
+        self.write_u8(Opcode::LoadCpReg.into());
+        self.write_u8(address);
+        self.write_u32(value);
+    }
+
+    /// Loads up to 16 consecutive words of XF memory starting at `address`.
+    fn load_xf_regs(&mut self, address: u16, values: &[u32]) {
+        assert!(
+            (1..=16).contains(&values.len()),
+            "XF loads must be between 1 and 16 words"
+        );
+
+        let count = u32::try_from(values.len() - 1).unwrap();
+        self.write_u8(Opcode::LoadXfReg.into());
+        self.write_u32(count << 16 | u32::from(address));
+        for value in values {
+            self.write_u32(*value);
+        }
+    }
+
+    fn load_xf_regs_f32(&mut self, address: u16, values: &[f32]) {
+        assert!(
+            (1..=16).contains(&values.len()),
+            "XF loads must be between 1 and 16 words"
+        );
+
+        let count = u32::try_from(values.len() - 1).unwrap();
+        self.write_u8(Opcode::LoadXfReg.into());
+        self.write_u32(count << 16 | u32::from(address));
+        for value in values {
+            self.write_f32(*value);
+        }
+    }
+
+    fn write_bp<R: BpRegister>(&mut self, reg: R)
+    where
+        Self: Sized,
+    {
+        self.load_bp_reg(reg.raw());
+    }
+
+    /// Writes only the bits of `reg` that are set in `mask`, the rest of the register is kept.
+    ///
+    /// The GP resets the mask after the next BP load, so it only ever covers `reg`.
+    fn write_bp_masked<R: BpRegister>(&mut self, mask: u32, reg: R)
+    where
+        Self: Sized,
+    {
+        BpMask::new().with_mask(mask).write(self);
+        self.load_bp_reg(reg.raw());
+    }
+
+    fn write_cp<R: CpRegister>(&mut self, reg: R)
+    where
+        Self: Sized,
+    {
+        self.load_cp_reg(R::ADDRESS, reg.value());
+    }
+
+    /// Writes one of the CP registers that is repeated per vertex format or per array.
+    fn write_cp_at<R: CpRegister>(&mut self, index: u8, reg: R)
+    where
+        Self: Sized,
+    {
+        self.load_cp_reg(R::ADDRESS + index, reg.value());
+    }
+
+    fn write_xf<R: XfRegister>(&mut self, reg: R)
+    where
+        Self: Sized,
+    {
+        self.load_xf_regs(R::ADDRESS, &[reg.value()]);
+    }
+
+    /// Writes one of the XF registers that is repeated per color channel.
+    fn write_xf_at<R: XfRegister>(&mut self, index: u8, reg: R)
+    where
+        Self: Sized,
+    {
+        self.load_xf_regs(R::ADDRESS + u16::from(index), &[reg.value()]);
+    }
+}
+
+impl CommandSink for WriteGatherPipe {
+    fn write_u8(&mut self, value: u8) {
+        Self::write_u8(self, value);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        Self::write_u16(self, value);
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        Self::write_u32(self, value);
+    }
+
+    fn write_f32(&mut self, value: f32) {
+        Self::write_f32(self, value);
+    }
+}
+
+impl CommandSink for Vec<u8> {
+    fn write_u8(&mut self, value: u8) {
+        self.push(value);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.extend_from_slice(&value.to_be_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use super::*;
+    use crate::gx::{bp::GenMode, cp::VcdLo};
+
+    #[test]
+    fn load_bp_reg() {
+        let mut stream = Vec::new();
+        stream.load_bp_reg(0x2000_1234);
+        assert_eq!(stream, [0x61, 0x20, 0x00, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn load_cp_reg() {
+        let mut stream = Vec::new();
+        stream.load_cp_reg(0x50, 0x0000_2200);
+        stream.write_cp(VcdLo::from(0x0000_2200));
+        assert_eq!(
+            stream,
+            [0x08, 0x50, 0x00, 0x00, 0x22, 0x00, 0x08, 0x50, 0x00, 0x00, 0x22, 0x00]
+        );
+    }
+
+    #[test]
+    fn load_xf_regs() {
+        let mut stream = Vec::new();
+        stream.load_xf_regs(0x1009, &[1]);
+        stream.load_xf_regs(0x0000, &[0x3F80_0000, 0, 0x4000_0000]);
+        assert_eq!(
+            stream,
+            [
+                0x10, 0x00, 0x00, 0x10, 0x09, 0x00, 0x00, 0x00, 0x01, //
+                0x10, 0x00, 0x02, 0x00, 0x00, 0x3F, 0x80, 0x00, 0x00, //
+                0x00, 0x00, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00,
+            ]
+        );
+
+        let mut floats = Vec::new();
+        floats.load_xf_regs_f32(0x0000, &[1.0, 0.0, 2.0]);
+        assert_eq!(floats, stream[9..]);
+    }
+
+    #[test]
+    #[should_panic = "XF loads must be between 1 and 16 words"]
+    fn load_xf_regs_rejects_empty_loads() {
+        Vec::new().load_xf_regs(0x1009, &[]);
+    }
+
+    #[test]
+    #[should_panic = "XF loads must be between 1 and 16 words"]
+    fn load_xf_regs_rejects_long_loads() {
+        Vec::new().load_xf_regs(0x0000, &[0; 17]);
+    }
+
+    #[test]
+    fn write_bp_masked() {
+        let mut gen_mode = GenMode::new();
+        gen_mode.with_multisampling(true);
+
+        let mut stream = Vec::new();
+        stream.write_bp_masked(1 << 9, gen_mode);
+        assert_eq!(
+            stream,
+            vec![0x61, 0xFE, 0x00, 0x02, 0x00, 0x61, 0x00, 0x00, 0x02, 0x00]
+        );
+    }
+}
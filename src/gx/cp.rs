@@ -0,0 +1,949 @@
+use bit_field::BitField;
+
+use crate::mmio::Physical;
+
+use super::command::CommandSink;
+
+/// A command processor register.
+///
+/// Registers that are repeated per vertex format (`VatA`, `VatB`, `VatC`) or per vertex array
+/// (`ArrayBase`, `ArrayStride`) are written with `write_at` which adds the index to `ADDRESS`.
+pub trait CpRegister: Copy {
+    const ADDRESS: u8;
+
+    fn value(self) -> u32;
+
+    fn write(self, sink: &mut impl CommandSink) {
+        sink.write_cp(self);
+    }
+
+    fn write_at(self, index: u8, sink: &mut impl CommandSink) {
+        sink.write_cp_at(index, self);
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum AttributeType {
+    None,
+    Direct,
+    Index8,
+    Index16,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidAttributeTypeError;
+
+impl TryFrom<u32> for AttributeType {
+    type Error = InvalidAttributeTypeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Direct),
+            2 => Ok(Self::Index8),
+            3 => Ok(Self::Index16),
+            _ => Err(InvalidAttributeTypeError),
+        }
+    }
+}
+
+impl From<AttributeType> for u32 {
+    fn from(value: AttributeType) -> Self {
+        match value {
+            AttributeType::None => 0,
+            AttributeType::Direct => 1,
+            AttributeType::Index8 => 2,
+            AttributeType::Index16 => 3,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ComponentType {
+    U8,
+    S8,
+    U16,
+    S16,
+    F32,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidComponentTypeError;
+
+impl TryFrom<u32> for ComponentType {
+    type Error = InvalidComponentTypeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::U8),
+            1 => Ok(Self::S8),
+            2 => Ok(Self::U16),
+            3 => Ok(Self::S16),
+            4 => Ok(Self::F32),
+            _ => Err(InvalidComponentTypeError),
+        }
+    }
+}
+
+impl From<ComponentType> for u32 {
+    fn from(value: ComponentType) -> Self {
+        match value {
+            ComponentType::U8 => 0,
+            ComponentType::S8 => 1,
+            ComponentType::U16 => 2,
+            ComponentType::S16 => 3,
+            ComponentType::F32 => 4,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ColorFormat {
+    Rgb565,
+    Rgb8,
+    Rgbx8,
+    Rgba4,
+    Rgba6,
+    Rgba8,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidColorFormatError;
+
+impl TryFrom<u32> for ColorFormat {
+    type Error = InvalidColorFormatError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Rgb565),
+            1 => Ok(Self::Rgb8),
+            2 => Ok(Self::Rgbx8),
+            3 => Ok(Self::Rgba4),
+            4 => Ok(Self::Rgba6),
+            5 => Ok(Self::Rgba8),
+            _ => Err(InvalidColorFormatError),
+        }
+    }
+}
+
+impl From<ColorFormat> for u32 {
+    fn from(value: ColorFormat) -> Self {
+        match value {
+            ColorFormat::Rgb565 => 0,
+            ColorFormat::Rgb8 => 1,
+            ColorFormat::Rgbx8 => 2,
+            ColorFormat::Rgba4 => 3,
+            ColorFormat::Rgba6 => 4,
+            ColorFormat::Rgba8 => 5,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum PositionElements {
+    Xy,
+    Xyz,
+}
+
+impl From<bool> for PositionElements {
+    fn from(value: bool) -> Self {
+        if value {
+            Self::Xyz
+        } else {
+            Self::Xy
+        }
+    }
+}
+
+impl From<PositionElements> for bool {
+    fn from(value: PositionElements) -> Self {
+        match value {
+            PositionElements::Xy => false,
+            PositionElements::Xyz => true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum NormalElements {
+    Normal,
+    NormalBinormalTangent,
+}
+
+impl From<bool> for NormalElements {
+    fn from(value: bool) -> Self {
+        if value {
+            Self::NormalBinormalTangent
+        } else {
+            Self::Normal
+        }
+    }
+}
+
+impl From<NormalElements> for bool {
+    fn from(value: NormalElements) -> Self {
+        match value {
+            NormalElements::Normal => false,
+            NormalElements::NormalBinormalTangent => true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ColorElements {
+    Rgb,
+    Rgba,
+}
+
+impl From<bool> for ColorElements {
+    fn from(value: bool) -> Self {
+        if value {
+            Self::Rgba
+        } else {
+            Self::Rgb
+        }
+    }
+}
+
+impl From<ColorElements> for bool {
+    fn from(value: ColorElements) -> Self {
+        match value {
+            ColorElements::Rgb => false,
+            ColorElements::Rgba => true,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TexCoordElements {
+    S,
+    St,
+}
+
+impl From<bool> for TexCoordElements {
+    fn from(value: bool) -> Self {
+        if value {
+            Self::St
+        } else {
+            Self::S
+        }
+    }
+}
+
+impl From<TexCoordElements> for bool {
+    fn from(value: TexCoordElements) -> Self {
+        match value {
+            TexCoordElements::S => false,
+            TexCoordElements::St => true,
+        }
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct MatrixIndexA(u32);
+
+impl MatrixIndexA {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn position_normal(&self) -> u8 {
+        self.0.get_bits(0..=5).try_into().unwrap()
+    }
+
+    pub fn with_position_normal(&mut self, index: u8) -> &mut Self {
+        self.0.set_bits(0..=5, index.into());
+        self
+    }
+
+    /// Texture matrix index for texture coordinates 0..=3.
+    pub fn tex_coord(&self, coord: usize) -> u8 {
+        assert!(
+            coord < 4,
+            "MatrixIndexA only holds texture coordinates 0..=3"
+        );
+        let base = 6 + coord * 6;
+        self.0.get_bits(base..base + 6).try_into().unwrap()
+    }
+
+    pub fn with_tex_coord(&mut self, coord: usize, index: u8) -> &mut Self {
+        assert!(
+            coord < 4,
+            "MatrixIndexA only holds texture coordinates 0..=3"
+        );
+        let base = 6 + coord * 6;
+        self.0.set_bits(base..base + 6, index.into());
+        self
+    }
+}
+
+impl Default for MatrixIndexA {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for MatrixIndexA {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<MatrixIndexA> for u32 {
+    fn from(value: MatrixIndexA) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for MatrixIndexA {
+    const ADDRESS: u8 = 0x30;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct MatrixIndexB(u32);
+
+impl MatrixIndexB {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Texture matrix index for texture coordinates 4..=7.
+    pub fn tex_coord(&self, coord: usize) -> u8 {
+        assert!(
+            (4..8).contains(&coord),
+            "MatrixIndexB only holds texture coordinates 4..=7"
+        );
+        let base = (coord - 4) * 6;
+        self.0.get_bits(base..base + 6).try_into().unwrap()
+    }
+
+    pub fn with_tex_coord(&mut self, coord: usize, index: u8) -> &mut Self {
+        assert!(
+            (4..8).contains(&coord),
+            "MatrixIndexB only holds texture coordinates 4..=7"
+        );
+        let base = (coord - 4) * 6;
+        self.0.set_bits(base..base + 6, index.into());
+        self
+    }
+}
+
+impl Default for MatrixIndexB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for MatrixIndexB {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<MatrixIndexB> for u32 {
+    fn from(value: MatrixIndexB) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for MatrixIndexB {
+    const ADDRESS: u8 = 0x40;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Low half of the vertex descriptor: matrix indices, position, normal and colors.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct VcdLo(u32);
+
+impl VcdLo {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn position_matrix_index(&self) -> bool {
+        self.0.get_bit(0)
+    }
+
+    pub fn with_position_matrix_index(&mut self, present: bool) -> &mut Self {
+        self.0.set_bit(0, present);
+        self
+    }
+
+    pub fn tex_matrix_index(&self, coord: usize) -> bool {
+        assert!(coord < 8, "There are only 8 texture matrix indices");
+        self.0.get_bit(1 + coord)
+    }
+
+    pub fn with_tex_matrix_index(&mut self, coord: usize, present: bool) -> &mut Self {
+        assert!(coord < 8, "There are only 8 texture matrix indices");
+        self.0.set_bit(1 + coord, present);
+        self
+    }
+
+    pub fn position(&self) -> AttributeType {
+        self.0.get_bits(9..=10).try_into().unwrap()
+    }
+
+    pub fn with_position(&mut self, ty: AttributeType) -> &mut Self {
+        self.0.set_bits(9..=10, ty.into());
+        self
+    }
+
+    pub fn normal(&self) -> AttributeType {
+        self.0.get_bits(11..=12).try_into().unwrap()
+    }
+
+    pub fn with_normal(&mut self, ty: AttributeType) -> &mut Self {
+        self.0.set_bits(11..=12, ty.into());
+        self
+    }
+
+    pub fn color(&self, channel: usize) -> AttributeType {
+        assert!(channel < 2, "There are only 2 color attributes");
+        let base = 13 + channel * 2;
+        self.0.get_bits(base..base + 2).try_into().unwrap()
+    }
+
+    pub fn with_color(&mut self, channel: usize, ty: AttributeType) -> &mut Self {
+        assert!(channel < 2, "There are only 2 color attributes");
+        let base = 13 + channel * 2;
+        self.0.set_bits(base..base + 2, ty.into());
+        self
+    }
+}
+
+impl Default for VcdLo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for VcdLo {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<VcdLo> for u32 {
+    fn from(value: VcdLo) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for VcdLo {
+    const ADDRESS: u8 = 0x50;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// High half of the vertex descriptor: the 8 texture coordinates.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct VcdHi(u32);
+
+impl VcdHi {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn tex_coord(&self, coord: usize) -> AttributeType {
+        assert!(coord < 8, "There are only 8 texture coordinates");
+        self.0
+            .get_bits(coord * 2..coord * 2 + 2)
+            .try_into()
+            .unwrap()
+    }
+
+    pub fn with_tex_coord(&mut self, coord: usize, ty: AttributeType) -> &mut Self {
+        assert!(coord < 8, "There are only 8 texture coordinates");
+        self.0.set_bits(coord * 2..coord * 2 + 2, ty.into());
+        self
+    }
+}
+
+impl Default for VcdHi {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for VcdHi {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<VcdHi> for u32 {
+    fn from(value: VcdHi) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for VcdHi {
+    const ADDRESS: u8 = 0x60;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// First vertex attribute table group: position, normal, both colors and texture coordinate 0.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct VatA(u32);
+
+impl VatA {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn position(&self) -> (PositionElements, ComponentType, u8) {
+        (
+            self.0.get_bit(0).into(),
+            self.0.get_bits(1..=3).try_into().unwrap(),
+            self.0.get_bits(4..=8).try_into().unwrap(),
+        )
+    }
+
+    pub fn with_position(
+        &mut self,
+        elements: PositionElements,
+        ty: ComponentType,
+        frac: u8,
+    ) -> &mut Self {
+        self.0.set_bit(0, elements.into());
+        self.0.set_bits(1..=3, ty.into());
+        self.0.set_bits(4..=8, frac.into());
+        self
+    }
+
+    pub fn normal(&self) -> (NormalElements, ComponentType) {
+        (
+            self.0.get_bit(9).into(),
+            self.0.get_bits(10..=12).try_into().unwrap(),
+        )
+    }
+
+    pub fn with_normal(&mut self, elements: NormalElements, ty: ComponentType) -> &mut Self {
+        self.0.set_bit(9, elements.into());
+        self.0.set_bits(10..=12, ty.into());
+        self
+    }
+
+    pub fn color(&self, channel: usize) -> (ColorElements, ColorFormat) {
+        assert!(channel < 2, "There are only 2 color attributes");
+        let base = 13 + channel * 4;
+        (
+            self.0.get_bit(base).into(),
+            self.0.get_bits(base + 1..base + 4).try_into().unwrap(),
+        )
+    }
+
+    pub fn with_color(
+        &mut self,
+        channel: usize,
+        elements: ColorElements,
+        format: ColorFormat,
+    ) -> &mut Self {
+        assert!(channel < 2, "There are only 2 color attributes");
+        let base = 13 + channel * 4;
+        self.0.set_bit(base, elements.into());
+        self.0.set_bits(base + 1..base + 4, format.into());
+        self
+    }
+
+    pub fn tex_coord(&self) -> (TexCoordElements, ComponentType, u8) {
+        (
+            self.0.get_bit(21).into(),
+            self.0.get_bits(22..=24).try_into().unwrap(),
+            self.0.get_bits(25..=29).try_into().unwrap(),
+        )
+    }
+
+    pub fn with_tex_coord(
+        &mut self,
+        elements: TexCoordElements,
+        ty: ComponentType,
+        frac: u8,
+    ) -> &mut Self {
+        self.0.set_bit(21, elements.into());
+        self.0.set_bits(22..=24, ty.into());
+        self.0.set_bits(25..=29, frac.into());
+        self
+    }
+
+    /// Must be set for 8 bit components to honor their fraction, every libogc program sets it.
+    pub fn byte_dequant(&self) -> bool {
+        self.0.get_bit(30)
+    }
+
+    pub fn with_byte_dequant(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(30, enable);
+        self
+    }
+
+    pub fn normal_index3(&self) -> bool {
+        self.0.get_bit(31)
+    }
+
+    pub fn with_normal_index3(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(31, enable);
+        self
+    }
+}
+
+impl Default for VatA {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for VatA {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<VatA> for u32 {
+    fn from(value: VatA) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for VatA {
+    const ADDRESS: u8 = 0x70;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Second vertex attribute table group: texture coordinates 1..=3 and the start of 4.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct VatB(u32);
+
+impl VatB {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn tex_coord(&self, coord: usize) -> (TexCoordElements, ComponentType, u8) {
+        assert!(
+            (1..4).contains(&coord),
+            "VatB only holds texture coordinates 1..=3"
+        );
+        let base = (coord - 1) * 9;
+        (
+            self.0.get_bit(base).into(),
+            self.0.get_bits(base + 1..base + 4).try_into().unwrap(),
+            self.0.get_bits(base + 4..base + 9).try_into().unwrap(),
+        )
+    }
+
+    pub fn with_tex_coord(
+        &mut self,
+        coord: usize,
+        elements: TexCoordElements,
+        ty: ComponentType,
+        frac: u8,
+    ) -> &mut Self {
+        assert!(
+            (1..4).contains(&coord),
+            "VatB only holds texture coordinates 1..=3"
+        );
+        let base = (coord - 1) * 9;
+        self.0.set_bit(base, elements.into());
+        self.0.set_bits(base + 1..base + 4, ty.into());
+        self.0.set_bits(base + 4..base + 9, frac.into());
+        self
+    }
+
+    /// Texture coordinate 4 is split, its fraction lives in `VatC`.
+    pub fn tex_coord4(&self) -> (TexCoordElements, ComponentType) {
+        (
+            self.0.get_bit(27).into(),
+            self.0.get_bits(28..=30).try_into().unwrap(),
+        )
+    }
+
+    pub fn with_tex_coord4(&mut self, elements: TexCoordElements, ty: ComponentType) -> &mut Self {
+        self.0.set_bit(27, elements.into());
+        self.0.set_bits(28..=30, ty.into());
+        self
+    }
+
+    /// Must always be set, the vertex cache misbehaves without it.
+    pub fn vcache_enhance(&self) -> bool {
+        self.0.get_bit(31)
+    }
+
+    pub fn with_vcache_enhance(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(31, enable);
+        self
+    }
+}
+
+impl Default for VatB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for VatB {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<VatB> for u32 {
+    fn from(value: VatB) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for VatB {
+    const ADDRESS: u8 = 0x80;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Third vertex attribute table group: the end of texture coordinate 4 and 5..=7.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct VatC(u32);
+
+impl VatC {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn tex_coord4_frac(&self) -> u8 {
+        self.0.get_bits(0..=4).try_into().unwrap()
+    }
+
+    pub fn with_tex_coord4_frac(&mut self, frac: u8) -> &mut Self {
+        self.0.set_bits(0..=4, frac.into());
+        self
+    }
+
+    pub fn tex_coord(&self, coord: usize) -> (TexCoordElements, ComponentType, u8) {
+        assert!(
+            (5..8).contains(&coord),
+            "VatC only holds texture coordinates 5..=7"
+        );
+        let base = 5 + (coord - 5) * 9;
+        (
+            self.0.get_bit(base).into(),
+            self.0.get_bits(base + 1..base + 4).try_into().unwrap(),
+            self.0.get_bits(base + 4..base + 9).try_into().unwrap(),
+        )
+    }
+
+    pub fn with_tex_coord(
+        &mut self,
+        coord: usize,
+        elements: TexCoordElements,
+        ty: ComponentType,
+        frac: u8,
+    ) -> &mut Self {
+        assert!(
+            (5..8).contains(&coord),
+            "VatC only holds texture coordinates 5..=7"
+        );
+        let base = 5 + (coord - 5) * 9;
+        self.0.set_bit(base, elements.into());
+        self.0.set_bits(base + 1..base + 4, ty.into());
+        self.0.set_bits(base + 4..base + 9, frac.into());
+        self
+    }
+}
+
+impl Default for VatC {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for VatC {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<VatC> for u32 {
+    fn from(value: VatC) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for VatC {
+    const ADDRESS: u8 = 0x90;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Physical base address of an indexed vertex attribute array.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ArrayBase(u32);
+
+impl ArrayBase {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn addr(&self) -> usize {
+        self.0.get_bits(0..=25).try_into().unwrap()
+    }
+
+    pub fn with_addr(&mut self, physical_addr: Physical<u8>) -> &mut Self {
+        self.0
+            .set_bits(0..=25, u32::try_from(physical_addr.addr()).unwrap());
+        self
+    }
+}
+
+impl Default for ArrayBase {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for ArrayBase {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ArrayBase> for u32 {
+    fn from(value: ArrayBase) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for ArrayBase {
+    const ADDRESS: u8 = 0xA0;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ArrayStride(u32);
+
+impl ArrayStride {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn stride(&self) -> u8 {
+        self.0.get_bits(0..=7).try_into().unwrap()
+    }
+
+    pub fn with_stride(&mut self, stride: u8) -> &mut Self {
+        self.0.set_bits(0..=7, stride.into());
+        self
+    }
+}
+
+impl Default for ArrayStride {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for ArrayStride {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ArrayStride> for u32 {
+    fn from(value: ArrayStride) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for ArrayStride {
+    const ADDRESS: u8 = 0xB0;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Vertex cache metric selection, the attribute its check/miss/stall counters look at and what
+/// CP performance counter 1 counts.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct PerfSelect(u32);
+
+impl PerfSelect {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// 0xF watches every attribute.
+    pub fn vcache_attribute(&self) -> u8 {
+        self.0.get_bits(0..=3).try_into().unwrap()
+    }
+
+    pub fn with_vcache_attribute(&mut self, attribute: u8) -> &mut Self {
+        self.0.set_bits(0..=3, attribute.into());
+        self
+    }
+
+    pub fn metric(&self) -> u8 {
+        self.0.get_bits(4..=7).try_into().unwrap()
+    }
+
+    pub fn with_metric(&mut self, metric: u8) -> &mut Self {
+        self.0.set_bits(4..=7, metric.into());
+        self
+    }
+}
+
+impl Default for PerfSelect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for PerfSelect {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PerfSelect> for u32 {
+    fn from(value: PerfSelect) -> Self {
+        value.0
+    }
+}
+
+impl CpRegister for PerfSelect {
+    const ADDRESS: u8 = 0x20;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
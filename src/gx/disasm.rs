@@ -0,0 +1,1328 @@
+//! Turns a GX command stream back into a readable listing.
+//!
+//! The `Disassembler` follows the CP vertex descriptor and vertex formats as the stream loads
+//! them, so draws can be split into their vertices. Streams that rely on state sent before them
+//! can be started off with `Disassembler::with_layout`.
+//!
+//! Registers are decoded straight from their bits rather than through the typed registers, since
+//! a broken stream can hold values those refuse. Nothing here touches hardware, it works the same
+//! on a FIFO recorded on the console and on the host.
+
+use core::fmt::{self, Write};
+
+use alloc::vec::Vec;
+use bit_field::BitField;
+
+use super::{
+    command::Opcode,
+    cp::{AttributeType, ColorFormat, ComponentType, CpRegister, VcdHi, VcdLo},
+    vertex::{Attribute, Primitive, VertexLayout},
+    xf::XfMemory,
+};
+
+/// Name, lowest and highest bit of every field of a register.
+type Fields = &'static [(&'static str, u8, u8)];
+
+/// One decoded command, borrowing its payload from the stream.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Command<'a> {
+    Nop,
+    InvalidateVertexCache,
+    /// Register address in bits 24..=31, value in 0..=23.
+    LoadBp(u32),
+    LoadCp {
+        address: u8,
+        value: u32,
+    },
+    /// `data` holds the big-endian words loaded from `address` on.
+    LoadXf {
+        address: u16,
+        data: &'a [u8],
+    },
+    /// Loads `count` XF words at `address` from entry `index` of CP array `array` (12..=15).
+    LoadIndexed {
+        array: u8,
+        index: u16,
+        address: u16,
+        count: u8,
+    },
+    CallDisplayList {
+        addr: u32,
+        size: u32,
+    },
+    Draw {
+        primitive: Primitive,
+        format: u8,
+        count: u16,
+        vertices: &'a [u8],
+    },
+    /// Not a command the GP knows, nothing after it can be decoded.
+    Unknown(u8),
+    /// A draw with a vertex format that can't be sized, nothing after it can be decoded.
+    InvalidVertexFormat(u8),
+    /// The stream ends in the middle of the command starting with this byte.
+    Truncated(u8),
+}
+
+/// How an attribute is laid out when sent direct.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Encoding {
+    MatrixIndex,
+    Components {
+        ty: ComponentType,
+        count: usize,
+        frac: u8,
+    },
+    Color(ColorFormat),
+}
+
+impl Encoding {
+    const fn size(self) -> usize {
+        match self {
+            Self::MatrixIndex => 1,
+            Self::Components { ty, count, .. } => count * component_size(ty),
+            Self::Color(ColorFormat::Rgb565 | ColorFormat::Rgba4) => 2,
+            Self::Color(ColorFormat::Rgb8 | ColorFormat::Rgba6) => 3,
+            Self::Color(ColorFormat::Rgbx8 | ColorFormat::Rgba8) => 4,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+struct Slot {
+    attribute: Attribute,
+    ty: AttributeType,
+    encoding: Encoding,
+    /// Normals with binormal and tangent can be sent as 3 separate indices.
+    indices: usize,
+}
+
+impl Slot {
+    const fn size(&self) -> usize {
+        match self.ty {
+            AttributeType::None => 0,
+            AttributeType::Direct => self.encoding.size(),
+            AttributeType::Index8 => self.indices,
+            AttributeType::Index16 => self.indices * 2,
+        }
+    }
+}
+
+/// Decodes GX commands, keeping the CP vertex state the stream loads along the way.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Disassembler {
+    vcd_lo: u32,
+    vcd_hi: u32,
+    vat: [[u32; 3]; 8],
+}
+
+impl Disassembler {
+    /// Starts with no vertex attributes enabled, like the GP after a reset.
+    pub const fn new() -> Self {
+        Self {
+            vcd_lo: 0,
+            vcd_hi: 0,
+            vat: [[0; 3]; 8],
+        }
+    }
+
+    /// Starts as if `layout` had already been set up.
+    #[must_use]
+    pub fn with_layout(mut self, layout: &VertexLayout) -> Self {
+        let (lo, hi) = layout.descriptor.registers();
+        self.vcd_lo = lo.value();
+        self.vcd_hi = hi.value();
+
+        let (a, b, c) = layout.format.registers();
+        self.vat[usize::from(layout.index)] = [a.value(), b.value(), c.value()];
+        self
+    }
+
+    /// Decodes the command at the start of `stream`, returning it with its size in bytes.
+    ///
+    /// CP loads of the vertex descriptor or formats take effect for the draws after them.
+    pub fn next_command<'a>(&mut self, stream: &'a [u8]) -> Option<(Command<'a>, usize)> {
+        let opcode = *stream.first()?;
+        let truncated = Some((Command::Truncated(opcode), stream.len()));
+
+        if let Some(primitive) = Primitive::from_opcode(opcode) {
+            let format = opcode & 0x7;
+            let Some(count) = read_u16(stream, 1) else {
+                return truncated;
+            };
+            let Some(slots) = self.slots(format) else {
+                return Some((Command::InvalidVertexFormat(opcode), stream.len()));
+            };
+            let size = 3 + usize::from(count) * slots.iter().map(Slot::size).sum::<usize>();
+            let Some(vertices) = stream.get(3..size) else {
+                return truncated;
+            };
+            let command = Command::Draw {
+                primitive,
+                format,
+                count,
+                vertices,
+            };
+            return Some((command, size));
+        }
+
+        let Ok(opcode) = Opcode::try_from(opcode) else {
+            return Some((Command::Unknown(opcode), stream.len()));
+        };
+
+        let decoded = match opcode {
+            Opcode::Nop => Some((Command::Nop, 1)),
+            Opcode::InvalidateVertexCache => Some((Command::InvalidateVertexCache, 1)),
+            Opcode::LoadBpReg => read_u32(stream, 1).map(|word| (Command::LoadBp(word), 5)),
+            Opcode::LoadCpReg => stream
+                .get(1)
+                .zip(read_u32(stream, 2))
+                .map(|(address, value)| {
+                    self.load_cp(*address, value);
+                    (
+                        Command::LoadCp {
+                            address: *address,
+                            value,
+                        },
+                        6,
+                    )
+                }),
+            Opcode::LoadXfReg => read_u32(stream, 1).and_then(|header| {
+                let count = usize::try_from(header.get_bits(16..=19)).unwrap() + 1;
+                let address = u16::try_from(header.get_bits(0..=15)).unwrap();
+                stream
+                    .get(5..5 + count * 4)
+                    .map(|data| (Command::LoadXf { address, data }, 5 + count * 4))
+            }),
+            Opcode::LoadIndexA | Opcode::LoadIndexB | Opcode::LoadIndexC | Opcode::LoadIndexD => {
+                read_u32(stream, 1).map(|word| {
+                    let command = Command::LoadIndexed {
+                        array: 12 + (u8::from(opcode) - u8::from(Opcode::LoadIndexA)) / 8,
+                        index: u16::try_from(word.get_bits(16..=31)).unwrap(),
+                        address: u16::try_from(word.get_bits(0..=11)).unwrap(),
+                        count: u8::try_from(word.get_bits(12..=15)).unwrap() + 1,
+                    };
+                    (command, 5)
+                })
+            }
+            Opcode::CallDisplayList => read_u32(stream, 1)
+                .zip(read_u32(stream, 5))
+                .map(|(addr, size)| (Command::CallDisplayList { addr, size }, 9)),
+        };
+
+        decoded.or(truncated)
+    }
+
+    /// Writes a listing of `stream`, one command per line prefixed with its offset.
+    ///
+    /// # Errors
+    ///
+    /// Only passes on errors from `out`.
+    pub fn disassemble(&mut self, stream: &[u8], out: &mut impl Write) -> fmt::Result {
+        let mut offset = 0;
+        while let Some((command, size)) = self.next_command(&stream[offset..]) {
+            write!(out, "{offset:06X}: ")?;
+            self.write_command(&command, out)?;
+            writeln!(out)?;
+            offset += size;
+        }
+        Ok(())
+    }
+
+    /// Writes `command` without a trailing newline, draws put each vertex on its own line.
+    ///
+    /// # Errors
+    ///
+    /// Only passes on errors from `out`.
+    pub fn write_command(&self, command: &Command, out: &mut impl Write) -> fmt::Result {
+        match *command {
+            Command::Nop => write!(out, "NOP"),
+            Command::InvalidateVertexCache => write!(out, "INVALIDATE_VERTEX_CACHE"),
+            Command::LoadBp(word) => write_bp(word, out),
+            Command::LoadCp { address, value } => write_cp(address, value, out),
+            Command::LoadXf { address, data } => write_xf(address, data, out),
+            Command::LoadIndexed {
+                array,
+                index,
+                address,
+                count,
+            } => {
+                let memory = match array {
+                    12 => "position matrix",
+                    13 => "normal matrix",
+                    14 => "post matrix",
+                    _ => "light",
+                };
+                write!(
+                    out,
+                    "XF  indexed {memory} array={array} index={index} address={address:#05X} count={count}"
+                )
+            }
+            Command::CallDisplayList { addr, size } => {
+                write!(out, "CALL_DISPLAY_LIST addr={addr:#010X} size={size:#X}")
+            }
+            Command::Draw {
+                primitive,
+                format,
+                count,
+                vertices,
+            } => self.write_draw(primitive, format, count, vertices, out),
+            Command::Unknown(opcode) => write!(out, "unknown opcode {opcode:#04X}, stopping"),
+            Command::InvalidVertexFormat(opcode) => write!(
+                out,
+                "draw {opcode:#04X} with an invalid vertex format {}, stopping",
+                opcode & 0x7
+            ),
+            Command::Truncated(opcode) => write!(out, "truncated command {opcode:#04X}"),
+        }
+    }
+
+    fn load_cp(&mut self, address: u8, value: u32) {
+        let format = usize::from(address & 0x7);
+        match address {
+            VcdLo::ADDRESS => self.vcd_lo = value,
+            VcdHi::ADDRESS => self.vcd_hi = value,
+            0x70..=0x77 => self.vat[format][0] = value,
+            0x80..=0x87 => self.vat[format][1] = value,
+            0x90..=0x97 => self.vat[format][2] = value,
+            _ => {}
+        }
+    }
+
+    /// The attributes of a vertex in `format`, in the order they are sent.
+    fn slots(&self, format: u8) -> Option<Vec<Slot>> {
+        let [a, b, c] = self.vat[usize::from(format)];
+        let attribute_type = |bits: u32| AttributeType::try_from(bits).unwrap();
+        let byte_dequant = a.get_bit(30);
+
+        // Element count bit, component type bits and fraction bits of one attribute.
+        let components = |elements: bool, counts: (usize, usize), ty: u32, frac: u32| {
+            let ty = ComponentType::try_from(ty).ok()?;
+            let frac = if matches!(ty, ComponentType::U8 | ComponentType::S8) && !byte_dequant {
+                0
+            } else {
+                u8::try_from(frac).unwrap()
+            };
+            Some(Encoding::Components {
+                ty,
+                count: if elements { counts.1 } else { counts.0 },
+                frac,
+            })
+        };
+
+        let mut slots = Vec::new();
+        let mut push = |attribute, ty, encoding: Option<Encoding>| {
+            if ty != AttributeType::None {
+                slots.push(Slot {
+                    attribute,
+                    ty,
+                    encoding: encoding?,
+                    indices: 1,
+                });
+            }
+            Some(())
+        };
+
+        let present = |bit| {
+            if bit {
+                AttributeType::Direct
+            } else {
+                AttributeType::None
+            }
+        };
+        push(
+            Attribute::PositionMatrixIndex,
+            present(self.vcd_lo.get_bit(0)),
+            Some(Encoding::MatrixIndex),
+        )?;
+        for coord in 0..8 {
+            push(
+                Attribute::TexMatrixIndex(coord),
+                present(self.vcd_lo.get_bit(1 + usize::from(coord))),
+                Some(Encoding::MatrixIndex),
+            )?;
+        }
+
+        push(
+            Attribute::Position,
+            attribute_type(self.vcd_lo.get_bits(9..=10)),
+            components(a.get_bit(0), (2, 3), a.get_bits(1..=3), a.get_bits(4..=8)),
+        )?;
+
+        // Normals use a fixed fraction: 6 bits for bytes, 14 for shorts.
+        let normal_ty = ComponentType::try_from(a.get_bits(10..=12)).ok();
+        let normal_frac = match normal_ty {
+            Some(ComponentType::U8 | ComponentType::S8) => 6,
+            Some(ComponentType::U16 | ComponentType::S16) => 14,
+            _ => 0,
+        };
+        push(
+            Attribute::Normal,
+            attribute_type(self.vcd_lo.get_bits(11..=12)),
+            normal_ty.map(|ty| Encoding::Components {
+                ty,
+                count: if a.get_bit(9) { 9 } else { 3 },
+                frac: normal_frac,
+            }),
+        )?;
+
+        for channel in 0..2 {
+            let base = 13 + usize::from(channel) * 4;
+            let vcd_base = 13 + usize::from(channel) * 2;
+            push(
+                Attribute::Color(channel),
+                attribute_type(self.vcd_lo.get_bits(vcd_base..vcd_base + 2)),
+                ColorFormat::try_from(a.get_bits(base + 1..base + 4))
+                    .ok()
+                    .map(Encoding::Color),
+            )?;
+        }
+
+        for coord in 0..8u8 {
+            let encoding = match coord {
+                0 => components(
+                    a.get_bit(21),
+                    (1, 2),
+                    a.get_bits(22..=24),
+                    a.get_bits(25..=29),
+                ),
+                1..=3 => {
+                    let base = usize::from(coord - 1) * 9;
+                    components(
+                        b.get_bit(base),
+                        (1, 2),
+                        b.get_bits(base + 1..base + 4),
+                        b.get_bits(base + 4..base + 9),
+                    )
+                }
+                4 => components(
+                    b.get_bit(27),
+                    (1, 2),
+                    b.get_bits(28..=30),
+                    c.get_bits(0..=4),
+                ),
+                _ => {
+                    let base = 5 + usize::from(coord - 5) * 9;
+                    components(
+                        c.get_bit(base),
+                        (1, 2),
+                        c.get_bits(base + 1..base + 4),
+                        c.get_bits(base + 4..base + 9),
+                    )
+                }
+            };
+            let vcd_base = usize::from(coord) * 2;
+            push(
+                Attribute::TexCoord(coord),
+                attribute_type(self.vcd_hi.get_bits(vcd_base..vcd_base + 2)),
+                encoding,
+            )?;
+        }
+
+        // Indexed normals with binormal and tangent can have an index for each of them.
+        if a.get_bit(9) && a.get_bit(31) {
+            if let Some(normal) = slots
+                .iter_mut()
+                .find(|slot| slot.attribute == Attribute::Normal)
+            {
+                normal.indices = 3;
+            }
+        }
+
+        Some(slots)
+    }
+
+    fn write_draw(
+        &self,
+        primitive: Primitive,
+        format: u8,
+        count: u16,
+        vertices: &[u8],
+        out: &mut impl Write,
+    ) -> fmt::Result {
+        write!(out, "DRAW {primitive:?} format={format} count={count}")?;
+
+        let Some(slots) = self.slots(format) else {
+            return Ok(());
+        };
+        let mut offset = 0;
+        for vertex in 0..count {
+            write!(out, "\n        {vertex:4}:")?;
+            for slot in &slots {
+                // A draw written with other vertex state than it was decoded with can run short.
+                let Some(data) = vertices.get(offset..offset + slot.size()) else {
+                    return write!(out, " truncated");
+                };
+                write!(out, " {}=", AttributeName(slot.attribute))?;
+                write_attribute(slot, data, out)?;
+                offset += slot.size();
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Disassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+const fn component_size(ty: ComponentType) -> usize {
+    match ty {
+        ComponentType::U8 | ComponentType::S8 => 1,
+        ComponentType::U16 | ComponentType::S16 => 2,
+        ComponentType::F32 => 4,
+    }
+}
+
+fn read_u16(stream: &[u8], at: usize) -> Option<u16> {
+    stream
+        .get(at..at + 2)
+        .map(|bytes| u16::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(stream: &[u8], at: usize) -> Option<u32> {
+    stream
+        .get(at..at + 4)
+        .map(|bytes| u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+/// Short names for the vertex listing.
+struct AttributeName(Attribute);
+
+impl fmt::Display for AttributeName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.0 {
+            Attribute::PositionMatrixIndex => write!(f, "pos_mtx"),
+            Attribute::TexMatrixIndex(coord) => write!(f, "tex{coord}_mtx"),
+            Attribute::Position => write!(f, "pos"),
+            Attribute::Normal => write!(f, "nrm"),
+            Attribute::Color(channel) => write!(f, "color{channel}"),
+            Attribute::TexCoord(coord) => write!(f, "tex{coord}"),
+        }
+    }
+}
+
+fn write_attribute(slot: &Slot, data: &[u8], out: &mut impl Write) -> fmt::Result {
+    match (slot.ty, slot.encoding) {
+        (AttributeType::Index8 | AttributeType::Index16, _) => {
+            let size = slot.size() / slot.indices;
+            write!(out, "[")?;
+            for (i, index) in data.chunks(size).enumerate() {
+                let index = if size == 1 {
+                    u16::from(index[0])
+                } else {
+                    u16::from_be_bytes([index[0], index[1]])
+                };
+                if i > 0 {
+                    write!(out, ", ")?;
+                }
+                write!(out, "{index}")?;
+            }
+            write!(out, "]")
+        }
+        (_, Encoding::MatrixIndex) => write!(out, "{}", data[0]),
+        (_, Encoding::Color(format)) => {
+            write!(out, "{format:?}:")?;
+            for byte in data {
+                write!(out, "{byte:02X}")?;
+            }
+            Ok(())
+        }
+        (_, Encoding::Components { ty, frac, .. }) => {
+            write!(out, "(")?;
+            for (i, component) in data.chunks(component_size(ty)).enumerate() {
+                if i > 0 {
+                    write!(out, ", ")?;
+                }
+                write!(out, "{}", component_value(ty, frac, component))?;
+            }
+            write!(out, ")")
+        }
+    }
+}
+
+fn component_value(ty: ComponentType, frac: u8, bytes: &[u8]) -> f32 {
+    let value = match ty {
+        ComponentType::U8 => f32::from(bytes[0]),
+        ComponentType::S8 => f32::from(i8::from_be_bytes([bytes[0]])),
+        ComponentType::U16 => f32::from(u16::from_be_bytes([bytes[0], bytes[1]])),
+        ComponentType::S16 => f32::from(i16::from_be_bytes([bytes[0], bytes[1]])),
+        ComponentType::F32 => {
+            return f32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+        }
+    };
+    (0..frac).fold(value, |value, _| value / 2.0)
+}
+
+fn write_fields(value: u32, fields: Fields, out: &mut impl Write) -> fmt::Result {
+    for (name, low, high) in fields {
+        let bits = value.get_bits(usize::from(*low)..=usize::from(*high));
+        write!(out, " {name}={bits}")?;
+    }
+    Ok(())
+}
+
+fn write_bp(word: u32, out: &mut impl Write) -> fmt::Result {
+    let address = u8::try_from(word.get_bits(24..=31)).unwrap();
+    let value = word.get_bits(0..=23);
+
+    match bp_register(address) {
+        Some((name, index, fields)) => {
+            write!(out, "BP  {address:02X} {name}")?;
+            if let Some(index) = index {
+                write!(out, "[{index}]")?;
+            }
+            write_fields(value, fields, out)
+        }
+        None => write!(out, "BP  {address:02X} = {value:#08X}"),
+    }
+}
+
+fn write_cp(address: u8, value: u32, out: &mut impl Write) -> fmt::Result {
+    match cp_register(address) {
+        Some((name, index, fields)) => {
+            write!(out, "CP  {address:02X} {name}")?;
+            if let Some(index) = index {
+                write!(out, "[{index}]")?;
+            }
+            write_fields(value, fields, out)
+        }
+        None => write!(out, "CP  {address:02X} = {value:#010X}"),
+    }
+}
+
+fn write_xf(address: u16, data: &[u8], out: &mut impl Write) -> fmt::Result {
+    let words = data
+        .chunks(4)
+        .map(|word| u32::from_be_bytes(word.try_into().unwrap()));
+
+    write!(out, "XF  {address:04X}")?;
+    if data.len() > 4 {
+        write!(out, "..{:04X}", usize::from(address) + data.len() / 4 - 1)?;
+    }
+
+    for (address, word) in (address..).zip(words) {
+        match XfMemory::from_address(address) {
+            Some(XfMemory::Register) => {
+                write!(out, "\n        {address:04X} ")?;
+                write_xf_register(address, word, out)?;
+            }
+            Some(memory @ XfMemory::Light) => {
+                let offset = address - memory.range().start;
+                write!(out, "\n        {address:04X} light[{}]", offset / 16)?;
+                match offset % 16 {
+                    3 => write!(out, " color={word:08X}")?,
+                    0..=2 => write!(out, " = {word:#010X}")?,
+                    _ => write!(out, " = {}", f32::from_bits(word))?,
+                }
+            }
+            Some(memory) => {
+                let offset = address - memory.range().start;
+                if offset % 4 == 0 {
+                    write!(
+                        out,
+                        "\n        {address:04X} {memory:?} row {}:",
+                        offset / 4
+                    )?;
+                }
+                write!(out, " {}", f32::from_bits(word))?;
+            }
+            None => write!(out, "\n        {address:04X} = {word:#010X}")?,
+        }
+    }
+    Ok(())
+}
+
+fn write_xf_register(address: u16, word: u32, out: &mut impl Write) -> fmt::Result {
+    const MATRIX_INDEX_A: Fields = &[
+        ("position_normal", 0, 5),
+        ("tex0", 6, 11),
+        ("tex1", 12, 17),
+        ("tex2", 18, 23),
+        ("tex3", 24, 29),
+    ];
+    const MATRIX_INDEX_B: Fields = &[
+        ("tex4", 0, 5),
+        ("tex5", 6, 11),
+        ("tex6", 12, 17),
+        ("tex7", 18, 23),
+    ];
+    const CHANNEL_CONTROL: Fields = &[
+        ("material_source", 0, 0),
+        ("lighting", 1, 1),
+        ("light_mask_lo", 2, 5),
+        ("ambient_source", 6, 6),
+        ("diffuse_fn", 7, 8),
+        ("attenuation", 9, 9),
+        ("spot", 10, 10),
+        ("light_mask_hi", 11, 14),
+    ];
+    const TEX_MTX_INFO: Fields = &[
+        ("projected", 1, 1),
+        ("abc1", 2, 2),
+        ("kind", 4, 6),
+        ("source_row", 7, 11),
+        ("emboss_source", 12, 14),
+        ("emboss_light", 15, 17),
+    ];
+    const POST_MTX_INFO: Fields = &[("row", 0, 5), ("normalize", 8, 8)];
+    const VIEWPORT: [&str; 6] = [
+        "scale_x", "scale_y", "scale_z", "offset_x", "offset_y", "offset_z",
+    ];
+
+    let (name, fields): (&str, Fields) = match address {
+        0x1006 => ("PerfSelect", &[("select", 0, 31)]),
+        0x1008 => (
+            "InVertexSpec",
+            &[
+                ("num_colors", 0, 1),
+                ("normals", 2, 3),
+                ("num_tex_coords", 4, 7),
+            ],
+        ),
+        0x1009 => ("NumColors", &[("count", 0, 1)]),
+        0x100A | 0x100B => {
+            return write!(out, "AmbientColor[{}] {word:08X}", address - 0x100A);
+        }
+        0x100C | 0x100D => {
+            return write!(out, "MaterialColor[{}] {word:08X}", address - 0x100C);
+        }
+        0x100E..=0x1011 => {
+            let names = ["Color0", "Color1", "Alpha0", "Alpha1"];
+            write!(
+                out,
+                "ChannelControl[{}]",
+                names[usize::from(address - 0x100E)]
+            )?;
+            return write_fields(word, CHANNEL_CONTROL, out);
+        }
+        0x1012 => ("DualTex", &[("enable", 0, 0)]),
+        0x1018 => ("MatrixIndexA", MATRIX_INDEX_A),
+        0x1019 => ("MatrixIndexB", MATRIX_INDEX_B),
+        0x101A..=0x101F => {
+            let name = VIEWPORT[usize::from(address - 0x101A)];
+            return write!(out, "Viewport {name}={}", f32::from_bits(word));
+        }
+        0x1020..=0x1025 => {
+            let index = address - 0x1020;
+            return write!(out, "Projection p{index}={}", f32::from_bits(word));
+        }
+        0x1026 => ("Projection", &[("orthographic", 0, 0)]),
+        0x103F => ("NumTexGens", &[("count", 0, 3)]),
+        0x1040..=0x1047 => {
+            write!(out, "TexMtxInfo[{}]", address - 0x1040)?;
+            return write_fields(word, TEX_MTX_INFO, out);
+        }
+        0x1050..=0x1057 => {
+            write!(out, "PostMtxInfo[{}]", address - 0x1050)?;
+            return write_fields(word, POST_MTX_INFO, out);
+        }
+        _ => return write!(out, "= {word:#010X}"),
+    };
+
+    write!(out, "{name}")?;
+    write_fields(word, fields, out)
+}
+
+const fn cp_register(address: u8) -> Option<(&'static str, Option<u8>, Fields)> {
+    const VAT_A: Fields = &[
+        ("pos_elements", 0, 0),
+        ("pos_type", 1, 3),
+        ("pos_frac", 4, 8),
+        ("nrm_elements", 9, 9),
+        ("nrm_type", 10, 12),
+        ("color0_elements", 13, 13),
+        ("color0_format", 14, 16),
+        ("color1_elements", 17, 17),
+        ("color1_format", 18, 20),
+        ("tex0_elements", 21, 21),
+        ("tex0_type", 22, 24),
+        ("tex0_frac", 25, 29),
+        ("byte_dequant", 30, 30),
+        ("normal_index3", 31, 31),
+    ];
+    const VAT_B: Fields = &[
+        ("tex1_elements", 0, 0),
+        ("tex1_type", 1, 3),
+        ("tex1_frac", 4, 8),
+        ("tex2_elements", 9, 9),
+        ("tex2_type", 10, 12),
+        ("tex2_frac", 13, 17),
+        ("tex3_elements", 18, 18),
+        ("tex3_type", 19, 21),
+        ("tex3_frac", 22, 26),
+        ("tex4_elements", 27, 27),
+        ("tex4_type", 28, 30),
+        ("vcache_enhance", 31, 31),
+    ];
+    const VAT_C: Fields = &[
+        ("tex4_frac", 0, 4),
+        ("tex5_elements", 5, 5),
+        ("tex5_type", 6, 8),
+        ("tex5_frac", 9, 13),
+        ("tex6_elements", 14, 14),
+        ("tex6_type", 15, 17),
+        ("tex6_frac", 18, 22),
+        ("tex7_elements", 23, 23),
+        ("tex7_type", 24, 26),
+        ("tex7_frac", 27, 31),
+    ];
+
+    let index = Some(address & 0xF);
+    Some(match address {
+        0x20 => (
+            "PerfSelect",
+            None,
+            &[("vcache_attribute", 0, 3), ("metric", 4, 7)],
+        ),
+        0x30 => (
+            "MatrixIndexA",
+            None,
+            &[
+                ("position_normal", 0, 5),
+                ("tex0", 6, 11),
+                ("tex1", 12, 17),
+                ("tex2", 18, 23),
+                ("tex3", 24, 29),
+            ],
+        ),
+        0x40 => (
+            "MatrixIndexB",
+            None,
+            &[
+                ("tex4", 0, 5),
+                ("tex5", 6, 11),
+                ("tex6", 12, 17),
+                ("tex7", 18, 23),
+            ],
+        ),
+        0x50 => (
+            "VcdLo",
+            None,
+            &[
+                ("pos_mtx", 0, 0),
+                ("tex0_mtx", 1, 1),
+                ("tex1_mtx", 2, 2),
+                ("tex2_mtx", 3, 3),
+                ("tex3_mtx", 4, 4),
+                ("tex4_mtx", 5, 5),
+                ("tex5_mtx", 6, 6),
+                ("tex6_mtx", 7, 7),
+                ("tex7_mtx", 8, 8),
+                ("pos", 9, 10),
+                ("nrm", 11, 12),
+                ("color0", 13, 14),
+                ("color1", 15, 16),
+            ],
+        ),
+        0x60 => (
+            "VcdHi",
+            None,
+            &[
+                ("tex0", 0, 1),
+                ("tex1", 2, 3),
+                ("tex2", 4, 5),
+                ("tex3", 6, 7),
+                ("tex4", 8, 9),
+                ("tex5", 10, 11),
+                ("tex6", 12, 13),
+                ("tex7", 14, 15),
+            ],
+        ),
+        0x70..=0x77 => ("VatA", index, VAT_A),
+        0x80..=0x87 => ("VatB", Some(address & 0x7), VAT_B),
+        0x90..=0x97 => ("VatC", Some(address & 0x7), VAT_C),
+        0xA0..=0xAF => ("ArrayBase", index, &[("addr", 0, 25)]),
+        0xB0..=0xBF => ("ArrayStride", index, &[("stride", 0, 7)]),
+        _ => return None,
+    })
+}
+
+fn bp_register(address: u8) -> Option<(&'static str, Option<u8>, Fields)> {
+    const SAMPLE_PATTERN: Fields = &[
+        ("x0", 0, 3),
+        ("y0", 4, 7),
+        ("x1", 8, 11),
+        ("y1", 12, 15),
+        ("x2", 16, 19),
+        ("y2", 20, 23),
+    ];
+    const TEV_ORDER: Fields = &[
+        ("map0", 0, 2),
+        ("coord0", 3, 5),
+        ("enable0", 6, 6),
+        ("channel0", 7, 9),
+        ("map1", 12, 14),
+        ("coord1", 15, 17),
+        ("enable1", 18, 18),
+        ("channel1", 19, 21),
+    ];
+    const TEV_COLOR_ENV: Fields = &[
+        ("d", 0, 3),
+        ("c", 4, 7),
+        ("b", 8, 11),
+        ("a", 12, 15),
+        ("bias", 16, 17),
+        ("subtract", 18, 18),
+        ("clamp", 19, 19),
+        ("scale", 20, 21),
+        ("dest", 22, 23),
+    ];
+    const TEV_ALPHA_ENV: Fields = &[
+        ("ras_swap", 0, 1),
+        ("tex_swap", 2, 3),
+        ("d", 4, 6),
+        ("c", 7, 9),
+        ("b", 10, 12),
+        ("a", 13, 15),
+        ("bias", 16, 17),
+        ("subtract", 18, 18),
+        ("clamp", 19, 19),
+        ("scale", 20, 21),
+        ("dest", 22, 23),
+    ];
+    const TEX_MODE0: Fields = &[
+        ("wrap_s", 0, 1),
+        ("wrap_t", 2, 3),
+        ("mag_filter", 4, 4),
+        ("min_filter", 5, 7),
+        ("diagonal_lod", 8, 8),
+        ("lod_bias", 9, 16),
+        ("max_anisotropy", 19, 20),
+        ("bias_clamp", 21, 21),
+    ];
+    const TEX_IMAGE0: Fields = &[
+        ("width_minus_1", 0, 9),
+        ("height_minus_1", 10, 19),
+        ("format", 20, 23),
+    ];
+    const TEX_IMAGE1: Fields = &[
+        ("tmem_addr", 0, 14),
+        ("cache_width", 15, 17),
+        ("cache_height", 18, 20),
+        ("preloaded", 21, 21),
+    ];
+    const TEX_IMAGE2: Fields = &[
+        ("tmem_addr", 0, 14),
+        ("cache_width", 15, 17),
+        ("cache_height", 18, 20),
+    ];
+
+    // Texture map registers repeat for maps 0..=3 and again 0x20 further for maps 4..=7.
+    let map = Some(address % 4 + u8::from(address.get_bit(5)) * 4);
+    let even_odd = Some(address.get_bits(1..=4));
+
+    Some(match address {
+        0x00 => (
+            "GenMode",
+            None,
+            &[
+                ("num_tex_gens", 0, 3),
+                ("num_color_channels", 4, 6),
+                ("multisampling", 9, 9),
+                ("num_tev_stages_minus_1", 10, 13),
+                ("cull_mode", 14, 15),
+                ("num_indirect_stages", 16, 18),
+                ("z_freeze", 19, 19),
+            ],
+        ),
+        0x01..=0x04 => ("SamplePattern", Some(address - 0x01), SAMPLE_PATTERN),
+        0x06..=0x0E => (
+            "IndMtx",
+            Some(address - 0x06),
+            &[("row0", 0, 10), ("row1", 11, 21), ("scale_bits", 22, 23)],
+        ),
+        0x10..=0x1F => (
+            "IndCmd",
+            Some(address - 0x10),
+            &[
+                ("ind_stage", 0, 1),
+                ("format", 2, 3),
+                ("bias", 4, 6),
+                ("alpha", 7, 8),
+                ("matrix", 9, 12),
+                ("wrap_s", 13, 15),
+                ("wrap_t", 16, 18),
+                ("unmodified_lod", 19, 19),
+                ("add_prev", 20, 20),
+            ],
+        ),
+        0x20 => ("ScissorTopLeft", None, &[("top", 0, 10), ("left", 12, 22)]),
+        0x21 => (
+            "ScissorBottomRight",
+            None,
+            &[("bottom", 0, 10), ("right", 12, 22)],
+        ),
+        0x22 => (
+            "SuLpSize",
+            None,
+            &[
+                ("line_width", 0, 7),
+                ("point_size", 8, 15),
+                ("line_offset", 16, 18),
+                ("point_offset", 19, 21),
+                ("line_aspect_ratio", 22, 22),
+            ],
+        ),
+        0x23 => ("SuPerfSelect", None, &[("select", 0, 23)]),
+        0x24 => ("RasPerfSelect", None, &[("select", 0, 23)]),
+        0x25 | 0x26 => (
+            "RasIndScale",
+            Some(address - 0x25),
+            &[
+                ("scale_s0", 0, 3),
+                ("scale_t0", 4, 7),
+                ("scale_s1", 8, 11),
+                ("scale_t1", 12, 15),
+            ],
+        ),
+        0x27 => (
+            "RasIndOrder",
+            None,
+            &[
+                ("map0", 0, 2),
+                ("coord0", 3, 5),
+                ("map1", 6, 8),
+                ("coord1", 9, 11),
+                ("map2", 12, 14),
+                ("coord2", 15, 17),
+                ("map3", 18, 20),
+                ("coord3", 21, 23),
+            ],
+        ),
+        0x28..=0x2F => ("TevStageOrders", Some(address - 0x28), TEV_ORDER),
+        0x40 => (
+            "ZMode",
+            None,
+            &[("enable", 0, 0), ("function", 1, 3), ("update", 4, 4)],
+        ),
+        0x41 => (
+            "BlendControl",
+            None,
+            &[
+                ("blend", 0, 0),
+                ("logic", 1, 1),
+                ("dither", 2, 2),
+                ("color_update", 3, 3),
+                ("alpha_update", 4, 4),
+                ("dst_factor", 5, 7),
+                ("src_factor", 8, 10),
+                ("subtract", 11, 11),
+                ("logic_op", 12, 15),
+            ],
+        ),
+        0x42 => ("ConstantAlpha", None, &[("alpha", 0, 7), ("enable", 8, 8)]),
+        0x43 => (
+            "PeControl",
+            None,
+            &[
+                ("pixel_format", 0, 2),
+                ("z_format", 3, 5),
+                ("z_compare_before_texture", 6, 6),
+            ],
+        ),
+        0x45 => ("PeDone", None, &[("finish", 1, 1)]),
+        0x47 => ("PeToken", None, &[("token", 0, 15)]),
+        0x48 => ("PeTokenInterrupt", None, &[("token", 0, 15)]),
+        0x49 => (
+            "EfbCopySourceTopLeft",
+            None,
+            &[("left", 0, 9), ("top", 10, 19)],
+        ),
+        0x4A => (
+            "EfbCopySourceSize",
+            None,
+            &[("width_minus_1", 0, 9), ("height_minus_1", 10, 19)],
+        ),
+        0x4B => ("EfbCopyDestination", None, &[("addr_div_32", 0, 23)]),
+        0x4D => ("EfbCopyStride", None, &[("stride_div_32", 0, 9)]),
+        0x4E => ("EfbCopyYScale", None, &[("y_scale", 0, 8)]),
+        0x4F => ("CopyClearAr", None, &[("red", 0, 7), ("alpha", 8, 15)]),
+        0x50 => ("CopyClearGb", None, &[("blue", 0, 7), ("green", 8, 15)]),
+        0x51 => ("CopyClearZ", None, &[("z", 0, 23)]),
+        0x52 => (
+            "EfbCopyControl",
+            None,
+            &[
+                ("clamp_top", 0, 0),
+                ("clamp_bottom", 1, 1),
+                ("texture_format", 3, 6),
+                ("gamma", 7, 8),
+                ("half_scale", 9, 9),
+                ("y_scale", 10, 10),
+                ("clear", 11, 11),
+                ("frame_to_field", 12, 13),
+                ("copy_to_xfb", 14, 14),
+                ("intensity_format", 15, 15),
+                ("auto_convert", 16, 16),
+            ],
+        ),
+        0x53 | 0x54 => (
+            "CopyFilter",
+            Some(address - 0x53),
+            &[("c0", 0, 5), ("c1", 6, 11), ("c2", 12, 17), ("c3", 18, 23)],
+        ),
+        0x57 => ("PePerfControl", None, &[("control", 0, 23)]),
+        0x59 => ("ScissorOffset", None, &[("x", 0, 9), ("y", 10, 19)]),
+        0x64 => ("TlutLoadSource", None, &[("addr_div_32", 0, 23)]),
+        0x65 => (
+            "TlutLoadDestination",
+            None,
+            &[("tmem_addr", 0, 9), ("lines", 10, 20)],
+        ),
+        0x66 => (
+            "TexCacheInvalidate",
+            None,
+            &[("line", 0, 8), ("size", 9, 12)],
+        ),
+        0x67 => ("TxPerfSelect", None, &[("select", 0, 23)]),
+        0x80..=0x83 | 0xA0..=0xA3 => ("TexMode0", map, TEX_MODE0),
+        0x84..=0x87 | 0xA4..=0xA7 => ("TexMode1", map, &[("min_lod", 0, 7), ("max_lod", 8, 15)]),
+        0x88..=0x8B | 0xA8..=0xAB => ("TexImage0", map, TEX_IMAGE0),
+        0x8C..=0x8F | 0xAC..=0xAF => ("TexImage1", map, TEX_IMAGE1),
+        0x90..=0x93 | 0xB0..=0xB3 => ("TexImage2", map, TEX_IMAGE2),
+        0x94..=0x97 | 0xB4..=0xB7 => ("TexImage3", map, &[("addr_div_32", 0, 23)]),
+        0x98..=0x9B | 0xB8..=0xBB => ("TexTlut", map, &[("tmem_addr", 0, 9), ("format", 10, 11)]),
+        0xC0..=0xDF if address % 2 == 0 => ("TevColorEnv", even_odd, TEV_COLOR_ENV),
+        0xC1..=0xDF => ("TevAlphaEnv", even_odd, TEV_ALPHA_ENV),
+        0xE0..=0xE7 if address % 2 == 0 => (
+            "TevColorRa",
+            Some(address.get_bits(1..=2)),
+            &[("red", 0, 10), ("alpha", 12, 22), ("konst", 23, 23)],
+        ),
+        0xE1..=0xE7 => (
+            "TevColorBg",
+            Some(address.get_bits(1..=2)),
+            &[("blue", 0, 10), ("green", 12, 22), ("konst", 23, 23)],
+        ),
+        0xE8 => (
+            "FogRange",
+            None,
+            &[("center_plus_342", 0, 9), ("enable", 10, 10)],
+        ),
+        0xE9..=0xED => (
+            "FogRangeK",
+            Some(address - 0xE9),
+            &[("low", 0, 11), ("high", 12, 23)],
+        ),
+        0xEE => (
+            "FogParam0",
+            None,
+            &[
+                ("a_mantissa", 0, 10),
+                ("a_exponent", 11, 18),
+                ("a_sign", 19, 19),
+            ],
+        ),
+        0xEF => ("FogParam1", None, &[("b_magnitude", 0, 23)]),
+        0xF0 => ("FogParam2", None, &[("b_shift", 0, 4)]),
+        0xF1 => (
+            "FogParam3",
+            None,
+            &[
+                ("c_mantissa", 0, 10),
+                ("c_exponent", 11, 18),
+                ("c_sign", 19, 19),
+                ("projection", 20, 20),
+                ("fog_type", 21, 23),
+            ],
+        ),
+        0xF2 => (
+            "FogColor",
+            None,
+            &[("blue", 0, 7), ("green", 8, 15), ("red", 16, 23)],
+        ),
+        0xF3 => (
+            "AlphaCompare",
+            None,
+            &[
+                ("ref0", 0, 7),
+                ("ref1", 8, 15),
+                ("comp0", 16, 18),
+                ("comp1", 19, 21),
+                ("op", 22, 23),
+            ],
+        ),
+        0xF6..=0xFD => (
+            "TevKSel",
+            Some(address - 0xF6),
+            &[
+                ("swap_rb", 0, 1),
+                ("swap_ga", 2, 3),
+                ("konst_color0", 4, 8),
+                ("konst_alpha0", 9, 13),
+                ("konst_color1", 14, 18),
+                ("konst_alpha1", 19, 23),
+            ],
+        ),
+        0xFE => ("BpMask", None, &[("mask", 0, 23)]),
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::String, vec};
+
+    use super::*;
+
+    fn listing(stream: &[u8]) -> String {
+        let mut out = String::new();
+        Disassembler::new().disassemble(stream, &mut out).unwrap();
+        out
+    }
+
+    fn bp(word: u32) -> Vec<u8> {
+        [&[0x61][..], &word.to_be_bytes()].concat()
+    }
+
+    fn cp(address: u8, value: u32) -> Vec<u8> {
+        [&[0x08, address][..], &value.to_be_bytes()].concat()
+    }
+
+    fn xf(address: u16, words: &[u32]) -> Vec<u8> {
+        let header = u32::try_from(words.len() - 1).unwrap() << 16 | u32::from(address);
+        let mut stream = vec![0x10];
+        stream.extend(header.to_be_bytes());
+        stream.extend(words.iter().flat_map(|word| word.to_be_bytes()));
+        stream
+    }
+
+    #[test]
+    fn register_loads() {
+        let stream = [
+            bp(0x00_000411),
+            bp(0x05_000012),
+            cp(0xA3, 0x0012_3440),
+            xf(0x1009, &[2]),
+            xf(0x0000, &[1.0f32, 0.0, 0.0, 2.0].map(f32::to_bits)),
+        ]
+        .concat();
+
+        assert_eq!(
+            listing(&stream),
+            "000000: BP  00 GenMode num_tex_gens=1 num_color_channels=1 multisampling=0 \
+             num_tev_stages_minus_1=1 cull_mode=0 num_indirect_stages=0 z_freeze=0\n\
+             000005: BP  05 = 0x000012\n\
+             00000A: CP  A3 ArrayBase[3] addr=1193024\n\
+             000010: XF  1009\n        1009 NumColors count=2\n\
+             000019: XF  0000..0003\n        0000 PositionMatrix row 0: 1 0 0 2\n"
+        );
+    }
+
+    #[test]
+    fn indexed_xf_loads() {
+        let stream = [
+            [0x20, 0x00, 0x05, 0xB0, 0x00],
+            [0x28, 0x00, 0x01, 0x84, 0x00],
+            [0x38, 0x00, 0x02, 0xF6, 0x00],
+        ]
+        .concat();
+
+        assert_eq!(
+            listing(&stream),
+            "000000: XF  indexed position matrix array=12 index=5 address=0x000 count=12\n\
+             000005: XF  indexed normal matrix array=13 index=1 address=0x400 count=9\n\
+             00000A: XF  indexed light array=15 index=2 address=0x600 count=16\n"
+        );
+    }
+
+    #[test]
+    fn draw_uses_format_loaded_earlier() {
+        // Position as three S16 with 8 fraction bits and color 0 as RGBA8, both direct.
+        let vertices = [
+            [0x01, 0x00, 0xFE, 0x00, 0x00, 0x80, 0xFF, 0x00, 0x00, 0xFF],
+            [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xFF, 0x00, 0xFF],
+        ];
+        let stream = [
+            cp(0x50, 0x2200),
+            cp(0x70, 0x0001_4087),
+            vec![0xA8, 0x00, 0x02],
+            vertices.concat(),
+            vec![0x00],
+        ]
+        .concat();
+
+        let listing = listing(&stream);
+        let lines: Vec<&str> = listing.lines().skip(2).collect();
+        assert_eq!(
+            lines,
+            [
+                "00000C: DRAW Lines format=0 count=2",
+                "           0: pos=(1, -2, 0.5) color0=Rgba8:FF0000FF",
+                "           1: pos=(0, 0, 0) color0=Rgba8:00FF00FF",
+                "000023: NOP",
+            ]
+        );
+    }
+
+    #[test]
+    fn draw_with_other_vertex_state_is_truncated() {
+        let mut disassembler = Disassembler::new();
+        let setup = [cp(0x50, 0x2200), cp(0x70, 0x0001_4087)].concat();
+        disassembler
+            .disassemble(&setup, &mut String::new())
+            .unwrap();
+
+        // One whole vertex, then a byte of the next.
+        let draw = Command::Draw {
+            primitive: Primitive::Lines,
+            format: 0,
+            count: 2,
+            vertices: &[
+                0x01, 0x00, 0xFE, 0x00, 0x00, 0x80, 0xFF, 0x00, 0x00, 0xFF, 0x00,
+            ],
+        };
+        let mut out = String::new();
+        disassembler.write_command(&draw, &mut out).unwrap();
+        assert_eq!(
+            out,
+            "DRAW Lines format=0 count=2\n           0: pos=(1, -2, 0.5) color0=Rgba8:FF0000FF\n           1: truncated"
+        );
+    }
+
+    #[test]
+    fn call_display_list() {
+        let stream = [
+            0x00, 0x48, 0x40, 0x80, 0x12, 0x34, 0x00, 0x00, 0x00, 0x01, 0x20,
+        ];
+
+        assert_eq!(
+            listing(&stream),
+            "000000: NOP\n\
+             000001: INVALIDATE_VERTEX_CACHE\n\
+             000002: CALL_DISPLAY_LIST addr=0x80123400 size=0x120\n"
+        );
+    }
+
+    #[test]
+    fn truncated_commands() {
+        assert_eq!(
+            listing(&[0x00, 0x61, 0x00, 0x00]),
+            "000000: NOP\n000001: truncated command 0x61\n"
+        );
+        assert_eq!(
+            listing(&xf(0x1009, &[2, 3])[..9]),
+            "000000: truncated command 0x10\n"
+        );
+
+        // Two vertices of two U8 position components each, with only one sent.
+        let stream = [cp(0x50, 0x200), vec![0xB8, 0x00, 0x02, 0x01, 0x02]].concat();
+        assert!(listing(&stream).ends_with("000006: truncated command 0xB8\n"));
+    }
+
+    #[test]
+    fn unknown_opcodes_stop_decoding() {
+        assert_eq!(
+            listing(&[0x00, 0x18, 0x00, 0x00]),
+            "000000: NOP\n000001: unknown opcode 0x18, stopping\n"
+        );
+
+        // Position enabled with component type 7, which doesn't exist.
+        let stream = [cp(0x50, 0x200), cp(0x71, 0xE), vec![0xB9, 0x00, 0x01, 0x00]].concat();
+        assert!(listing(&stream)
+            .ends_with("00000C: draw 0xB9 with an invalid vertex format 1, stopping\n"));
+    }
+}
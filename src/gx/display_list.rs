@@ -0,0 +1,107 @@
+use alloc::vec::Vec;
+
+use crate::{cache::dc_flush_range, os::Align32};
+
+use super::command::{CommandSink, Opcode};
+
+/// Records GX commands into memory instead of sending them down the write-gather pipe.
+///
+/// Every `GxCommands` call works on the builder, `finish` then pads and flushes it into a
+/// `DisplayList` that the GP can read with `Fifo::call_display_list`.
+pub struct DisplayListBuilder {
+    chunks: Vec<Align32<[u8; 32]>>,
+    len: usize,
+}
+
+impl DisplayListBuilder {
+    pub const fn new() -> Self {
+        Self {
+            chunks: Vec::new(),
+            len: 0,
+        }
+    }
+
+    /// Preallocates room for `capacity` bytes of commands.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            chunks: Vec::with_capacity(capacity.div_ceil(32)),
+            len: 0,
+        }
+    }
+
+    pub const fn len(&self) -> usize {
+        self.len
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn push(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            if self.len == self.chunks.len() * 32 {
+                self.chunks.push(Align32([0u8; 32]));
+            }
+            self.chunks[self.len / 32].0[self.len % 32] = *byte;
+            self.len += 1;
+        }
+    }
+
+    /// Pads the list with NOPs up to the next 32 byte boundary and flushes it out of the data
+    /// cache so the GP sees what was recorded.
+    pub fn finish(mut self) -> DisplayList {
+        while self.len % 32 != 0 {
+            self.push(&[Opcode::Nop.into()]);
+        }
+
+        let list = DisplayList {
+            chunks: self.chunks,
+        };
+        dc_flush_range(list.as_ptr(), list.len());
+        list
+    }
+}
+
+impl Default for DisplayListBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommandSink for DisplayListBuilder {
+    fn write_u8(&mut self, value: u8) {
+        self.push(&[value]);
+    }
+
+    fn write_u16(&mut self, value: u16) {
+        self.push(&value.to_be_bytes());
+    }
+
+    fn write_u32(&mut self, value: u32) {
+        self.push(&value.to_be_bytes());
+    }
+}
+
+/// A finished, 32 byte aligned and padded display list.
+pub struct DisplayList {
+    chunks: Vec<Align32<[u8; 32]>>,
+}
+
+impl DisplayList {
+    pub fn as_ptr(&self) -> *const u8 {
+        self.chunks.as_ptr().cast::<u8>()
+    }
+
+    /// Size in bytes, always a multiple of 32.
+    pub fn len(&self) -> usize {
+        self.chunks.len() * 32
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        unsafe { core::slice::from_raw_parts(self.as_ptr(), self.len()) }
+    }
+}
@@ -0,0 +1,223 @@
+//! CPU access to the EFB through its physical aperture.
+//!
+//! The EFB shows up at physical `0x0800_0000` with one 32 bit word per pixel, 4 bytes per column
+//! and 4 KiB per row, and Z 4 MiB further in. Every access goes through the pixel engine, which
+//! converts colors to and from ARGB8 words and runs Z writes through the test set up with
+//! `set_poke_z_mode`. Draws still in flight land after the reads, so wait for them with
+//! `Fifo::wait_draw_done` first.
+
+use voladdress::{Safe, VolAddress};
+
+use super::{
+    bp::PeControl,
+    pe::{CompareFn, PixelFormat, ZFormat},
+};
+use crate::mmio::{
+    pe::{AlphaRead, AlphaReadMode, ZConfig},
+    vi::Enabled,
+};
+
+/// Largest EFB size, in pixels.
+pub const WIDTH: u16 = 640;
+pub const HEIGHT: u16 = 528;
+
+/// Uncached mirror of the aperture.
+const APERTURE: usize = 0xC800_0000;
+
+/// Offset of the Z words from the color words.
+const Z_OFFSET: usize = 0x40_0000;
+
+/// Where `Efb::read_rgba` takes its pixels from.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Readback {
+    /// Colors, with alpha read back as set by the mode.
+    Color(AlphaReadMode),
+    /// The top 8 bits of Z as gray with full alpha, for looking at the depth buffer.
+    Z,
+}
+
+/// The EFB as laid out by one pixel format.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Efb {
+    format: PixelFormat,
+    z_format: ZFormat,
+}
+
+impl Efb {
+    pub const fn new(format: PixelFormat, z_format: ZFormat) -> Self {
+        Self { format, z_format }
+    }
+
+    /// The layout set by the EFB format half of `control`.
+    pub fn from_control(control: PeControl) -> Self {
+        Self::new(control.pixel_format(), control.z_format())
+    }
+
+    pub const fn format(&self) -> PixelFormat {
+        self.format
+    }
+
+    pub const fn z_format(&self) -> ZFormat {
+        self.z_format
+    }
+
+    /// Whether the format stores an alpha channel, without one reads get alpha from
+    /// `AlphaRead`.
+    pub const fn has_alpha(&self) -> bool {
+        matches!(self.format, PixelFormat::Rgba6Z24)
+    }
+
+    /// Bits of Z a pixel holds, 16 for `Rgb565Z16` and 24 otherwise.
+    pub const fn z_bits(&self) -> u32 {
+        match self.format {
+            PixelFormat::Rgb565Z16 => 16,
+            _ => 24,
+        }
+    }
+
+    /// Largest Z value, what the far plane and a default clear end up as.
+    pub const fn max_z(&self) -> u32 {
+        (1 << self.z_bits()) - 1
+    }
+
+    /// RGBA color of the pixel at `x`, `y`.
+    pub fn peek_color(&self, x: u16, y: u16) -> [u8; 4] {
+        self.assert_color();
+        let [alpha, red, green, blue] = pixel(x, y, 0).read().to_be_bytes();
+        [red, green, blue, alpha]
+    }
+
+    /// Writes `color` over the pixel at `x`, `y`, rounded down to what the format stores. The
+    /// write skips blending and the Z test.
+    pub fn poke_color(&self, x: u16, y: u16, color: [u8; 4]) {
+        self.assert_color();
+        let [red, green, blue, alpha] = self.quantize(color);
+        pixel(x, y, 0).write(u32::from_be_bytes([alpha, red, green, blue]));
+    }
+
+    /// Z of the pixel at `x`, `y`, in `z_bits` bits.
+    pub fn peek_z(&self, x: u16, y: u16) -> u32 {
+        let z = pixel(x, y, Z_OFFSET).read() & 0xFF_FFFF;
+        z >> (24 - self.z_bits())
+    }
+
+    /// Writes `z` to the pixel at `x`, `y` if it passes the test set with `set_poke_z_mode`.
+    pub fn poke_z(&self, x: u16, y: u16, z: u32) {
+        assert!(z <= self.max_z(), "Z doesn't fit in the pixel format");
+        pixel(x, y, Z_OFFSET).write(z << (24 - self.z_bits()));
+    }
+
+    /// Reads the `width` x `height` rectangle at `left`, `top` into `out` row by row.
+    ///
+    /// Color reads switch `AlphaRead` to the requested mode for the duration of the readback.
+    pub fn read_rgba(
+        &self,
+        left: u16,
+        top: u16,
+        width: u16,
+        height: u16,
+        source: Readback,
+        out: &mut [[u8; 4]],
+    ) {
+        assert!(
+            u32::from(left) + u32::from(width) <= u32::from(WIDTH)
+                && u32::from(top) + u32::from(height) <= u32::from(HEIGHT),
+            "Readback rectangle is outside of the EFB"
+        );
+        assert!(
+            out.len() >= usize::from(width) * usize::from(height),
+            "Readback buffer is smaller than the rectangle"
+        );
+
+        let previous = AlphaRead::read();
+        if let Readback::Color(mode) = source {
+            self.assert_color();
+            AlphaRead::new().with_mode(mode).write();
+        }
+
+        let coords = (top..top + height).flat_map(|y| (left..left + width).map(move |x| (x, y)));
+        for ((x, y), rgba) in coords.zip(out.iter_mut()) {
+            *rgba = match source {
+                Readback::Color(_) => self.peek_color(x, y),
+                Readback::Z => {
+                    let gray = u8::try_from(self.peek_z(x, y) >> (self.z_bits() - 8)).unwrap();
+                    [gray, gray, gray, 0xFF]
+                }
+            };
+        }
+
+        previous.write();
+    }
+
+    fn assert_color(self) {
+        assert!(
+            matches!(
+                self.format,
+                PixelFormat::Rgb8Z24 | PixelFormat::Rgba6Z24 | PixelFormat::Rgb565Z16
+            ),
+            "The EFB pixel format has no color"
+        );
+    }
+
+    /// Drops the bits the format has no room for, repeating the top bits into the low ones like
+    /// the PE does when it reads them back.
+    const fn quantize(self, color: [u8; 4]) -> [u8; 4] {
+        let [red, green, blue, alpha] = color;
+        match self.format {
+            PixelFormat::Rgba6Z24 => [
+                expand(red, 6),
+                expand(green, 6),
+                expand(blue, 6),
+                expand(alpha, 6),
+            ],
+            PixelFormat::Rgb565Z16 => [expand(red, 5), expand(green, 6), expand(blue, 5), 0xFF],
+            _ => [red, green, blue, 0xFF],
+        }
+    }
+}
+
+/// Sets the alpha CPU reads see, `Stored` only differs from `Full` with `PixelFormat::Rgba6Z24`.
+pub fn set_alpha_read(mode: AlphaReadMode) {
+    AlphaRead::new().with_mode(mode).write();
+}
+
+/// Sets the Z test and update applied to `Efb::poke_z`, the poked Z is on the left.
+pub fn set_poke_z_mode(enable: bool, function: CompareFn, update: bool) {
+    ZConfig::new()
+        .with_compare_enable(enable.into())
+        .with_compare_fn(u32::from(function).try_into().unwrap())
+        .with_update_enable(update.into())
+        .write();
+}
+
+/// Turns off the poke Z test and lets every Z write through, which is what plain overlays want.
+pub fn reset_poke_z_mode() {
+    ZConfig::new()
+        .with_compare_enable(Enabled::Disabled)
+        .with_update_enable(Enabled::Enabled)
+        .write();
+}
+
+const fn expand(channel: u8, bits: u32) -> u8 {
+    let top = channel >> (8 - bits);
+    (top << (8 - bits)) | (top >> (2 * bits - 8))
+}
+
+fn pixel(x: u16, y: u16, offset: usize) -> VolAddress<u32, Safe, Safe> {
+    assert!(x < WIDTH && y < HEIGHT, "Pixel is outside of the EFB");
+    let address = APERTURE + offset + (usize::from(x) << 2) + (usize::from(y) << 12);
+    // Safety: every pixel of the EFB has a word in the aperture.
+    unsafe { VolAddress::new(address) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "Readback rectangle is outside of the EFB")]
+    fn read_rgba_rejects_rectangles_wrapping_around() {
+        let efb = Efb::new(PixelFormat::Rgb8Z24, ZFormat::Linear);
+        efb.read_rgba(u16::MAX, 0, 2, 1, Readback::Z, &mut [[0; 4]; 2]);
+    }
+}
@@ -0,0 +1,546 @@
+//! Indirect texturing.
+//!
+//! Up to 4 indirect stages each sample a texture whose texels are used as offsets. A TEV stage
+//! can pick one of those results, run it through one of 3 indirect matrices and add it to its own
+//! texture coordinate before the lookup, which is what bump mapping, heat haze and warping effects
+//! are built from.
+
+use bit_field::BitField;
+
+use super::bp::{IndCmd, IndMtx, RasIndOrder, RasIndScale};
+
+/// How many bits of each indirect texel channel are used as offset.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum IndFormat {
+    Bits8,
+    Bits5,
+    Bits4,
+    Bits3,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidIndFormatError;
+
+impl TryFrom<u32> for IndFormat {
+    type Error = InvalidIndFormatError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Bits8),
+            1 => Ok(Self::Bits5),
+            2 => Ok(Self::Bits4),
+            3 => Ok(Self::Bits3),
+            _ => Err(InvalidIndFormatError),
+        }
+    }
+}
+
+impl From<IndFormat> for u32 {
+    fn from(value: IndFormat) -> Self {
+        match value {
+            IndFormat::Bits8 => 0,
+            IndFormat::Bits5 => 1,
+            IndFormat::Bits4 => 2,
+            IndFormat::Bits3 => 3,
+        }
+    }
+}
+
+/// Offset components that get a bias added, -128 for 8 bit offsets and +1 for the others.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum IndBias {
+    None,
+    S,
+    T,
+    St,
+    U,
+    Su,
+    Tu,
+    Stu,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidIndBiasError;
+
+impl TryFrom<u32> for IndBias {
+    type Error = InvalidIndBiasError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::S),
+            2 => Ok(Self::T),
+            3 => Ok(Self::St),
+            4 => Ok(Self::U),
+            5 => Ok(Self::Su),
+            6 => Ok(Self::Tu),
+            7 => Ok(Self::Stu),
+            _ => Err(InvalidIndBiasError),
+        }
+    }
+}
+
+impl From<IndBias> for u32 {
+    fn from(value: IndBias) -> Self {
+        match value {
+            IndBias::None => 0,
+            IndBias::S => 1,
+            IndBias::T => 2,
+            IndBias::St => 3,
+            IndBias::U => 4,
+            IndBias::Su => 5,
+            IndBias::Tu => 6,
+            IndBias::Stu => 7,
+        }
+    }
+}
+
+/// Offset component whose leftover low bits are sent to the TEV as bump alpha.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum IndAlpha {
+    Off,
+    S,
+    T,
+    U,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidIndAlphaError;
+
+impl TryFrom<u32> for IndAlpha {
+    type Error = InvalidIndAlphaError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Off),
+            1 => Ok(Self::S),
+            2 => Ok(Self::T),
+            3 => Ok(Self::U),
+            _ => Err(InvalidIndAlphaError),
+        }
+    }
+}
+
+impl From<IndAlpha> for u32 {
+    fn from(value: IndAlpha) -> Self {
+        match value {
+            IndAlpha::Off => 0,
+            IndAlpha::S => 1,
+            IndAlpha::T => 2,
+            IndAlpha::U => 3,
+        }
+    }
+}
+
+/// Wraps the regular texture coordinate before the offset is added, `Zero` drops it entirely.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum IndWrap {
+    Off,
+    Wrap256,
+    Wrap128,
+    Wrap64,
+    Wrap32,
+    Wrap16,
+    Zero,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidIndWrapError;
+
+impl TryFrom<u32> for IndWrap {
+    type Error = InvalidIndWrapError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Off),
+            1 => Ok(Self::Wrap256),
+            2 => Ok(Self::Wrap128),
+            3 => Ok(Self::Wrap64),
+            4 => Ok(Self::Wrap32),
+            5 => Ok(Self::Wrap16),
+            6 => Ok(Self::Zero),
+            _ => Err(InvalidIndWrapError),
+        }
+    }
+}
+
+impl From<IndWrap> for u32 {
+    fn from(value: IndWrap) -> Self {
+        match value {
+            IndWrap::Off => 0,
+            IndWrap::Wrap256 => 1,
+            IndWrap::Wrap128 => 2,
+            IndWrap::Wrap64 => 3,
+            IndWrap::Wrap32 => 4,
+            IndWrap::Wrap16 => 5,
+            IndWrap::Zero => 6,
+        }
+    }
+}
+
+/// Divides the texture coordinate an indirect stage samples with.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum IndScale {
+    Div1,
+    Div2,
+    Div4,
+    Div8,
+    Div16,
+    Div32,
+    Div64,
+    Div128,
+    Div256,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidIndScaleError;
+
+impl TryFrom<u32> for IndScale {
+    type Error = InvalidIndScaleError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Div1),
+            1 => Ok(Self::Div2),
+            2 => Ok(Self::Div4),
+            3 => Ok(Self::Div8),
+            4 => Ok(Self::Div16),
+            5 => Ok(Self::Div32),
+            6 => Ok(Self::Div64),
+            7 => Ok(Self::Div128),
+            8 => Ok(Self::Div256),
+            _ => Err(InvalidIndScaleError),
+        }
+    }
+}
+
+impl From<IndScale> for u32 {
+    fn from(value: IndScale) -> Self {
+        match value {
+            IndScale::Div1 => 0,
+            IndScale::Div2 => 1,
+            IndScale::Div4 => 2,
+            IndScale::Div8 => 3,
+            IndScale::Div16 => 4,
+            IndScale::Div32 => 5,
+            IndScale::Div64 => 6,
+            IndScale::Div128 => 7,
+            IndScale::Div256 => 8,
+        }
+    }
+}
+
+/// Matrix applied to the offsets of a TEV stage.
+///
+/// The dynamic matrices use the stage's own texture coordinate scaled by the exponent of one of
+/// the indirect matrices, along S or T.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum IndMatrixSelect {
+    Off,
+    Static(u8),
+    DynamicS(u8),
+    DynamicT(u8),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidIndMatrixSelectError;
+
+impl TryFrom<u32> for IndMatrixSelect {
+    type Error = InvalidIndMatrixSelectError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        let index = u8::try_from(value & 0x3).unwrap();
+        match value {
+            0 => Ok(Self::Off),
+            1..=3 => Ok(Self::Static(index - 1)),
+            5..=7 => Ok(Self::DynamicS(index - 1)),
+            9..=11 => Ok(Self::DynamicT(index - 1)),
+            _ => Err(InvalidIndMatrixSelectError),
+        }
+    }
+}
+
+impl From<IndMatrixSelect> for u32 {
+    fn from(value: IndMatrixSelect) -> Self {
+        let (base, index) = match value {
+            IndMatrixSelect::Off => return 0,
+            IndMatrixSelect::Static(index) => (1, index),
+            IndMatrixSelect::DynamicS(index) => (5, index),
+            IndMatrixSelect::DynamicT(index) => (9, index),
+        };
+        assert!(index < 3, "There are only 3 indirect matrices");
+        base + Self::from(index)
+    }
+}
+
+/// Texture an indirect stage samples, and how its coordinate is scaled down first.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct IndStage {
+    pub tex_coord: u8,
+    pub tex_map: u8,
+    pub scale_s: IndScale,
+    pub scale_t: IndScale,
+}
+
+impl IndStage {
+    pub const fn new(tex_coord: u8, tex_map: u8) -> Self {
+        Self {
+            tex_coord,
+            tex_map,
+            scale_s: IndScale::Div1,
+            scale_t: IndScale::Div1,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_scale(mut self, scale_s: IndScale, scale_t: IndScale) -> Self {
+        self.scale_s = scale_s;
+        self.scale_t = scale_t;
+        self
+    }
+
+    /// Writes the map and coordinate into the part of `reg` that belongs to `ind_stage`.
+    pub fn apply_order(&self, ind_stage: u8, reg: &mut RasIndOrder) {
+        assert!(self.tex_map < 8, "There are only 8 texture maps");
+        assert!(self.tex_coord < 8, "There are only 8 texture coordinates");
+        reg.with_tex_map(ind_stage, self.tex_map)
+            .with_tex_coord(ind_stage, self.tex_coord);
+    }
+
+    /// Writes the scale into the half of `reg` that belongs to `ind_stage`.
+    pub fn apply_scale(&self, ind_stage: u8, reg: &mut RasIndScale) {
+        let odd = ind_stage % 2 == 1;
+        reg.with_scale_s(odd, self.scale_s)
+            .with_scale_t(odd, self.scale_t);
+    }
+}
+
+/// 2x3 matrix turning an indirect texel `(s, t, u)` into a texture coordinate offset.
+///
+/// Elements are stored with 10 fraction bits so they have to lie in `-1.0..1.0`, the whole matrix
+/// is then scaled by `2^scale_exp`, with `scale_exp` in `-17..=46`.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct IndTexMatrix {
+    pub matrix: [[f32; 3]; 2],
+    pub scale_exp: i8,
+}
+
+impl IndTexMatrix {
+    pub const fn new(matrix: [[f32; 3]; 2], scale_exp: i8) -> Self {
+        Self { matrix, scale_exp }
+    }
+
+    /// Picks the exponent that keeps the most precision for `matrix`.
+    pub fn fit(mut matrix: [[f32; 3]; 2]) -> Self {
+        let mut max = matrix
+            .iter()
+            .flatten()
+            .fold(0.0f32, |max, e| max.max(*e).max(-*e));
+        let mut scale_exp = 0i8;
+        let mut factor = 1.0;
+        while max >= 1.0 && scale_exp < 46 {
+            max /= 2.0;
+            factor /= 2.0;
+            scale_exp += 1;
+        }
+        while max > 0.0 && max < 0.5 && scale_exp > -17 {
+            max *= 2.0;
+            factor *= 2.0;
+            scale_exp -= 1;
+        }
+
+        for element in matrix.iter_mut().flatten() {
+            *element *= factor;
+        }
+        Self { matrix, scale_exp }
+    }
+
+    /// Scales S offsets by `s` and T offsets by `t`, as used for warps and heat haze.
+    pub fn scale(s: f32, t: f32) -> Self {
+        Self::fit([[s, 0.0, 0.0], [0.0, t, 0.0]])
+    }
+
+    /// Each register holds one column of the matrix and two bits of the exponent.
+    pub fn registers(&self, index: u8) -> [IndMtx; 3] {
+        assert!(
+            (-17..=46).contains(&self.scale_exp),
+            "Indirect matrix exponents go from -17 to 46"
+        );
+        let exp = u8::try_from(self.scale_exp + 17).unwrap();
+
+        [0, 1, 2].map(|column| {
+            let mut reg = IndMtx::new(index, column);
+            reg.with_row0(fixed(self.matrix[0][usize::from(column)]))
+                .with_row1(fixed(self.matrix[1][usize::from(column)]))
+                .with_scale_bits(
+                    exp.get_bits(usize::from(column) * 2..usize::from(column) * 2 + 2),
+                );
+            reg
+        })
+    }
+}
+
+impl Default for IndTexMatrix {
+    fn default() -> Self {
+        Self::new([[0.5, 0.0, 0.0], [0.0, 0.5, 0.0]], 1)
+    }
+}
+
+/// Signed 1.10 fixed point element of an indirect matrix.
+#[allow(clippy::cast_possible_truncation)]
+fn fixed(value: f32) -> i16 {
+    (value * 1024.0).clamp(-1024.0, 1023.0) as i16
+}
+
+/// Indirect settings of one TEV stage.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TevIndirect {
+    pub ind_stage: u8,
+    pub format: IndFormat,
+    pub bias: IndBias,
+    pub alpha: IndAlpha,
+    pub matrix: IndMatrixSelect,
+    pub wrap_s: IndWrap,
+    pub wrap_t: IndWrap,
+    /// Adds the texture coordinate computed by the previous stage.
+    pub add_prev: bool,
+    /// Picks the texture LOD from the coordinate before the offset was added.
+    pub unmodified_lod: bool,
+}
+
+impl TevIndirect {
+    /// No indirect offset, the stage samples with its own texture coordinate.
+    pub const DIRECT: Self = Self::new(0);
+
+    pub const fn new(ind_stage: u8) -> Self {
+        Self {
+            ind_stage,
+            format: IndFormat::Bits8,
+            bias: IndBias::None,
+            alpha: IndAlpha::Off,
+            matrix: IndMatrixSelect::Off,
+            wrap_s: IndWrap::Off,
+            wrap_t: IndWrap::Off,
+            add_prev: false,
+            unmodified_lod: false,
+        }
+    }
+
+    /// Offsets the texture coordinate of the stage by the texels of `ind_stage` run through
+    /// matrix `matrix`. `signed` treats the texels as centred on 128 and `replace` drops the
+    /// stage's own coordinate so the texels are the coordinate.
+    pub const fn warp(ind_stage: u8, matrix: u8, signed: bool, replace: bool) -> Self {
+        let mut this = Self::new(ind_stage);
+        this.matrix = IndMatrixSelect::Static(matrix);
+        if signed {
+            this.bias = IndBias::Stu;
+        }
+        if replace {
+            this.wrap_s = IndWrap::Zero;
+            this.wrap_t = IndWrap::Zero;
+        }
+        this
+    }
+
+    /// Bump mapping against an environment map, with the indirect texels holding a signed normal
+    /// perturbation.
+    pub const fn bump_xyz(ind_stage: u8, matrix: u8) -> Self {
+        let mut this = Self::new(ind_stage);
+        this.bias = IndBias::Stu;
+        this.matrix = IndMatrixSelect::Static(matrix);
+        this
+    }
+
+    /// Reuses the texture coordinate computed by the previous stage.
+    pub const fn repeat() -> Self {
+        let mut this = Self::new(0);
+        this.wrap_s = IndWrap::Zero;
+        this.wrap_t = IndWrap::Zero;
+        this.add_prev = true;
+        this
+    }
+
+    /// The three stages of a bump map lookup in S and T, starting from the first of them. The
+    /// bump texels are turned into S and T offsets by the dynamic matrices of `matrix`, and the
+    /// third stage looks up the sum.
+    pub const fn bump_st(ind_stage: u8, matrix: u8) -> [Self; 3] {
+        let mut s = Self::new(ind_stage);
+        s.bias = IndBias::St;
+        s.matrix = IndMatrixSelect::DynamicS(matrix);
+        s.wrap_s = IndWrap::Zero;
+        s.wrap_t = IndWrap::Zero;
+
+        let mut t = s;
+        t.matrix = IndMatrixSelect::DynamicT(matrix);
+        t.add_prev = true;
+
+        let mut lookup = Self::new(ind_stage);
+        lookup.add_prev = true;
+
+        [s, t, lookup]
+    }
+
+    #[must_use]
+    pub const fn with_format(mut self, format: IndFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_bias(mut self, bias: IndBias) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_alpha(mut self, alpha: IndAlpha) -> Self {
+        self.alpha = alpha;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_matrix(mut self, matrix: IndMatrixSelect) -> Self {
+        self.matrix = matrix;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_wrap(mut self, wrap_s: IndWrap, wrap_t: IndWrap) -> Self {
+        self.wrap_s = wrap_s;
+        self.wrap_t = wrap_t;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_add_prev(mut self, add_prev: bool) -> Self {
+        self.add_prev = add_prev;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_unmodified_lod(mut self, unmodified_lod: bool) -> Self {
+        self.unmodified_lod = unmodified_lod;
+        self
+    }
+
+    pub fn register(&self, stage: u8) -> IndCmd {
+        assert!(self.ind_stage < 4, "There are only 4 indirect stages");
+        let mut reg = IndCmd::new(stage);
+        reg.with_ind_stage(self.ind_stage)
+            .with_format(self.format)
+            .with_bias(self.bias)
+            .with_alpha(self.alpha)
+            .with_matrix(self.matrix)
+            .with_wrap_s(self.wrap_s)
+            .with_wrap_t(self.wrap_t)
+            .with_unmodified_lod(self.unmodified_lod)
+            .with_add_prev(self.add_prev);
+        reg
+    }
+}
+
+impl Default for TevIndirect {
+    fn default() -> Self {
+        Self::DIRECT
+    }
+}
@@ -0,0 +1,412 @@
+//! XF lighting: color channels and hardware lights.
+//!
+//! Each of the two color channels and two alpha channels computes
+//! `material * clamp(ambient + sum(attenuation * diffuse * light color))` per vertex, summing
+//! over the lights in its mask. Lights live in 8 slots of XF memory and take view space
+//! positions and directions.
+
+use crate::math::{cos, Vec3};
+
+use super::xf::ChannelControl;
+
+/// Where a channel takes its material or ambient color from.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ColorSource {
+    Register,
+    Vertex,
+}
+
+impl From<bool> for ColorSource {
+    fn from(value: bool) -> Self {
+        if value {
+            Self::Vertex
+        } else {
+            Self::Register
+        }
+    }
+}
+
+impl From<ColorSource> for bool {
+    fn from(value: ColorSource) -> Self {
+        match value {
+            ColorSource::Register => false,
+            ColorSource::Vertex => true,
+        }
+    }
+}
+
+/// What happens to `N.L` before it scales the light color.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum DiffuseFn {
+    /// Always 1, for specular lights.
+    None,
+    Sign,
+    /// Negative values become 0, the usual diffuse lighting.
+    Clamp,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidDiffuseFnError;
+
+impl TryFrom<u32> for DiffuseFn {
+    type Error = InvalidDiffuseFnError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            1 => Ok(Self::Sign),
+            2 => Ok(Self::Clamp),
+            _ => Err(InvalidDiffuseFnError),
+        }
+    }
+}
+
+impl From<DiffuseFn> for u32 {
+    fn from(value: DiffuseFn) -> Self {
+        match value {
+            DiffuseFn::None => 0,
+            DiffuseFn::Sign => 1,
+            DiffuseFn::Clamp => 2,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum AttenuationFn {
+    /// Angle attenuation against the half-angle vector, for specular highlights.
+    Specular,
+    /// Angle and distance attenuation, for spot and point lights.
+    Spot,
+    None,
+}
+
+/// A channel, or a color and alpha channel pair set up together.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Channel {
+    Color0,
+    Color1,
+    Alpha0,
+    Alpha1,
+    Color0A0,
+    Color1A1,
+}
+
+impl Channel {
+    /// `ChannelControl` indices the channel covers.
+    pub const fn control_indices(self) -> &'static [u8] {
+        match self {
+            Self::Color0 => &[0],
+            Self::Color1 => &[1],
+            Self::Alpha0 => &[2],
+            Self::Alpha1 => &[3],
+            Self::Color0A0 => &[0, 2],
+            Self::Color1A1 => &[1, 3],
+        }
+    }
+
+    /// Index of the ambient and material color registers the channel uses.
+    pub const fn color_index(self) -> u8 {
+        match self {
+            Self::Color0 | Self::Alpha0 | Self::Color0A0 => 0,
+            Self::Color1 | Self::Alpha1 | Self::Color1A1 => 1,
+        }
+    }
+}
+
+/// Channel setup, turned into a `ChannelControl` register by `register`.
+///
+/// The default just passes the vertex color through.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ChannelConfig {
+    pub lighting: bool,
+    pub material_source: ColorSource,
+    pub ambient_source: ColorSource,
+    pub light_mask: u8,
+    pub diffuse: DiffuseFn,
+    pub attenuation: AttenuationFn,
+}
+
+impl ChannelConfig {
+    pub const fn new() -> Self {
+        Self {
+            lighting: false,
+            material_source: ColorSource::Vertex,
+            ambient_source: ColorSource::Register,
+            light_mask: 0,
+            diffuse: DiffuseFn::None,
+            attenuation: AttenuationFn::None,
+        }
+    }
+
+    /// Diffuse lighting by the lights in `light_mask` with spot and distance attenuation,
+    /// material and ambient from the registers.
+    pub const fn lit(light_mask: u8) -> Self {
+        Self {
+            lighting: true,
+            material_source: ColorSource::Register,
+            ambient_source: ColorSource::Register,
+            light_mask,
+            diffuse: DiffuseFn::Clamp,
+            attenuation: AttenuationFn::Spot,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_lighting(mut self, lighting: bool) -> Self {
+        self.lighting = lighting;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_material_source(mut self, source: ColorSource) -> Self {
+        self.material_source = source;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_ambient_source(mut self, source: ColorSource) -> Self {
+        self.ambient_source = source;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_light_mask(mut self, light_mask: u8) -> Self {
+        self.light_mask = light_mask;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_diffuse(mut self, diffuse: DiffuseFn) -> Self {
+        self.diffuse = diffuse;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_attenuation(mut self, attenuation: AttenuationFn) -> Self {
+        self.attenuation = attenuation;
+        self
+    }
+
+    /// Specular attenuation needs the diffuse function off, so it is dropped in that case.
+    pub fn register(&self) -> ChannelControl {
+        let diffuse = if self.attenuation == AttenuationFn::Specular {
+            DiffuseFn::None
+        } else {
+            self.diffuse
+        };
+
+        let mut control = ChannelControl::new();
+        control
+            .with_lighting(self.lighting)
+            .with_material_source(self.material_source)
+            .with_ambient_source(self.ambient_source)
+            .with_light_mask(self.light_mask)
+            .with_diffuse_fn(diffuse)
+            .with_attenuation_fn(self.attenuation);
+        control
+    }
+}
+
+impl Default for ChannelConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Spot light falloff presets for `LightObj::with_spot`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum SpotFn {
+    Off,
+    Flat,
+    Cos,
+    Cos2,
+    Sharp,
+    Ring1,
+    Ring2,
+}
+
+/// Distance falloff presets for `LightObj::with_distance`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum DistanceFn {
+    Off,
+    Gentle,
+    Medium,
+    Steep,
+}
+
+/// One hardware light, loaded into a slot with `GxCommands::load_light`.
+///
+/// Attenuation is `clamp(a0 + a1 * cos + a2 * cos^2) / (k0 + k1 * d + k2 * d^2)`, with `cos`
+/// the angle to the light direction and `d` the distance to the light.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct LightObj {
+    position: Vec3,
+    direction: Vec3,
+    color: [u8; 4],
+    angle_attenuation: [f32; 3],
+    distance_attenuation: [f32; 3],
+}
+
+impl LightObj {
+    /// A white light at the origin pointing down -Z, without attenuation.
+    pub const fn new() -> Self {
+        Self {
+            position: Vec3::ZERO,
+            direction: Vec3::new(0.0, 0.0, -1.0),
+            color: [0xFF; 4],
+            angle_attenuation: [1.0, 0.0, 0.0],
+            distance_attenuation: [1.0, 0.0, 0.0],
+        }
+    }
+
+    /// In view space.
+    #[must_use]
+    pub const fn with_position(mut self, position: Vec3) -> Self {
+        self.position = position;
+        self
+    }
+
+    /// In view space, normalized here.
+    #[must_use]
+    pub fn with_direction(mut self, direction: Vec3) -> Self {
+        self.direction = direction.normalize();
+        self
+    }
+
+    #[must_use]
+    pub const fn with_color(mut self, color: [u8; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_angle_attenuation(mut self, a0: f32, a1: f32, a2: f32) -> Self {
+        self.angle_attenuation = [a0, a1, a2];
+        self
+    }
+
+    #[must_use]
+    pub const fn with_distance_attenuation(mut self, k0: f32, k1: f32, k2: f32) -> Self {
+        self.distance_attenuation = [k0, k1, k2];
+        self
+    }
+
+    /// Angle attenuation for a spot light with a `cutoff` half angle in radians.
+    ///
+    /// Cutoffs outside of `0..=PI/2` turn the spot off.
+    #[must_use]
+    pub fn with_spot(self, cutoff: f32, spot: SpotFn) -> Self {
+        let spot = if cutoff <= 0.0 || cutoff > core::f32::consts::FRAC_PI_2 {
+            SpotFn::Off
+        } else {
+            spot
+        };
+
+        let cos_cutoff = cos(cutoff);
+        let squared = (1.0 - cos_cutoff) * (1.0 - cos_cutoff);
+        let [a0, a1, a2] = match spot {
+            SpotFn::Off => [1.0, 0.0, 0.0],
+            SpotFn::Flat => [-1000.0 * cos_cutoff, 1000.0, 0.0],
+            SpotFn::Cos => [
+                -cos_cutoff / (1.0 - cos_cutoff),
+                1.0 / (1.0 - cos_cutoff),
+                0.0,
+            ],
+            SpotFn::Cos2 => [
+                0.0,
+                -cos_cutoff / (1.0 - cos_cutoff),
+                1.0 / (1.0 - cos_cutoff),
+            ],
+            SpotFn::Sharp => [
+                cos_cutoff * (cos_cutoff - 2.0) / squared,
+                2.0 / squared,
+                -1.0 / squared,
+            ],
+            SpotFn::Ring1 => [
+                -4.0 * cos_cutoff / squared,
+                4.0 * (1.0 + cos_cutoff) / squared,
+                -4.0 / squared,
+            ],
+            SpotFn::Ring2 => [
+                1.0 - 2.0 * cos_cutoff * cos_cutoff / squared,
+                4.0 * cos_cutoff / squared,
+                -2.0 / squared,
+            ],
+        };
+        self.with_angle_attenuation(a0, a1, a2)
+    }
+
+    /// Distance attenuation reaching `brightness` (between 0 and 1) at `distance`.
+    ///
+    /// Anything out of range turns distance attenuation off.
+    #[must_use]
+    pub fn with_distance(self, distance: f32, brightness: f32, falloff: DistanceFn) -> Self {
+        let falloff = if distance < 0.0 || brightness <= 0.0 || brightness >= 1.0 {
+            DistanceFn::Off
+        } else {
+            falloff
+        };
+
+        let factor = (1.0 - brightness) / brightness;
+        let [k0, k1, k2] = match falloff {
+            DistanceFn::Off => [1.0, 0.0, 0.0],
+            DistanceFn::Gentle => [1.0, factor / distance, 0.0],
+            DistanceFn::Medium => [
+                1.0,
+                0.5 * factor / distance,
+                0.5 * factor / (distance * distance),
+            ],
+            DistanceFn::Steep => [1.0, 0.0, factor / (distance * distance)],
+        };
+        self.with_distance_attenuation(k0, k1, k2)
+    }
+
+    pub const fn position(&self) -> Vec3 {
+        self.position
+    }
+
+    pub const fn direction(&self) -> Vec3 {
+        self.direction
+    }
+
+    pub const fn color(&self) -> [u8; 4] {
+        self.color
+    }
+
+    /// The words of the light from its color on, as laid out in XF light memory.
+    pub fn to_words(&self) -> [u32; LIGHT_WORDS] {
+        let [a0, a1, a2] = self.angle_attenuation;
+        let [k0, k1, k2] = self.distance_attenuation;
+        let Vec3 { x, y, z } = self.position;
+        let Vec3 {
+            x: dir_x,
+            y: dir_y,
+            z: dir_z,
+        } = self.direction;
+
+        let mut words = [0; LIGHT_WORDS];
+        words[0] = u32::from_be_bytes(self.color);
+        for (word, value) in words[1..]
+            .iter_mut()
+            .zip([a0, a1, a2, k0, k1, k2, x, y, z, dir_x, dir_y, dir_z])
+        {
+            *word = value.to_bits();
+        }
+        words
+    }
+}
+
+impl Default for LightObj {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Words loaded per light, the three before the color in each slot are unused.
+pub const LIGHT_WORDS: usize = 13;
+
+/// Offset of a light slot's color in XF light memory.
+pub fn light_offset(slot: u8) -> u16 {
+    assert!(slot < 8, "XF only has 8 lights");
+    u16::from(slot) * 16 + 3
+}
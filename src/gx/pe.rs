@@ -0,0 +1,842 @@
+//! Pixel engine state: blending, depth and alpha tests, and fog.
+
+use crate::math::{sqrt, Mtx44};
+
+use super::{
+    bp::{
+        EfbCopyControl, FogColor, FogParam0, FogParam1, FogParam2, FogParam3, FogRange, FogRangeK,
+        FrameToField, Gamma, PeControl,
+    },
+    texture::TextureFormat,
+    xf::ProjectionType,
+};
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum BlendMode {
+    /// The source color replaces the destination.
+    None,
+    /// `src * src_factor + dst * dst_factor`.
+    Blend,
+    /// A bitwise logic op between source and destination.
+    Logic,
+    /// `dst - src`, the factors are ignored.
+    Subtract,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    /// The destination color as source factor, the source color as destination factor.
+    OtherColor,
+    InvOtherColor,
+    SrcAlpha,
+    InvSrcAlpha,
+    DstAlpha,
+    InvDstAlpha,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidBlendFactorError;
+
+impl TryFrom<u32> for BlendFactor {
+    type Error = InvalidBlendFactorError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Zero),
+            1 => Ok(Self::One),
+            2 => Ok(Self::OtherColor),
+            3 => Ok(Self::InvOtherColor),
+            4 => Ok(Self::SrcAlpha),
+            5 => Ok(Self::InvSrcAlpha),
+            6 => Ok(Self::DstAlpha),
+            7 => Ok(Self::InvDstAlpha),
+            _ => Err(InvalidBlendFactorError),
+        }
+    }
+}
+
+impl From<BlendFactor> for u32 {
+    fn from(value: BlendFactor) -> Self {
+        match value {
+            BlendFactor::Zero => 0,
+            BlendFactor::One => 1,
+            BlendFactor::OtherColor => 2,
+            BlendFactor::InvOtherColor => 3,
+            BlendFactor::SrcAlpha => 4,
+            BlendFactor::InvSrcAlpha => 5,
+            BlendFactor::DstAlpha => 6,
+            BlendFactor::InvDstAlpha => 7,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum LogicOp {
+    Clear,
+    And,
+    RevAnd,
+    Copy,
+    InvAnd,
+    NoOp,
+    Xor,
+    Or,
+    Nor,
+    Equiv,
+    Inv,
+    RevOr,
+    InvCopy,
+    InvOr,
+    Nand,
+    Set,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidLogicOpError;
+
+impl TryFrom<u32> for LogicOp {
+    type Error = InvalidLogicOpError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(Self::Clear),
+            0x1 => Ok(Self::And),
+            0x2 => Ok(Self::RevAnd),
+            0x3 => Ok(Self::Copy),
+            0x4 => Ok(Self::InvAnd),
+            0x5 => Ok(Self::NoOp),
+            0x6 => Ok(Self::Xor),
+            0x7 => Ok(Self::Or),
+            0x8 => Ok(Self::Nor),
+            0x9 => Ok(Self::Equiv),
+            0xA => Ok(Self::Inv),
+            0xB => Ok(Self::RevOr),
+            0xC => Ok(Self::InvCopy),
+            0xD => Ok(Self::InvOr),
+            0xE => Ok(Self::Nand),
+            0xF => Ok(Self::Set),
+            _ => Err(InvalidLogicOpError),
+        }
+    }
+}
+
+impl From<LogicOp> for u32 {
+    fn from(value: LogicOp) -> Self {
+        match value {
+            LogicOp::Clear => 0x0,
+            LogicOp::And => 0x1,
+            LogicOp::RevAnd => 0x2,
+            LogicOp::Copy => 0x3,
+            LogicOp::InvAnd => 0x4,
+            LogicOp::NoOp => 0x5,
+            LogicOp::Xor => 0x6,
+            LogicOp::Or => 0x7,
+            LogicOp::Nor => 0x8,
+            LogicOp::Equiv => 0x9,
+            LogicOp::Inv => 0xA,
+            LogicOp::RevOr => 0xB,
+            LogicOp::InvCopy => 0xC,
+            LogicOp::InvOr => 0xD,
+            LogicOp::Nand => 0xE,
+            LogicOp::Set => 0xF,
+        }
+    }
+}
+
+/// Comparison used by the Z and alpha tests, the incoming value is on the left.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum CompareFn {
+    Never,
+    Less,
+    Equal,
+    LessEqual,
+    Greater,
+    NotEqual,
+    GreaterEqual,
+    Always,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidCompareFnError;
+
+impl TryFrom<u32> for CompareFn {
+    type Error = InvalidCompareFnError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Never),
+            1 => Ok(Self::Less),
+            2 => Ok(Self::Equal),
+            3 => Ok(Self::LessEqual),
+            4 => Ok(Self::Greater),
+            5 => Ok(Self::NotEqual),
+            6 => Ok(Self::GreaterEqual),
+            7 => Ok(Self::Always),
+            _ => Err(InvalidCompareFnError),
+        }
+    }
+}
+
+impl From<CompareFn> for u32 {
+    fn from(value: CompareFn) -> Self {
+        match value {
+            CompareFn::Never => 0,
+            CompareFn::Less => 1,
+            CompareFn::Equal => 2,
+            CompareFn::LessEqual => 3,
+            CompareFn::Greater => 4,
+            CompareFn::NotEqual => 5,
+            CompareFn::GreaterEqual => 6,
+            CompareFn::Always => 7,
+        }
+    }
+}
+
+/// How the two alpha compare results are combined.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum AlphaOp {
+    And,
+    Or,
+    Xor,
+    Xnor,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidAlphaOpError;
+
+impl TryFrom<u32> for AlphaOp {
+    type Error = InvalidAlphaOpError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::And),
+            1 => Ok(Self::Or),
+            2 => Ok(Self::Xor),
+            3 => Ok(Self::Xnor),
+            _ => Err(InvalidAlphaOpError),
+        }
+    }
+}
+
+impl From<AlphaOp> for u32 {
+    fn from(value: AlphaOp) -> Self {
+        match value {
+            AlphaOp::And => 0,
+            AlphaOp::Or => 1,
+            AlphaOp::Xor => 2,
+            AlphaOp::Xnor => 3,
+        }
+    }
+}
+
+/// EFB pixel layout, set in `PeControl`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum PixelFormat {
+    Rgb8Z24,
+    Rgba6Z24,
+    /// Multisampled, used for anti-aliasing.
+    Rgb565Z16,
+    Z24,
+    Y8,
+    U8,
+    V8,
+    Yuv420,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidPixelFormatError;
+
+impl TryFrom<u32> for PixelFormat {
+    type Error = InvalidPixelFormatError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Rgb8Z24),
+            1 => Ok(Self::Rgba6Z24),
+            2 => Ok(Self::Rgb565Z16),
+            3 => Ok(Self::Z24),
+            4 => Ok(Self::Y8),
+            5 => Ok(Self::U8),
+            6 => Ok(Self::V8),
+            7 => Ok(Self::Yuv420),
+            _ => Err(InvalidPixelFormatError),
+        }
+    }
+}
+
+impl From<PixelFormat> for u32 {
+    fn from(value: PixelFormat) -> Self {
+        match value {
+            PixelFormat::Rgb8Z24 => 0,
+            PixelFormat::Rgba6Z24 => 1,
+            PixelFormat::Rgb565Z16 => 2,
+            PixelFormat::Z24 => 3,
+            PixelFormat::Y8 => 4,
+            PixelFormat::U8 => 5,
+            PixelFormat::V8 => 6,
+            PixelFormat::Yuv420 => 7,
+        }
+    }
+}
+
+/// How 16 bit Z is stored with `PixelFormat::Rgb565Z16`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ZFormat {
+    Linear,
+    Near,
+    Mid,
+    Far,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidZFormatError;
+
+impl TryFrom<u32> for ZFormat {
+    type Error = InvalidZFormatError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Linear),
+            1 => Ok(Self::Near),
+            2 => Ok(Self::Mid),
+            3 => Ok(Self::Far),
+            _ => Err(InvalidZFormatError),
+        }
+    }
+}
+
+impl From<ZFormat> for u32 {
+    fn from(value: ZFormat) -> Self {
+        match value {
+            ZFormat::Linear => 0,
+            ZFormat::Near => 1,
+            ZFormat::Mid => 2,
+            ZFormat::Far => 3,
+        }
+    }
+}
+
+/// Fog density curve over the `start_z..end_z` range.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum FogType {
+    None,
+    Linear,
+    Exp,
+    Exp2,
+    ReverseExp,
+    ReverseExp2,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidFogTypeError;
+
+impl TryFrom<u32> for FogType {
+    type Error = InvalidFogTypeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::None),
+            2 => Ok(Self::Linear),
+            4 => Ok(Self::Exp),
+            5 => Ok(Self::Exp2),
+            6 => Ok(Self::ReverseExp),
+            7 => Ok(Self::ReverseExp2),
+            _ => Err(InvalidFogTypeError),
+        }
+    }
+}
+
+impl From<FogType> for u32 {
+    fn from(value: FogType) -> Self {
+        match value {
+            FogType::None => 0,
+            FogType::Linear => 2,
+            FogType::Exp => 4,
+            FogType::Exp2 => 5,
+            FogType::ReverseExp => 6,
+            FogType::ReverseExp2 => 7,
+        }
+    }
+}
+
+/// Fog setup, turned into the fog registers by `registers`.
+///
+/// `near` and `far` have to match the projection, `projection` tells the hardware how to get
+/// eye space depth back out of screen Z.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Fog {
+    pub ty: FogType,
+    pub projection: ProjectionType,
+    pub start_z: f32,
+    pub end_z: f32,
+    pub near: f32,
+    pub far: f32,
+    pub color: [u8; 3],
+}
+
+impl Fog {
+    /// Fog turned off.
+    pub const fn new() -> Self {
+        Self {
+            ty: FogType::None,
+            projection: ProjectionType::Perspective,
+            start_z: 0.0,
+            end_z: 1.0,
+            near: 0.1,
+            far: 1.0,
+            color: [0; 3],
+        }
+    }
+
+    #[must_use]
+    pub const fn with_type(mut self, ty: FogType, projection: ProjectionType) -> Self {
+        self.ty = ty;
+        self.projection = projection;
+        self
+    }
+
+    /// Fog goes from nothing at `start_z` to full at `end_z`, both eye space distances.
+    #[must_use]
+    pub const fn with_range(mut self, start_z: f32, end_z: f32, near: f32, far: f32) -> Self {
+        self.start_z = start_z;
+        self.end_z = end_z;
+        self.near = near;
+        self.far = far;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_color(mut self, color: [u8; 3]) -> Self {
+        self.color = color;
+        self
+    }
+
+    pub fn registers(&self) -> (FogParam0, FogParam1, FogParam2, FogParam3, FogColor) {
+        let degenerate = self.far - self.near == 0.0 || self.end_z - self.start_z == 0.0;
+
+        let (a, b_magnitude, b_shift, c) = match self.projection {
+            ProjectionType::Orthographic => {
+                if degenerate {
+                    (0.0, 0, 0, 0.0)
+                } else {
+                    let scale = 1.0 / (self.end_z - self.start_z);
+                    let a = (self.far - self.near) * scale;
+                    (a, 0, 0, (self.start_z - self.near) * scale)
+                }
+            }
+            ProjectionType::Perspective => {
+                let (a, b, c) = if degenerate {
+                    (0.0, 0.5, 0.0)
+                } else {
+                    let range = self.end_z - self.start_z;
+                    (
+                        (self.far * self.near) / ((self.far - self.near) * range),
+                        self.far / (self.far - self.near),
+                        self.start_z / range,
+                    )
+                };
+
+                // B is split into a 24 bit mantissa in 0.5..=1 and a shift, which A absorbs.
+                let mut mantissa = b;
+                let mut shift = 1i32;
+                while mantissa > 1.0 {
+                    mantissa /= 2.0;
+                    shift += 1;
+                }
+                while mantissa > 0.0 && mantissa < 0.5 {
+                    mantissa *= 2.0;
+                    shift -= 1;
+                }
+                (a / exp2(shift), fog_mantissa(mantissa), shift, c)
+            }
+        };
+
+        let mut param0 = FogParam0::new();
+        param0.with_a(a);
+        let mut param1 = FogParam1::new();
+        param1.with_b_magnitude(b_magnitude);
+        let mut param2 = FogParam2::new();
+        param2.with_b_shift(b_shift.try_into().unwrap());
+        let mut param3 = FogParam3::new();
+        param3
+            .with_c(c)
+            .with_projection(self.projection)
+            .with_fog_type(self.ty);
+        let mut color = FogColor::new();
+        color.with_color(self.color);
+
+        (param0, param1, param2, param3, color)
+    }
+}
+
+impl Default for Fog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Per column fog multipliers, making fog depend on the distance to the eye rather than on Z.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct FogRangeTable(pub [u16; 10]);
+
+impl FogRangeTable {
+    /// Table for a viewport `width` pixels wide drawn with `projection`.
+    pub fn new(width: u16, projection: &Mtx44) -> Self {
+        let m = &projection.0;
+        let (depth, scale) = if m[3][3] == 0.0 {
+            let depth = m[2][3] / (m[2][2] - 1.0);
+            (depth, depth / m[0][0])
+        } else {
+            let scale = 1.0 / m[0][0];
+            (scale * 1.732_05, scale)
+        };
+
+        let mut table = [0; 10];
+        let half_width = f32::from(width) * 0.5;
+        for (column, entry) in (1u8..).zip(&mut table) {
+            let x = scale * f32::from(column) * 32.0 / half_width;
+            *entry = fog_range_entry(sqrt(depth * depth + x * x) / depth);
+        }
+        Self(table)
+    }
+
+    /// The five registers holding two 12 bit entries each.
+    pub fn registers(&self) -> [FogRangeK; 5] {
+        core::array::from_fn(|index| {
+            let mut register = FogRangeK::new(u8::try_from(index).unwrap());
+            register
+                .with_low(self.0[index * 2])
+                .with_high(self.0[index * 2 + 1]);
+            register
+        })
+    }
+
+    /// Range adjustment centered on screen column `center`.
+    pub fn range_register(center: u16, enable: bool) -> FogRange {
+        let mut range = FogRange::new();
+        range.with_center(center).with_enable(enable);
+        range
+    }
+}
+
+fn exp2(shift: i32) -> f32 {
+    f32::from_bits(u32::try_from(127 + shift).unwrap() << 23)
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn fog_mantissa(mantissa: f32) -> u32 {
+    (mantissa * 8_388_638.0) as u32
+}
+
+/// Range multipliers are 4.8 fixed point.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn fog_range_entry(factor: f32) -> u16 {
+    ((factor * 256.0) as u16).min(0xFFF)
+}
+
+/// Where SU puts the 3 samples of each pixel of a 2x2 quad when multisampling, as (x, y) in
+/// twelfths of a pixel.
+pub type SamplePositions = [[(u8, u8); 3]; 4];
+
+/// Every sample in the pixel center, for the single sampled formats.
+pub const CENTERED_SAMPLES: SamplePositions = [[(6, 6); 3]; 4];
+
+/// The pattern the SDK's anti-aliased video modes use, alternating between columns so a pair of
+/// pixels covers 6 distinct positions.
+pub const AA_SAMPLES: SamplePositions = [
+    [(3, 2), (9, 6), (3, 10)],
+    [(3, 2), (9, 6), (3, 10)],
+    [(9, 2), (3, 6), (9, 10)],
+    [(9, 2), (3, 6), (9, 10)],
+];
+
+/// Weights out of 64 the display copy gives the lines 3 above to 3 below each line it writes.
+pub type VerticalFilter = [u8; 7];
+
+/// Blends each line with the ones right above and below it, against interlace flicker.
+pub const DEFAULT_VERTICAL_FILTER: VerticalFilter = [0, 0, 21, 22, 21, 0, 0];
+
+/// Wider blend over the multisampled lines, from the SDK's anti-aliased video modes.
+pub const AA_VERTICAL_FILTER: VerticalFilter = [4, 8, 12, 16, 12, 8, 4];
+
+/// Lines the multisampled EFB holds, taller frames are drawn and copied out in strips.
+pub const AA_EFB_HEIGHT: usize = 264;
+
+/// How the EFB gets copied out to the XFB, see `Fifo::set_copy_display_control`.
+#[allow(clippy::struct_excessive_bools)]
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct DisplayCopy {
+    /// Keep the vertical filter from reading lines above the copied rectangle.
+    pub clamp_top: bool,
+    /// Keep the vertical filter from reading lines below the copied rectangle.
+    pub clamp_bottom: bool,
+    pub gamma: Gamma,
+    /// Scale lines by the factor set with `set_y_scale`.
+    pub y_scale: bool,
+    /// Clear the copied rectangle to the `set_copy_clear` values once it is copied.
+    pub clear: bool,
+    /// Copy every line, or only the even or odd ones for a single interlaced field.
+    pub frame_to_field: FrameToField,
+    pub pixel_format: PixelFormat,
+    pub z_format: ZFormat,
+}
+
+impl DisplayCopy {
+    /// Progressive, clamped, gamma 1.0 copy of an RGB8 EFB without clearing it.
+    pub const fn new() -> Self {
+        Self {
+            clamp_top: true,
+            clamp_bottom: true,
+            gamma: Gamma::One,
+            y_scale: false,
+            clear: false,
+            frame_to_field: FrameToField::Progressive,
+            pixel_format: PixelFormat::Rgb8Z24,
+            z_format: ZFormat::Linear,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_clamp(mut self, top: bool, bottom: bool) -> Self {
+        self.clamp_top = top;
+        self.clamp_bottom = bottom;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_gamma(mut self, gamma: Gamma) -> Self {
+        self.gamma = gamma;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_y_scale(mut self, enable: bool) -> Self {
+        self.y_scale = enable;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_clear(mut self, enable: bool) -> Self {
+        self.clear = enable;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_frame_to_field(mut self, mode: FrameToField) -> Self {
+        self.frame_to_field = mode;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_pixel_format(mut self, format: PixelFormat, z_format: ZFormat) -> Self {
+        self.pixel_format = format;
+        self.z_format = z_format;
+        self
+    }
+
+    /// The copy control register, writing it starts the copy.
+    pub fn register(&self) -> EfbCopyControl {
+        let mut control = EfbCopyControl::new();
+        control
+            .with_clamp_top(self.clamp_top)
+            .with_clamp_bottom(self.clamp_bottom)
+            .with_gamma(self.gamma)
+            .with_y_scale(self.y_scale)
+            .with_clear(self.clear)
+            .with_frame_to_field(self.frame_to_field)
+            .with_copy_to_xfb(true);
+        control
+    }
+
+    /// The EFB format half of `PeControl`, only bits 0..=5 belong to it.
+    pub fn pe_control(&self) -> PeControl {
+        let mut control = PeControl::new();
+        control
+            .with_pixel_format(self.pixel_format)
+            .with_z_format(self.z_format);
+        control
+    }
+}
+
+impl Default for DisplayCopy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// What an EFB to texture copy writes out.
+///
+/// Besides the regular texture formats, copies can pick out single channels of the color or Z
+/// buffer. Every format comes back out as one of the `TextureFormat`s when sampled.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum CopyFormat {
+    I4,
+    I8,
+    Ia4,
+    Ia8,
+    Rgb565,
+    Rgb5a3,
+    Rgba8,
+    R4,
+    Ra4,
+    Ra8,
+    Yuva8,
+    A8,
+    R8,
+    G8,
+    B8,
+    Rg8,
+    Gb8,
+    /// The top 4 bits of Z.
+    Z4,
+    /// The top 8 bits of Z.
+    Z8,
+    /// The middle 8 bits of Z.
+    Z8Mid,
+    /// The bottom 8 bits of Z.
+    Z8Low,
+    /// The top 16 bits of Z.
+    Z16,
+    /// The bottom 16 bits of Z, stored the other way around from `Z16`.
+    Z16Low,
+    Z24X8,
+}
+
+impl CopyFormat {
+    /// The format to sample the copied texture with.
+    pub const fn texture_format(self) -> TextureFormat {
+        match self {
+            Self::I4 | Self::R4 | Self::Z4 => TextureFormat::I4,
+            Self::I8
+            | Self::A8
+            | Self::R8
+            | Self::G8
+            | Self::B8
+            | Self::Z8
+            | Self::Z8Mid
+            | Self::Z8Low => TextureFormat::I8,
+            Self::Ia4 | Self::Ra4 => TextureFormat::Ia4,
+            Self::Ia8 | Self::Ra8 | Self::Rg8 | Self::Gb8 | Self::Z16 | Self::Z16Low => {
+                TextureFormat::Ia8
+            }
+            Self::Rgb565 => TextureFormat::Rgb565,
+            Self::Rgb5a3 => TextureFormat::Rgb5a3,
+            Self::Rgba8 | Self::Yuva8 | Self::Z24X8 => TextureFormat::Rgba8,
+        }
+    }
+
+    /// Z formats copy from the Z buffer instead of the color buffer.
+    pub const fn is_z(self) -> bool {
+        matches!(
+            self,
+            Self::Z4
+                | Self::Z8
+                | Self::Z8Mid
+                | Self::Z8Low
+                | Self::Z16
+                | Self::Z16Low
+                | Self::Z24X8
+        )
+    }
+
+    /// Intensity formats have the copy convert RGB to Y first.
+    pub const fn is_intensity(self) -> bool {
+        matches!(
+            self,
+            Self::I4 | Self::I8 | Self::Ia4 | Self::Ia8 | Self::Yuva8
+        )
+    }
+
+    /// Value of `EfbCopyControl::texture_format`.
+    pub const fn raw(self) -> u8 {
+        match self {
+            Self::I4 | Self::R4 | Self::Z4 => 0x0,
+            Self::I8 | Self::Z8 => 0x1,
+            Self::Ia4 | Self::Ra4 => 0x2,
+            Self::Ia8 | Self::Ra8 => 0x3,
+            Self::Rgb565 => 0x4,
+            Self::Rgb5a3 => 0x5,
+            Self::Rgba8 | Self::Yuva8 | Self::Z24X8 => 0x6,
+            Self::A8 => 0x7,
+            Self::R8 => 0x8,
+            Self::G8 | Self::Z8Mid => 0x9,
+            Self::B8 | Self::Z8Low => 0xA,
+            Self::Rg8 | Self::Z16 => 0xB,
+            Self::Gb8 | Self::Z16Low => 0xC,
+        }
+    }
+}
+
+/// An EFB to texture copy, see `Fifo::copy_tex`.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TexCopy {
+    pub left: u16,
+    pub top: u16,
+    pub width: u16,
+    pub height: u16,
+    pub format: CopyFormat,
+    /// Box filter 2x2 blocks of EFB pixels down to one texel, for building the next mipmap level.
+    pub mipmap: bool,
+    /// Clear the copied rectangle to the `set_copy_clear` values once it is copied. The clear
+    /// goes through the color, alpha and Z update masks like any other EFB write.
+    pub clear: bool,
+}
+
+impl TexCopy {
+    /// Copies the `width` x `height` EFB rectangle at `left`, `top`.
+    pub const fn new(left: u16, top: u16, width: u16, height: u16, format: CopyFormat) -> Self {
+        Self {
+            left,
+            top,
+            width,
+            height,
+            format,
+            mipmap: false,
+            clear: false,
+        }
+    }
+
+    #[must_use]
+    pub const fn with_mipmap(mut self, enable: bool) -> Self {
+        self.mipmap = enable;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_clear(mut self, enable: bool) -> Self {
+        self.clear = enable;
+        self
+    }
+
+    /// Width and height of the texture the copy produces.
+    pub const fn texture_size(&self) -> (u16, u16) {
+        if self.mipmap {
+            (self.width / 2, self.height / 2)
+        } else {
+            (self.width, self.height)
+        }
+    }
+
+    /// Bytes the destination of the copy needs.
+    pub const fn buffer_size(&self) -> usize {
+        let (width, height) = self.texture_size();
+        self.format
+            .texture_format()
+            .size(width as usize, height as usize)
+    }
+
+    /// The copy control register, writing it starts the copy.
+    pub fn register(&self) -> EfbCopyControl {
+        let mut control = EfbCopyControl::new();
+        control
+            .with_clamp_top(true)
+            .with_clamp_bottom(true)
+            .with_texture_format(self.format.raw())
+            .with_half_scale(self.mipmap)
+            .with_clear(self.clear)
+            .with_intensity_format(self.format.is_intensity())
+            .with_auto_convert(true);
+        control
+    }
+}
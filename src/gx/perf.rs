@@ -0,0 +1,320 @@
+//! GP performance counters.
+//!
+//! The GP has two general counters: counter 0 counts an XF, setup unit or rasterizer event, and
+//! counter 1 counts a texture unit or CP one. Only one event can be picked for each at a time.
+//! The vertex cache and PE pixel counters always count. `PerfMonitor` picks the events, and
+//! `PerfMonitor::end_frame` reads and clears everything once a frame to give a `PerfReport`.
+
+use core::fmt;
+
+use super::{
+    bp::{PePerfControl, RasPerfSelect, SuPerfSelect, TxPerfSelect},
+    command::CommandSink,
+    cp::PerfSelect as CpPerfSelect,
+    read_split,
+    xf::PerfSelect as XfPerfSelect,
+    Fifo,
+};
+use crate::mmio::{
+    cp::{
+        CommandClear, CP_PERF0_HI, CP_PERF0_LO, CP_PERF1_HI, CP_PERF1_LO, CP_VCACHE_CHECK_HI,
+        CP_VCACHE_CHECK_LO, CP_VCACHE_MISS_HI, CP_VCACHE_MISS_LO, CP_VCACHE_STALL_HI,
+        CP_VCACHE_STALL_LO, PERF_SELECT,
+    },
+    pe::{
+        PE_COLOR_IN_HI, PE_COLOR_IN_LO, PE_COPY_CLOCKS_HI, PE_COPY_CLOCKS_LO, PE_Z_BOTTOM_IN_HI,
+        PE_Z_BOTTOM_IN_LO, PE_Z_BOTTOM_OUT_HI, PE_Z_BOTTOM_OUT_LO, PE_Z_TOP_IN_HI, PE_Z_TOP_IN_LO,
+        PE_Z_TOP_OUT_HI, PE_Z_TOP_OUT_LO,
+    },
+};
+
+/// Vertex cache attribute select watching every attribute.
+const VCACHE_ALL_ATTRIBUTES: u8 = 0xF;
+
+/// Events counter 0 can count.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Perf0Metric {
+    Vertices,
+    ClipVertices,
+    ClipClocks,
+    XfWaitIn,
+    XfWaitOut,
+    XfTransformClocks,
+    XfLightingClocks,
+    XfBottomClocks,
+    XfRegisterLoadClocks,
+    XfRegisterReadClocks,
+    Triangles,
+    TrianglesCulled,
+    TrianglesPassed,
+    TrianglesScissored,
+    /// Triangles with this many texture coordinates, 0..=8.
+    TrianglesTex(u8),
+    /// Triangles with this many color channels, 0..=2.
+    TrianglesColor(u8),
+    /// Quads with this many covered pixels, 0..=4.
+    QuadCoverage(u8),
+    QuadNonZeroCoverage,
+    AverageQuadCount,
+    Clocks,
+}
+
+/// Events counter 1 can count.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Perf1Metric {
+    Texels,
+    TxIdle,
+    TxRegisters,
+    TxMemoryStall,
+    TexCacheCheck12,
+    TexCacheCheck34,
+    TexCacheCheck56,
+    TexCacheCheck78,
+    TexCacheMiss,
+    VcacheElementQueueFull,
+    VcacheMissQueueFull,
+    VcacheMemoryRequestFull,
+    VcacheStatus7,
+    VcacheMissReplaceFull,
+    VcacheStreamBufferLow,
+    /// Every way the vertex cache can stall the CP.
+    VcacheAllStalls,
+    Vertices,
+    FifoRequests,
+    CallRequests,
+    VcacheMissRequests,
+    CpAllRequests,
+}
+
+/// Which unit an event is selected in, and the value that selects it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Select {
+    Xf(u32),
+    Su(u32),
+    Ras(u32),
+    Tx(u32),
+    Cp(u8),
+    Memory(u16),
+}
+
+impl Perf0Metric {
+    fn select(self) -> Select {
+        match self {
+            Self::Vertices => Select::Xf(0x14A),
+            Self::ClipVertices => Select::Xf(0x16B),
+            Self::ClipClocks => Select::Xf(0x84),
+            Self::XfWaitIn => Select::Xf(0xC6),
+            Self::XfWaitOut => Select::Xf(0x210),
+            Self::XfTransformClocks => Select::Xf(0x252),
+            Self::XfLightingClocks => Select::Xf(0x231),
+            Self::XfBottomClocks => Select::Xf(0x1AD),
+            Self::XfRegisterLoadClocks => Select::Xf(0x1CE),
+            Self::XfRegisterReadClocks => Select::Xf(0x21),
+            Self::Clocks => Select::Xf(0x273),
+            Self::Triangles => Select::Su(0xAE7F),
+            Self::TrianglesCulled => Select::Su(0x8E7F),
+            Self::TrianglesPassed => Select::Su(0x9E7F),
+            Self::TrianglesScissored => Select::Su(0x1E7F),
+            Self::TrianglesTex(count) => {
+                assert!(count <= 8, "XF only has 8 texture coordinates");
+                Select::Su(0xAC3F + u32::from(count) * 0x40)
+            }
+            Self::TrianglesColor(count) => {
+                assert!(count <= 2, "XF only has 2 color channels");
+                Select::Su(0xA27F + u32::from(count) * 0x400)
+            }
+            Self::QuadCoverage(pixels) => {
+                assert!(pixels <= 4, "a quad only has 4 pixels");
+                Select::Ras(0x2_C0C6 + u32::from(pixels) * 0x21)
+            }
+            Self::QuadNonZeroCoverage => Select::Ras(0x2_C16B),
+            Self::AverageQuadCount => Select::Ras(0x2_C1AD),
+        }
+    }
+}
+
+impl Perf1Metric {
+    const fn select(self) -> Select {
+        match self {
+            Self::Texels => Select::Tx(0x42),
+            Self::TxIdle => Select::Tx(0x84),
+            Self::TxRegisters => Select::Tx(0x63),
+            Self::TxMemoryStall => Select::Tx(0x129),
+            Self::TexCacheCheck12 => Select::Tx(0x252),
+            Self::TexCacheCheck34 => Select::Tx(0x231),
+            Self::TexCacheCheck56 => Select::Tx(0x14A),
+            Self::TexCacheCheck78 => Select::Tx(0x18C),
+            Self::TexCacheMiss => Select::Tx(0x21),
+            Self::VcacheElementQueueFull => Select::Cp(2),
+            Self::VcacheMissQueueFull => Select::Cp(3),
+            Self::VcacheMemoryRequestFull => Select::Cp(4),
+            Self::VcacheStatus7 => Select::Cp(5),
+            Self::VcacheMissReplaceFull => Select::Cp(6),
+            Self::VcacheStreamBufferLow => Select::Cp(7),
+            Self::Vertices => Select::Cp(8),
+            Self::VcacheAllStalls => Select::Cp(9),
+            Self::FifoRequests => Select::Memory(2),
+            Self::CallRequests => Select::Memory(3),
+            Self::VcacheMissRequests => Select::Memory(4),
+            Self::CpAllRequests => Select::Memory(5),
+        }
+    }
+}
+
+/// Picks the counted events and collects a `PerfReport` every frame.
+pub struct PerfMonitor {
+    perf0: Option<Perf0Metric>,
+    perf1: Option<Perf1Metric>,
+    frame: u32,
+}
+
+impl PerfMonitor {
+    /// Starts with neither general counter counting anything.
+    pub const fn new() -> Self {
+        Self {
+            perf0: None,
+            perf1: None,
+            frame: 0,
+        }
+    }
+
+    /// Switches the general counters over to `perf0` and `perf1`, turning off whatever they
+    /// counted before, and clears every counter.
+    pub fn set_metrics(
+        &mut self,
+        fifo: &mut Fifo,
+        perf0: Option<Perf0Metric>,
+        perf1: Option<Perf1Metric>,
+    ) {
+        let previous = [
+            self.perf0.map(Perf0Metric::select),
+            self.perf1.map(Perf1Metric::select),
+        ];
+        for select in previous.into_iter().flatten() {
+            write_select(fifo, select, false);
+        }
+
+        self.perf0 = perf0;
+        self.perf1 = perf1;
+        let current = [
+            perf0.map(Perf0Metric::select),
+            perf1.map(Perf1Metric::select),
+        ];
+        for select in current.into_iter().flatten() {
+            write_select(fifo, select, true);
+        }
+
+        self.reset(fifo);
+    }
+
+    /// Zeroes the GP and PE counters. The PE clear goes through `fifo`, so it lands after
+    /// anything already sent.
+    pub fn reset(&self, fifo: &mut Fifo) {
+        CommandClear::new().with_metrics(true).write();
+
+        fifo.write_bp(*PePerfControl::new().with_control(0));
+        fifo.write_bp(*PePerfControl::new().with_control(0xAAA));
+    }
+
+    /// Reads every counter as it stands.
+    pub fn read(&self) -> PerfReport {
+        PerfReport {
+            frame: self.frame,
+            perf0: self
+                .perf0
+                .map(|metric| (metric, read_split(CP_PERF0_LO, CP_PERF0_HI))),
+            perf1: self
+                .perf1
+                .map(|metric| (metric, read_split(CP_PERF1_LO, CP_PERF1_HI))),
+            vcache_checks: read_split(CP_VCACHE_CHECK_LO, CP_VCACHE_CHECK_HI),
+            vcache_misses: read_split(CP_VCACHE_MISS_LO, CP_VCACHE_MISS_HI),
+            vcache_stalls: read_split(CP_VCACHE_STALL_LO, CP_VCACHE_STALL_HI),
+            z_top_in: read_split(PE_Z_TOP_IN_LO, PE_Z_TOP_IN_HI),
+            z_top_out: read_split(PE_Z_TOP_OUT_LO, PE_Z_TOP_OUT_HI),
+            z_bottom_in: read_split(PE_Z_BOTTOM_IN_LO, PE_Z_BOTTOM_IN_HI),
+            z_bottom_out: read_split(PE_Z_BOTTOM_OUT_LO, PE_Z_BOTTOM_OUT_HI),
+            color_in: read_split(PE_COLOR_IN_LO, PE_COLOR_IN_HI),
+            copy_clocks: read_split(PE_COPY_CLOCKS_LO, PE_COPY_CLOCKS_HI),
+        }
+    }
+
+    /// Reads the counters for the frame just drawn and clears them for the next one. Call it once
+    /// the GP is done with the frame, after `Fifo::copy_display` for example, which waits for it.
+    pub fn end_frame(&mut self, fifo: &mut Fifo) -> PerfReport {
+        let report = self.read();
+        self.reset(fifo);
+        self.frame = self.frame.wrapping_add(1);
+        report
+    }
+}
+
+impl Default for PerfMonitor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn write_select(fifo: &mut Fifo, select: Select, enable: bool) {
+    let value = |select: u32| if enable { select } else { 0 };
+    match select {
+        Select::Xf(select) => fifo.write_xf(*XfPerfSelect::new().with_select(value(select))),
+        Select::Su(select) => fifo.write_bp(*SuPerfSelect::new().with_select(value(select))),
+        Select::Ras(select) => fifo.write_bp(*RasPerfSelect::new().with_select(value(select))),
+        Select::Tx(select) => fifo.write_bp(*TxPerfSelect::new().with_select(value(select))),
+        Select::Cp(metric) => fifo.write_cp(
+            *CpPerfSelect::new()
+                .with_vcache_attribute(VCACHE_ALL_ATTRIBUTES)
+                .with_metric(if enable { metric } else { 0 }),
+        ),
+        Select::Memory(select) => PERF_SELECT.write(if enable { select } else { 0 }),
+    }
+}
+
+/// Counter values for one frame. Pixel counts from the PE are in 2x2 quads.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct PerfReport {
+    pub frame: u32,
+    pub perf0: Option<(Perf0Metric, u32)>,
+    pub perf1: Option<(Perf1Metric, u32)>,
+    pub vcache_checks: u32,
+    pub vcache_misses: u32,
+    pub vcache_stalls: u32,
+    pub z_top_in: u32,
+    pub z_top_out: u32,
+    pub z_bottom_in: u32,
+    pub z_bottom_out: u32,
+    pub color_in: u32,
+    pub copy_clocks: u32,
+}
+
+impl fmt::Display for PerfReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "frame {}", self.frame)?;
+        if let Some((metric, count)) = self.perf0 {
+            writeln!(f, "  {metric:?}: {count}")?;
+        }
+        if let Some((metric, count)) = self.perf1 {
+            writeln!(f, "  {metric:?}: {count}")?;
+        }
+        writeln!(
+            f,
+            "  vertex cache: checks={} misses={} stalls={}",
+            self.vcache_checks, self.vcache_misses, self.vcache_stalls
+        )?;
+        writeln!(
+            f,
+            "  z before texturing: in={} out={}",
+            self.z_top_in, self.z_top_out
+        )?;
+        writeln!(
+            f,
+            "  z after texturing: in={} out={}",
+            self.z_bottom_in, self.z_bottom_out
+        )?;
+        write!(
+            f,
+            "  color in={} copy clocks={}",
+            self.color_in, self.copy_clocks
+        )
+    }
+}
@@ -0,0 +1,389 @@
+//! Shadow copy of the BP, CP and XF registers a `Fifo` has written.
+//!
+//! Every register load going through a `Fifo` is checked against the last value written to that
+//! register, and dropped if it wouldn't change anything. Masked BP writes only compare the bits
+//! under the mask, and registers whose writes start something, like copies, tokens and cache
+//! invalidation, always go out. The GP can change registers behind the shadow's back when it runs
+//! a display list, which is what `Fifo::invalidate_state` is for.
+//!
+//! Since the shadow knows what the registers hold, `Fifo::save_state` and `Fifo::restore_state`
+//! can also put back state that some piece of code changed for its own drawing.
+
+use alloc::boxed::Box;
+use bit_field::BitField;
+
+use super::{
+    bp::{
+        BpMask, EfbCopyControl, PeControl, PeDone, PePerfControl, PeToken, PeTokenInterrupt,
+        TevColorBg, TexCacheInvalidate, TlutLoadDestination,
+    },
+    xf::XfMemory,
+};
+
+/// Register writes a `Fifo` let through and dropped since the stats were last reset.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub struct ShadowStats {
+    pub emitted: u32,
+    pub skipped: u32,
+}
+
+/// What to do with a BP load.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub(super) enum BpLoad {
+    Skip,
+    Emit,
+    /// Emit a `BpMask` with this mask first.
+    EmitMasked(u32),
+}
+
+const XF_REGISTERS: usize = 0x58;
+
+/// The shadowed registers at the time of `Fifo::save_state`.
+#[derive(Clone)]
+pub struct StateSnapshot(pub(super) Box<StateShadow>);
+
+#[derive(Clone)]
+pub(super) struct StateShadow {
+    bp: [u32; 256],
+    /// Bits of each BP register the shadow knows the value of.
+    bp_known: [u32; 256],
+    /// Mask of a `BpMask` load held back until the register it applies to.
+    pending_mask: u32,
+    masked: bool,
+    cp: [u32; 256],
+    cp_known: [u32; 8],
+    xf: [u32; XF_REGISTERS],
+    xf_known: u128,
+    stats: ShadowStats,
+}
+
+impl StateShadow {
+    /// A shadow that doesn't know any register value yet.
+    pub(super) const fn new() -> Self {
+        Self {
+            bp: [0; 256],
+            bp_known: [0; 256],
+            pending_mask: 0,
+            masked: false,
+            cp: [0; 256],
+            cp_known: [0; 8],
+            xf: [0; XF_REGISTERS],
+            xf_known: 0,
+            stats: ShadowStats {
+                emitted: 0,
+                skipped: 0,
+            },
+        }
+    }
+
+    pub(super) const fn stats(&self) -> ShadowStats {
+        self.stats
+    }
+
+    pub(super) fn reset_stats(&mut self) {
+        self.stats = ShadowStats::default();
+    }
+
+    /// Forgets every register value, the next write to each of them goes out.
+    pub(super) fn invalidate(&mut self) {
+        self.bp_known = [0; 256];
+        self.cp_known = [0; 8];
+        self.xf_known = 0;
+    }
+
+    pub(super) fn load_bp(&mut self, word: u32) -> BpLoad {
+        let address = u8::try_from(word.get_bits(24..=31)).unwrap();
+        let value = word.get_bits(0..=23);
+
+        // The GP resets the mask after the next BP load, the shadow does the same.
+        if address == BpMask::ADDRESS {
+            self.pending_mask = value;
+            self.masked = true;
+            return BpLoad::Skip;
+        }
+        let mask = if self.masked {
+            self.pending_mask
+        } else {
+            0xFF_FFFF
+        };
+        let masked = self.masked && mask != 0xFF_FFFF;
+        self.masked = false;
+
+        let index = usize::from(address);
+        let old = self.bp[index];
+        let known = self.bp_known[index];
+        self.bp[index] = (old & !mask) | (value & mask);
+        self.bp_known[index] |= mask;
+
+        let writes = if masked { 2 } else { 1 };
+        if !is_trigger(address) && known & mask == mask && (old ^ value) & mask == 0 {
+            self.stats.skipped += writes;
+            BpLoad::Skip
+        } else {
+            self.stats.emitted += writes;
+            if masked {
+                BpLoad::EmitMasked(mask)
+            } else {
+                BpLoad::Emit
+            }
+        }
+    }
+
+    /// Whether a CP load has to be emitted.
+    pub(super) fn load_cp(&mut self, address: u8, value: u32) -> bool {
+        let index = usize::from(address);
+        let known = self.cp_known[index / 32].get_bit(index % 32);
+        let changed = !known || self.cp[index] != value;
+        self.cp[index] = value;
+        self.cp_known[index / 32].set_bit(index % 32, true);
+        self.count(changed)
+    }
+
+    /// Whether an XF load has to be emitted. Only loads entirely within register space are
+    /// shadowed, matrix and light memory always goes out.
+    pub(super) fn load_xf(&mut self, address: u16, values: &[u32]) -> bool {
+        let registers = XfMemory::Register.range();
+        let end = address + u16::try_from(values.len()).unwrap();
+        if address < registers.start || end > registers.end {
+            return true;
+        }
+
+        let mut changed = false;
+        let start = usize::from(address - registers.start);
+        for (index, value) in (start..).zip(values) {
+            changed |= !self.xf_known.get_bit(index) || self.xf[index] != *value;
+            self.xf[index] = *value;
+            self.xf_known.set_bit(index, true);
+        }
+        self.count(changed)
+    }
+
+    /// The masked BP load that puts `address` back to what it held in `saved`, if it changed
+    /// since. Commands aren't state and are never put back.
+    pub(super) fn restore_bp(&self, saved: &Self, address: u8) -> Option<(u32, u32)> {
+        let index = usize::from(address);
+        let mask = saved.bp_known[index];
+        if mask == 0 || address == BpMask::ADDRESS || is_command(address) {
+            return None;
+        }
+        let value = saved.bp[index];
+        let unchanged = self.bp_known[index] & mask == mask && (self.bp[index] ^ value) & mask == 0;
+        (!unchanged).then_some((mask, u32::from(address) << 24 | value))
+    }
+
+    /// The CP load that puts `address` back to what it held in `saved`, if it changed since.
+    pub(super) fn restore_cp(&self, saved: &Self, address: u8) -> Option<u32> {
+        let index = usize::from(address);
+        let known = |shadow: &Self| shadow.cp_known[index / 32].get_bit(index % 32);
+        let unchanged = known(self) && self.cp[index] == saved.cp[index];
+        (known(saved) && !unchanged).then_some(saved.cp[index])
+    }
+
+    /// The XF register load that puts `address` back to what it held in `saved`, if it changed
+    /// since.
+    pub(super) fn restore_xf(&self, saved: &Self, address: u16) -> Option<u32> {
+        let index = usize::from(address - XfMemory::Register.range().start);
+        let unchanged = self.xf_known.get_bit(index) && self.xf[index] == saved.xf[index];
+        (saved.xf_known.get_bit(index) && !unchanged).then_some(saved.xf[index])
+    }
+
+    fn count(&mut self, changed: bool) -> bool {
+        if changed {
+            self.stats.emitted += 1;
+        } else {
+            self.stats.skipped += 1;
+        }
+        changed
+    }
+}
+
+/// BP registers whose writes do something beyond setting state, so they can't be dropped.
+fn is_trigger(address: u8) -> bool {
+    // The PE control write after a texture copy doubles as a wait for the copy.
+    is_command(address)
+        || address == PeControl::ADDRESS
+        // The TEV color blue/green halves are loaded several times on purpose.
+        || (TevColorBg::ADDRESS..TevColorBg::ADDRESS + 8)
+            .step_by(2)
+            .any(|bg| bg == address)
+}
+
+/// BP registers that start something rather than hold state.
+fn is_command(address: u8) -> bool {
+    const TMEM_PRELOAD: u8 = 0x63;
+
+    [
+        PeDone::ADDRESS,
+        PeToken::ADDRESS,
+        PeTokenInterrupt::ADDRESS,
+        EfbCopyControl::ADDRESS,
+        PePerfControl::ADDRESS,
+        TMEM_PRELOAD,
+        TlutLoadDestination::ADDRESS,
+        TexCacheInvalidate::ADDRESS,
+    ]
+    .contains(&address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MASK: u32 = (BpMask::ADDRESS as u32) << 24;
+
+    fn bp(address: u8, value: u32) -> u32 {
+        u32::from(address) << 24 | value
+    }
+
+    #[test]
+    fn duplicate_loads_are_skipped() {
+        let mut shadow = StateShadow::new();
+        assert_eq!(shadow.load_bp(0x2012_3456), BpLoad::Emit);
+        assert_eq!(shadow.load_bp(0x2012_3456), BpLoad::Skip);
+        assert_eq!(shadow.load_bp(0x2012_3457), BpLoad::Emit);
+
+        assert!(shadow.load_cp(0x50, 1));
+        assert!(!shadow.load_cp(0x50, 1));
+        assert!(shadow.load_cp(0x50, 2));
+
+        assert!(shadow.load_xf(0x1009, &[1, 2]));
+        assert!(!shadow.load_xf(0x1009, &[1, 2]));
+        assert!(!shadow.load_xf(0x100A, &[2]));
+        assert!(shadow.load_xf(0x1009, &[1, 3]));
+    }
+
+    #[test]
+    fn masked_loads_compare_bits_under_the_mask() {
+        let mut shadow = StateShadow::new();
+        // Nothing is known about the register yet.
+        assert_eq!(shadow.load_bp(MASK | 0x0F), BpLoad::Skip);
+        assert_eq!(shadow.load_bp(0x2000_00FF), BpLoad::EmitMasked(0x0F));
+
+        assert_eq!(shadow.load_bp(MASK | 0x0F), BpLoad::Skip);
+        assert_eq!(shadow.load_bp(0x2000_00AF), BpLoad::Skip);
+        assert_eq!(shadow.load_bp(MASK | 0x0F), BpLoad::Skip);
+        assert_eq!(shadow.load_bp(0x2000_00A0), BpLoad::EmitMasked(0x0F));
+
+        // Only the bits under the mask are known, the rest still has to go out.
+        assert_eq!(shadow.load_bp(0x2000_0000), BpLoad::Emit);
+        assert_eq!(shadow.load_bp(0x2000_0000), BpLoad::Skip);
+
+        // The mask only applies to the next load, a full mask is a plain load.
+        assert_eq!(shadow.load_bp(MASK | 0xFF_FFFF), BpLoad::Skip);
+        assert_eq!(shadow.load_bp(0x2000_0000), BpLoad::Skip);
+        assert_eq!(shadow.load_bp(0x2000_0001), BpLoad::Emit);
+    }
+
+    #[test]
+    fn triggers_are_never_skipped() {
+        let mut shadow = StateShadow::new();
+        for address in [
+            PeControl::ADDRESS,
+            TevColorBg::ADDRESS,
+            TevColorBg::ADDRESS + 6,
+            EfbCopyControl::ADDRESS,
+            PeToken::ADDRESS,
+            PeTokenInterrupt::ADDRESS,
+            TexCacheInvalidate::ADDRESS,
+        ] {
+            assert_eq!(shadow.load_bp(bp(address, 0x12)), BpLoad::Emit);
+            assert_eq!(shadow.load_bp(bp(address, 0x12)), BpLoad::Emit);
+        }
+        assert_eq!(shadow.load_bp(MASK | 0xFF), BpLoad::Skip);
+        assert_eq!(
+            shadow.load_bp(bp(PeControl::ADDRESS, 0x12)),
+            BpLoad::EmitMasked(0xFF)
+        );
+    }
+
+    #[test]
+    fn xf_loads_outside_registers_always_go_out() {
+        let mut shadow = StateShadow::new();
+        for _ in 0..2 {
+            assert!(shadow.load_xf(0x0000, &[1, 2, 3, 4]));
+            assert!(shadow.load_xf(0x0FFF, &[1, 2]));
+            assert!(shadow.load_xf(0x1057, &[1, 2]));
+            assert!(shadow.load_xf(0x1058, &[1]));
+        }
+        assert!(shadow.load_xf(0x1057, &[1]));
+        assert!(!shadow.load_xf(0x1057, &[1]));
+    }
+
+    #[test]
+    fn invalidate_forces_emitting_again() {
+        let mut shadow = StateShadow::new();
+        shadow.load_bp(0x2012_3456);
+        shadow.load_cp(0x50, 1);
+        shadow.load_xf(0x1009, &[1]);
+
+        shadow.invalidate();
+        assert_eq!(shadow.load_bp(0x2012_3456), BpLoad::Emit);
+        assert!(shadow.load_cp(0x50, 1));
+        assert!(shadow.load_xf(0x1009, &[1]));
+    }
+
+    #[test]
+    fn restore_only_returns_changed_registers() {
+        let mut shadow = StateShadow::new();
+        shadow.load_bp(0x2012_3456);
+        shadow.load_bp(0x2100_0001);
+        shadow.load_bp(MASK | 0x0F);
+        shadow.load_bp(0x2200_0005);
+        shadow.load_bp(bp(PeToken::ADDRESS, 1));
+        shadow.load_cp(0x50, 1);
+        shadow.load_cp(0x60, 2);
+        shadow.load_xf(0x1009, &[1, 2]);
+        let saved = shadow.clone();
+
+        shadow.load_bp(0x2012_3456);
+        shadow.load_bp(0x2100_0002);
+        shadow.load_bp(0x2200_0006);
+        shadow.load_bp(bp(PeToken::ADDRESS, 2));
+        shadow.load_cp(0x60, 3);
+        shadow.load_xf(0x100A, &[3]);
+        shadow.load_xf(0x100B, &[4]);
+
+        assert_eq!(shadow.restore_bp(&saved, 0x20), None);
+        assert_eq!(
+            shadow.restore_bp(&saved, 0x21),
+            Some((0xFF_FFFF, 0x2100_0001))
+        );
+        assert_eq!(shadow.restore_bp(&saved, 0x22), Some((0x0F, 0x2200_0005)));
+        assert_eq!(shadow.restore_bp(&saved, PeToken::ADDRESS), None);
+        assert_eq!(shadow.restore_bp(&saved, 0x23), None);
+
+        assert_eq!(shadow.restore_cp(&saved, 0x50), None);
+        assert_eq!(shadow.restore_cp(&saved, 0x60), Some(2));
+        assert_eq!(shadow.restore_cp(&saved, 0x70), None);
+
+        assert_eq!(shadow.restore_xf(&saved, 0x1009), None);
+        assert_eq!(shadow.restore_xf(&saved, 0x100A), Some(2));
+        assert_eq!(shadow.restore_xf(&saved, 0x100B), None);
+    }
+
+    #[test]
+    fn stats_count_masked_loads_twice() {
+        let mut shadow = StateShadow::new();
+        shadow.load_bp(0x2012_3456);
+        shadow.load_bp(0x2012_3456);
+        shadow.load_bp(MASK | 0x0F);
+        shadow.load_bp(0x2012_3450);
+        shadow.load_bp(MASK | 0x0F);
+        shadow.load_bp(0x2012_3450);
+        shadow.load_cp(0x50, 1);
+        shadow.load_cp(0x50, 1);
+        shadow.load_xf(0x1009, &[1, 2]);
+        // Matrix memory isn't a register, it doesn't count either way.
+        shadow.load_xf(0x0000, &[1]);
+
+        assert_eq!(
+            shadow.stats(),
+            ShadowStats {
+                emitted: 5,
+                skipped: 4,
+            }
+        );
+        shadow.reset_stats();
+        assert_eq!(shadow.stats(), ShadowStats::default());
+    }
+}
@@ -0,0 +1,303 @@
+//! Batched 2D sprites.
+//!
+//! A `SpriteBatch` collects textured, tinted quads positioned in screen pixels and draws them in
+//! one go, sorted by depth and grouped by texture so each run of sprites sharing a texture is a
+//! single quad draw. The GX state it sets up for that is put back through the `Fifo` register
+//! shadow once it is done, so 3D drawing can carry on around it.
+
+use alloc::vec::Vec;
+
+use super::{
+    bp::CullMode,
+    cp::{
+        AttributeType, ColorElements, ColorFormat, ComponentType, PositionElements,
+        TexCoordElements,
+    },
+    light::{Channel, ChannelConfig},
+    pe::{AlphaOp, BlendFactor, BlendMode, CompareFn, LogicOp},
+    tev::{RasChannel, TevOrder, TevPreset},
+    texgen::TexCoordGen,
+    texture::TexObj,
+    vertex::{Primitive, VertexDescriptor, VertexFormat, VertexLayout},
+    xf::ProjectionType,
+    Fifo, GxCommands,
+};
+use crate::math::{sin_cos, Mtx34, Mtx44};
+
+/// Vertex format slot sprites are drawn with.
+pub const VERTEX_FORMAT: u8 = 7;
+
+/// Most quads a single draw can hold, the vertex count is 16 bits.
+const MAX_QUADS: usize = 0xFFFF / 4;
+
+/// One textured quad, positioned in screen pixels.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Sprite {
+    /// Where `origin` ends up on screen.
+    pub position: [f32; 2],
+    /// Size on screen before scaling, the size of `source` unless changed.
+    pub size: [f32; 2],
+    /// Left, top, width and height of the texels shown, for picking a sprite out of an atlas.
+    pub source: [u16; 4],
+    /// Point the sprite is positioned, scaled and rotated around, in unscaled pixels from its
+    /// top left corner.
+    pub origin: [f32; 2],
+    pub scale: [f32; 2],
+    /// Clockwise on screen, in radians.
+    pub rotation: f32,
+    /// Multiplied with the texture color.
+    pub color: [u8; 4],
+    /// Sprites with a higher depth are drawn over lower ones.
+    pub depth: i16,
+}
+
+impl Sprite {
+    /// The `source` rectangle of a texture drawn unscaled with its top left corner at
+    /// `position`.
+    pub fn new(position: [f32; 2], source: [u16; 4]) -> Self {
+        let [_, _, width, height] = source;
+        Self {
+            position,
+            size: [f32::from(width), f32::from(height)],
+            source,
+            origin: [0.0, 0.0],
+            scale: [1.0, 1.0],
+            rotation: 0.0,
+            color: [0xFF; 4],
+            depth: 0,
+        }
+    }
+
+    /// The whole of `texture` with its top left corner at `position`.
+    pub fn whole(texture: &TexObj, position: [f32; 2]) -> Self {
+        Self::new(position, [0, 0, texture.width(), texture.height()])
+    }
+
+    #[must_use]
+    pub const fn with_size(mut self, size: [f32; 2]) -> Self {
+        self.size = size;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_origin(mut self, origin: [f32; 2]) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Moves the origin to the middle of the sprite, so it rotates in place.
+    #[must_use]
+    pub fn centered(mut self) -> Self {
+        self.origin = [self.size[0] * 0.5, self.size[1] * 0.5];
+        self
+    }
+
+    #[must_use]
+    pub const fn with_scale(mut self, scale: [f32; 2]) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_rotation(mut self, rotation: f32) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_color(mut self, color: [u8; 4]) -> Self {
+        self.color = color;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_depth(mut self, depth: i16) -> Self {
+        self.depth = depth;
+        self
+    }
+
+    /// Screen positions of the top left, top right, bottom right and bottom left corners.
+    pub fn corners(&self) -> [[f32; 2]; 4] {
+        let [width, height] = self.size;
+        let (sin, cos) = if self.rotation == 0.0 {
+            (0.0, 1.0)
+        } else {
+            sin_cos(self.rotation)
+        };
+
+        [[0.0, 0.0], [width, 0.0], [width, height], [0.0, height]].map(|[x, y]| {
+            let x = (x - self.origin[0]) * self.scale[0];
+            let y = (y - self.origin[1]) * self.scale[1];
+            [
+                self.position[0] + x * cos - y * sin,
+                self.position[1] + x * sin + y * cos,
+            ]
+        })
+    }
+
+    /// Texture coordinates of the corners, in the order of `corners`.
+    fn tex_coords(&self, texture: &TexObj) -> [[f32; 2]; 4] {
+        let [left, top, width, height] = self.source.map(f32::from);
+        let (texture_width, texture_height) =
+            (f32::from(texture.width()), f32::from(texture.height()));
+        let (s0, t0) = (left / texture_width, top / texture_height);
+        let (s1, t1) = (
+            (left + width) / texture_width,
+            (top + height) / texture_height,
+        );
+        [[s0, t0], [s1, t0], [s1, t1], [s0, t1]]
+    }
+}
+
+/// Sprites waiting to be drawn onto a `width` x `height` screen.
+pub struct SpriteBatch {
+    width: u16,
+    height: u16,
+    matrix_slot: u8,
+    textures: Vec<TexObj>,
+    sprites: Vec<(usize, Sprite)>,
+}
+
+impl SpriteBatch {
+    /// A batch for an EFB of `width` x `height` pixels, which also becomes the viewport and
+    /// scissor while it draws.
+    pub const fn new(width: u16, height: u16) -> Self {
+        Self {
+            width,
+            height,
+            matrix_slot: 9,
+            textures: Vec::new(),
+            sprites: Vec::new(),
+        }
+    }
+
+    /// Position matrix slot the batch loads its identity matrix into, 9 by default. Matrix
+    /// memory isn't shadowed, so whatever was in the slot is gone after a `submit`.
+    #[must_use]
+    pub const fn with_matrix_slot(mut self, slot: u8) -> Self {
+        assert!(slot < 10, "There are only 10 position matrix slots");
+        self.matrix_slot = slot;
+        self
+    }
+
+    /// Queues `sprite`, showing part of `texture`. The texture data has to stay alive until the
+    /// GP is done with the batch.
+    pub fn draw(&mut self, texture: &TexObj, sprite: Sprite) {
+        let index = self
+            .textures
+            .iter()
+            .position(|queued| queued == texture)
+            .unwrap_or_else(|| {
+                self.textures.push(*texture);
+                self.textures.len() - 1
+            });
+        self.sprites.push((index, sprite));
+    }
+
+    pub fn len(&self) -> usize {
+        self.sprites.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sprites.is_empty()
+    }
+
+    /// Drops the queued sprites without drawing them.
+    pub fn clear(&mut self) {
+        self.textures.clear();
+        self.sprites.clear();
+    }
+
+    /// Draws every queued sprite through texture map 0 and puts the GX state back, leaving the
+    /// batch empty.
+    ///
+    /// Sprites are sorted by depth and, within a depth, by texture, so equal depth sprites only
+    /// keep the order they were queued in when they share a texture.
+    pub fn submit(&mut self, fifo: &mut Fifo) {
+        if self.sprites.is_empty() {
+            return;
+        }
+
+        let saved = fifo.save_state();
+        let layout = Self::layout();
+        self.set_up(fifo, &layout);
+
+        self.sprites
+            .sort_by_key(|(texture, sprite)| (sprite.depth, *texture));
+        for run in self.sprites.chunk_by(|a, b| a.0 == b.0) {
+            let texture = &self.textures[run[0].0];
+            fifo.load_tex_obj(texture, 0);
+
+            for quads in run.chunks(MAX_QUADS) {
+                let count = u16::try_from(quads.len() * 4).unwrap();
+                let mut primitive = fifo.begin(Primitive::Quads, &layout, count);
+                for (_, sprite) in quads {
+                    let corners = sprite.corners();
+                    let tex_coords = sprite.tex_coords(texture);
+                    for (corner, tex_coord) in corners.iter().zip(&tex_coords) {
+                        primitive
+                            .position(corner)
+                            .color(0, sprite.color)
+                            .tex_coord(0, tex_coord);
+                    }
+                }
+                primitive.end();
+            }
+        }
+
+        fifo.restore_state(&saved);
+        self.clear();
+    }
+
+    /// Screen position, color and texture coordinate, all sent direct.
+    fn layout() -> VertexLayout {
+        VertexLayout::new(
+            VERTEX_FORMAT,
+            VertexDescriptor::new()
+                .with_position(AttributeType::Direct)
+                .with_color(0, AttributeType::Direct)
+                .with_tex_coord(0, AttributeType::Direct),
+            VertexFormat::new()
+                .with_position(PositionElements::Xy, ComponentType::F32, 0)
+                .with_color(0, ColorElements::Rgba, ColorFormat::Rgba8)
+                .with_tex_coord(0, TexCoordElements::St, ComponentType::F32, 0),
+        )
+    }
+
+    /// Pixel space projection, one textured and tinted TEV stage and alpha blending, without
+    /// culling, Z or alpha tests.
+    fn set_up(&self, fifo: &mut Fifo, layout: &VertexLayout) {
+        let (width, height) = (f32::from(self.width), f32::from(self.height));
+
+        fifo.set_vertex_layout(layout);
+        fifo.load_identity_matrix();
+        fifo.load_position_matrix(&Mtx34::IDENTITY, self.matrix_slot);
+        fifo.set_current_matrix(self.matrix_slot);
+        fifo.set_projection(
+            &Mtx44::orthographic(0.0, height, 0.0, width, -1.0, 1.0),
+            ProjectionType::Orthographic,
+        );
+        fifo.set_viewport(0.0, 0.0, width, height, 0.0, 1.0);
+        fifo.set_scissor(0, 0, self.width.into(), self.height.into());
+        fifo.set_cull_mode(CullMode::None);
+
+        fifo.set_num_channels(1);
+        fifo.set_channel(Channel::Color0A0, &ChannelConfig::new());
+        fifo.set_tex_coord_gens(&[TexCoordGen::default()]);
+        fifo.set_num_ind_stages(0);
+        fifo.set_tev_direct(0);
+        fifo.set_num_tev_stages(1);
+        fifo.set_tev_order(0, &TevOrder::new(Some(0), Some(0), RasChannel::Color0));
+        fifo.set_tev_op(0, TevPreset::Modulate);
+
+        fifo.set_z_mode(false, CompareFn::Always, false);
+        fifo.set_alpha_compare(CompareFn::Always, 0, AlphaOp::And, CompareFn::Always, 0);
+        fifo.set_blend_mode(
+            BlendMode::Blend,
+            BlendFactor::SrcAlpha,
+            BlendFactor::InvSrcAlpha,
+            LogicOp::Copy,
+        );
+        fifo.set_color_update(true);
+    }
+}
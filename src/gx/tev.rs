@@ -0,0 +1,755 @@
+//! Texture environment (TEV) configuration.
+//!
+//! Every stage computes `d + ((1 - c) * a + c * b)` (or `d - ...`), then applies a bias and a
+//! scale and stores the result into one of the four TEV registers. Stages are chained in order,
+//! the output of the last one is what reaches the pixel engine.
+
+use super::bp::{TevAlphaEnv, TevColorEnv, TevKSel, TevStageOrders};
+
+/// Color combiner input.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ColorInput {
+    Prev,
+    PrevAlpha,
+    Reg0,
+    Reg0Alpha,
+    Reg1,
+    Reg1Alpha,
+    Reg2,
+    Reg2Alpha,
+    Tex,
+    TexAlpha,
+    Ras,
+    RasAlpha,
+    One,
+    Half,
+    Konst,
+    Zero,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidColorInputError;
+
+impl TryFrom<u32> for ColorInput {
+    type Error = InvalidColorInputError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(Self::Prev),
+            0x1 => Ok(Self::PrevAlpha),
+            0x2 => Ok(Self::Reg0),
+            0x3 => Ok(Self::Reg0Alpha),
+            0x4 => Ok(Self::Reg1),
+            0x5 => Ok(Self::Reg1Alpha),
+            0x6 => Ok(Self::Reg2),
+            0x7 => Ok(Self::Reg2Alpha),
+            0x8 => Ok(Self::Tex),
+            0x9 => Ok(Self::TexAlpha),
+            0xA => Ok(Self::Ras),
+            0xB => Ok(Self::RasAlpha),
+            0xC => Ok(Self::One),
+            0xD => Ok(Self::Half),
+            0xE => Ok(Self::Konst),
+            0xF => Ok(Self::Zero),
+            _ => Err(InvalidColorInputError),
+        }
+    }
+}
+
+impl From<ColorInput> for u32 {
+    fn from(value: ColorInput) -> Self {
+        match value {
+            ColorInput::Prev => 0x0,
+            ColorInput::PrevAlpha => 0x1,
+            ColorInput::Reg0 => 0x2,
+            ColorInput::Reg0Alpha => 0x3,
+            ColorInput::Reg1 => 0x4,
+            ColorInput::Reg1Alpha => 0x5,
+            ColorInput::Reg2 => 0x6,
+            ColorInput::Reg2Alpha => 0x7,
+            ColorInput::Tex => 0x8,
+            ColorInput::TexAlpha => 0x9,
+            ColorInput::Ras => 0xA,
+            ColorInput::RasAlpha => 0xB,
+            ColorInput::One => 0xC,
+            ColorInput::Half => 0xD,
+            ColorInput::Konst => 0xE,
+            ColorInput::Zero => 0xF,
+        }
+    }
+}
+
+/// Alpha combiner input.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum AlphaInput {
+    Prev,
+    Reg0,
+    Reg1,
+    Reg2,
+    Tex,
+    Ras,
+    Konst,
+    Zero,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidAlphaInputError;
+
+impl TryFrom<u32> for AlphaInput {
+    type Error = InvalidAlphaInputError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Prev),
+            1 => Ok(Self::Reg0),
+            2 => Ok(Self::Reg1),
+            3 => Ok(Self::Reg2),
+            4 => Ok(Self::Tex),
+            5 => Ok(Self::Ras),
+            6 => Ok(Self::Konst),
+            7 => Ok(Self::Zero),
+            _ => Err(InvalidAlphaInputError),
+        }
+    }
+}
+
+impl From<AlphaInput> for u32 {
+    fn from(value: AlphaInput) -> Self {
+        match value {
+            AlphaInput::Prev => 0,
+            AlphaInput::Reg0 => 1,
+            AlphaInput::Reg1 => 2,
+            AlphaInput::Reg2 => 3,
+            AlphaInput::Tex => 4,
+            AlphaInput::Ras => 5,
+            AlphaInput::Konst => 6,
+            AlphaInput::Zero => 7,
+        }
+    }
+}
+
+/// One of the four TEV color registers, `Prev` is where the final stage has to write.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TevRegister {
+    Prev,
+    Reg0,
+    Reg1,
+    Reg2,
+}
+
+impl TevRegister {
+    pub const fn index(self) -> u8 {
+        match self {
+            Self::Prev => 0,
+            Self::Reg0 => 1,
+            Self::Reg1 => 2,
+            Self::Reg2 => 3,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidTevRegisterError;
+
+impl TryFrom<u32> for TevRegister {
+    type Error = InvalidTevRegisterError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Prev),
+            1 => Ok(Self::Reg0),
+            2 => Ok(Self::Reg1),
+            3 => Ok(Self::Reg2),
+            _ => Err(InvalidTevRegisterError),
+        }
+    }
+}
+
+impl From<TevRegister> for u32 {
+    fn from(value: TevRegister) -> Self {
+        value.index().into()
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Bias {
+    Zero,
+    AddHalf,
+    SubHalf,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidBiasError;
+
+impl TryFrom<u32> for Bias {
+    type Error = InvalidBiasError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Zero),
+            1 => Ok(Self::AddHalf),
+            2 => Ok(Self::SubHalf),
+            _ => Err(InvalidBiasError),
+        }
+    }
+}
+
+impl From<Bias> for u32 {
+    fn from(value: Bias) -> Self {
+        match value {
+            Bias::Zero => 0,
+            Bias::AddHalf => 1,
+            Bias::SubHalf => 2,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Scale {
+    One,
+    Two,
+    Four,
+    Half,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidScaleError;
+
+impl TryFrom<u32> for Scale {
+    type Error = InvalidScaleError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::One),
+            1 => Ok(Self::Two),
+            2 => Ok(Self::Four),
+            3 => Ok(Self::Half),
+            _ => Err(InvalidScaleError),
+        }
+    }
+}
+
+impl From<Scale> for u32 {
+    fn from(value: Scale) -> Self {
+        match value {
+            Scale::One => 0,
+            Scale::Two => 1,
+            Scale::Four => 2,
+            Scale::Half => 3,
+        }
+    }
+}
+
+/// Which channels of `a` and `b` are compared in compare mode.
+///
+/// `Rgb8` compares each channel on its own, for the alpha combiner it compares alpha instead.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum CompareTarget {
+    R8,
+    Gr16,
+    Bgr24,
+    Rgb8,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidCompareTargetError;
+
+impl TryFrom<u32> for CompareTarget {
+    type Error = InvalidCompareTargetError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::R8),
+            1 => Ok(Self::Gr16),
+            2 => Ok(Self::Bgr24),
+            3 => Ok(Self::Rgb8),
+            _ => Err(InvalidCompareTargetError),
+        }
+    }
+}
+
+impl From<CompareTarget> for u32 {
+    fn from(value: CompareTarget) -> Self {
+        match value {
+            CompareTarget::R8 => 0,
+            CompareTarget::Gr16 => 1,
+            CompareTarget::Bgr24 => 2,
+            CompareTarget::Rgb8 => 3,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum CompareOp {
+    Greater,
+    Equal,
+}
+
+/// What a stage does with its inputs.
+///
+/// In compare mode the stage computes `d + (a <op> b ? c : 0)` and ignores bias and scale.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TevOp {
+    Add,
+    Subtract,
+    Compare(CompareTarget, CompareOp),
+}
+
+/// Rasterized color fed into a stage.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum RasChannel {
+    Color0,
+    Color1,
+    AlphaBump,
+    AlphaBumpNormalized,
+    Zero,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidRasChannelError;
+
+impl TryFrom<u32> for RasChannel {
+    type Error = InvalidRasChannelError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Color0),
+            1 => Ok(Self::Color1),
+            5 => Ok(Self::AlphaBump),
+            6 => Ok(Self::AlphaBumpNormalized),
+            7 => Ok(Self::Zero),
+            _ => Err(InvalidRasChannelError),
+        }
+    }
+}
+
+impl From<RasChannel> for u32 {
+    fn from(value: RasChannel) -> Self {
+        match value {
+            RasChannel::Color0 => 0,
+            RasChannel::Color1 => 1,
+            RasChannel::AlphaBump => 5,
+            RasChannel::AlphaBumpNormalized => 6,
+            RasChannel::Zero => 7,
+        }
+    }
+}
+
+/// Source channel of one swap table entry.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum SwapChannel {
+    Red,
+    Green,
+    Blue,
+    Alpha,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidSwapChannelError;
+
+impl TryFrom<u32> for SwapChannel {
+    type Error = InvalidSwapChannelError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Red),
+            1 => Ok(Self::Green),
+            2 => Ok(Self::Blue),
+            3 => Ok(Self::Alpha),
+            _ => Err(InvalidSwapChannelError),
+        }
+    }
+}
+
+impl From<SwapChannel> for u32 {
+    fn from(value: SwapChannel) -> Self {
+        match value {
+            SwapChannel::Red => 0,
+            SwapChannel::Green => 1,
+            SwapChannel::Blue => 2,
+            SwapChannel::Alpha => 3,
+        }
+    }
+}
+
+/// What `ColorInput::Konst` and `AlphaInput::Konst` read in a stage.
+///
+/// The fractions are constants, the other variants pick one of the four konst colors (or one of
+/// its channels, broadcast to all of them).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum KonstSelect {
+    One,
+    SevenEighths,
+    ThreeQuarters,
+    FiveEighths,
+    Half,
+    ThreeEighths,
+    Quarter,
+    Eighth,
+    Color(u8),
+    Red(u8),
+    Green(u8),
+    Blue(u8),
+    Alpha(u8),
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidKonstSelectError;
+
+impl TryFrom<u32> for KonstSelect {
+    type Error = InvalidKonstSelectError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        let index = (value & 3).try_into().unwrap();
+        match value {
+            0x00 => Ok(Self::One),
+            0x01 => Ok(Self::SevenEighths),
+            0x02 => Ok(Self::ThreeQuarters),
+            0x03 => Ok(Self::FiveEighths),
+            0x04 => Ok(Self::Half),
+            0x05 => Ok(Self::ThreeEighths),
+            0x06 => Ok(Self::Quarter),
+            0x07 => Ok(Self::Eighth),
+            0x0C..=0x0F => Ok(Self::Color(index)),
+            0x10..=0x13 => Ok(Self::Red(index)),
+            0x14..=0x17 => Ok(Self::Green(index)),
+            0x18..=0x1B => Ok(Self::Blue(index)),
+            0x1C..=0x1F => Ok(Self::Alpha(index)),
+            _ => Err(InvalidKonstSelectError),
+        }
+    }
+}
+
+impl From<KonstSelect> for u32 {
+    fn from(value: KonstSelect) -> Self {
+        let konst = |base: Self, index: u8| {
+            assert!(index < 4, "There are only 4 konst colors");
+            base + Self::from(index)
+        };
+
+        match value {
+            KonstSelect::One => 0x00,
+            KonstSelect::SevenEighths => 0x01,
+            KonstSelect::ThreeQuarters => 0x02,
+            KonstSelect::FiveEighths => 0x03,
+            KonstSelect::Half => 0x04,
+            KonstSelect::ThreeEighths => 0x05,
+            KonstSelect::Quarter => 0x06,
+            KonstSelect::Eighth => 0x07,
+            KonstSelect::Color(index) => konst(0x0C, index),
+            KonstSelect::Red(index) => konst(0x10, index),
+            KonstSelect::Green(index) => konst(0x14, index),
+            KonstSelect::Blue(index) => konst(0x18, index),
+            KonstSelect::Alpha(index) => konst(0x1C, index),
+        }
+    }
+}
+
+/// Color half of a TEV stage.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct ColorCombiner {
+    pub inputs: [ColorInput; 4],
+    pub op: TevOp,
+    pub bias: Bias,
+    pub scale: Scale,
+    pub clamp: bool,
+    pub dest: TevRegister,
+}
+
+impl ColorCombiner {
+    /// `d + ((1 - c) * a + c * b)` clamped and written to `Prev`.
+    pub const fn new(a: ColorInput, b: ColorInput, c: ColorInput, d: ColorInput) -> Self {
+        Self {
+            inputs: [a, b, c, d],
+            op: TevOp::Add,
+            bias: Bias::Zero,
+            scale: Scale::One,
+            clamp: true,
+            dest: TevRegister::Prev,
+        }
+    }
+
+    /// Passes `input` through unchanged.
+    pub const fn pass(input: ColorInput) -> Self {
+        Self::new(ColorInput::Zero, ColorInput::Zero, ColorInput::Zero, input)
+    }
+
+    #[must_use]
+    pub const fn with_op(mut self, op: TevOp) -> Self {
+        self.op = op;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_bias(mut self, bias: Bias) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_scale(mut self, scale: Scale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_clamp(mut self, clamp: bool) -> Self {
+        self.clamp = clamp;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_dest(mut self, dest: TevRegister) -> Self {
+        self.dest = dest;
+        self
+    }
+
+    pub fn register(&self, stage: u8) -> TevColorEnv {
+        let [a, b, c, d] = self.inputs;
+        let mut reg = TevColorEnv::new(stage);
+        reg.with_a(a)
+            .with_b(b)
+            .with_c(c)
+            .with_d(d)
+            .with_op(self.op, self.bias, self.scale)
+            .with_clamp(self.clamp)
+            .with_dest(self.dest);
+        reg
+    }
+}
+
+/// Alpha half of a TEV stage.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct AlphaCombiner {
+    pub inputs: [AlphaInput; 4],
+    pub op: TevOp,
+    pub bias: Bias,
+    pub scale: Scale,
+    pub clamp: bool,
+    pub dest: TevRegister,
+}
+
+impl AlphaCombiner {
+    /// `d + ((1 - c) * a + c * b)` clamped and written to `Prev`.
+    pub const fn new(a: AlphaInput, b: AlphaInput, c: AlphaInput, d: AlphaInput) -> Self {
+        Self {
+            inputs: [a, b, c, d],
+            op: TevOp::Add,
+            bias: Bias::Zero,
+            scale: Scale::One,
+            clamp: true,
+            dest: TevRegister::Prev,
+        }
+    }
+
+    /// Passes `input` through unchanged.
+    pub const fn pass(input: AlphaInput) -> Self {
+        Self::new(AlphaInput::Zero, AlphaInput::Zero, AlphaInput::Zero, input)
+    }
+
+    #[must_use]
+    pub const fn with_op(mut self, op: TevOp) -> Self {
+        self.op = op;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_bias(mut self, bias: Bias) -> Self {
+        self.bias = bias;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_scale(mut self, scale: Scale) -> Self {
+        self.scale = scale;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_clamp(mut self, clamp: bool) -> Self {
+        self.clamp = clamp;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_dest(mut self, dest: TevRegister) -> Self {
+        self.dest = dest;
+        self
+    }
+
+    /// The swap table selections of the register are left at 0, they are set separately.
+    pub fn register(&self, stage: u8) -> TevAlphaEnv {
+        let [a, b, c, d] = self.inputs;
+        let mut reg = TevAlphaEnv::new(stage);
+        reg.with_a(a)
+            .with_b(b)
+            .with_c(c)
+            .with_d(d)
+            .with_op(self.op, self.bias, self.scale)
+            .with_clamp(self.clamp)
+            .with_dest(self.dest);
+        reg
+    }
+}
+
+/// Common stage setups. Like `GX_SetTevOp`, stages after the first take the previous stage's
+/// output wherever stage 0 takes the rasterized color.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TevPreset {
+    /// Texture multiplied by the rasterized color.
+    Modulate,
+    /// Texture only.
+    Replace,
+    /// Texture blended over the rasterized color by texture alpha.
+    Decal,
+    /// Rasterized color blended towards white by the texture.
+    Blend,
+    /// Rasterized color passed through.
+    PassColor,
+}
+
+impl TevPreset {
+    pub const fn combiners(self, stage: u8) -> (ColorCombiner, AlphaCombiner) {
+        use AlphaInput as A;
+        use ColorInput as C;
+
+        let (color, alpha) = if stage == 0 {
+            (C::Ras, A::Ras)
+        } else {
+            (C::Prev, A::Prev)
+        };
+
+        match self {
+            Self::Modulate => (
+                ColorCombiner::new(C::Zero, C::Tex, color, C::Zero),
+                AlphaCombiner::new(A::Zero, A::Tex, alpha, A::Zero),
+            ),
+            Self::Replace => (ColorCombiner::pass(C::Tex), AlphaCombiner::pass(A::Tex)),
+            Self::Decal => (
+                ColorCombiner::new(color, C::Tex, C::TexAlpha, C::Zero),
+                AlphaCombiner::pass(alpha),
+            ),
+            Self::Blend => (
+                ColorCombiner::new(color, C::One, C::Tex, C::Zero),
+                AlphaCombiner::new(A::Zero, A::Tex, alpha, A::Zero),
+            ),
+            Self::PassColor => (ColorCombiner::pass(color), AlphaCombiner::pass(alpha)),
+        }
+    }
+}
+
+/// Inputs routed into a stage: a texture map sampled with a texture coordinate, and a rasterized
+/// color channel.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TevOrder {
+    pub tex_coord: Option<u8>,
+    pub tex_map: Option<u8>,
+    pub channel: RasChannel,
+}
+
+impl TevOrder {
+    pub const fn new(tex_coord: Option<u8>, tex_map: Option<u8>, channel: RasChannel) -> Self {
+        Self {
+            tex_coord,
+            tex_map,
+            channel,
+        }
+    }
+
+    /// Only a rasterized color, no texture lookup.
+    pub const fn color_only(channel: RasChannel) -> Self {
+        Self::new(None, None, channel)
+    }
+
+    /// Writes this order into the half of `reg` that belongs to `stage`.
+    pub fn apply(&self, stage: u8, reg: &mut TevStageOrders) {
+        let odd = stage % 2 == 1;
+        if let Some(map) = self.tex_map {
+            assert!(map < 8, "There are only 8 texture maps");
+        }
+        if let Some(coord) = self.tex_coord {
+            assert!(coord < 8, "There are only 8 texture coordinates");
+        }
+
+        reg.with_tex_map(odd, self.tex_map.unwrap_or(0))
+            .with_tex_coord(odd, self.tex_coord.unwrap_or(0))
+            .with_tex_enable(odd, self.tex_map.is_some())
+            .with_channel(odd, self.channel);
+    }
+}
+
+/// Reorders the channels of a texture or rasterized color before a stage uses it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct SwapTable {
+    pub red: SwapChannel,
+    pub green: SwapChannel,
+    pub blue: SwapChannel,
+    pub alpha: SwapChannel,
+}
+
+impl SwapTable {
+    pub const IDENTITY: Self = Self::new(
+        SwapChannel::Red,
+        SwapChannel::Green,
+        SwapChannel::Blue,
+        SwapChannel::Alpha,
+    );
+
+    pub const fn new(
+        red: SwapChannel,
+        green: SwapChannel,
+        blue: SwapChannel,
+        alpha: SwapChannel,
+    ) -> Self {
+        Self {
+            red,
+            green,
+            blue,
+            alpha,
+        }
+    }
+
+    /// A table is spread over the low bits of two consecutive konst selection registers.
+    pub fn registers(&self, table: u8) -> (TevKSel, TevKSel) {
+        assert!(table < 4, "There are only 4 swap tables");
+        let mut rg = TevKSel::new(table * 2);
+        rg.with_swap_rb(self.red).with_swap_ga(self.green);
+        let mut ba = TevKSel::new(table * 2 + 1);
+        ba.with_swap_rb(self.blue).with_swap_ga(self.alpha);
+        (rg, ba)
+    }
+}
+
+impl Default for SwapTable {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn presets_take_previous_stage_after_the_first() {
+        let (color, alpha) = TevPreset::Modulate.combiners(0);
+        assert_eq!(color.inputs[2], ColorInput::Ras);
+        assert_eq!(alpha.inputs[2], AlphaInput::Ras);
+
+        let (color, alpha) = TevPreset::Modulate.combiners(1);
+        assert_eq!(color.inputs[2], ColorInput::Prev);
+        assert_eq!(alpha.inputs[2], AlphaInput::Prev);
+
+        let (color, alpha) = TevPreset::Decal.combiners(2);
+        assert_eq!(color.inputs[0], ColorInput::Prev);
+        assert_eq!(alpha.inputs[3], AlphaInput::Prev);
+
+        let (color, alpha) = TevPreset::Blend.combiners(3);
+        assert_eq!(color.inputs[0], ColorInput::Prev);
+        assert_eq!(alpha.inputs[2], AlphaInput::Prev);
+
+        let (color, alpha) = TevPreset::Replace.combiners(1);
+        assert_eq!(color, ColorCombiner::pass(ColorInput::Tex));
+        assert_eq!(alpha, AlphaCombiner::pass(AlphaInput::Tex));
+    }
+}
@@ -0,0 +1,226 @@
+//! Texture coordinate generation.
+//!
+//! XF has 8 generators producing the texture coordinates the TEV stages sample with. A regular
+//! generator transforms a vertex attribute by the texture matrix selected for its coordinate. With
+//! dual texturing on the result can then be normalized and transformed again by a post-transform
+//! matrix. Emboss generators shift an earlier coordinate along a light direction for bump mapping,
+//! and SRTG generators use the red and green of a lit color channel as coordinate, which is how
+//! lighting ramps are looked up.
+
+use super::xf::{PostMtxInfo, TexMtxInfo};
+
+/// Row of post-transform matrix memory the SDK keeps an identity matrix in, after the 20 slots.
+pub(super) const POST_IDENTITY_ROW: u8 = 61;
+
+/// What a generator does with its source, stored in `TexMtxInfo`.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TexGenKind {
+    Regular,
+    Emboss,
+    Color0,
+    Color1,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidTexGenKindError;
+
+impl TryFrom<u32> for TexGenKind {
+    type Error = InvalidTexGenKindError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Regular),
+            1 => Ok(Self::Emboss),
+            2 => Ok(Self::Color0),
+            3 => Ok(Self::Color1),
+            _ => Err(InvalidTexGenKindError),
+        }
+    }
+}
+
+impl From<TexGenKind> for u32 {
+    fn from(value: TexGenKind) -> Self {
+        match value {
+            TexGenKind::Regular => 0,
+            TexGenKind::Emboss => 1,
+            TexGenKind::Color0 => 2,
+            TexGenKind::Color1 => 3,
+        }
+    }
+}
+
+/// Vertex attribute a generator reads.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TexGenSource {
+    Position,
+    Normal,
+    /// The lit color channels, what SRTG generators read.
+    Colors,
+    Binormal,
+    Tangent,
+    /// Vertex texture coordinate 0..=7.
+    TexCoord(u8),
+}
+
+impl TexGenSource {
+    /// Positions and normals have a meaningful third component, texture coordinates don't.
+    pub const fn is_abc1(self) -> bool {
+        matches!(
+            self,
+            Self::Position | Self::Normal | Self::Binormal | Self::Tangent
+        )
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidTexGenSourceError;
+
+impl TryFrom<u32> for TexGenSource {
+    type Error = InvalidTexGenSourceError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Position),
+            1 => Ok(Self::Normal),
+            2 => Ok(Self::Colors),
+            3 => Ok(Self::Binormal),
+            4 => Ok(Self::Tangent),
+            5..=12 => Ok(Self::TexCoord((value - 5).try_into().unwrap())),
+            _ => Err(InvalidTexGenSourceError),
+        }
+    }
+}
+
+impl From<TexGenSource> for u32 {
+    fn from(value: TexGenSource) -> Self {
+        match value {
+            TexGenSource::Position => 0,
+            TexGenSource::Normal => 1,
+            TexGenSource::Colors => 2,
+            TexGenSource::Binormal => 3,
+            TexGenSource::Tangent => 4,
+            TexGenSource::TexCoord(coord) => {
+                assert!(coord < 8, "There are only 8 vertex texture coordinates");
+                5 + Self::from(coord)
+            }
+        }
+    }
+}
+
+/// How a generator comes up with its coordinate.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TexGenType {
+    /// s and t from a 2x4 texture matrix.
+    Mtx2x4(TexGenSource),
+    /// s, t and q from a 3x4 texture matrix, for projected textures.
+    Mtx3x4(TexGenSource),
+    /// Generated coordinate `coord` shifted towards light `light`. Emboss generators have to
+    /// come after the coordinate they shift.
+    Emboss { coord: u8, light: u8 },
+    /// Red and green of color channel 0 or 1 as s and t.
+    Srtg(u8),
+}
+
+/// Setup of one texture coordinate generator, turned into its XF registers by `registers`.
+///
+/// The default passes vertex texture coordinate 0 through the selected texture matrix.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexCoordGen {
+    pub ty: TexGenType,
+    /// Normalize the transformed coordinate before the post-transform matrix.
+    pub normalize: bool,
+    /// Post-transform matrix slot 0..20, `None` for the identity matrix.
+    pub post_matrix: Option<u8>,
+}
+
+impl TexCoordGen {
+    pub const fn new(ty: TexGenType) -> Self {
+        Self {
+            ty,
+            normalize: false,
+            post_matrix: None,
+        }
+    }
+
+    pub const fn mtx2x4(source: TexGenSource) -> Self {
+        Self::new(TexGenType::Mtx2x4(source))
+    }
+
+    pub const fn mtx3x4(source: TexGenSource) -> Self {
+        Self::new(TexGenType::Mtx3x4(source))
+    }
+
+    pub const fn emboss(coord: u8, light: u8) -> Self {
+        Self::new(TexGenType::Emboss { coord, light })
+    }
+
+    pub const fn srtg(channel: u8) -> Self {
+        Self::new(TexGenType::Srtg(channel))
+    }
+
+    /// Sphere map style reflection: view space normals through a 3x4 texture matrix, normalized
+    /// and then scaled into texture space by post-transform matrix `post_matrix`.
+    pub const fn reflection(post_matrix: u8) -> Self {
+        Self::mtx3x4(TexGenSource::Normal)
+            .with_normalize(true)
+            .with_post_matrix(post_matrix)
+    }
+
+    #[must_use]
+    pub const fn with_normalize(mut self, normalize: bool) -> Self {
+        self.normalize = normalize;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_post_matrix(mut self, slot: u8) -> Self {
+        assert!(slot < 20, "There are only 20 post-transform matrix slots");
+        self.post_matrix = Some(slot);
+        self
+    }
+
+    /// Whether the generator needs dual texturing turned on to come out as set up.
+    pub const fn is_dual(&self) -> bool {
+        self.normalize || self.post_matrix.is_some()
+    }
+
+    pub fn registers(&self) -> (TexMtxInfo, PostMtxInfo) {
+        let mut info = TexMtxInfo::new();
+        match self.ty {
+            TexGenType::Mtx2x4(source) | TexGenType::Mtx3x4(source) => {
+                info.with_projected(matches!(self.ty, TexGenType::Mtx3x4(_)))
+                    .with_abc1(source.is_abc1())
+                    .with_kind(TexGenKind::Regular)
+                    .with_source(source);
+            }
+            TexGenType::Emboss { coord, light } => {
+                assert!(coord < 8, "There are only 8 generated texture coordinates");
+                assert!(light < 8, "There are only 8 lights");
+                info.with_kind(TexGenKind::Emboss)
+                    .with_source(TexGenSource::TexCoord(coord))
+                    .with_emboss_source(coord)
+                    .with_emboss_light(light);
+            }
+            TexGenType::Srtg(channel) => {
+                assert!(channel < 2, "XF only has 2 color channels");
+                let kind = if channel == 0 {
+                    TexGenKind::Color0
+                } else {
+                    TexGenKind::Color1
+                };
+                info.with_kind(kind).with_source(TexGenSource::Colors);
+            }
+        }
+
+        let mut post = PostMtxInfo::new();
+        post.with_row(self.post_matrix.map_or(POST_IDENTITY_ROW, |slot| slot * 3))
+            .with_normalize(self.normalize);
+        (info, post)
+    }
+}
+
+impl Default for TexCoordGen {
+    fn default() -> Self {
+        Self::mtx2x4(TexGenSource::TexCoord(0))
+    }
+}
@@ -0,0 +1,715 @@
+//! Texture objects, TMEM regions and palettes.
+//!
+//! A `TexObj` only describes a texture sitting in main memory, binding it to one of the 8 texture
+//! maps also needs a `TexRegion` of TMEM for the GP to cache it in. Color indexed textures look
+//! their colors up in a `TlutObj` that has to be loaded into TMEM first.
+
+use crate::{cache::dc_flush_range, mmio::Physical};
+
+use super::bp::{TexImage0, TexImage1, TexImage2, TexImage3, TexMode0, TexMode1, TexTlut};
+
+pub mod codec;
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TextureFormat {
+    I4,
+    I8,
+    Ia4,
+    Ia8,
+    Rgb565,
+    Rgb5a3,
+    Rgba8,
+    Ci4,
+    Ci8,
+    Ci14,
+    Cmpr,
+}
+
+impl TextureFormat {
+    /// Width and height in texels of one tile.
+    pub const fn block_dimensions(self) -> (usize, usize) {
+        match self {
+            Self::I4 | Self::Ci4 | Self::Cmpr => (8, 8),
+            Self::I8 | Self::Ia4 | Self::Ci8 => (8, 4),
+            Self::Ia8 | Self::Rgb565 | Self::Rgb5a3 | Self::Rgba8 | Self::Ci14 => (4, 4),
+        }
+    }
+
+    /// Every tile is 32 bytes, except RGBA8 which stores its AR and GB halves in two of them.
+    pub const fn block_size(self) -> usize {
+        match self {
+            Self::Rgba8 => 64,
+            _ => 32,
+        }
+    }
+
+    pub const fn bits_per_texel(self) -> usize {
+        match self {
+            Self::I4 | Self::Ci4 | Self::Cmpr => 4,
+            Self::I8 | Self::Ia4 | Self::Ci8 => 8,
+            Self::Ia8 | Self::Rgb565 | Self::Rgb5a3 | Self::Ci14 => 16,
+            Self::Rgba8 => 32,
+        }
+    }
+
+    pub const fn is_indexed(self) -> bool {
+        matches!(self, Self::Ci4 | Self::Ci8 | Self::Ci14)
+    }
+
+    /// Bytes taken by one `width` x `height` image, padded out to whole tiles.
+    pub const fn size(self, width: usize, height: usize) -> usize {
+        let (block_width, block_height) = self.block_dimensions();
+        width.div_ceil(block_width) * height.div_ceil(block_height) * self.block_size()
+    }
+
+    /// Bytes taken by `levels` mipmap levels, each half the size of the previous one.
+    pub const fn mipmap_size(self, width: usize, height: usize, levels: u8) -> usize {
+        let mut size = 0;
+        let mut level = 0;
+        while level < levels {
+            let width = if width >> level == 0 {
+                1
+            } else {
+                width >> level
+            };
+            let height = if height >> level == 0 {
+                1
+            } else {
+                height >> level
+            };
+            size += self.size(width, height);
+            level += 1;
+        }
+        size
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidTextureFormatError;
+
+impl TryFrom<u32> for TextureFormat {
+    type Error = InvalidTextureFormatError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(Self::I4),
+            0x1 => Ok(Self::I8),
+            0x2 => Ok(Self::Ia4),
+            0x3 => Ok(Self::Ia8),
+            0x4 => Ok(Self::Rgb565),
+            0x5 => Ok(Self::Rgb5a3),
+            0x6 => Ok(Self::Rgba8),
+            0x8 => Ok(Self::Ci4),
+            0x9 => Ok(Self::Ci8),
+            0xA => Ok(Self::Ci14),
+            0xE => Ok(Self::Cmpr),
+            _ => Err(InvalidTextureFormatError),
+        }
+    }
+}
+
+impl From<TextureFormat> for u32 {
+    fn from(value: TextureFormat) -> Self {
+        match value {
+            TextureFormat::I4 => 0x0,
+            TextureFormat::I8 => 0x1,
+            TextureFormat::Ia4 => 0x2,
+            TextureFormat::Ia8 => 0x3,
+            TextureFormat::Rgb565 => 0x4,
+            TextureFormat::Rgb5a3 => 0x5,
+            TextureFormat::Rgba8 => 0x6,
+            TextureFormat::Ci4 => 0x8,
+            TextureFormat::Ci8 => 0x9,
+            TextureFormat::Ci14 => 0xA,
+            TextureFormat::Cmpr => 0xE,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum WrapMode {
+    Clamp,
+    Repeat,
+    Mirror,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidWrapModeError;
+
+impl TryFrom<u32> for WrapMode {
+    type Error = InvalidWrapModeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Clamp),
+            1 => Ok(Self::Repeat),
+            2 => Ok(Self::Mirror),
+            _ => Err(InvalidWrapModeError),
+        }
+    }
+}
+
+impl From<WrapMode> for u32 {
+    fn from(value: WrapMode) -> Self {
+        match value {
+            WrapMode::Clamp => 0,
+            WrapMode::Repeat => 1,
+            WrapMode::Mirror => 2,
+        }
+    }
+}
+
+/// Texel filter, the `Mip` variants also pick how neighbouring mipmap levels are combined.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TextureFilter {
+    Near,
+    NearMipNear,
+    NearMipLinear,
+    Linear,
+    LinearMipNear,
+    LinearMipLinear,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidTextureFilterError;
+
+impl TryFrom<u32> for TextureFilter {
+    type Error = InvalidTextureFilterError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Near),
+            1 => Ok(Self::NearMipNear),
+            2 => Ok(Self::NearMipLinear),
+            4 => Ok(Self::Linear),
+            5 => Ok(Self::LinearMipNear),
+            6 => Ok(Self::LinearMipLinear),
+            _ => Err(InvalidTextureFilterError),
+        }
+    }
+}
+
+impl From<TextureFilter> for u32 {
+    fn from(value: TextureFilter) -> Self {
+        match value {
+            TextureFilter::Near => 0,
+            TextureFilter::NearMipNear => 1,
+            TextureFilter::NearMipLinear => 2,
+            TextureFilter::Linear => 4,
+            TextureFilter::LinearMipNear => 5,
+            TextureFilter::LinearMipLinear => 6,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Anisotropy {
+    One,
+    Two,
+    Four,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidAnisotropyError;
+
+impl TryFrom<u32> for Anisotropy {
+    type Error = InvalidAnisotropyError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::One),
+            1 => Ok(Self::Two),
+            2 => Ok(Self::Four),
+            _ => Err(InvalidAnisotropyError),
+        }
+    }
+}
+
+impl From<Anisotropy> for u32 {
+    fn from(value: Anisotropy) -> Self {
+        match value {
+            Anisotropy::One => 0,
+            Anisotropy::Two => 1,
+            Anisotropy::Four => 2,
+        }
+    }
+}
+
+/// Size of one half of a TMEM cache region.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum CacheSize {
+    Size32K,
+    Size128K,
+    Size512K,
+}
+
+impl CacheSize {
+    pub const fn bytes(self) -> u32 {
+        match self {
+            Self::Size32K => 32 * 1024,
+            Self::Size128K => 128 * 1024,
+            Self::Size512K => 512 * 1024,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidCacheSizeError;
+
+impl TryFrom<u32> for CacheSize {
+    type Error = InvalidCacheSizeError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            3 => Ok(Self::Size32K),
+            4 => Ok(Self::Size128K),
+            5 => Ok(Self::Size512K),
+            _ => Err(InvalidCacheSizeError),
+        }
+    }
+}
+
+impl From<CacheSize> for u32 {
+    fn from(value: CacheSize) -> Self {
+        match value {
+            CacheSize::Size32K => 3,
+            CacheSize::Size128K => 4,
+            CacheSize::Size512K => 5,
+        }
+    }
+}
+
+/// Color format of the palette entries of a TLUT.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum TlutFormat {
+    Ia8,
+    Rgb565,
+    Rgb5a3,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidTlutFormatError;
+
+impl TryFrom<u32> for TlutFormat {
+    type Error = InvalidTlutFormatError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Ia8),
+            1 => Ok(Self::Rgb565),
+            2 => Ok(Self::Rgb5a3),
+            _ => Err(InvalidTlutFormatError),
+        }
+    }
+}
+
+impl From<TlutFormat> for u32 {
+    fn from(value: TlutFormat) -> Self {
+        match value {
+            TlutFormat::Ia8 => 0,
+            TlutFormat::Rgb565 => 1,
+            TlutFormat::Rgb5a3 => 2,
+        }
+    }
+}
+
+/// TMEM a texture map caches its texels in.
+///
+/// Even mipmap levels go in the lower half of TMEM and odd levels in the upper half, a texture
+/// without mipmaps only needs the even half.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TexRegion {
+    pub even: (u32, CacheSize),
+    pub odd: Option<(u32, CacheSize)>,
+}
+
+impl TexRegion {
+    /// The fixed 32K + 32K region of `map`, these fill the first 256K of both TMEM halves.
+    pub fn for_map(map: u8) -> Self {
+        assert!(map < 8, "There are only 8 texture maps");
+        let offset = u32::from(map) * CacheSize::Size32K.bytes();
+        Self {
+            even: (offset, CacheSize::Size32K),
+            odd: Some((TMEM_HALF + offset, CacheSize::Size32K)),
+        }
+    }
+}
+
+/// TMEM a TLUT is loaded into.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TlutRegion {
+    pub tmem_addr: u32,
+    pub entries: u16,
+}
+
+impl TlutRegion {
+    /// One of the 16 fixed 256 entry regions at the top of TMEM.
+    pub fn for_index(index: u8) -> Self {
+        assert!(index < 16, "There are only 16 fixed TLUT regions");
+        Self {
+            tmem_addr: TLUT_START + u32::from(index) * 256 * 2,
+            entries: 256,
+        }
+    }
+}
+
+const TMEM_HALF: u32 = 0x8_0000;
+const TMEM_END: u32 = 0x10_0000;
+const TLUT_START: u32 = 0xE_0000;
+const TLUT_ALLOC_START: u32 = TLUT_START + 16 * 256 * 2;
+
+/// Hands out TMEM for cache regions and TLUTs.
+///
+/// Allocation starts after the fixed `TexRegion::for_map` and `TlutRegion::for_index` regions,
+/// so both can be used side by side. Nothing is freed individually, `reset` starts over.
+pub struct TmemAllocator {
+    even: u32,
+    odd: u32,
+    tlut: u32,
+}
+
+impl TmemAllocator {
+    pub const fn new() -> Self {
+        Self {
+            even: 8 * 32 * 1024,
+            odd: TMEM_HALF + 8 * 32 * 1024,
+            tlut: TLUT_ALLOC_START,
+        }
+    }
+
+    /// Allocates an even region of `even` and, for mipmapped textures, an odd one of `odd`.
+    pub fn alloc_region(&mut self, even: CacheSize, odd: Option<CacheSize>) -> Option<TexRegion> {
+        let even_addr = self.even.next_multiple_of(even.bytes());
+        if even_addr + even.bytes() > TMEM_HALF {
+            return None;
+        }
+
+        let odd = match odd {
+            Some(size) => {
+                let addr = self.odd.next_multiple_of(size.bytes());
+                if addr + size.bytes() > TLUT_START {
+                    return None;
+                }
+                self.odd = addr + size.bytes();
+                Some((addr, size))
+            }
+            None => None,
+        };
+        self.even = even_addr + even.bytes();
+
+        Some(TexRegion {
+            even: (even_addr, even),
+            odd,
+        })
+    }
+
+    /// Allocates room for `entries` palette entries, rounded up to whole 16 entry lines.
+    pub fn alloc_tlut(&mut self, entries: u16) -> Option<TlutRegion> {
+        let entries = entries.next_multiple_of(16);
+        let addr = self.tlut;
+        let size = (u32::from(entries) * 2).next_multiple_of(512);
+        if addr + size > TMEM_END {
+            return None;
+        }
+        self.tlut = addr + size;
+
+        Some(TlutRegion {
+            tmem_addr: addr,
+            entries,
+        })
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+}
+
+impl Default for TmemAllocator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A palette for CI4, CI8 and CI14 textures.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct TlutObj {
+    addr: u32,
+    format: TlutFormat,
+    entries: u16,
+}
+
+impl TlutObj {
+    /// `data` is flushed out of the data cache and has to stay alive until the TLUT is loaded.
+    pub fn new(data: &[u16], format: TlutFormat) -> Self {
+        assert!(
+            data.as_ptr().addr() % 32 == 0,
+            "TLUTs must be 32 byte aligned"
+        );
+        assert!(
+            !data.is_empty() && data.len() % 16 == 0 && data.len() <= 16384,
+            "TLUTs hold 16 to 16384 entries in lines of 16"
+        );
+        dc_flush_range(data.as_ptr().cast(), core::mem::size_of_val(data));
+
+        Self {
+            addr: physical_addr(data.as_ptr().cast()),
+            format,
+            entries: data.len().try_into().unwrap(),
+        }
+    }
+
+    pub const fn addr(&self) -> u32 {
+        self.addr
+    }
+
+    pub const fn format(&self) -> TlutFormat {
+        self.format
+    }
+
+    pub const fn entries(&self) -> u16 {
+        self.entries
+    }
+}
+
+/// A texture in main memory, plus how it is sampled.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct TexObj {
+    addr: u32,
+    len: usize,
+    width: u16,
+    height: u16,
+    format: TextureFormat,
+    levels: u8,
+    tlut: Option<(TlutRegion, TlutFormat)>,
+    pub wrap_s: WrapMode,
+    pub wrap_t: WrapMode,
+    pub min_filter: TextureFilter,
+    pub mag_filter: TextureFilter,
+    pub min_lod: f32,
+    pub max_lod: f32,
+    pub lod_bias: f32,
+    pub bias_clamp: bool,
+    pub edge_lod: bool,
+    pub max_anisotropy: Anisotropy,
+}
+
+impl TexObj {
+    /// Wraps already encoded texture data, which is flushed out of the data cache.
+    ///
+    /// `data` has to stay alive for as long as the GP may sample it.
+    pub fn new(data: &[u8], width: u16, height: u16, format: TextureFormat) -> Self {
+        assert!(
+            data.as_ptr().addr() % 32 == 0,
+            "Textures must be 32 byte aligned"
+        );
+        assert!(
+            (1..=1024).contains(&width) && (1..=1024).contains(&height),
+            "Textures are 1 to 1024 texels in each dimension"
+        );
+        assert!(
+            data.len() >= format.size(width.into(), height.into()),
+            "Texture data is smaller than its size and format need"
+        );
+        dc_flush_range(data.as_ptr(), data.len());
+
+        Self {
+            addr: physical_addr(data.as_ptr()),
+            len: data.len(),
+            width,
+            height,
+            format,
+            levels: 1,
+            tlut: None,
+            wrap_s: WrapMode::Clamp,
+            wrap_t: WrapMode::Clamp,
+            min_filter: TextureFilter::Linear,
+            mag_filter: TextureFilter::Linear,
+            min_lod: 0.0,
+            max_lod: 0.0,
+            lod_bias: 0.0,
+            bias_clamp: false,
+            edge_lod: false,
+            max_anisotropy: Anisotropy::One,
+        }
+    }
+
+    pub const fn addr(&self) -> u32 {
+        self.addr
+    }
+
+    pub const fn width(&self) -> u16 {
+        self.width
+    }
+
+    pub const fn height(&self) -> u16 {
+        self.height
+    }
+
+    pub const fn format(&self) -> TextureFormat {
+        self.format
+    }
+
+    pub const fn levels(&self) -> u8 {
+        self.levels
+    }
+
+    pub const fn tlut(&self) -> Option<(TlutRegion, TlutFormat)> {
+        self.tlut
+    }
+
+    #[must_use]
+    pub const fn with_wrap(mut self, wrap_s: WrapMode, wrap_t: WrapMode) -> Self {
+        self.wrap_s = wrap_s;
+        self.wrap_t = wrap_t;
+        self
+    }
+
+    #[must_use]
+    pub fn with_filter(mut self, min_filter: TextureFilter, mag_filter: TextureFilter) -> Self {
+        assert!(
+            matches!(mag_filter, TextureFilter::Near | TextureFilter::Linear),
+            "Magnification can't use mipmaps"
+        );
+        self.min_filter = min_filter;
+        self.mag_filter = mag_filter;
+        self
+    }
+
+    /// Marks the data as holding `levels` mipmap levels, switching to trilinear filtering.
+    #[must_use]
+    pub fn with_mipmaps(mut self, levels: u8) -> Self {
+        assert!((1..=11).contains(&levels), "Textures have 1 to 11 levels");
+        assert!(
+            self.len
+                >= self
+                    .format
+                    .mipmap_size(self.width.into(), self.height.into(), levels),
+            "Texture data is smaller than its mipmap levels need"
+        );
+        self.levels = levels;
+        self.min_filter = if levels > 1 {
+            TextureFilter::LinearMipLinear
+        } else {
+            TextureFilter::Linear
+        };
+        self.min_lod = 0.0;
+        self.max_lod = f32::from(levels - 1);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_lod(mut self, min_lod: f32, max_lod: f32, lod_bias: f32) -> Self {
+        self.min_lod = min_lod;
+        self.max_lod = max_lod;
+        self.lod_bias = lod_bias;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_bias_clamp(mut self, enable: bool) -> Self {
+        self.bias_clamp = enable;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_edge_lod(mut self, enable: bool) -> Self {
+        self.edge_lod = enable;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_max_anisotropy(mut self, anisotropy: Anisotropy) -> Self {
+        self.max_anisotropy = anisotropy;
+        self
+    }
+
+    /// Points a color indexed texture at the TMEM its palette was loaded into.
+    #[must_use]
+    pub fn with_tlut(mut self, tlut: &TlutObj, region: TlutRegion) -> Self {
+        assert!(
+            self.format.is_indexed(),
+            "Only color indexed textures use a TLUT"
+        );
+        self.tlut = Some((region, tlut.format()));
+        self
+    }
+
+    pub fn registers(
+        &self,
+        map: u8,
+        region: &TexRegion,
+    ) -> (
+        TexMode0,
+        TexMode1,
+        TexImage0,
+        TexImage1,
+        TexImage2,
+        TexImage3,
+    ) {
+        let mut mode0 = TexMode0::new(map);
+        mode0
+            .with_wrap_s(self.wrap_s)
+            .with_wrap_t(self.wrap_t)
+            .with_min_filter(self.min_filter)
+            .with_mag_filter(self.mag_filter)
+            .with_diagonal_lod(!self.edge_lod)
+            .with_lod_bias(lod_bias(self.lod_bias))
+            .with_max_anisotropy(self.max_anisotropy)
+            .with_bias_clamp(self.bias_clamp);
+
+        let mut mode1 = TexMode1::new(map);
+        mode1
+            .with_min_lod(lod(self.min_lod))
+            .with_max_lod(lod(self.max_lod));
+
+        let mut image0 = TexImage0::new(map);
+        image0
+            .with_width(self.width)
+            .with_height(self.height)
+            .with_format(self.format);
+
+        let (even_addr, even_size) = region.even;
+        let mut image1 = TexImage1::new(map);
+        image1
+            .with_tmem_addr(even_addr)
+            .with_cache_size(even_size)
+            .with_preloaded(false);
+
+        let mut image2 = TexImage2::new(map);
+        if let Some((odd_addr, odd_size)) = region.odd {
+            image2.with_tmem_addr(odd_addr).with_cache_size(odd_size);
+        } else {
+            assert!(self.levels == 1, "Mipmapped textures need an odd region");
+        }
+
+        let mut image3 = TexImage3::new(map);
+        image3.with_addr(self.addr);
+
+        (mode0, mode1, image0, image1, image2, image3)
+    }
+
+    /// Only color indexed textures have one.
+    pub fn tlut_register(&self, map: u8) -> Option<TexTlut> {
+        self.tlut.map(|(region, format)| {
+            let mut reg = TexTlut::new(map);
+            reg.with_tmem_addr(region.tmem_addr).with_format(format);
+            reg
+        })
+    }
+}
+
+fn physical_addr(ptr: *const u8) -> u32 {
+    Physical::new(ptr.cast_mut()).addr().try_into().unwrap()
+}
+
+/// LODs are unsigned 4.4 fixed point, capped at the 10 levels a 1024x1024 texture can have.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+fn lod(value: f32) -> u8 {
+    (value.clamp(0.0, 10.0) * 16.0) as u8
+}
+
+/// The LOD bias is signed 2.5 fixed point.
+#[allow(clippy::cast_possible_truncation)]
+fn lod_bias(value: f32) -> i8 {
+    (value.clamp(-4.0, 3.99) * 32.0) as i8
+}
@@ -0,0 +1,857 @@
+//! Conversion between linear RGBA8 images and the tiled GX texture formats.
+//!
+//! Nothing in here touches the hardware, so the same code encodes procedurally generated textures
+//! at runtime and runs on the host. Images are rows of `[r, g, b, a]` bytes, encoded data is
+//! padded out to whole tiles the way the GP expects it.
+
+use alloc::{vec, vec::Vec};
+
+use super::{TextureFormat, TlutFormat};
+
+#[derive(Debug)]
+pub enum Error {
+    /// Width or height is zero, or larger than a texture can be.
+    InvalidDimensions,
+    /// An input or output buffer doesn't match the image size.
+    WrongLength { expected: usize, got: usize },
+    /// Color indexed formats go through `encode_indexed` and `decode_indexed`.
+    Indexed,
+    /// `encode_indexed` and `decode_indexed` only take color indexed formats.
+    NotIndexed,
+    /// The image has more distinct colors than the format can index.
+    TooManyColors,
+    /// A texel indexes past the end of the palette.
+    IndexOutOfRange,
+}
+
+/// Encodes an RGBA8 image into `out`, which has to be exactly `format.size(width, height)` long.
+///
+/// # Errors
+///
+/// Fails on color indexed formats and on buffers that don't match the image size.
+pub fn encode_into(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    format: TextureFormat,
+    out: &mut [u8],
+) -> Result<(), Error> {
+    check_dimensions(width, height)?;
+    check_len(rgba.len(), width * height * 4)?;
+    check_len(out.len(), format.size(width, height))?;
+    if format.is_indexed() {
+        return Err(Error::Indexed);
+    }
+
+    let pixel = |x: usize, y: usize| -> [u8; 4] {
+        let offset = (y * width + x) * 4;
+        rgba[offset..offset + 4].try_into().unwrap()
+    };
+
+    match format {
+        TextureFormat::Rgba8 => encode_rgba8(width, height, out, pixel),
+        TextureFormat::Cmpr => encode_cmpr(width, height, out, pixel),
+        _ => pack(format, width, height, out, |x, y| {
+            encode_texel(format, pixel(x, y))
+        }),
+    }
+    Ok(())
+}
+
+/// Same as `encode_into`, into a new buffer.
+///
+/// The buffer isn't 32 byte aligned, copy it somewhere that is before handing it to a `TexObj`.
+///
+/// # Errors
+///
+/// See `encode_into`.
+pub fn encode(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    format: TextureFormat,
+) -> Result<Vec<u8>, Error> {
+    check_dimensions(width, height)?;
+    let mut out = vec![0; format.size(width, height)];
+    encode_into(rgba, width, height, format, &mut out)?;
+    Ok(out)
+}
+
+/// Decodes texture data back into an RGBA8 image.
+///
+/// # Errors
+///
+/// Fails on color indexed formats and when `data` is shorter than the image.
+pub fn decode(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    format: TextureFormat,
+) -> Result<Vec<u8>, Error> {
+    check_dimensions(width, height)?;
+    check_min_len(data.len(), format.size(width, height))?;
+    if format.is_indexed() {
+        return Err(Error::Indexed);
+    }
+
+    let mut rgba = vec![0; width * height * 4];
+    let mut put = |x: usize, y: usize, color: [u8; 4]| {
+        let offset = (y * width + x) * 4;
+        rgba[offset..offset + 4].copy_from_slice(&color);
+    };
+
+    match format {
+        TextureFormat::Rgba8 => decode_rgba8(data, width, height, put),
+        TextureFormat::Cmpr => decode_cmpr(data, width, height, put),
+        _ => unpack(format, data, width, height, |x, y, texel| {
+            put(x, y, decode_texel(format, texel));
+        }),
+    }
+    Ok(rgba)
+}
+
+/// Encodes an RGBA8 image as a color indexed texture, building the palette on the way.
+///
+/// Colors are quantized to `tlut_format` first and the palette is padded to whole 16 entry lines,
+/// so it can go straight into a `TlutObj`.
+///
+/// # Errors
+///
+/// Fails on direct color formats and when the image has more colors than `format` can index.
+pub fn encode_indexed(
+    rgba: &[u8],
+    width: usize,
+    height: usize,
+    format: TextureFormat,
+    tlut_format: TlutFormat,
+) -> Result<(Vec<u8>, Vec<u16>), Error> {
+    check_dimensions(width, height)?;
+    check_len(rgba.len(), width * height * 4)?;
+    if !format.is_indexed() {
+        return Err(Error::NotIndexed);
+    }
+
+    let colors: Vec<u16> = rgba
+        .chunks_exact(4)
+        .map(|pixel| encode_color(tlut_format, pixel.try_into().unwrap()))
+        .collect();
+
+    let mut palette = colors.clone();
+    palette.sort_unstable();
+    palette.dedup();
+    if palette.len() > 1 << format.bits_per_texel().min(14) {
+        return Err(Error::TooManyColors);
+    }
+
+    let indices: Vec<u16> = colors
+        .iter()
+        .map(|color| palette.binary_search(color).unwrap().try_into().unwrap())
+        .collect();
+    let mut out = vec![0; format.size(width, height)];
+    encode_indices_into(&indices, width, height, format, &mut out)?;
+
+    palette.resize(palette.len().next_multiple_of(16), 0);
+    Ok((out, palette))
+}
+
+/// Encodes palette indices, one per texel in row order, into `out`.
+///
+/// # Errors
+///
+/// Fails on direct color formats, indices that don't fit the format and wrongly sized buffers.
+pub fn encode_indices_into(
+    indices: &[u16],
+    width: usize,
+    height: usize,
+    format: TextureFormat,
+    out: &mut [u8],
+) -> Result<(), Error> {
+    check_dimensions(width, height)?;
+    check_len(indices.len(), width * height)?;
+    check_len(out.len(), format.size(width, height))?;
+    if !format.is_indexed() {
+        return Err(Error::NotIndexed);
+    }
+    let limit = 1 << format.bits_per_texel().min(14);
+    if indices.iter().any(|index| usize::from(*index) >= limit) {
+        return Err(Error::IndexOutOfRange);
+    }
+
+    pack(format, width, height, out, |x, y| indices[y * width + x]);
+    Ok(())
+}
+
+/// Decodes a color indexed texture through its palette into an RGBA8 image.
+///
+/// # Errors
+///
+/// Fails on direct color formats, short data and texels indexing past the palette.
+pub fn decode_indexed(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    format: TextureFormat,
+    palette: &[u16],
+    tlut_format: TlutFormat,
+) -> Result<Vec<u8>, Error> {
+    check_dimensions(width, height)?;
+    check_min_len(data.len(), format.size(width, height))?;
+    if !format.is_indexed() {
+        return Err(Error::NotIndexed);
+    }
+
+    let mut rgba = vec![0; width * height * 4];
+    let mut result = Ok(());
+    unpack(format, data, width, height, |x, y, texel| {
+        let texel = if format == TextureFormat::Ci14 {
+            texel & 0x3FFF
+        } else {
+            texel
+        };
+        match palette.get(usize::from(texel)) {
+            Some(color) => {
+                let offset = (y * width + x) * 4;
+                rgba[offset..offset + 4].copy_from_slice(&decode_color(tlut_format, *color));
+            }
+            None => result = Err(Error::IndexOutOfRange),
+        }
+    });
+    result.map(|()| rgba)
+}
+
+/// Encodes one palette entry.
+pub fn encode_color(format: TlutFormat, color: [u8; 4]) -> u16 {
+    encode_texel(tlut_texture_format(format), color)
+}
+
+/// Decodes one palette entry.
+pub fn decode_color(format: TlutFormat, color: u16) -> [u8; 4] {
+    decode_texel(tlut_texture_format(format), color)
+}
+
+const fn tlut_texture_format(format: TlutFormat) -> TextureFormat {
+    match format {
+        TlutFormat::Ia8 => TextureFormat::Ia8,
+        TlutFormat::Rgb565 => TextureFormat::Rgb565,
+        TlutFormat::Rgb5a3 => TextureFormat::Rgb5a3,
+    }
+}
+
+const fn check_dimensions(width: usize, height: usize) -> Result<(), Error> {
+    if width == 0 || height == 0 || width > 1024 || height > 1024 {
+        Err(Error::InvalidDimensions)
+    } else {
+        Ok(())
+    }
+}
+
+const fn check_len(got: usize, expected: usize) -> Result<(), Error> {
+    if got == expected {
+        Ok(())
+    } else {
+        Err(Error::WrongLength { expected, got })
+    }
+}
+
+const fn check_min_len(got: usize, expected: usize) -> Result<(), Error> {
+    if got >= expected {
+        Ok(())
+    } else {
+        Err(Error::WrongLength { expected, got })
+    }
+}
+
+/// Top left texel of every tile, in the order the tiles are stored.
+fn tiles(
+    width: usize,
+    height: usize,
+    format: TextureFormat,
+) -> impl Iterator<Item = (usize, usize)> {
+    let (block_width, block_height) = format.block_dimensions();
+    (0..height)
+        .step_by(block_height)
+        .flat_map(move |y| (0..width).step_by(block_width).map(move |x| (x, y)))
+}
+
+/// Writes one texel value per position, tile by tile, padding partial tiles with zero.
+fn pack(
+    format: TextureFormat,
+    width: usize,
+    height: usize,
+    out: &mut [u8],
+    mut texel: impl FnMut(usize, usize) -> u16,
+) {
+    let (block_width, block_height) = format.block_dimensions();
+    let bits = format.bits_per_texel();
+    let mut position = 0;
+
+    for (tile_x, tile_y) in tiles(width, height, format) {
+        for y in tile_y..tile_y + block_height {
+            for x in tile_x..tile_x + block_width {
+                let value = if x < width && y < height {
+                    texel(x, y)
+                } else {
+                    0
+                };
+                let [high, low] = value.to_be_bytes();
+                let offset = position / 8;
+                match bits {
+                    4 if position % 8 == 0 => out[offset] = low << 4,
+                    4 => out[offset] |= low & 0xF,
+                    8 => out[offset] = low,
+                    _ => out[offset..offset + 2].copy_from_slice(&[high, low]),
+                }
+                position += bits;
+            }
+        }
+    }
+}
+
+/// Reads one texel value per position inside the image, tile by tile.
+fn unpack(
+    format: TextureFormat,
+    data: &[u8],
+    width: usize,
+    height: usize,
+    mut texel: impl FnMut(usize, usize, u16),
+) {
+    let (block_width, block_height) = format.block_dimensions();
+    let bits = format.bits_per_texel();
+    let mut position = 0;
+
+    for (tile_x, tile_y) in tiles(width, height, format) {
+        for y in tile_y..tile_y + block_height {
+            for x in tile_x..tile_x + block_width {
+                let offset = position / 8;
+                let value = match bits {
+                    4 if position % 8 == 0 => u16::from(data[offset] >> 4),
+                    4 => u16::from(data[offset] & 0xF),
+                    8 => u16::from(data[offset]),
+                    _ => u16::from_be_bytes([data[offset], data[offset + 1]]),
+                };
+                if x < width && y < height {
+                    texel(x, y, value);
+                }
+                position += bits;
+            }
+        }
+    }
+}
+
+fn encode_rgba8(
+    width: usize,
+    height: usize,
+    out: &mut [u8],
+    pixel: impl Fn(usize, usize) -> [u8; 4],
+) {
+    for (tile, (tile_x, tile_y)) in tiles(width, height, TextureFormat::Rgba8).enumerate() {
+        let base = tile * 64;
+        for texel in 0..16 {
+            let (x, y) = (tile_x + texel % 4, tile_y + texel / 4);
+            let [red, green, blue, alpha] = if x < width && y < height {
+                pixel(x, y)
+            } else {
+                [0; 4]
+            };
+            let offset = base + texel * 2;
+            out[offset..offset + 2].copy_from_slice(&[alpha, red]);
+            out[offset + 32..offset + 34].copy_from_slice(&[green, blue]);
+        }
+    }
+}
+
+fn decode_rgba8(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    mut put: impl FnMut(usize, usize, [u8; 4]),
+) {
+    for (tile, (tile_x, tile_y)) in tiles(width, height, TextureFormat::Rgba8).enumerate() {
+        let base = tile * 64;
+        for texel in 0..16 {
+            let (x, y) = (tile_x + texel % 4, tile_y + texel / 4);
+            if x < width && y < height {
+                let offset = base + texel * 2;
+                let (alpha, red) = (data[offset], data[offset + 1]);
+                let (green, blue) = (data[offset + 32], data[offset + 33]);
+                put(x, y, [red, green, blue, alpha]);
+            }
+        }
+    }
+}
+
+/// Every 8x8 CMPR tile holds four DXT1 blocks: top left, top right, bottom left, bottom right.
+fn encode_cmpr(
+    width: usize,
+    height: usize,
+    out: &mut [u8],
+    pixel: impl Fn(usize, usize) -> [u8; 4],
+) {
+    for (tile, (tile_x, tile_y)) in tiles(width, height, TextureFormat::Cmpr).enumerate() {
+        for sub_block in 0..4 {
+            let (block_x, block_y) = (tile_x + sub_block % 2 * 4, tile_y + sub_block / 2 * 4);
+            let mut pixels = [None; 16];
+            for (texel, slot) in pixels.iter_mut().enumerate() {
+                let (x, y) = (block_x + texel % 4, block_y + texel / 4);
+                if x < width && y < height {
+                    *slot = Some(pixel(x, y));
+                }
+            }
+
+            let offset = tile * 32 + sub_block * 8;
+            out[offset..offset + 8].copy_from_slice(&encode_dxt1_block(&pixels));
+        }
+    }
+}
+
+fn decode_cmpr(
+    data: &[u8],
+    width: usize,
+    height: usize,
+    mut put: impl FnMut(usize, usize, [u8; 4]),
+) {
+    for (tile, (tile_x, tile_y)) in tiles(width, height, TextureFormat::Cmpr).enumerate() {
+        for sub_block in 0..4 {
+            let (block_x, block_y) = (tile_x + sub_block % 2 * 4, tile_y + sub_block / 2 * 4);
+            let offset = tile * 32 + sub_block * 8;
+            let block = &data[offset..offset + 8];
+            let palette = dxt1_palette(
+                u16::from_be_bytes([block[0], block[1]]),
+                u16::from_be_bytes([block[2], block[3]]),
+            );
+
+            for texel in 0..16 {
+                let (x, y) = (block_x + texel % 4, block_y + texel / 4);
+                if x < width && y < height {
+                    let index = (block[4 + texel / 4] >> (6 - texel % 4 * 2)) & 3;
+                    put(x, y, palette[usize::from(index)]);
+                }
+            }
+        }
+    }
+}
+
+/// Picks the two most distant opaque colors as endpoints and maps every texel to the closest
+/// palette entry. Blocks with transparent texels use the three color mode.
+fn encode_dxt1_block(pixels: &[Option<[u8; 4]>; 16]) -> [u8; 8] {
+    let opaque: Vec<[u8; 4]> = pixels
+        .iter()
+        .flatten()
+        .filter(|p| p[3] >= 128)
+        .copied()
+        .collect();
+    let transparent = pixels.iter().flatten().any(|p| p[3] < 128);
+
+    let (mut first, mut second) = (0, 0);
+    let mut best = 0;
+    for (i, a) in opaque.iter().enumerate() {
+        for b in &opaque[i..] {
+            let distance = color_distance(*a, *b);
+            if distance >= best {
+                best = distance;
+                first = encode_texel(TextureFormat::Rgb565, *a);
+                second = encode_texel(TextureFormat::Rgb565, *b);
+            }
+        }
+    }
+
+    // The endpoint order selects the mode: color0 > color1 is four colors, otherwise three colors
+    // plus transparent.
+    let (color0, color1) = if transparent == (first > second) {
+        (second, first)
+    } else {
+        (first, second)
+    };
+    let palette = dxt1_palette(color0, color1);
+    let usable = if color0 > color1 { 4 } else { 3 };
+
+    let [c0_high, c0_low] = color0.to_be_bytes();
+    let [c1_high, c1_low] = color1.to_be_bytes();
+    let mut block = [c0_high, c0_low, c1_high, c1_low, 0, 0, 0, 0];
+    for (texel, pixel) in pixels.iter().enumerate() {
+        let index = match pixel {
+            Some(pixel) if pixel[3] < 128 => 3,
+            Some(pixel) => (0..usable)
+                .min_by_key(|index| color_distance(*pixel, palette[*index]))
+                .unwrap(),
+            None => 0,
+        };
+        block[4 + texel / 4] |= u8::try_from(index).unwrap() << (6 - texel % 4 * 2);
+    }
+    block
+}
+
+/// The blends match what the hardware does rather than the exact thirds of DXT1.
+fn dxt1_palette(color0: u16, color1: u16) -> [[u8; 4]; 4] {
+    let c0 = decode_texel(TextureFormat::Rgb565, color0);
+    let c1 = decode_texel(TextureFormat::Rgb565, color1);
+    let blend = |a: u8, b: u8, wa: u16, wb: u16| {
+        u8::try_from((u16::from(a) * wa + u16::from(b) * wb) / (wa + wb)).unwrap()
+    };
+    let mix = |wa: u16, wb: u16, alpha: u8| {
+        [
+            blend(c0[0], c1[0], wa, wb),
+            blend(c0[1], c1[1], wa, wb),
+            blend(c0[2], c1[2], wa, wb),
+            alpha,
+        ]
+    };
+
+    if color0 > color1 {
+        [c0, c1, mix(5, 3, 255), mix(3, 5, 255)]
+    } else {
+        [c0, c1, mix(1, 1, 255), mix(1, 1, 0)]
+    }
+}
+
+fn color_distance(a: [u8; 4], b: [u8; 4]) -> u32 {
+    a[..3]
+        .iter()
+        .zip(&b[..3])
+        .map(|(a, b)| u32::from(a.abs_diff(*b)).pow(2))
+        .sum()
+}
+
+fn intensity([r, g, b, _]: [u8; 4]) -> u8 {
+    let luma = (u32::from(r) * 299 + u32::from(g) * 587 + u32::from(b) * 114 + 500) / 1000;
+    luma.try_into().unwrap()
+}
+
+/// Rounds an 8 bit channel down to `bits` bits.
+fn quantize(value: u8, bits: u32) -> u16 {
+    let max = (1 << bits) - 1;
+    ((u32::from(value) * max + 127) / 255).try_into().unwrap()
+}
+
+/// Widens a `bits` bit channel back to 8 bits by repeating its bits, like the GP does.
+fn expand(value: u16, bits: u32) -> u8 {
+    let mut result = (u32::from(value) & ((1 << bits) - 1)) << (8 - bits);
+    let mut filled = bits;
+    while filled < 8 {
+        result |= result >> filled;
+        filled *= 2;
+    }
+    (result & 0xFF).try_into().unwrap()
+}
+
+/// Texel value of the formats that store one color per texel.
+fn encode_texel(format: TextureFormat, color: [u8; 4]) -> u16 {
+    let [r, g, b, a] = color;
+    match format {
+        TextureFormat::I4 => quantize(intensity(color), 4),
+        TextureFormat::I8 => intensity(color).into(),
+        TextureFormat::Ia4 => quantize(a, 4) << 4 | quantize(intensity(color), 4),
+        TextureFormat::Ia8 => u16::from(a) << 8 | u16::from(intensity(color)),
+        TextureFormat::Rgb565 => quantize(r, 5) << 11 | quantize(g, 6) << 5 | quantize(b, 5),
+        TextureFormat::Rgb5a3 if quantize(a, 3) == 7 => {
+            0x8000 | quantize(r, 5) << 10 | quantize(g, 5) << 5 | quantize(b, 5)
+        }
+        TextureFormat::Rgb5a3 => {
+            quantize(a, 3) << 12 | quantize(r, 4) << 8 | quantize(g, 4) << 4 | quantize(b, 4)
+        }
+        _ => unreachable!("{format:?} doesn't store one color per texel"),
+    }
+}
+
+fn decode_texel(format: TextureFormat, texel: u16) -> [u8; 4] {
+    match format {
+        TextureFormat::I4 => [expand(texel, 4); 4],
+        TextureFormat::I8 => [expand(texel, 8); 4],
+        TextureFormat::Ia4 => {
+            let i = expand(texel, 4);
+            [i, i, i, expand(texel >> 4, 4)]
+        }
+        TextureFormat::Ia8 => {
+            let [a, i] = texel.to_be_bytes();
+            [i, i, i, a]
+        }
+        TextureFormat::Rgb565 => [
+            expand(texel >> 11, 5),
+            expand(texel >> 5, 6),
+            expand(texel, 5),
+            0xFF,
+        ],
+        TextureFormat::Rgb5a3 if texel & 0x8000 != 0 => [
+            expand(texel >> 10, 5),
+            expand(texel >> 5, 5),
+            expand(texel, 5),
+            0xFF,
+        ],
+        TextureFormat::Rgb5a3 => [
+            expand(texel >> 8, 4),
+            expand(texel >> 4, 4),
+            expand(texel, 4),
+            expand(texel >> 12, 3),
+        ],
+        _ => unreachable!("{format:?} doesn't store one color per texel"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn image(pixels: &[[u8; 4]]) -> Vec<u8> {
+        pixels.concat()
+    }
+
+    fn gray(value: u8) -> [u8; 4] {
+        [value, value, value, 0xFF]
+    }
+
+    /// `size` zero bytes with `runs` of known bytes written at their offsets.
+    fn expected(size: usize, runs: &[(usize, &[u8])]) -> Vec<u8> {
+        let mut bytes = vec![0; size];
+        for (offset, run) in runs {
+            bytes[*offset..offset + run.len()].copy_from_slice(run);
+        }
+        bytes
+    }
+
+    fn check_round_trip(
+        rgba: &[u8],
+        width: usize,
+        height: usize,
+        format: TextureFormat,
+        encoded: &[u8],
+        decoded: &[u8],
+    ) {
+        assert_eq!(encode(rgba, width, height, format).unwrap(), encoded);
+        assert_eq!(decode(encoded, width, height, format).unwrap(), decoded);
+    }
+
+    #[test]
+    fn i4_partial_tile() {
+        let values = [0x00, 0x11, 0x22, 0xFF, 0xEE, 0xDD];
+        let rgba = image(&values.map(gray));
+        let encoded = expected(32, &[(0, &[0x01, 0x20]), (4, &[0xFE, 0xD0])]);
+        check_round_trip(
+            &rgba,
+            3,
+            2,
+            TextureFormat::I4,
+            &encoded,
+            &image(&values.map(|v| [v; 4])),
+        );
+    }
+
+    #[test]
+    fn i8_two_tiles_wide() {
+        let values: Vec<u8> = (1..=10).map(|value| value * 0x10).collect();
+        let rgba = image(&values.iter().copied().map(gray).collect::<Vec<_>>());
+        let encoded = expected(64, &[(0, &values[..8]), (32, &values[8..])]);
+        let decoded = image(&values.iter().map(|&v| [v; 4]).collect::<Vec<_>>());
+        check_round_trip(&rgba, 10, 1, TextureFormat::I8, &encoded, &decoded);
+    }
+
+    #[test]
+    fn ia4() {
+        let rgba = image(&[[0x33, 0x33, 0x33, 0xAA], [0xFF, 0xFF, 0xFF, 0x00]]);
+        let encoded = expected(32, &[(0, &[0xA3, 0x0F])]);
+        check_round_trip(&rgba, 2, 1, TextureFormat::Ia4, &encoded, &rgba);
+    }
+
+    #[test]
+    fn ia8_two_tiles_wide() {
+        let pixels = [
+            [0x10, 0x10, 0x10, 0x01],
+            [0x20, 0x20, 0x20, 0x02],
+            [0x30, 0x30, 0x30, 0x03],
+            [0x40, 0x40, 0x40, 0x04],
+            [0x50, 0x50, 0x50, 0x05],
+        ];
+        let rgba = image(&pixels);
+        let encoded = expected(
+            64,
+            &[
+                (0, &[0x01, 0x10, 0x02, 0x20, 0x03, 0x30, 0x04, 0x40]),
+                (32, &[0x05, 0x50]),
+            ],
+        );
+        check_round_trip(&rgba, 5, 1, TextureFormat::Ia8, &encoded, &rgba);
+    }
+
+    #[test]
+    fn rgb565() {
+        let rgba = image(&[
+            [0xFF, 0x00, 0x00, 0xFF],
+            [0x00, 0xFF, 0x00, 0xFF],
+            [0x00, 0x00, 0xFF, 0xFF],
+            [0xFF, 0xFF, 0xFF, 0xFF],
+        ]);
+        let encoded = expected(
+            32,
+            &[
+                (0, &[0xF8, 0x00, 0x07, 0xE0]),
+                (8, &[0x00, 0x1F, 0xFF, 0xFF]),
+            ],
+        );
+        check_round_trip(&rgba, 2, 2, TextureFormat::Rgb565, &encoded, &rgba);
+    }
+
+    #[test]
+    fn rgb5a3_opaque_and_translucent() {
+        let rgba = image(&[[0xFF, 0x00, 0x00, 0xFF], [0x11, 0x22, 0x33, 0x92]]);
+        let encoded = expected(32, &[(0, &[0xFC, 0x00, 0x41, 0x23])]);
+        check_round_trip(&rgba, 2, 1, TextureFormat::Rgb5a3, &encoded, &rgba);
+    }
+
+    #[test]
+    fn rgba8_splits_ar_and_gb() {
+        let pixels: Vec<[u8; 4]> = (0..5).map(|x| [x, 0x10 + x, 0x20 + x, 0x30 + x]).collect();
+        let rgba = image(&pixels);
+        let encoded = expected(
+            128,
+            &[
+                (0, &[0x30, 0x00, 0x31, 0x01, 0x32, 0x02, 0x33, 0x03]),
+                (32, &[0x10, 0x20, 0x11, 0x21, 0x12, 0x22, 0x13, 0x23]),
+                (64, &[0x34, 0x04]),
+                (96, &[0x14, 0x24]),
+            ],
+        );
+        check_round_trip(&rgba, 5, 1, TextureFormat::Rgba8, &encoded, &rgba);
+    }
+
+    #[test]
+    fn ci4_indices_and_palette() {
+        let palette = [0x0000, 0xF800, 0x07E0, 0x001F];
+        let mut encoded = vec![0; TextureFormat::Ci4.size(3, 1)];
+        encode_indices_into(&[1, 2, 3], 3, 1, TextureFormat::Ci4, &mut encoded).unwrap();
+        assert_eq!(encoded, expected(32, &[(0, &[0x12, 0x30])]));
+
+        let decoded = decode_indexed(
+            &encoded,
+            3,
+            1,
+            TextureFormat::Ci4,
+            &palette,
+            TlutFormat::Rgb565,
+        );
+        let rgba = image(&[
+            [0xFF, 0x00, 0x00, 0xFF],
+            [0x00, 0xFF, 0x00, 0xFF],
+            [0x00, 0x00, 0xFF, 0xFF],
+        ]);
+        assert_eq!(decoded.unwrap(), rgba);
+    }
+
+    #[test]
+    fn ci8_two_tiles_wide() {
+        let indices: Vec<u16> = (0..9).map(|index| index * 3).collect();
+        let mut encoded = vec![0; TextureFormat::Ci8.size(9, 1)];
+        encode_indices_into(&indices, 9, 1, TextureFormat::Ci8, &mut encoded).unwrap();
+        let bytes: Vec<u8> = indices.iter().map(|&index| index as u8).collect();
+        assert_eq!(
+            encoded,
+            expected(64, &[(0, &bytes[..8]), (32, &bytes[8..])])
+        );
+
+        let palette: Vec<u16> = (0..32).map(|index| index << 8 | 0x80).collect();
+        let decoded = decode_indexed(
+            &encoded,
+            9,
+            1,
+            TextureFormat::Ci8,
+            &palette,
+            TlutFormat::Ia8,
+        )
+        .unwrap();
+        let pixels: Vec<[u8; 4]> = bytes
+            .iter()
+            .map(|&index| [0x80, 0x80, 0x80, index])
+            .collect();
+        assert_eq!(decoded, image(&pixels));
+    }
+
+    #[test]
+    fn encode_indexed_builds_sorted_palette() {
+        let red = [0xFF, 0x00, 0x00, 0xFF];
+        let blue = [0x00, 0x00, 0xFF, 0xFF];
+        let rgba = image(&[red, blue, red]);
+        let (encoded, palette) =
+            encode_indexed(&rgba, 3, 1, TextureFormat::Ci4, TlutFormat::Rgb565).unwrap();
+
+        assert_eq!(palette.len(), 16);
+        assert_eq!(palette[..2], [0x001F, 0xF800]);
+        assert_eq!(encoded, expected(32, &[(0, &[0x10, 0x10])]));
+        let decoded = decode_indexed(
+            &encoded,
+            3,
+            1,
+            TextureFormat::Ci4,
+            &palette,
+            TlutFormat::Rgb565,
+        );
+        assert_eq!(decoded.unwrap(), rgba);
+    }
+
+    #[test]
+    fn decode_indexed_rejects_short_palette() {
+        let encoded = expected(32, &[(0, &[0x20])]);
+        let decoded = decode_indexed(
+            &encoded,
+            1,
+            1,
+            TextureFormat::Ci4,
+            &[0; 2],
+            TlutFormat::Rgb565,
+        );
+        assert!(matches!(decoded, Err(Error::IndexOutOfRange)));
+    }
+
+    #[test]
+    fn cmpr_four_color_block() {
+        let (black, white) = ([0x00, 0x00, 0x00, 0xFF], [0xFF; 4]);
+        let rgba = image(&[[black, black, white, white]; 4].concat());
+        let encoded = expected(
+            32,
+            &[(0, &[0xFF, 0xFF, 0x00, 0x00, 0x50, 0x50, 0x50, 0x50])],
+        );
+        check_round_trip(&rgba, 4, 4, TextureFormat::Cmpr, &encoded, &rgba);
+    }
+
+    #[test]
+    fn cmpr_transparent_texel() {
+        let red = [0xFF, 0x00, 0x00, 0xFF];
+        let mut pixels = [red; 16];
+        pixels[0] = [0x00; 4];
+        let rgba = image(&pixels);
+        let encoded = expected(32, &[(0, &[0xF8, 0x00, 0xF8, 0x00, 0xC0])]);
+        let mut decoded = rgba.clone();
+        decoded[..4].copy_from_slice(&[0xFF, 0x00, 0x00, 0x00]);
+        check_round_trip(&rgba, 4, 4, TextureFormat::Cmpr, &encoded, &decoded);
+    }
+
+    #[test]
+    fn cmpr_partial_tile() {
+        let red = [0xFF, 0x00, 0x00, 0xFF];
+        let rgba = image(&[red; 25]);
+        let block: &[u8] = &[0xF8, 0x00, 0xF8, 0x00];
+        let encoded = expected(32, &[(0, block), (8, block), (16, block), (24, block)]);
+        check_round_trip(&rgba, 5, 5, TextureFormat::Cmpr, &encoded, &rgba);
+    }
+
+    #[test]
+    fn rejects_wrong_formats_and_sizes() {
+        let rgba = image(&[[0; 4]; 4]);
+        assert!(matches!(
+            encode(&rgba, 2, 2, TextureFormat::Ci8),
+            Err(Error::Indexed)
+        ));
+        assert!(matches!(
+            decode(&[0; 16], 4, 4, TextureFormat::Rgb565),
+            Err(Error::WrongLength {
+                expected: 32,
+                got: 16
+            })
+        ));
+        assert!(matches!(
+            encode(&rgba, 0, 4, TextureFormat::I8),
+            Err(Error::InvalidDimensions)
+        ));
+        assert!(matches!(
+            encode_indexed(&rgba, 2, 2, TextureFormat::I8, TlutFormat::Rgb565),
+            Err(Error::NotIndexed)
+        ));
+    }
+}
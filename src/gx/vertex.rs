@@ -0,0 +1,594 @@
+use super::{
+    command::CommandSink,
+    cp::{
+        AttributeType, ColorElements, ColorFormat, ComponentType, NormalElements, PositionElements,
+        TexCoordElements, VatA, VatB, VatC, VcdHi, VcdLo,
+    },
+    xf::InVertexSpec,
+};
+
+/// Every vertex attribute, in the order the GP expects them inside a vertex.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Attribute {
+    PositionMatrixIndex,
+    TexMatrixIndex(u8),
+    Position,
+    Normal,
+    Color(u8),
+    TexCoord(u8),
+}
+
+impl Attribute {
+    /// Index of the CP array backing this attribute when it is sent indexed.
+    pub fn array_index(self) -> u8 {
+        match self {
+            Self::Position => 0,
+            Self::Normal => 1,
+            Self::Color(channel) if channel < 2 => 2 + channel,
+            Self::TexCoord(coord) if coord < 8 => 4 + coord,
+            _ => panic!("{self:?} can't be sent indexed"),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Primitive {
+    Quads,
+    Triangles,
+    TriangleStrip,
+    TriangleFan,
+    Lines,
+    LineStrip,
+    Points,
+}
+
+impl Primitive {
+    pub const fn opcode(self) -> u8 {
+        match self {
+            Self::Quads => 0x80,
+            Self::Triangles => 0x90,
+            Self::TriangleStrip => 0x98,
+            Self::TriangleFan => 0xA0,
+            Self::Lines => 0xA8,
+            Self::LineStrip => 0xB0,
+            Self::Points => 0xB8,
+        }
+    }
+
+    pub const fn from_opcode(opcode: u8) -> Option<Self> {
+        match opcode & 0xF8 {
+            0x80 | 0x88 => Some(Self::Quads),
+            0x90 => Some(Self::Triangles),
+            0x98 => Some(Self::TriangleStrip),
+            0xA0 => Some(Self::TriangleFan),
+            0xA8 => Some(Self::Lines),
+            0xB0 => Some(Self::LineStrip),
+            0xB8 => Some(Self::Points),
+            _ => None,
+        }
+    }
+
+    pub const fn is_valid_count(self, count: u16) -> bool {
+        match self {
+            Self::Quads => count % 4 == 0,
+            Self::Triangles => count % 3 == 0,
+            Self::TriangleStrip | Self::TriangleFan => count >= 3,
+            Self::Lines => count % 2 == 0,
+            Self::LineStrip => count >= 2,
+            Self::Points => count >= 1,
+        }
+    }
+}
+
+/// Which attributes a vertex has and how each of them is sent (the CP VCD).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VertexDescriptor {
+    pub position_matrix_index: bool,
+    pub tex_matrix_index: [bool; 8],
+    pub position: AttributeType,
+    pub normal: AttributeType,
+    pub colors: [AttributeType; 2],
+    pub tex_coords: [AttributeType; 8],
+}
+
+impl VertexDescriptor {
+    pub const fn new() -> Self {
+        Self {
+            position_matrix_index: false,
+            tex_matrix_index: [false; 8],
+            position: AttributeType::None,
+            normal: AttributeType::None,
+            colors: [AttributeType::None; 2],
+            tex_coords: [AttributeType::None; 8],
+        }
+    }
+
+    #[must_use]
+    pub const fn with_position_matrix_index(mut self, present: bool) -> Self {
+        self.position_matrix_index = present;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_tex_matrix_index(mut self, coord: usize, present: bool) -> Self {
+        self.tex_matrix_index[coord] = present;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_position(mut self, ty: AttributeType) -> Self {
+        self.position = ty;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_normal(mut self, ty: AttributeType) -> Self {
+        self.normal = ty;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_color(mut self, channel: usize, ty: AttributeType) -> Self {
+        self.colors[channel] = ty;
+        self
+    }
+
+    #[must_use]
+    pub const fn with_tex_coord(mut self, coord: usize, ty: AttributeType) -> Self {
+        self.tex_coords[coord] = ty;
+        self
+    }
+
+    /// The attributes present in a vertex, in the order they have to be written.
+    pub fn attributes(&self) -> impl Iterator<Item = (Attribute, AttributeType)> + '_ {
+        let matrix_index = |present: bool| {
+            if present {
+                AttributeType::Direct
+            } else {
+                AttributeType::None
+            }
+        };
+
+        core::iter::once((
+            Attribute::PositionMatrixIndex,
+            matrix_index(self.position_matrix_index),
+        ))
+        .chain((0u8..8).map(move |coord| {
+            (
+                Attribute::TexMatrixIndex(coord),
+                matrix_index(self.tex_matrix_index[usize::from(coord)]),
+            )
+        }))
+        .chain([
+            (Attribute::Position, self.position),
+            (Attribute::Normal, self.normal),
+            (Attribute::Color(0), self.colors[0]),
+            (Attribute::Color(1), self.colors[1]),
+        ])
+        .chain((0u8..8).map(move |coord| {
+            (
+                Attribute::TexCoord(coord),
+                self.tex_coords[usize::from(coord)],
+            )
+        }))
+        .filter(|(_, ty)| *ty != AttributeType::None)
+    }
+
+    pub fn registers(&self) -> (VcdLo, VcdHi) {
+        let mut lo = VcdLo::new();
+        lo.with_position_matrix_index(self.position_matrix_index)
+            .with_position(self.position)
+            .with_normal(self.normal)
+            .with_color(0, self.colors[0])
+            .with_color(1, self.colors[1]);
+        for (coord, present) in self.tex_matrix_index.iter().enumerate() {
+            lo.with_tex_matrix_index(coord, *present);
+        }
+
+        let mut hi = VcdHi::new();
+        for (coord, ty) in self.tex_coords.iter().enumerate() {
+            hi.with_tex_coord(coord, *ty);
+        }
+
+        (lo, hi)
+    }
+}
+
+impl Default for VertexDescriptor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Component layout of every attribute (one CP VAT entry).
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VertexFormat {
+    pub position: (PositionElements, ComponentType, u8),
+    pub normal: (NormalElements, ComponentType),
+    pub colors: [(ColorElements, ColorFormat); 2],
+    pub tex_coords: [(TexCoordElements, ComponentType, u8); 8],
+}
+
+impl VertexFormat {
+    pub const fn new() -> Self {
+        Self {
+            position: (PositionElements::Xyz, ComponentType::F32, 0),
+            normal: (NormalElements::Normal, ComponentType::F32),
+            colors: [(ColorElements::Rgba, ColorFormat::Rgba8); 2],
+            tex_coords: [(TexCoordElements::St, ComponentType::F32, 0); 8],
+        }
+    }
+
+    #[must_use]
+    pub const fn with_position(
+        mut self,
+        elements: PositionElements,
+        ty: ComponentType,
+        frac: u8,
+    ) -> Self {
+        self.position = (elements, ty, frac);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_normal(mut self, elements: NormalElements, ty: ComponentType) -> Self {
+        self.normal = (elements, ty);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_color(
+        mut self,
+        channel: usize,
+        elements: ColorElements,
+        format: ColorFormat,
+    ) -> Self {
+        self.colors[channel] = (elements, format);
+        self
+    }
+
+    #[must_use]
+    pub const fn with_tex_coord(
+        mut self,
+        coord: usize,
+        elements: TexCoordElements,
+        ty: ComponentType,
+        frac: u8,
+    ) -> Self {
+        self.tex_coords[coord] = (elements, ty, frac);
+        self
+    }
+
+    pub fn registers(&self) -> (VatA, VatB, VatC) {
+        let (position_elements, position_ty, position_frac) = self.position;
+        let (normal_elements, normal_ty) = self.normal;
+
+        let mut a = VatA::new();
+        a.with_position(position_elements, position_ty, position_frac)
+            .with_normal(normal_elements, normal_ty)
+            .with_color(0, self.colors[0].0, self.colors[0].1)
+            .with_color(1, self.colors[1].0, self.colors[1].1)
+            .with_byte_dequant(true);
+        let (elements, ty, frac) = self.tex_coords[0];
+        a.with_tex_coord(elements, ty, frac);
+
+        let mut b = VatB::new();
+        for coord in 1..4 {
+            let (elements, ty, frac) = self.tex_coords[coord];
+            b.with_tex_coord(coord, elements, ty, frac);
+        }
+        let (elements, ty, frac) = self.tex_coords[4];
+        b.with_tex_coord4(elements, ty).with_vcache_enhance(true);
+
+        let mut c = VatC::new();
+        c.with_tex_coord4_frac(frac);
+        for coord in 5..8 {
+            let (elements, ty, frac) = self.tex_coords[coord];
+            c.with_tex_coord(coord, elements, ty, frac);
+        }
+
+        (a, b, c)
+    }
+
+    /// Number of components `attribute` has when sent direct.
+    pub fn component_count(&self, attribute: Attribute) -> usize {
+        match attribute {
+            Attribute::Position => match self.position.0 {
+                PositionElements::Xy => 2,
+                PositionElements::Xyz => 3,
+            },
+            Attribute::Normal => match self.normal.0 {
+                NormalElements::Normal => 3,
+                NormalElements::NormalBinormalTangent => 9,
+            },
+            Attribute::TexCoord(coord) => match self.tex_coords[usize::from(coord)].0 {
+                TexCoordElements::S => 1,
+                TexCoordElements::St => 2,
+            },
+            Attribute::PositionMatrixIndex | Attribute::TexMatrixIndex(_) | Attribute::Color(_) => {
+                1
+            }
+        }
+    }
+}
+
+impl Default for VertexFormat {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// The vertex descriptor together with the vertex format slot (0..=7) it is drawn with.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct VertexLayout {
+    pub descriptor: VertexDescriptor,
+    pub format: VertexFormat,
+    pub index: u8,
+}
+
+impl VertexLayout {
+    pub fn new(index: u8, descriptor: VertexDescriptor, format: VertexFormat) -> Self {
+        assert!(index < 8, "There are only 8 vertex formats");
+        assert!(
+            descriptor.position != AttributeType::None,
+            "Every vertex needs a position"
+        );
+        Self {
+            descriptor,
+            format,
+            index,
+        }
+    }
+
+    /// What XF needs to know about the incoming vertices.
+    pub fn in_vertex_spec(&self) -> InVertexSpec {
+        let colors = self
+            .descriptor
+            .colors
+            .iter()
+            .filter(|ty| **ty != AttributeType::None)
+            .count();
+        let normals = match (self.descriptor.normal, self.format.normal.0) {
+            (AttributeType::None, _) => 0,
+            (_, NormalElements::Normal) => 1,
+            (_, NormalElements::NormalBinormalTangent) => 2,
+        };
+        let tex_coords = self
+            .descriptor
+            .tex_coords
+            .iter()
+            .filter(|ty| **ty != AttributeType::None)
+            .count();
+
+        let mut spec = InVertexSpec::new();
+        spec.with_num_colors(colors.try_into().unwrap())
+            .with_normals(normals)
+            .with_num_tex_coords(tex_coords.try_into().unwrap());
+        spec
+    }
+}
+
+/// A type that can be sent as a direct vertex component.
+pub trait Component: Copy {
+    const TYPE: ComponentType;
+
+    fn write(self, sink: &mut impl CommandSink);
+}
+
+impl Component for u8 {
+    const TYPE: ComponentType = ComponentType::U8;
+
+    fn write(self, sink: &mut impl CommandSink) {
+        sink.write_u8(self);
+    }
+}
+
+impl Component for i8 {
+    const TYPE: ComponentType = ComponentType::S8;
+
+    fn write(self, sink: &mut impl CommandSink) {
+        sink.write_u8(u8::from_be_bytes(self.to_be_bytes()));
+    }
+}
+
+impl Component for u16 {
+    const TYPE: ComponentType = ComponentType::U16;
+
+    fn write(self, sink: &mut impl CommandSink) {
+        sink.write_u16(self);
+    }
+}
+
+impl Component for i16 {
+    const TYPE: ComponentType = ComponentType::S16;
+
+    fn write(self, sink: &mut impl CommandSink) {
+        sink.write_u16(u16::from_be_bytes(self.to_be_bytes()));
+    }
+}
+
+impl Component for f32 {
+    const TYPE: ComponentType = ComponentType::F32;
+
+    fn write(self, sink: &mut impl CommandSink) {
+        sink.write_f32(self);
+    }
+}
+
+/// Writes the vertices of one primitive, checking each attribute against the layout it was
+/// started with.
+pub struct PrimitiveBuilder<'a, S: CommandSink> {
+    sink: &'a mut S,
+    format: VertexFormat,
+    attributes: [(Attribute, AttributeType); 21],
+    attribute_count: usize,
+    cursor: usize,
+    vertices: u16,
+    count: u16,
+}
+
+impl<'a, S: CommandSink> PrimitiveBuilder<'a, S> {
+    pub(crate) fn new(
+        sink: &'a mut S,
+        primitive: Primitive,
+        layout: &VertexLayout,
+        count: u16,
+    ) -> Self {
+        assert!(
+            primitive.is_valid_count(count),
+            "{count} vertices don't make up whole {primitive:?}"
+        );
+
+        let mut attributes = [(Attribute::Position, AttributeType::None); 21];
+        let mut attribute_count = 0;
+        for attribute in layout.descriptor.attributes() {
+            attributes[attribute_count] = attribute;
+            attribute_count += 1;
+        }
+
+        sink.write_u8(primitive.opcode() | layout.index);
+        sink.write_u16(count);
+
+        Self {
+            sink,
+            format: layout.format,
+            attributes,
+            attribute_count,
+            cursor: 0,
+            vertices: 0,
+            count,
+        }
+    }
+
+    fn next(&mut self, attribute: Attribute) -> AttributeType {
+        assert!(
+            self.vertices < self.count,
+            "More vertices written than the {} declared",
+            self.count
+        );
+        let (expected, ty) = self.attributes[self.cursor];
+        assert!(
+            expected == attribute,
+            "Expected {expected:?} but {attribute:?} was written"
+        );
+
+        self.cursor += 1;
+        if self.cursor == self.attribute_count {
+            self.cursor = 0;
+            self.vertices += 1;
+        }
+        ty
+    }
+
+    fn direct<T: Component>(&mut self, attribute: Attribute, ty: ComponentType, values: &[T]) {
+        assert!(
+            self.next(attribute) == AttributeType::Direct,
+            "{attribute:?} isn't sent direct"
+        );
+        assert!(
+            T::TYPE == ty,
+            "{attribute:?} is {ty:?} but {:?} was written",
+            T::TYPE
+        );
+        assert!(
+            values.len() == self.format.component_count(attribute),
+            "{attribute:?} has {} components",
+            self.format.component_count(attribute)
+        );
+
+        for value in values {
+            value.write(self.sink);
+        }
+    }
+
+    pub fn position_matrix_index(&mut self, index: u8) -> &mut Self {
+        self.next(Attribute::PositionMatrixIndex);
+        self.sink.write_u8(index);
+        self
+    }
+
+    pub fn tex_matrix_index(&mut self, coord: u8, index: u8) -> &mut Self {
+        self.next(Attribute::TexMatrixIndex(coord));
+        self.sink.write_u8(index);
+        self
+    }
+
+    pub fn position<T: Component>(&mut self, values: &[T]) -> &mut Self {
+        self.direct(Attribute::Position, self.format.position.1, values);
+        self
+    }
+
+    pub fn normal<T: Component>(&mut self, values: &[T]) -> &mut Self {
+        self.direct(Attribute::Normal, self.format.normal.1, values);
+        self
+    }
+
+    /// Writes a color, converted from RGBA8 into whatever format the layout uses.
+    pub fn color(&mut self, channel: u8, rgba: [u8; 4]) -> &mut Self {
+        let attribute = Attribute::Color(channel);
+        assert!(
+            self.next(attribute) == AttributeType::Direct,
+            "{attribute:?} isn't sent direct"
+        );
+
+        let [r, g, b, a] = rgba.map(u32::from);
+        match self.format.colors[usize::from(channel)].1 {
+            ColorFormat::Rgb565 => {
+                let value = (r >> 3) << 11 | (g >> 2) << 5 | b >> 3;
+                self.sink.write_u16(value.try_into().unwrap());
+            }
+            ColorFormat::Rgb8 => {
+                self.sink.write_u8(rgba[0]);
+                self.sink.write_u8(rgba[1]);
+                self.sink.write_u8(rgba[2]);
+            }
+            ColorFormat::Rgbx8 => {
+                self.sink.write_u32(r << 24 | g << 16 | b << 8);
+            }
+            ColorFormat::Rgba4 => {
+                let value = (r >> 4) << 12 | (g >> 4) << 8 | (b >> 4) << 4 | a >> 4;
+                self.sink.write_u16(value.try_into().unwrap());
+            }
+            ColorFormat::Rgba6 => {
+                let value = (r >> 2) << 18 | (g >> 2) << 12 | (b >> 2) << 6 | a >> 2;
+                let [_, hi, mid, lo] = value.to_be_bytes();
+                self.sink.write_u8(hi);
+                self.sink.write_u8(mid);
+                self.sink.write_u8(lo);
+            }
+            ColorFormat::Rgba8 => {
+                self.sink.write_u32(u32::from_be_bytes(rgba));
+            }
+        }
+        self
+    }
+
+    pub fn tex_coord<T: Component>(&mut self, coord: u8, values: &[T]) -> &mut Self {
+        self.direct(
+            Attribute::TexCoord(coord),
+            self.format.tex_coords[usize::from(coord)].1,
+            values,
+        );
+        self
+    }
+
+    /// Writes the array index for an attribute that is sent indexed.
+    pub fn index(&mut self, attribute: Attribute, index: u16) -> &mut Self {
+        match self.next(attribute) {
+            AttributeType::Index8 => self.sink.write_u8(index.try_into().unwrap()),
+            AttributeType::Index16 => self.sink.write_u16(index),
+            _ => panic!("{attribute:?} isn't sent indexed"),
+        }
+        self
+    }
+
+    /// Checks every declared vertex was written.
+    pub fn end(self) {
+        assert!(
+            self.vertices == self.count && self.cursor == 0,
+            "Only {} of {} vertices were written",
+            self.vertices,
+            self.count
+        );
+    }
+}
@@ -0,0 +1,229 @@
+//! Notices when the GP stops reading its FIFO.
+//!
+//! A GP stuck on a bad command leaves everything waiting on it spinning forever. The `Watchdog`
+//! is polled once a frame, for example right after `VideoSystem::wait_for_retrace`, and samples
+//! the CP read pointer and idle bits whenever a VI retrace has gone by since the last poll. Once
+//! the read pointer has sat still with commands left to read for long enough it hands back a
+//! `HangReport`, after which `Watchdog::recover` throws the FIFO contents away and links CPU and
+//! GPU back up.
+
+use core::{
+    fmt::{self, Write},
+    sync::atomic::Ordering,
+};
+
+use super::{
+    disasm::{Command, Disassembler},
+    read_split,
+    vertex::VertexLayout,
+    Fifo,
+};
+use crate::{
+    mmio::{
+        cp::{
+            CommandControl, CommandStatus, CP_FIFO_BASE_HI, CP_FIFO_BASE_LO, CP_FIFO_END_HI,
+            CP_FIFO_END_LO, CP_FIFO_READ_PTR_HI, CP_FIFO_READ_PTR_LO, CP_FIFO_READ_WRITE_DST_HI,
+            CP_FIFO_READ_WRITE_DST_LO, CP_FIFO_WRITE_PTR_HI, CP_FIFO_WRITE_PTR_LO,
+        },
+        pi::InterruptState,
+        vi::Enabled,
+    },
+    vi::RETRACE_COUNT,
+};
+
+/// Bytes leading up to the read pointer kept in a `HangReport`.
+pub const HISTORY: usize = 128;
+
+pub struct Watchdog {
+    timeout: u32,
+    disassembler: Disassembler,
+    last_retrace: usize,
+    last_read: u32,
+    stuck: u32,
+    reported: bool,
+}
+
+impl Watchdog {
+    /// Reports a hang after a second of PAL retraces without progress.
+    pub const fn new() -> Self {
+        Self {
+            timeout: 50,
+            disassembler: Disassembler::new(),
+            last_retrace: 0,
+            last_read: 0,
+            stuck: 0,
+            reported: false,
+        }
+    }
+
+    /// Retraces the read pointer has to sit still for before it counts as a hang.
+    #[must_use]
+    pub const fn with_timeout(mut self, retraces: u32) -> Self {
+        self.timeout = retraces;
+        self
+    }
+
+    /// Vertex layout set up before the commands a report will show, so their draws decode.
+    #[must_use]
+    pub fn with_layout(mut self, layout: &VertexLayout) -> Self {
+        self.disassembler = self.disassembler.with_layout(layout);
+        self
+    }
+
+    /// Samples the GPU FIFO `fifo` if a retrace went by since the last call, returning a report
+    /// the first time it is found hung. Reads stopped on purpose, by turning GP reads off or by
+    /// a CP breakpoint, don't count.
+    ///
+    /// `fifo` has to be the FIFO the CP reads, its history comes from the CP read pointer.
+    pub fn poll(&mut self, fifo: &Fifo) -> Option<HangReport> {
+        let retrace = RETRACE_COUNT.load(Ordering::Relaxed);
+        let elapsed = retrace.wrapping_sub(self.last_retrace);
+        if elapsed == 0 {
+            return None;
+        }
+        self.last_retrace = retrace;
+
+        let base = read_split(CP_FIFO_BASE_LO, CP_FIFO_BASE_HI);
+        assert_eq!(
+            base & 0x1FFF_FFFF,
+            u32::try_from(fifo.buf_start.addr() & 0x1FFF_FFFF).unwrap(),
+            "The watchdog has to poll the FIFO the GPU reads"
+        );
+
+        let status = CommandStatus::read();
+        let control = CommandControl::read();
+        let read = read_split(CP_FIFO_READ_PTR_LO, CP_FIFO_READ_PTR_HI);
+        let distance = read_split(CP_FIFO_READ_WRITE_DST_LO, CP_FIFO_READ_WRITE_DST_HI);
+
+        let idle = status.fifo_read_idle() && status.fifo_command_idle() && distance == 0;
+        let paused = matches!(control.gp_fifo_read_enable(), Enabled::Disabled)
+            || (control.fifo_breakpoint_interrupt() == InterruptState::Happened
+                && status.fifo_breakpoint_interrupt() == InterruptState::Happened);
+
+        if read != self.last_read || idle || paused {
+            self.last_read = read;
+            self.stuck = 0;
+            self.reported = false;
+            return None;
+        }
+
+        self.stuck = self
+            .stuck
+            .saturating_add(u32::try_from(elapsed).unwrap_or(u32::MAX));
+        if self.stuck < self.timeout || self.reported {
+            return None;
+        }
+        self.reported = true;
+
+        let mut report = HangReport {
+            retraces: self.stuck,
+            status,
+            base,
+            end: read_split(CP_FIFO_END_LO, CP_FIFO_END_HI),
+            read,
+            write: read_split(CP_FIFO_WRITE_PTR_LO, CP_FIFO_WRITE_PTR_HI),
+            distance,
+            history: [0; HISTORY],
+            disassembler: self.disassembler,
+        };
+
+        // Read through the uncached mirror, the CPU never sees the GP side of the buffer.
+        let size = usize::try_from(fifo.size).unwrap();
+        let buffer = (fifo.buf_start.addr() & 0x1FFF_FFFF) | 0xC000_0000;
+        let read_offset = usize::try_from(read.wrapping_sub(base)).unwrap() % size.max(1);
+        for (i, byte) in report.history.iter_mut().enumerate() {
+            let offset = (read_offset + size * HISTORY - HISTORY + i) % size;
+            *byte = unsafe { core::ptr::read_volatile((buffer + offset) as *const u8) };
+        }
+
+        Some(report)
+    }
+
+    /// Drops the commands in `fifo` and starts it over as the linked CPU and GPU FIFO, see
+    /// `Fifo::reset`.
+    pub fn recover(&mut self, fifo: &mut Fifo) -> bool {
+        self.stuck = 0;
+        self.reported = false;
+        self.last_read = 0;
+        fifo.reset()
+    }
+}
+
+impl Default for Watchdog {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// FIFO state at the time a hang was noticed.
+#[derive(Copy, Clone, Debug)]
+pub struct HangReport {
+    /// Retraces the read pointer sat still for.
+    pub retraces: u32,
+    pub status: CommandStatus,
+    pub base: u32,
+    pub end: u32,
+    pub read: u32,
+    pub write: u32,
+    pub distance: u32,
+    /// The bytes right before the read pointer, the GP is stuck somewhere in there.
+    pub history: [u8; HISTORY],
+    disassembler: Disassembler,
+}
+
+impl HangReport {
+    /// The part of `history` starting at the first offset that decodes as a run of known
+    /// commands. The FIFO doesn't record where commands start, so this is a best guess.
+    pub fn commands(&self) -> &[u8] {
+        let start = (0..HISTORY)
+            .find(|&start| self.decodes_cleanly(&self.history[start..]))
+            .unwrap_or(HISTORY);
+        &self.history[start..]
+    }
+
+    /// Writes the FIFO state followed by a listing of the last commands.
+    ///
+    /// # Errors
+    ///
+    /// Only passes on errors from `out`.
+    pub fn write_report(&self, out: &mut impl Write) -> fmt::Result {
+        writeln!(out, "GP hung for {} retraces", self.retraces)?;
+        writeln!(
+            out,
+            "status: read_idle={} command_idle={} overflow={} underflow={} breakpoint={:?}",
+            self.status.fifo_read_idle(),
+            self.status.fifo_command_idle(),
+            self.status.fifo_overflow(),
+            self.status.fifo_underflow(),
+            self.status.fifo_breakpoint_interrupt(),
+        )?;
+        writeln!(
+            out,
+            "fifo: base={:#010X} end={:#010X} read={:#010X} write={:#010X} distance={:#X}",
+            self.base, self.end, self.read, self.write, self.distance
+        )?;
+
+        let commands = self.commands();
+        let start = self
+            .read
+            .wrapping_sub(u32::try_from(commands.len()).unwrap());
+        writeln!(out, "last commands, from {start:#010X}:")?;
+        let mut disassembler = self.disassembler;
+        disassembler.disassemble(commands, out)
+    }
+
+    fn decodes_cleanly(&self, stream: &[u8]) -> bool {
+        let mut disassembler = self.disassembler;
+        let mut offset = 0;
+        while let Some((command, size)) = disassembler.next_command(&stream[offset..]) {
+            if matches!(
+                command,
+                Command::Unknown(_) | Command::InvalidVertexFormat(_)
+            ) {
+                return false;
+            }
+            offset += size;
+        }
+        true
+    }
+}
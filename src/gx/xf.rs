@@ -0,0 +1,862 @@
+use core::ops::Range;
+
+use bit_field::BitField;
+
+use super::{
+    command::CommandSink,
+    light::{AttenuationFn, ColorSource, DiffuseFn},
+    texgen::{TexGenKind, TexGenSource},
+};
+
+/// A single word register in XF register space (0x1000..0x1058).
+///
+/// Registers that are repeated per color channel (`AmbientColor`, `MaterialColor`,
+/// `ChannelControl`) are written with `write_at` which adds the index to `ADDRESS`.
+pub trait XfRegister: Copy {
+    const ADDRESS: u16;
+
+    fn value(self) -> u32;
+
+    fn write(self, sink: &mut impl CommandSink) {
+        sink.write_xf(self);
+    }
+
+    fn write_at(self, index: u8, sink: &mut impl CommandSink) {
+        sink.write_xf_at(index, self);
+    }
+}
+
+/// The separately addressed regions of XF memory.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum XfMemory {
+    PositionMatrix,
+    NormalMatrix,
+    PostMatrix,
+    Light,
+    Register,
+}
+
+impl XfMemory {
+    pub const fn range(self) -> Range<u16> {
+        match self {
+            Self::PositionMatrix => 0x0000..0x0100,
+            Self::NormalMatrix => 0x0400..0x0460,
+            Self::PostMatrix => 0x0500..0x0600,
+            Self::Light => 0x0600..0x0680,
+            Self::Register => 0x1000..0x1058,
+        }
+    }
+
+    pub fn from_address(address: u16) -> Option<Self> {
+        [
+            Self::PositionMatrix,
+            Self::NormalMatrix,
+            Self::PostMatrix,
+            Self::Light,
+            Self::Register,
+        ]
+        .into_iter()
+        .find(|memory| memory.range().contains(&address))
+    }
+
+    /// Address of the word `offset` words into this region.
+    pub fn address(self, offset: u16) -> u16 {
+        let range = self.range();
+        assert!(
+            offset < range.end - range.start,
+            "Offset is outside of the XF memory region"
+        );
+        range.start + offset
+    }
+
+    /// Loads `values` into this region starting `offset` words in.
+    pub fn load(self, offset: u16, values: &[f32], sink: &mut impl CommandSink) {
+        let len = u16::try_from(values.len()).unwrap();
+        assert!(
+            offset + len <= self.range().end - self.range().start,
+            "XF load runs past the end of its memory region"
+        );
+        sink.load_xf_regs_f32(self.address(offset), values);
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InVertexSpec(u32);
+
+impl InVertexSpec {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn num_colors(&self) -> u8 {
+        self.0.get_bits(0..=1).try_into().unwrap()
+    }
+
+    pub fn with_num_colors(&mut self, count: u8) -> &mut Self {
+        self.0.set_bits(0..=1, count.into());
+        self
+    }
+
+    /// 0 for no normals, 1 for a normal and 2 for normal, binormal and tangent.
+    pub fn normals(&self) -> u8 {
+        self.0.get_bits(2..=3).try_into().unwrap()
+    }
+
+    pub fn with_normals(&mut self, normals: u8) -> &mut Self {
+        self.0.set_bits(2..=3, normals.into());
+        self
+    }
+
+    pub fn num_tex_coords(&self) -> u8 {
+        self.0.get_bits(4..=7).try_into().unwrap()
+    }
+
+    pub fn with_num_tex_coords(&mut self, count: u8) -> &mut Self {
+        self.0.set_bits(4..=7, count.into());
+        self
+    }
+}
+
+impl Default for InVertexSpec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for InVertexSpec {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<InVertexSpec> for u32 {
+    fn from(value: InVertexSpec) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for InVertexSpec {
+    const ADDRESS: u16 = 0x1008;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct NumColors(u32);
+
+impl NumColors {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn count(&self) -> u8 {
+        self.0.get_bits(0..=1).try_into().unwrap()
+    }
+
+    pub fn with_count(&mut self, count: u8) -> &mut Self {
+        assert!(count <= 2, "XF only has 2 color channels");
+        self.0.set_bits(0..=1, count.into());
+        self
+    }
+}
+
+impl Default for NumColors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for NumColors {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<NumColors> for u32 {
+    fn from(value: NumColors) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for NumColors {
+    const ADDRESS: u16 = 0x1009;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct NumTexGens(u32);
+
+impl NumTexGens {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn count(&self) -> u8 {
+        self.0.get_bits(0..=3).try_into().unwrap()
+    }
+
+    pub fn with_count(&mut self, count: u8) -> &mut Self {
+        assert!(count <= 8, "XF only has 8 texture coordinate generators");
+        self.0.set_bits(0..=3, count.into());
+        self
+    }
+}
+
+impl Default for NumTexGens {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for NumTexGens {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<NumTexGens> for u32 {
+    fn from(value: NumTexGens) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for NumTexGens {
+    const ADDRESS: u16 = 0x103F;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// The six viewport scale and offset words starting at 0x101A.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Viewport {
+    pub scale: [f32; 3],
+    pub offset: [f32; 3],
+}
+
+impl Viewport {
+    pub const ADDRESS: u16 = 0x101A;
+
+    pub fn write(&self, sink: &mut impl CommandSink) {
+        let [scale_x, scale_y, scale_z] = self.scale;
+        let [offset_x, offset_y, offset_z] = self.offset;
+        sink.load_xf_regs_f32(
+            Self::ADDRESS,
+            &[scale_x, scale_y, scale_z, offset_x, offset_y, offset_z],
+        );
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum ProjectionType {
+    Perspective,
+    Orthographic,
+}
+
+impl From<ProjectionType> for u32 {
+    fn from(value: ProjectionType) -> Self {
+        match value {
+            ProjectionType::Perspective => 0,
+            ProjectionType::Orthographic => 1,
+        }
+    }
+}
+
+impl From<u32> for ProjectionType {
+    fn from(value: u32) -> Self {
+        if value == 0 {
+            Self::Perspective
+        } else {
+            Self::Orthographic
+        }
+    }
+}
+
+/// The six projection parameters and the projection type starting at 0x1020.
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Projection {
+    pub params: [f32; 6],
+    pub ty: ProjectionType,
+}
+
+impl Projection {
+    pub const ADDRESS: u16 = 0x1020;
+
+    pub fn write(&self, sink: &mut impl CommandSink) {
+        let mut words = [0u32; 7];
+        for (word, param) in words.iter_mut().zip(self.params) {
+            *word = param.to_bits();
+        }
+        words[6] = self.ty.into();
+        sink.load_xf_regs(Self::ADDRESS, &words);
+    }
+}
+
+/// Position/normal and texture 0..=3 matrix selection, mirrored from CP `MatrixIndexA`.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct MatrixIndexA(u32);
+
+impl MatrixIndexA {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn position_normal(&self) -> u8 {
+        self.0.get_bits(0..=5).try_into().unwrap()
+    }
+
+    pub fn with_position_normal(&mut self, index: u8) -> &mut Self {
+        self.0.set_bits(0..=5, index.into());
+        self
+    }
+
+    pub fn tex_coord(&self, coord: usize) -> u8 {
+        assert!(
+            coord < 4,
+            "MatrixIndexA only holds texture coordinates 0..=3"
+        );
+        let base = 6 + coord * 6;
+        self.0.get_bits(base..base + 6).try_into().unwrap()
+    }
+
+    pub fn with_tex_coord(&mut self, coord: usize, index: u8) -> &mut Self {
+        assert!(
+            coord < 4,
+            "MatrixIndexA only holds texture coordinates 0..=3"
+        );
+        let base = 6 + coord * 6;
+        self.0.set_bits(base..base + 6, index.into());
+        self
+    }
+}
+
+impl Default for MatrixIndexA {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for MatrixIndexA {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<MatrixIndexA> for u32 {
+    fn from(value: MatrixIndexA) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for MatrixIndexA {
+    const ADDRESS: u16 = 0x1018;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Texture 4..=7 matrix selection, mirrored from CP `MatrixIndexB`.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct MatrixIndexB(u32);
+
+impl MatrixIndexB {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn tex_coord(&self, coord: usize) -> u8 {
+        assert!(
+            (4..8).contains(&coord),
+            "MatrixIndexB only holds texture coordinates 4..=7"
+        );
+        let base = (coord - 4) * 6;
+        self.0.get_bits(base..base + 6).try_into().unwrap()
+    }
+
+    pub fn with_tex_coord(&mut self, coord: usize, index: u8) -> &mut Self {
+        assert!(
+            (4..8).contains(&coord),
+            "MatrixIndexB only holds texture coordinates 4..=7"
+        );
+        let base = (coord - 4) * 6;
+        self.0.set_bits(base..base + 6, index.into());
+        self
+    }
+}
+
+impl Default for MatrixIndexB {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for MatrixIndexB {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<MatrixIndexB> for u32 {
+    fn from(value: MatrixIndexB) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for MatrixIndexB {
+    const ADDRESS: u16 = 0x1019;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Ambient color of color channel 0 or 1, the alpha is used by the matching alpha channel.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct AmbientColor(u32);
+
+impl AmbientColor {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub const fn color(&self) -> [u8; 4] {
+        self.0.to_be_bytes()
+    }
+
+    pub fn with_color(&mut self, color: [u8; 4]) -> &mut Self {
+        self.0 = u32::from_be_bytes(color);
+        self
+    }
+}
+
+impl Default for AmbientColor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for AmbientColor {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<AmbientColor> for u32 {
+    fn from(value: AmbientColor) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for AmbientColor {
+    const ADDRESS: u16 = 0x100A;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Material color of color channel 0 or 1, used when the material source is the register.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct MaterialColor(u32);
+
+impl MaterialColor {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub const fn color(&self) -> [u8; 4] {
+        self.0.to_be_bytes()
+    }
+
+    pub fn with_color(&mut self, color: [u8; 4]) -> &mut Self {
+        self.0 = u32::from_be_bytes(color);
+        self
+    }
+}
+
+impl Default for MaterialColor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for MaterialColor {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<MaterialColor> for u32 {
+    fn from(value: MaterialColor) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for MaterialColor {
+    const ADDRESS: u16 = 0x100C;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Lighting setup of one channel, written at index 0 and 1 for the color channels and 2 and 3
+/// for the alpha channels.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct ChannelControl(u32);
+
+impl ChannelControl {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn material_source(&self) -> ColorSource {
+        self.0.get_bit(0).into()
+    }
+
+    pub fn with_material_source(&mut self, source: ColorSource) -> &mut Self {
+        self.0.set_bit(0, source.into());
+        self
+    }
+
+    pub fn lighting(&self) -> bool {
+        self.0.get_bit(1)
+    }
+
+    pub fn with_lighting(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(1, enable);
+        self
+    }
+
+    /// Bit `n` set means light `n` contributes to the channel.
+    pub fn light_mask(&self) -> u8 {
+        let low = self.0.get_bits(2..=5);
+        let high = self.0.get_bits(11..=14);
+        (high << 4 | low).try_into().unwrap()
+    }
+
+    pub fn with_light_mask(&mut self, mask: u8) -> &mut Self {
+        self.0.set_bits(2..=5, u32::from(mask & 0xF));
+        self.0.set_bits(11..=14, u32::from(mask >> 4));
+        self
+    }
+
+    pub fn ambient_source(&self) -> ColorSource {
+        self.0.get_bit(6).into()
+    }
+
+    pub fn with_ambient_source(&mut self, source: ColorSource) -> &mut Self {
+        self.0.set_bit(6, source.into());
+        self
+    }
+
+    pub fn diffuse_fn(&self) -> DiffuseFn {
+        self.0.get_bits(7..=8).try_into().unwrap()
+    }
+
+    pub fn with_diffuse_fn(&mut self, diffuse: DiffuseFn) -> &mut Self {
+        self.0.set_bits(7..=8, diffuse.into());
+        self
+    }
+
+    /// Attenuation is stored as an enable bit and a spot/specular select bit.
+    pub fn attenuation_fn(&self) -> AttenuationFn {
+        match (self.0.get_bit(9), self.0.get_bit(10)) {
+            (false, _) => AttenuationFn::None,
+            (true, false) => AttenuationFn::Specular,
+            (true, true) => AttenuationFn::Spot,
+        }
+    }
+
+    pub fn with_attenuation_fn(&mut self, attenuation: AttenuationFn) -> &mut Self {
+        self.0
+            .set_bit(9, attenuation != AttenuationFn::None)
+            .set_bit(10, attenuation != AttenuationFn::Specular);
+        self
+    }
+}
+
+impl Default for ChannelControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for ChannelControl {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ChannelControl> for u32 {
+    fn from(value: ChannelControl) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for ChannelControl {
+    const ADDRESS: u16 = 0x100E;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Selects what XF performance counter 0 counts.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct PerfSelect(u32);
+
+impl PerfSelect {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub const fn select(&self) -> u32 {
+        self.0
+    }
+
+    pub fn with_select(&mut self, select: u32) -> &mut Self {
+        self.0 = select;
+        self
+    }
+}
+
+impl Default for PerfSelect {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for PerfSelect {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PerfSelect> for u32 {
+    fn from(value: PerfSelect) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for PerfSelect {
+    const ADDRESS: u16 = 0x1006;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Turns the post-transform matrix and normalization of all texture coordinate generators on.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct DualTex(u32);
+
+impl DualTex {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn enable(&self) -> bool {
+        self.0.get_bit(0)
+    }
+
+    pub fn with_enable(&mut self, enable: bool) -> &mut Self {
+        self.0.set_bit(0, enable);
+        self
+    }
+}
+
+impl Default for DualTex {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for DualTex {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<DualTex> for u32 {
+    fn from(value: DualTex) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for DualTex {
+    const ADDRESS: u16 = 0x1012;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// First transform of texture coordinate generator 0..=7, written with `write_at`.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct TexMtxInfo(u32);
+
+impl TexMtxInfo {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Whether the matrix has a third row producing q, instead of just s and t.
+    pub fn projected(&self) -> bool {
+        self.0.get_bit(1)
+    }
+
+    pub fn with_projected(&mut self, projected: bool) -> &mut Self {
+        self.0.set_bit(1, projected);
+        self
+    }
+
+    /// Whether the source is read as (a, b, c, 1) rather than (a, b, 1, 1).
+    pub fn abc1(&self) -> bool {
+        self.0.get_bit(2)
+    }
+
+    pub fn with_abc1(&mut self, abc1: bool) -> &mut Self {
+        self.0.set_bit(2, abc1);
+        self
+    }
+
+    pub fn kind(&self) -> TexGenKind {
+        self.0.get_bits(4..=6).try_into().unwrap()
+    }
+
+    pub fn with_kind(&mut self, kind: TexGenKind) -> &mut Self {
+        self.0.set_bits(4..=6, kind.into());
+        self
+    }
+
+    pub fn source(&self) -> TexGenSource {
+        self.0.get_bits(7..=11).try_into().unwrap()
+    }
+
+    pub fn with_source(&mut self, source: TexGenSource) -> &mut Self {
+        self.0.set_bits(7..=11, source.into());
+        self
+    }
+
+    /// Generated texture coordinate an emboss generator offsets.
+    pub fn emboss_source(&self) -> u8 {
+        self.0.get_bits(12..=14).try_into().unwrap()
+    }
+
+    pub fn with_emboss_source(&mut self, coord: u8) -> &mut Self {
+        self.0.set_bits(12..=14, coord.into());
+        self
+    }
+
+    /// Light whose direction an emboss generator offsets along.
+    pub fn emboss_light(&self) -> u8 {
+        self.0.get_bits(15..=17).try_into().unwrap()
+    }
+
+    pub fn with_emboss_light(&mut self, light: u8) -> &mut Self {
+        self.0.set_bits(15..=17, light.into());
+        self
+    }
+}
+
+impl Default for TexMtxInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for TexMtxInfo {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TexMtxInfo> for u32 {
+    fn from(value: TexMtxInfo) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for TexMtxInfo {
+    const ADDRESS: u16 = 0x1040;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
+
+/// Second transform of texture coordinate generator 0..=7, only applied with `DualTex` on.
+#[repr(transparent)]
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct PostMtxInfo(u32);
+
+impl PostMtxInfo {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// First row of the matrix in post-transform matrix memory.
+    pub fn row(&self) -> u8 {
+        self.0.get_bits(0..=5).try_into().unwrap()
+    }
+
+    pub fn with_row(&mut self, row: u8) -> &mut Self {
+        assert!(row < 62, "Post-transform matrices start at rows 0..=61");
+        self.0.set_bits(0..=5, row.into());
+        self
+    }
+
+    pub fn normalize(&self) -> bool {
+        self.0.get_bit(8)
+    }
+
+    pub fn with_normalize(&mut self, normalize: bool) -> &mut Self {
+        self.0.set_bit(8, normalize);
+        self
+    }
+}
+
+impl Default for PostMtxInfo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<u32> for PostMtxInfo {
+    fn from(value: u32) -> Self {
+        Self(value)
+    }
+}
+
+impl From<PostMtxInfo> for u32 {
+    fn from(value: PostMtxInfo) -> Self {
+        value.0
+    }
+}
+
+impl XfRegister for PostMtxInfo {
+    const ADDRESS: u16 = 0x1050;
+
+    fn value(self) -> u32 {
+        self.0
+    }
+}
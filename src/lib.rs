@@ -28,29 +28,36 @@ use alloc::string::ToString;
 use spin::Mutex;
 
 extern crate alloc;
+#[cfg(test)]
+extern crate std;
 
 pub mod video;
 
 pub mod isfs;
 
 pub mod arch;
+#[cfg(target_arch = "powerpc")]
 pub mod asm_runtime;
 pub mod cache;
 pub mod clock;
 pub mod config;
 pub mod exception;
 pub mod exi;
+pub mod font;
 pub mod gfx;
 pub mod interrupts;
 pub mod ios;
 pub mod ipc;
+pub mod math;
 pub mod mmio;
 pub mod os;
 pub mod pad;
 pub mod si;
 pub mod sram;
+pub mod tpl;
 pub mod vi;
 pub mod wii;
+pub mod yay0;
 
 pub mod utils;
 
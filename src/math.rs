@@ -0,0 +1,574 @@
+//! Vectors and matrices for the GX transform pipeline.
+//!
+//! Matrices are row major like XF memory: `Mtx34` is the 3x4 affine form used for position,
+//! normal and texture matrices, `Mtx44` is only needed for projections. Angles are in radians.
+
+pub mod ps;
+
+use core::{
+    f32::consts::{FRAC_PI_2, PI, TAU},
+    ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign},
+};
+
+use crate::gx::xf::{Projection, ProjectionType};
+
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Debug, Default)]
+pub struct Vec3 {
+    pub x: f32,
+    pub y: f32,
+    pub z: f32,
+}
+
+impl Vec3 {
+    pub const ZERO: Self = Self::new(0.0, 0.0, 0.0);
+    pub const X: Self = Self::new(1.0, 0.0, 0.0);
+    pub const Y: Self = Self::new(0.0, 1.0, 0.0);
+    pub const Z: Self = Self::new(0.0, 0.0, 1.0);
+
+    pub const fn new(x: f32, y: f32, z: f32) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn dot(self, other: Self) -> f32 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    #[must_use]
+    pub fn cross(self, other: Self) -> Self {
+        Self::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(self) -> f32 {
+        sqrt(self.dot(self))
+    }
+
+    /// Zero stays zero instead of turning into NaNs.
+    #[must_use]
+    pub fn normalize(self) -> Self {
+        let length = self.length();
+        if length == 0.0 {
+            self
+        } else {
+            self * (1.0 / length)
+        }
+    }
+
+    pub const fn to_array(self) -> [f32; 3] {
+        [self.x, self.y, self.z]
+    }
+}
+
+impl From<[f32; 3]> for Vec3 {
+    fn from([x, y, z]: [f32; 3]) -> Self {
+        Self::new(x, y, z)
+    }
+}
+
+impl From<Vec3> for [f32; 3] {
+    fn from(value: Vec3) -> Self {
+        value.to_array()
+    }
+}
+
+impl Add for Vec3 {
+    type Output = Self;
+
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.x + rhs.x, self.y + rhs.y, self.z + rhs.z)
+    }
+}
+
+impl AddAssign for Vec3 {
+    fn add_assign(&mut self, rhs: Self) {
+        *self = *self + rhs;
+    }
+}
+
+impl Sub for Vec3 {
+    type Output = Self;
+
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.x - rhs.x, self.y - rhs.y, self.z - rhs.z)
+    }
+}
+
+impl SubAssign for Vec3 {
+    fn sub_assign(&mut self, rhs: Self) {
+        *self = *self - rhs;
+    }
+}
+
+impl Mul<f32> for Vec3 {
+    type Output = Self;
+
+    fn mul(self, rhs: f32) -> Self {
+        Self::new(self.x * rhs, self.y * rhs, self.z * rhs)
+    }
+}
+
+impl Neg for Vec3 {
+    type Output = Self;
+
+    fn neg(self) -> Self {
+        Self::new(-self.x, -self.y, -self.z)
+    }
+}
+
+/// Affine transform: a 3x3 linear part plus a translation in the last column.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Mtx34(pub [[f32; 4]; 3]);
+
+impl Mtx34 {
+    pub const IDENTITY: Self = Self([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+    ]);
+
+    pub const fn translation(offset: Vec3) -> Self {
+        Self([
+            [1.0, 0.0, 0.0, offset.x],
+            [0.0, 1.0, 0.0, offset.y],
+            [0.0, 0.0, 1.0, offset.z],
+        ])
+    }
+
+    pub const fn scale(factors: Vec3) -> Self {
+        Self([
+            [factors.x, 0.0, 0.0, 0.0],
+            [0.0, factors.y, 0.0, 0.0],
+            [0.0, 0.0, factors.z, 0.0],
+        ])
+    }
+
+    pub fn rotation_x(angle: f32) -> Self {
+        let (sin, cos) = sin_cos(angle);
+        Self([
+            [1.0, 0.0, 0.0, 0.0],
+            [0.0, cos, -sin, 0.0],
+            [0.0, sin, cos, 0.0],
+        ])
+    }
+
+    pub fn rotation_y(angle: f32) -> Self {
+        let (sin, cos) = sin_cos(angle);
+        Self([
+            [cos, 0.0, sin, 0.0],
+            [0.0, 1.0, 0.0, 0.0],
+            [-sin, 0.0, cos, 0.0],
+        ])
+    }
+
+    pub fn rotation_z(angle: f32) -> Self {
+        let (sin, cos) = sin_cos(angle);
+        Self([
+            [cos, -sin, 0.0, 0.0],
+            [sin, cos, 0.0, 0.0],
+            [0.0, 0.0, 1.0, 0.0],
+        ])
+    }
+
+    /// Rotation by `angle` around an arbitrary `axis`, which doesn't have to be normalized.
+    pub fn rotation(axis: Vec3, angle: f32) -> Self {
+        let Vec3 { x, y, z } = axis.normalize();
+        let (sin, cos) = sin_cos(angle);
+        let t = 1.0 - cos;
+
+        Self([
+            [
+                t * x * x + cos,
+                t * x * y - sin * z,
+                t * x * z + sin * y,
+                0.0,
+            ],
+            [
+                t * x * y + sin * z,
+                t * y * y + cos,
+                t * y * z - sin * x,
+                0.0,
+            ],
+            [
+                t * x * z - sin * y,
+                t * y * z + sin * x,
+                t * z * z + cos,
+                0.0,
+            ],
+        ])
+    }
+
+    /// View matrix of a camera at `eye` looking at `target`, with the camera looking down -Z.
+    pub fn look_at(eye: Vec3, up: Vec3, target: Vec3) -> Self {
+        let look = (eye - target).normalize();
+        let right = up.cross(look).normalize();
+        let up = look.cross(right);
+
+        Self([
+            [right.x, right.y, right.z, -eye.dot(right)],
+            [up.x, up.y, up.z, -eye.dot(up)],
+            [look.x, look.y, look.z, -eye.dot(look)],
+        ])
+    }
+
+    pub fn transform_point(&self, point: Vec3) -> Vec3 {
+        let m = &self.0;
+        Vec3::new(
+            m[0][0] * point.x + m[0][1] * point.y + m[0][2] * point.z + m[0][3],
+            m[1][0] * point.x + m[1][1] * point.y + m[1][2] * point.z + m[1][3],
+            m[2][0] * point.x + m[2][1] * point.y + m[2][2] * point.z + m[2][3],
+        )
+    }
+
+    /// Like `transform_point` but ignoring the translation.
+    pub fn transform_vector(&self, vector: Vec3) -> Vec3 {
+        let m = &self.0;
+        Vec3::new(
+            m[0][0] * vector.x + m[0][1] * vector.y + m[0][2] * vector.z,
+            m[1][0] * vector.x + m[1][1] * vector.y + m[1][2] * vector.z,
+            m[2][0] * vector.x + m[2][1] * vector.y + m[2][2] * vector.z,
+        )
+    }
+
+    /// `None` when the linear part is singular.
+    pub fn inverse(&self) -> Option<Self> {
+        let linear = self.inverse_linear()?;
+        let translation = Vec3::new(self.0[0][3], self.0[1][3], self.0[2][3]);
+        let offset = -linear.transform_vector(translation);
+
+        let mut result = linear;
+        result.0[0][3] = offset.x;
+        result.0[1][3] = offset.y;
+        result.0[2][3] = offset.z;
+        Some(result)
+    }
+
+    /// Matrix for transforming normals, without translation. `None` when singular.
+    pub fn inverse_transpose(&self) -> Option<Self> {
+        self.inverse_linear()
+            .map(|inverse| inverse.transpose_linear())
+    }
+
+    /// Transposes the 3x3 part and drops the translation.
+    #[must_use]
+    pub const fn transpose_linear(&self) -> Self {
+        let m = &self.0;
+        Self([
+            [m[0][0], m[1][0], m[2][0], 0.0],
+            [m[0][1], m[1][1], m[2][1], 0.0],
+            [m[0][2], m[1][2], m[2][2], 0.0],
+        ])
+    }
+
+    fn inverse_linear(&self) -> Option<Self> {
+        let m = &self.0;
+        let cofactor = |r0: usize, r1: usize, c0: usize, c1: usize| {
+            m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+        };
+
+        let c00 = cofactor(1, 2, 1, 2);
+        let c01 = -cofactor(1, 2, 0, 2);
+        let c02 = cofactor(1, 2, 0, 1);
+        let det = m[0][0] * c00 + m[0][1] * c01 + m[0][2] * c02;
+        if det == 0.0 {
+            return None;
+        }
+        let inv = 1.0 / det;
+
+        Some(Self([
+            [
+                c00 * inv,
+                -cofactor(0, 2, 1, 2) * inv,
+                cofactor(0, 1, 1, 2) * inv,
+                0.0,
+            ],
+            [
+                c01 * inv,
+                cofactor(0, 2, 0, 2) * inv,
+                -cofactor(0, 1, 0, 2) * inv,
+                0.0,
+            ],
+            [
+                c02 * inv,
+                -cofactor(0, 2, 0, 1) * inv,
+                cofactor(0, 1, 0, 1) * inv,
+                0.0,
+            ],
+        ]))
+    }
+
+    /// The twelve words in the order XF memory takes them.
+    pub fn to_words(&self) -> [f32; 12] {
+        let mut words = [0.0; 12];
+        for (chunk, row) in words.chunks_exact_mut(4).zip(&self.0) {
+            chunk.copy_from_slice(row);
+        }
+        words
+    }
+
+    /// The 3x3 part, which is all normal matrix memory holds.
+    pub fn to_normal_words(&self) -> [f32; 9] {
+        let mut words = [0.0; 9];
+        for (chunk, row) in words.chunks_exact_mut(3).zip(&self.0) {
+            chunk.copy_from_slice(&row[..3]);
+        }
+        words
+    }
+}
+
+impl Default for Mtx34 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+/// `a * b` applies `b` first, then `a`.
+impl Mul for Mtx34 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (&self.0, &rhs.0);
+        let mut result = [[0.0; 4]; 3];
+        for (row, out) in result.iter_mut().enumerate() {
+            for (column, value) in out.iter_mut().enumerate() {
+                *value =
+                    a[row][0] * b[0][column] + a[row][1] * b[1][column] + a[row][2] * b[2][column];
+            }
+            out[3] += a[row][3];
+        }
+        Self(result)
+    }
+}
+
+impl Mul<Vec3> for Mtx34 {
+    type Output = Vec3;
+
+    fn mul(self, rhs: Vec3) -> Vec3 {
+        self.transform_point(rhs)
+    }
+}
+
+/// Full 4x4 matrix, used for projections.
+#[repr(C)]
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Mtx44(pub [[f32; 4]; 4]);
+
+impl Mtx44 {
+    pub const IDENTITY: Self = Self([
+        [1.0, 0.0, 0.0, 0.0],
+        [0.0, 1.0, 0.0, 0.0],
+        [0.0, 0.0, 1.0, 0.0],
+        [0.0, 0.0, 0.0, 1.0],
+    ]);
+
+    /// Perspective projection from a vertical field of view.
+    ///
+    /// GX clips depth to -w..=0 rather than -w..=w, so `near` maps to -1 and `far` to 0 after
+    /// the divide.
+    pub fn perspective(fov_y: f32, aspect: f32, near: f32, far: f32) -> Self {
+        let cot = 1.0 / tan(fov_y * 0.5);
+        let depth = 1.0 / (far - near);
+
+        Self([
+            [cot / aspect, 0.0, 0.0, 0.0],
+            [0.0, cot, 0.0, 0.0],
+            [0.0, 0.0, -near * depth, -(far * near) * depth],
+            [0.0, 0.0, -1.0, 0.0],
+        ])
+    }
+
+    /// Perspective projection of an off-center view volume on the near plane.
+    pub fn frustum(top: f32, bottom: f32, left: f32, right: f32, near: f32, far: f32) -> Self {
+        let width = 1.0 / (right - left);
+        let height = 1.0 / (top - bottom);
+        let depth = 1.0 / (far - near);
+
+        Self([
+            [2.0 * near * width, 0.0, (right + left) * width, 0.0],
+            [0.0, 2.0 * near * height, (top + bottom) * height, 0.0],
+            [0.0, 0.0, -near * depth, -(far * near) * depth],
+            [0.0, 0.0, -1.0, 0.0],
+        ])
+    }
+
+    /// Orthographic projection, with the same depth range as `perspective`.
+    pub fn orthographic(top: f32, bottom: f32, left: f32, right: f32, near: f32, far: f32) -> Self {
+        let width = 1.0 / (right - left);
+        let height = 1.0 / (top - bottom);
+        let depth = 1.0 / (far - near);
+
+        Self([
+            [2.0 * width, 0.0, 0.0, -(right + left) * width],
+            [0.0, 2.0 * height, 0.0, -(top + bottom) * height],
+            [0.0, 0.0, -depth, -far * depth],
+            [0.0, 0.0, 0.0, 1.0],
+        ])
+    }
+
+    /// The six entries XF keeps of a projection matrix, the rest are implied by `ty`.
+    pub const fn to_projection(&self, ty: ProjectionType) -> Projection {
+        let m = &self.0;
+        let params = match ty {
+            ProjectionType::Perspective => [m[0][0], m[0][2], m[1][1], m[1][2], m[2][2], m[2][3]],
+            ProjectionType::Orthographic => [m[0][0], m[0][3], m[1][1], m[1][3], m[2][2], m[2][3]],
+        };
+        Projection { params, ty }
+    }
+}
+
+impl Default for Mtx44 {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+impl Mul for Mtx44 {
+    type Output = Self;
+
+    fn mul(self, rhs: Self) -> Self {
+        let (a, b) = (&self.0, &rhs.0);
+        let mut result = [[0.0; 4]; 4];
+        for (row, out) in result.iter_mut().enumerate() {
+            for (column, value) in out.iter_mut().enumerate() {
+                *value = (0..4).map(|k| a[row][k] * b[k][column]).sum();
+            }
+        }
+        Self(result)
+    }
+}
+
+/// Square root by Newton's method, the 750CL has no `fsqrt`.
+pub fn sqrt(value: f32) -> f32 {
+    if value.is_nan() || value < 0.0 {
+        return f32::NAN;
+    }
+    if value == 0.0 || value.is_infinite() {
+        return value;
+    }
+
+    let mut root = f32::from_bits((value.to_bits() >> 1) + 0x1FBD_1DF5);
+    for _ in 0..3 {
+        root = 0.5 * (root + value / root);
+    }
+    root
+}
+
+/// Sine and cosine together, both come out of the same range reduction.
+pub fn sin_cos(angle: f32) -> (f32, f32) {
+    let turns = round(angle / TAU);
+    let x = angle - turns * TAU;
+    let (x, cos_sign) = if x > FRAC_PI_2 {
+        (PI - x, -1.0)
+    } else if x < -FRAC_PI_2 {
+        (-PI - x, -1.0)
+    } else {
+        (x, 1.0)
+    };
+
+    // Taylor series, accurate to single precision on -pi/2..=pi/2.
+    let x2 = x * x;
+    let sin = x
+        * (1.0
+            - x2 / 6.0
+                * (1.0 - x2 / 20.0 * (1.0 - x2 / 42.0 * (1.0 - x2 / 72.0 * (1.0 - x2 / 110.0)))));
+    let cos = 1.0
+        - x2 / 2.0
+            * (1.0
+                - x2 / 12.0
+                    * (1.0
+                        - x2 / 30.0 * (1.0 - x2 / 56.0 * (1.0 - x2 / 90.0 * (1.0 - x2 / 132.0)))));
+
+    (sin, cos * cos_sign)
+}
+
+pub fn sin(angle: f32) -> f32 {
+    sin_cos(angle).0
+}
+
+pub fn cos(angle: f32) -> f32 {
+    sin_cos(angle).1
+}
+
+pub fn tan(angle: f32) -> f32 {
+    let (sin, cos) = sin_cos(angle);
+    sin / cos
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+fn round(value: f32) -> f32 {
+    let half = if value < 0.0 { -0.5 } else { 0.5 };
+    (value + half) as i32 as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: &[[f32; 4]], b: &[[f32; 4]]) {
+        for (a, b) in a.iter().flatten().zip(b.iter().flatten()) {
+            assert!((a - b).abs() < 1.0e-5, "{a} != {b}");
+        }
+    }
+
+    // The expected matrices are what libogc's guPerspective, guOrtho and guLookAt produce.
+
+    #[test]
+    fn perspective_matches_gu_perspective() {
+        let m = Mtx44::perspective(60.0_f32.to_radians(), 4.0 / 3.0, 1.0, 1000.0);
+        assert_close(
+            &m.0,
+            &[
+                [1.299_038_1, 0.0, 0.0, 0.0],
+                [0.0, 1.732_050_8, 0.0, 0.0],
+                [0.0, 0.0, -0.001_001_001, -1.001_001],
+                [0.0, 0.0, -1.0, 0.0],
+            ],
+        );
+    }
+
+    #[test]
+    fn orthographic_matches_gu_ortho() {
+        let m = Mtx44::orthographic(0.0, 479.0, 0.0, 639.0, 0.0, 300.0);
+        assert_close(
+            &m.0,
+            &[
+                [0.003_129_890_5, 0.0, 0.0, -1.0],
+                [0.0, -0.004_175_365_4, 0.0, 1.0],
+                [0.0, 0.0, -0.003_333_333_3, -1.0],
+                [0.0, 0.0, 0.0, 1.0],
+            ],
+        );
+    }
+
+    #[test]
+    fn look_at_matches_gu_look_at() {
+        let m = Mtx34::look_at(Vec3::new(1.0, 2.0, 3.0), Vec3::Y, Vec3::ZERO);
+        assert_close(
+            &m.0,
+            &[
+                [0.948_683_3, 0.0, -0.316_227_77, 0.0],
+                [-0.169_030_85, 0.845_154_25, -0.507_092_55, 0.0],
+                [0.267_261_24, 0.534_522_5, 0.801_783_7, -3.741_657_4],
+            ],
+        );
+
+        let m = Mtx34::look_at(Vec3::ZERO, Vec3::Y, Vec3::new(0.0, 0.0, -1.0));
+        assert_close(&m.0, &Mtx34::IDENTITY.0);
+    }
+
+    #[test]
+    fn inverse_undoes_the_matrix() {
+        let m = Mtx34::translation(Vec3::new(4.0, -5.0, 6.0))
+            * Mtx34::rotation(Vec3::new(1.0, 2.0, 3.0), 0.7)
+            * Mtx34::scale(Vec3::new(2.0, 0.5, -1.5));
+        let inverse = m.inverse().unwrap();
+        assert_close(&(inverse * m).0, &Mtx34::IDENTITY.0);
+        assert_close(&(m * inverse).0, &Mtx34::IDENTITY.0);
+
+        assert_eq!(Mtx34::scale(Vec3::new(1.0, 0.0, 1.0)).inverse(), None);
+    }
+}
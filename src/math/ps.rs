@@ -0,0 +1,178 @@
+//! Paired-single versions of the hot math routines.
+//!
+//! On the console these run on the paired-single unit, doing two f32 operations per
+//! instruction. Anywhere else they fall back to the `scalar` versions, which are always
+//! available to check the paired-single results against.
+//!
+//! The float routines expect GQR0 to hold `GraphicsQuantizationRegister::FLOAT`, which the
+//! runtime sets up at boot. `quantize` and `dequantize` reprogram `QUANTIZE_GQR` on every call.
+
+#[cfg(target_arch = "powerpc")]
+mod paired;
+pub mod scalar;
+
+#[cfg(target_arch = "powerpc")]
+pub use paired::{concat, cross, dequantize, dot, normalize, quantize, transform_point};
+#[cfg(not(target_arch = "powerpc"))]
+pub use scalar::{concat, cross, dequantize, dot, normalize, quantize, transform_point};
+
+use crate::arch::{GraphicsQuantizationRegister, QuantizeType};
+
+/// Quantization register used by `quantize` and `dequantize`, don't rely on it keeping a value.
+pub const QUANTIZE_GQR: u8 = 7;
+
+/// Integer types the paired-single unit converts to and from.
+///
+/// # Safety
+///
+/// `TYPE` has to be a format of exactly the size of `Self`, the paired-single routines read
+/// and write memory according to it.
+pub unsafe trait Quantized: Copy {
+    const TYPE: QuantizeType;
+
+    /// Saturates and rounds toward zero, like `psq_st` does.
+    fn from_f32(value: f32) -> Self;
+
+    fn to_f32(self) -> f32;
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+unsafe impl Quantized for u8 {
+    const TYPE: QuantizeType = QuantizeType::U8;
+
+    fn from_f32(value: f32) -> Self {
+        value as Self
+    }
+
+    fn to_f32(self) -> f32 {
+        self.into()
+    }
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+unsafe impl Quantized for u16 {
+    const TYPE: QuantizeType = QuantizeType::U16;
+
+    fn from_f32(value: f32) -> Self {
+        value as Self
+    }
+
+    fn to_f32(self) -> f32 {
+        self.into()
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+unsafe impl Quantized for i8 {
+    const TYPE: QuantizeType = QuantizeType::I8;
+
+    fn from_f32(value: f32) -> Self {
+        value as Self
+    }
+
+    fn to_f32(self) -> f32 {
+        self.into()
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+unsafe impl Quantized for i16 {
+    const TYPE: QuantizeType = QuantizeType::I16;
+
+    fn from_f32(value: f32) -> Self {
+        value as Self
+    }
+
+    fn to_f32(self) -> f32 {
+        self.into()
+    }
+}
+
+/// Register value converting between f32 and `T` with `frac_bits` fractional bits.
+pub fn quantize_register<T: Quantized>(frac_bits: u8) -> GraphicsQuantizationRegister {
+    let scale = i8::try_from(frac_bits)
+        .ok()
+        .filter(|scale| *scale < 32)
+        .expect("At most 31 fractional bits");
+
+    let mut gqr = GraphicsQuantizationRegister::new();
+    gqr.with_load_type(T::TYPE)
+        .with_load_scale(scale)
+        .with_store_type(T::TYPE)
+        .with_store_scale(scale);
+    gqr
+}
+
+/// Largest difference between a paired-single result and its scalar version, relative to the
+/// larger of the value and 1. `ps_rsqrte` and the fused multiply-adds round differently.
+#[cfg(target_arch = "powerpc")]
+const TOLERANCE: f32 = 1.0e-5;
+
+/// Runs every paired-single routine on a few inputs and compares it with its `scalar` version.
+/// Host tests can't reach the paired-single unit, so this is the check to run on the console,
+/// which the `paired_check` example does.
+///
+/// # Errors
+///
+/// Returns the name of the first routine whose result is off by more than `TOLERANCE`, or for
+/// `quantize`, that doesn't come out exactly the same.
+#[cfg(target_arch = "powerpc")]
+pub fn check_paired() -> Result<(), &'static str> {
+    use crate::math::{Mtx34, Vec3};
+
+    // core has no `f32::abs`, clearing the sign bit is all it does.
+    let abs = |value: f32| f32::from_bits(value.to_bits() & !(1 << 31));
+    let close = |a: &[f32], b: &[f32]| {
+        a.iter()
+            .zip(b)
+            .all(|(a, b)| abs(a - b) <= TOLERANCE * abs(*a).max(1.0))
+    };
+    let check = |routine, paired: &[f32], scalar: &[f32]| {
+        if close(paired, scalar) {
+            Ok(())
+        } else {
+            Err(routine)
+        }
+    };
+
+    let a = Mtx34::rotation(Vec3::new(1.0, 2.0, 3.0).normalize(), 0.7)
+        * Mtx34::translation(Vec3::new(4.0, -5.0, 6.0));
+    let b = Mtx34::scale(Vec3::new(2.0, 0.5, -1.5)) * Mtx34::rotation_y(-1.2);
+    let u = Vec3::new(1.5, -2.25, 3.0);
+    let v = Vec3::new(-0.5, 4.0, 0.125);
+
+    check(
+        "concat",
+        &paired::concat(&a, &b).to_words(),
+        &scalar::concat(&a, &b).to_words(),
+    )?;
+    check(
+        "transform_point",
+        &paired::transform_point(&a, u).to_array(),
+        &scalar::transform_point(&a, u).to_array(),
+    )?;
+    check("dot", &[paired::dot(u, v)], &[scalar::dot(u, v)])?;
+    check(
+        "cross",
+        &paired::cross(u, v).to_array(),
+        &scalar::cross(u, v).to_array(),
+    )?;
+    check(
+        "normalize",
+        &paired::normalize(u).to_array(),
+        &scalar::normalize(u).to_array(),
+    )?;
+
+    let values = [1.5, -2.25, 1000.0, -0.001];
+    let (mut paired_fixed, mut scalar_fixed) = ([0i16; 4], [0i16; 4]);
+    paired::quantize(&values, 8, &mut paired_fixed);
+    scalar::quantize(&values, 8, &mut scalar_fixed);
+    if paired_fixed != scalar_fixed {
+        return Err("quantize");
+    }
+
+    let (mut paired_values, mut scalar_values) = ([0.0; 4], [0.0; 4]);
+    paired::dequantize(&scalar_fixed, 8, &mut paired_values);
+    scalar::dequantize(&scalar_fixed, 8, &mut scalar_values);
+    check("dequantize", &paired_values, &scalar_values)
+}
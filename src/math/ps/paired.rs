@@ -0,0 +1,385 @@
+//! The paired-single implementations.
+//!
+//! LLVM doesn't know the Gekko paired-single instructions, so they are emitted as raw words by
+//! the `const fn` encoders at the bottom, with the registers they touch pinned in the `asm!`
+//! operands.
+
+use core::ptr;
+
+use crate::math::{Mtx34, Vec3};
+
+use super::{quantize_register, Quantized, QUANTIZE_GQR};
+
+/// Emits the encoded instructions, followed by the usual `asm!` operands after a `;`.
+macro_rules! paired {
+    ($($instruction:expr),+ ; $($operands:tt)*) => {
+        core::arch::asm!($(paired!(@word $instruction),)+ $(const $instruction,)+ $($operands)*)
+    };
+    (@word $instruction:expr) => {
+        ".long {}"
+    };
+}
+
+/// `(0.0, 1.0)`, multiplied by the translation column to add it to the second pair of a row.
+static ZERO_ONE: [f32; 2] = [0.0, 1.0];
+
+/// Newton-Raphson step for `ps_rsqrte`: `y * 0.5 * (3.0 - x * y * y)`.
+static RSQRT_CONSTANTS: [f32; 2] = [0.5, 3.0];
+
+pub fn concat(a: &Mtx34, b: &Mtx34) -> Mtx34 {
+    let mut result = Mtx34::IDENTITY;
+    // Safety: the pointers are valid for the whole matrices and only f0-f10 are written.
+    unsafe {
+        paired!(
+            // Rows of b, split into (x, y) and (z, w) pairs, and (0, 1).
+            psq_l(0, 0, 4, 0, 0),
+            psq_l(1, 8, 4, 0, 0),
+            psq_l(2, 16, 4, 0, 0),
+            psq_l(3, 24, 4, 0, 0),
+            psq_l(4, 32, 4, 0, 0),
+            psq_l(5, 40, 4, 0, 0),
+            psq_l(6, 0, 6, 0, 0),
+            // Row 0
+            psq_l(7, 0, 3, 0, 0),
+            psq_l(8, 8, 3, 0, 0),
+            ps_muls0(9, 0, 7),
+            ps_madds1(9, 2, 7, 9),
+            ps_madds0(9, 4, 8, 9),
+            ps_muls0(10, 1, 7),
+            ps_madds1(10, 3, 7, 10),
+            ps_madds0(10, 5, 8, 10),
+            ps_madds1(10, 6, 8, 10),
+            psq_st(9, 0, 5, 0, 0),
+            psq_st(10, 8, 5, 0, 0),
+            // Row 1
+            psq_l(7, 16, 3, 0, 0),
+            psq_l(8, 24, 3, 0, 0),
+            ps_muls0(9, 0, 7),
+            ps_madds1(9, 2, 7, 9),
+            ps_madds0(9, 4, 8, 9),
+            ps_muls0(10, 1, 7),
+            ps_madds1(10, 3, 7, 10),
+            ps_madds0(10, 5, 8, 10),
+            ps_madds1(10, 6, 8, 10),
+            psq_st(9, 16, 5, 0, 0),
+            psq_st(10, 24, 5, 0, 0),
+            // Row 2
+            psq_l(7, 32, 3, 0, 0),
+            psq_l(8, 40, 3, 0, 0),
+            ps_muls0(9, 0, 7),
+            ps_madds1(9, 2, 7, 9),
+            ps_madds0(9, 4, 8, 9),
+            ps_muls0(10, 1, 7),
+            ps_madds1(10, 3, 7, 10),
+            ps_madds0(10, 5, 8, 10),
+            ps_madds1(10, 6, 8, 10),
+            psq_st(9, 32, 5, 0, 0),
+            psq_st(10, 40, 5, 0, 0);
+            in("r3") ptr::from_ref(a),
+            in("r4") ptr::from_ref(b),
+            in("r5") ptr::from_mut(&mut result),
+            in("r6") ZERO_ONE.as_ptr(),
+            out("f0") _, out("f1") _, out("f2") _, out("f3") _, out("f4") _, out("f5") _,
+            out("f6") _, out("f7") _, out("f8") _, out("f9") _, out("f10") _,
+            options(nostack),
+        );
+    }
+    result
+}
+
+pub fn transform_point(matrix: &Mtx34, point: Vec3) -> Vec3 {
+    let mut result = Vec3::ZERO;
+    // Safety: the pointers are valid for the whole matrix and vectors and only f0-f4 are
+    // written.
+    unsafe {
+        paired!(
+            // (x, y) and (z, 1), then each row is x*m0 + y*m1 + z*m2 + m3.
+            psq_l(0, 0, 4, 0, 0),
+            psq_l(1, 8, 4, 1, 0),
+            psq_l(2, 0, 3, 0, 0),
+            psq_l(3, 8, 3, 0, 0),
+            ps_mul(4, 2, 0),
+            ps_madd(4, 3, 1, 4),
+            ps_sum0(4, 4, 4, 4),
+            psq_st(4, 0, 5, 1, 0),
+            psq_l(2, 16, 3, 0, 0),
+            psq_l(3, 24, 3, 0, 0),
+            ps_mul(4, 2, 0),
+            ps_madd(4, 3, 1, 4),
+            ps_sum0(4, 4, 4, 4),
+            psq_st(4, 4, 5, 1, 0),
+            psq_l(2, 32, 3, 0, 0),
+            psq_l(3, 40, 3, 0, 0),
+            ps_mul(4, 2, 0),
+            ps_madd(4, 3, 1, 4),
+            ps_sum0(4, 4, 4, 4),
+            psq_st(4, 8, 5, 1, 0);
+            in("r3") ptr::from_ref(matrix),
+            in("r4") ptr::from_ref(&point),
+            in("r5") ptr::from_mut(&mut result),
+            out("f0") _, out("f1") _, out("f2") _, out("f3") _, out("f4") _,
+            options(nostack),
+        );
+    }
+    result
+}
+
+pub fn dot(a: Vec3, b: Vec3) -> f32 {
+    let result: f32;
+    // Safety: the pointers are valid for both vectors and only f1-f5 are written.
+    unsafe {
+        paired!(
+            psq_l(2, 0, 3, 0, 0),
+            psq_l(3, 8, 3, 1, 0),
+            psq_l(4, 0, 4, 0, 0),
+            psq_l(5, 8, 4, 1, 0),
+            ps_mul(2, 2, 4),
+            ps_sum0(2, 2, 2, 2),
+            ps_madd(1, 3, 5, 2);
+            in("r3") ptr::from_ref(&a),
+            in("r4") ptr::from_ref(&b),
+            out("f1") result,
+            out("f2") _, out("f3") _, out("f4") _, out("f5") _,
+            options(nostack, readonly),
+        );
+    }
+    result
+}
+
+pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    let mut result = Vec3::ZERO;
+    // Safety: the pointers are valid for the vectors and only f0-f10 are written.
+    unsafe {
+        paired!(
+            psq_l(0, 0, 3, 0, 0),
+            psq_l(1, 8, 3, 1, 0),
+            psq_l(2, 0, 4, 0, 0),
+            psq_l(3, 8, 4, 1, 0),
+            // (x, y) = (ay, az) * (bz, bx) - (az, ax) * (by, bz)
+            ps_merge10(4, 0, 1),
+            ps_merge00(5, 3, 2),
+            ps_merge00(6, 1, 0),
+            ps_merge10(7, 2, 3),
+            ps_mul(8, 6, 7),
+            ps_msub(8, 4, 5, 8),
+            // z = ax * by - ay * bx
+            ps_merge10(9, 2, 2),
+            ps_mul(9, 0, 9),
+            ps_neg(10, 9),
+            ps_sum0(9, 9, 9, 10),
+            psq_st(8, 0, 5, 0, 0),
+            psq_st(9, 8, 5, 1, 0);
+            in("r3") ptr::from_ref(&a),
+            in("r4") ptr::from_ref(&b),
+            in("r5") ptr::from_mut(&mut result),
+            out("f0") _, out("f1") _, out("f2") _, out("f3") _, out("f4") _, out("f5") _,
+            out("f6") _, out("f7") _, out("f8") _, out("f9") _, out("f10") _,
+            options(nostack),
+        );
+    }
+    result
+}
+
+/// Zero stays zero instead of turning into NaNs.
+pub fn normalize(vector: Vec3) -> Vec3 {
+    let length_squared = dot(vector, vector);
+    if length_squared == 0.0 {
+        return vector;
+    }
+
+    let mut result = Vec3::ZERO;
+    // Safety: the pointers are valid for the vectors and constants, only f1-f7 are written.
+    unsafe {
+        paired!(
+            ps_rsqrte(2, 1),
+            psq_l(3, 0, 6, 1, 0),
+            psq_l(7, 4, 6, 1, 0),
+            ps_mul(4, 1, 2),
+            ps_nmsub(4, 4, 2, 7),
+            ps_mul(2, 2, 3),
+            ps_mul(2, 2, 4),
+            psq_l(5, 0, 3, 0, 0),
+            psq_l(6, 8, 3, 1, 0),
+            ps_muls0(5, 5, 2),
+            ps_muls0(6, 6, 2),
+            psq_st(5, 0, 4, 0, 0),
+            psq_st(6, 8, 4, 1, 0);
+            in("r3") ptr::from_ref(&vector),
+            in("r4") ptr::from_mut(&mut result),
+            in("r6") RSQRT_CONSTANTS.as_ptr(),
+            inout("f1") length_squared => _,
+            out("f2") _, out("f3") _, out("f4") _, out("f5") _, out("f6") _, out("f7") _,
+            options(nostack),
+        );
+    }
+    result
+}
+
+/// Converts `src` to fixed point with `frac_bits` fractional bits, saturating.
+pub fn quantize<T: Quantized>(src: &[f32], frac_bits: u8, dst: &mut [T]) {
+    assert_eq!(
+        src.len(),
+        dst.len(),
+        "Source and destination lengths differ"
+    );
+    quantize_register::<T>(frac_bits).write(QUANTIZE_GQR);
+
+    for (from, to) in src.chunks_exact(2).zip(dst.chunks_exact_mut(2)) {
+        // Safety: both chunks hold two elements and `T::TYPE` matches the size of `T`.
+        unsafe {
+            paired!(
+                psq_l(0, 0, 3, 0, 0),
+                psq_st(0, 0, 4, 0, QUANTIZE_GQR);
+                in("r3") from.as_ptr(),
+                in("r4") to.as_mut_ptr(),
+                out("f0") _,
+                options(nostack),
+            );
+        }
+    }
+
+    if let [from] = src.chunks_exact(2).remainder() {
+        let to = dst.last_mut().unwrap();
+        // Safety: single element load and store of one `T`.
+        unsafe {
+            paired!(
+                psq_l(0, 0, 3, 1, 0),
+                psq_st(0, 0, 4, 1, QUANTIZE_GQR);
+                in("r3") ptr::from_ref(from),
+                in("r4") ptr::from_mut(to),
+                out("f0") _,
+                options(nostack),
+            );
+        }
+    }
+}
+
+/// Converts fixed point values with `frac_bits` fractional bits back to f32.
+pub fn dequantize<T: Quantized>(src: &[T], frac_bits: u8, dst: &mut [f32]) {
+    assert_eq!(
+        src.len(),
+        dst.len(),
+        "Source and destination lengths differ"
+    );
+    quantize_register::<T>(frac_bits).write(QUANTIZE_GQR);
+
+    for (from, to) in src.chunks_exact(2).zip(dst.chunks_exact_mut(2)) {
+        // Safety: both chunks hold two elements and `T::TYPE` matches the size of `T`.
+        unsafe {
+            paired!(
+                psq_l(0, 0, 3, 0, QUANTIZE_GQR),
+                psq_st(0, 0, 4, 0, 0);
+                in("r3") from.as_ptr(),
+                in("r4") to.as_mut_ptr(),
+                out("f0") _,
+                options(nostack),
+            );
+        }
+    }
+
+    if let [from] = src.chunks_exact(2).remainder() {
+        let to = dst.last_mut().unwrap();
+        // Safety: single element load and store of one `T`.
+        unsafe {
+            paired!(
+                psq_l(0, 0, 3, 1, QUANTIZE_GQR),
+                psq_st(0, 0, 4, 1, 0);
+                in("r3") ptr::from_ref(from),
+                in("r4") ptr::from_mut(to),
+                out("f0") _,
+                options(nostack),
+            );
+        }
+    }
+}
+
+// Encoders, operands in assembly order. `single` loads or stores only ps0 (and loads 1.0 into
+// ps1), `gqr` picks the quantization register.
+
+const fn psq_l(d: u8, offset: u16, ra: u8, single: u8, gqr: u8) -> u32 {
+    quantized(56, d, offset, ra, single, gqr)
+}
+
+const fn psq_st(s: u8, offset: u16, ra: u8, single: u8, gqr: u8) -> u32 {
+    quantized(60, s, offset, ra, single, gqr)
+}
+
+const fn quantized(opcode: u32, register: u8, offset: u16, ra: u8, single: u8, gqr: u8) -> u32 {
+    assert!(offset < 0x800 && single < 2 && gqr < 8);
+    opcode << 26
+        | (register as u32) << 21
+        | (ra as u32) << 16
+        | (single as u32) << 15
+        | (gqr as u32) << 12
+        | offset as u32
+}
+
+/// A-form arithmetic, `xo` picks the operation.
+const fn a_form(xo: u32, d: u8, a: u8, b: u8, c: u8) -> u32 {
+    4 << 26 | (d as u32) << 21 | (a as u32) << 16 | (b as u32) << 11 | (c as u32) << 6 | xo << 1
+}
+
+/// X-form, with a 10 bit extended opcode.
+const fn x_form(xo: u32, d: u8, a: u8, b: u8) -> u32 {
+    4 << 26 | (d as u32) << 21 | (a as u32) << 16 | (b as u32) << 11 | xo << 1
+}
+
+/// `d = (a.0 + b.1, c.1)`
+const fn ps_sum0(d: u8, a: u8, c: u8, b: u8) -> u32 {
+    a_form(10, d, a, b, c)
+}
+
+/// `d = a * c.0`
+const fn ps_muls0(d: u8, a: u8, c: u8) -> u32 {
+    a_form(12, d, a, 0, c)
+}
+
+/// `d = a * c.0 + b`
+const fn ps_madds0(d: u8, a: u8, c: u8, b: u8) -> u32 {
+    a_form(14, d, a, b, c)
+}
+
+/// `d = a * c.1 + b`
+const fn ps_madds1(d: u8, a: u8, c: u8, b: u8) -> u32 {
+    a_form(15, d, a, b, c)
+}
+
+/// `d = a * c`
+const fn ps_mul(d: u8, a: u8, c: u8) -> u32 {
+    a_form(25, d, a, 0, c)
+}
+
+/// `d = 1 / sqrt(b)` estimate, good to about 12 bits.
+const fn ps_rsqrte(d: u8, b: u8) -> u32 {
+    a_form(26, d, 0, b, 0)
+}
+
+/// `d = a * c - b`
+const fn ps_msub(d: u8, a: u8, c: u8, b: u8) -> u32 {
+    a_form(28, d, a, b, c)
+}
+
+/// `d = a * c + b`
+const fn ps_madd(d: u8, a: u8, c: u8, b: u8) -> u32 {
+    a_form(29, d, a, b, c)
+}
+
+/// `d = b - a * c`
+const fn ps_nmsub(d: u8, a: u8, c: u8, b: u8) -> u32 {
+    a_form(30, d, a, b, c)
+}
+
+/// `d = -b`
+const fn ps_neg(d: u8, b: u8) -> u32 {
+    x_form(40, d, 0, b)
+}
+
+/// `d = (a.0, b.0)`
+const fn ps_merge00(d: u8, a: u8, b: u8) -> u32 {
+    x_form(528, d, a, b)
+}
+
+/// `d = (a.1, b.0)`
+const fn ps_merge10(d: u8, a: u8, b: u8) -> u32 {
+    x_form(592, d, a, b)
+}
@@ -0,0 +1,152 @@
+//! Plain f32 versions of the paired-single routines, with the same signatures.
+
+use crate::math::{Mtx34, Vec3};
+
+use super::Quantized;
+
+pub fn concat(a: &Mtx34, b: &Mtx34) -> Mtx34 {
+    *a * *b
+}
+
+pub fn transform_point(matrix: &Mtx34, point: Vec3) -> Vec3 {
+    matrix.transform_point(point)
+}
+
+pub fn dot(a: Vec3, b: Vec3) -> f32 {
+    a.dot(b)
+}
+
+pub fn cross(a: Vec3, b: Vec3) -> Vec3 {
+    a.cross(b)
+}
+
+pub fn normalize(vector: Vec3) -> Vec3 {
+    vector.normalize()
+}
+
+/// Converts `src` to fixed point with `frac_bits` fractional bits.
+pub fn quantize<T: Quantized>(src: &[f32], frac_bits: u8, dst: &mut [T]) {
+    assert_eq!(
+        src.len(),
+        dst.len(),
+        "Source and destination lengths differ"
+    );
+    let scale = exp2(frac_bits);
+    for (value, out) in src.iter().zip(dst) {
+        *out = T::from_f32(value * scale);
+    }
+}
+
+/// Converts fixed point values with `frac_bits` fractional bits back to f32.
+pub fn dequantize<T: Quantized>(src: &[T], frac_bits: u8, dst: &mut [f32]) {
+    assert_eq!(
+        src.len(),
+        dst.len(),
+        "Source and destination lengths differ"
+    );
+    let scale = 1.0 / exp2(frac_bits);
+    for (value, out) in src.iter().zip(dst) {
+        *out = value.to_f32() * scale;
+    }
+}
+
+fn exp2(frac_bits: u8) -> f32 {
+    assert!(frac_bits < 32, "At most 31 fractional bits");
+    f32::from_bits((127 + u32::from(frac_bits)) << 23)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: Vec3, b: Vec3) {
+        for (a, b) in a.to_array().into_iter().zip(b.to_array()) {
+            assert!((a - b).abs() < 1.0e-6, "{a} != {b}");
+        }
+    }
+
+    #[test]
+    fn concat_applies_right_first() {
+        let a = Mtx34::translation(Vec3::new(1.0, 2.0, 3.0));
+        let b = Mtx34::scale(Vec3::new(2.0, 2.0, 2.0));
+        assert_eq!(
+            concat(&a, &b),
+            Mtx34([
+                [2.0, 0.0, 0.0, 1.0],
+                [0.0, 2.0, 0.0, 2.0],
+                [0.0, 0.0, 2.0, 3.0],
+            ])
+        );
+        assert_eq!(
+            concat(&b, &a),
+            Mtx34([
+                [2.0, 0.0, 0.0, 2.0],
+                [0.0, 2.0, 0.0, 4.0],
+                [0.0, 0.0, 2.0, 6.0],
+            ])
+        );
+    }
+
+    #[test]
+    fn transform_point_adds_translation() {
+        let matrix = Mtx34([
+            [1.0, 2.0, 3.0, 4.0],
+            [5.0, 6.0, 7.0, 8.0],
+            [9.0, 10.0, 11.0, 12.0],
+        ]);
+        assert_eq!(
+            transform_point(&matrix, Vec3::new(1.0, 1.0, 1.0)),
+            Vec3::new(10.0, 26.0, 42.0)
+        );
+    }
+
+    #[test]
+    fn dot_and_cross() {
+        let (a, b) = (Vec3::new(1.0, 2.0, 3.0), Vec3::new(4.0, 5.0, 6.0));
+        assert_eq!(dot(a, b), 32.0);
+        assert_eq!(cross(a, b), Vec3::new(-3.0, 6.0, -3.0));
+        assert_eq!(
+            cross(Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0)),
+            Vec3::new(0.0, 0.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn normalize_to_unit_length() {
+        assert_close(
+            normalize(Vec3::new(3.0, 0.0, 4.0)),
+            Vec3::new(0.6, 0.0, 0.8),
+        );
+        assert_close(
+            normalize(Vec3::new(0.0, -0.5, 0.0)),
+            Vec3::new(0.0, -1.0, 0.0),
+        );
+    }
+
+    #[test]
+    fn quantize_rounds_toward_zero_and_saturates() {
+        let mut fixed = [0i16; 4];
+        quantize(&[1.5, -2.25, 1000.0, -0.001], 8, &mut fixed);
+        assert_eq!(fixed, [384, -576, i16::MAX, 0]);
+
+        let mut bytes = [0u8; 3];
+        quantize(&[-1.0, 2.7, 300.0], 0, &mut bytes);
+        assert_eq!(bytes, [0, 2, u8::MAX]);
+    }
+
+    #[test]
+    fn dequantize_scales_back() {
+        let mut values = [0.0; 3];
+        dequantize(&[384i16, -576, i16::MAX], 8, &mut values);
+        assert_eq!(values, [1.5, -2.25, 127.996_09]);
+
+        dequantize(&[1u16, 2, 3], 2, &mut values);
+        assert_eq!(values, [0.25, 0.5, 0.75]);
+    }
+
+    #[test]
+    #[should_panic = "Source and destination lengths differ"]
+    fn quantize_checks_lengths() {
+        quantize(&[1.0, 2.0], 0, &mut [0u8; 3]);
+    }
+}
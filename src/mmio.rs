@@ -4,11 +4,11 @@
 // 0xC000_0000 = Uncached<T>;
 
 pub mod ai;
+pub mod cp;
 pub mod dsp;
 pub mod exi;
 pub mod ipc;
-//pub mod pe;
-pub mod cp;
+pub mod pe;
 pub mod pi;
 pub mod si;
 pub mod vi;
@@ -182,8 +182,21 @@ impl CommandClear {
         self.0.set_bit(1, underflow);
         self
     }
+
+    /// Zeroes the GP performance counters.
+    pub fn metrics(&self) -> bool {
+        self.0.get_bit(2)
+    }
+
+    pub fn with_metrics(&mut self, clear: bool) -> &mut Self {
+        self.0.set_bit(2, clear);
+        self
+    }
 }
 
+/// Selects which memory requests CP performance counter 1 counts.
+pub const PERF_SELECT: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x6) };
+
 pub const CP_FIFO_BASE_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x20) };
 pub const CP_FIFO_BASE_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x22) };
 
@@ -215,3 +228,18 @@ pub const CP_FIFO_READ_PTR_HI: VolAddress<u16, Safe, Safe> =
 
 pub const CP_FIFO_BP_PTR_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x3C) };
 pub const CP_FIFO_BP_PTR_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x3E) };
+
+/// GP performance counters 0 and 1, counting whatever the XF, SU, RAS, TX or CP selects route to
+/// them.
+pub const CP_PERF0_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x40) };
+pub const CP_PERF0_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x42) };
+pub const CP_PERF1_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x44) };
+pub const CP_PERF1_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x46) };
+
+/// Vertex cache lookups, misses and stalls for the attribute picked by `gx::cp::PerfSelect`.
+pub const CP_VCACHE_CHECK_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x50) };
+pub const CP_VCACHE_CHECK_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x52) };
+pub const CP_VCACHE_MISS_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x54) };
+pub const CP_VCACHE_MISS_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x56) };
+pub const CP_VCACHE_STALL_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x58) };
+pub const CP_VCACHE_STALL_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x5A) };
@@ -0,0 +1,219 @@
+use bit_field::BitField;
+use voladdress::{Safe, VolAddress};
+
+use super::{pi::InterruptState, vi::Enabled};
+
+pub const BASE: usize = 0xCC00_1000;
+
+/// Z test applied to CPU writes of Z through the EFB aperture.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug)]
+pub struct ZConfig(u16);
+
+pub const Z_CONFIG: VolAddress<ZConfig, Safe, Safe> = unsafe { VolAddress::new(BASE) };
+
+impl ZConfig {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn read() -> Self {
+        Z_CONFIG.read()
+    }
+
+    pub fn write(self) {
+        Z_CONFIG.write(self);
+    }
+
+    pub fn compare_enable(&self) -> Enabled {
+        self.0.get_bit(0).into()
+    }
+
+    pub fn with_compare_enable(&mut self, enable: Enabled) -> &mut Self {
+        self.0.set_bit(0, enable.into());
+        self
+    }
+
+    /// Raw `gx::pe::CompareFn`, the poked value is on the left.
+    pub fn compare_fn(&self) -> u8 {
+        self.0.get_bits(1..=3).try_into().unwrap()
+    }
+
+    pub fn with_compare_fn(&mut self, function: u8) -> &mut Self {
+        self.0.set_bits(1..=3, function.into());
+        self
+    }
+
+    pub fn update_enable(&self) -> Enabled {
+        self.0.get_bit(4).into()
+    }
+
+    pub fn with_update_enable(&mut self, enable: Enabled) -> &mut Self {
+        self.0.set_bit(4, enable.into());
+        self
+    }
+}
+
+impl Default for ZConfig {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Alpha returned by CPU reads through the EFB aperture.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum AlphaReadMode {
+    Zero,
+    Full,
+    /// Whatever the EFB holds, only meaningful for `PixelFormat::Rgba6Z24`.
+    Stored,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct InvalidAlphaReadModeError;
+
+impl TryFrom<u16> for AlphaReadMode {
+    type Error = InvalidAlphaReadModeError;
+
+    fn try_from(value: u16) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Self::Zero),
+            1 => Ok(Self::Full),
+            2 => Ok(Self::Stored),
+            _ => Err(InvalidAlphaReadModeError),
+        }
+    }
+}
+
+impl From<AlphaReadMode> for u16 {
+    fn from(value: AlphaReadMode) -> Self {
+        match value {
+            AlphaReadMode::Zero => 0,
+            AlphaReadMode::Full => 1,
+            AlphaReadMode::Stored => 2,
+        }
+    }
+}
+
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug)]
+pub struct AlphaRead(u16);
+
+pub const ALPHA_READ: VolAddress<AlphaRead, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x8) };
+
+impl AlphaRead {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn read() -> Self {
+        ALPHA_READ.read()
+    }
+
+    pub fn write(self) {
+        ALPHA_READ.write(self);
+    }
+
+    pub fn mode(&self) -> AlphaReadMode {
+        self.0.get_bits(0..=1).try_into().unwrap()
+    }
+
+    pub fn with_mode(&mut self, mode: AlphaReadMode) -> &mut Self {
+        self.0.set_bits(0..=1, mode.into());
+        self
+    }
+}
+
+impl Default for AlphaRead {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Token and finish interrupt enables and status, a status bit is acknowledged by writing it
+/// back as `Happened`.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug)]
+pub struct InterruptControl(u16);
+
+pub const INTERRUPT_CONTROL: VolAddress<InterruptControl, Safe, Safe> =
+    unsafe { VolAddress::new(BASE + 0xA) };
+
+impl InterruptControl {
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    pub fn read() -> Self {
+        INTERRUPT_CONTROL.read()
+    }
+
+    pub fn write(self) {
+        INTERRUPT_CONTROL.write(self);
+    }
+
+    pub fn token_enable(&self) -> Enabled {
+        self.0.get_bit(0).into()
+    }
+
+    pub fn with_token_enable(&mut self, enable: Enabled) -> &mut Self {
+        self.0.set_bit(0, enable.into());
+        self
+    }
+
+    pub fn finish_enable(&self) -> Enabled {
+        self.0.get_bit(1).into()
+    }
+
+    pub fn with_finish_enable(&mut self, enable: Enabled) -> &mut Self {
+        self.0.set_bit(1, enable.into());
+        self
+    }
+
+    pub fn token(&self) -> InterruptState {
+        self.0.get_bit(2).into()
+    }
+
+    pub fn with_token(&mut self, state: InterruptState) -> &mut Self {
+        self.0.set_bit(2, state.into());
+        self
+    }
+
+    pub fn finish(&self) -> InterruptState {
+        self.0.get_bit(3).into()
+    }
+
+    pub fn with_finish(&mut self, state: InterruptState) -> &mut Self {
+        self.0.set_bit(3, state.into());
+        self
+    }
+}
+
+impl Default for InterruptControl {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Last token the PE drew past.
+pub const PE_TOKEN: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0xE) };
+
+/// Quads passing and failing the Z test before texturing, cleared through `gx::bp::PePerfControl`.
+pub const PE_Z_TOP_IN_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x18) };
+pub const PE_Z_TOP_IN_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x1A) };
+pub const PE_Z_TOP_OUT_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x1C) };
+pub const PE_Z_TOP_OUT_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x1E) };
+
+/// Quads going into and coming out of the Z test after texturing.
+pub const PE_Z_BOTTOM_IN_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x20) };
+pub const PE_Z_BOTTOM_IN_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x22) };
+pub const PE_Z_BOTTOM_OUT_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x24) };
+pub const PE_Z_BOTTOM_OUT_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x26) };
+
+/// Quads reaching color blending.
+pub const PE_COLOR_IN_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x28) };
+pub const PE_COLOR_IN_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x2A) };
+
+/// GP clocks spent on EFB copies.
+pub const PE_COPY_CLOCKS_LO: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x2C) };
+pub const PE_COPY_CLOCKS_HI: VolAddress<u16, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x2E) };
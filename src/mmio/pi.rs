@@ -354,8 +354,8 @@ pub const FIFO_END: VolAddress<usize, Safe, Safe> = unsafe { VolAddress::new(BAS
 
 pub const FIFO_WRITE_PTR: VolAddress<usize, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x14) };
 
-// TODO: Properly do FIFO_RESET https://github.com/dolphin-emu/dolphin/blob/master/Source/Core/Core/HW/ProcessorInterface.h
-//pub const FIFO_RESET: VolAddress<usize, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x18) };
+/// Writing 1 holds the CPU to GP FIFO path in reset until 0 is written back.
+pub const FIFO_RESET: VolAddress<u32, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x18) };
 
 // TODO: Properly do RESET_CODE https://github.com/dolphin-emu/dolphin/blob/master/Source/Core/Core/HW/ProcessorInterface.h
 //pub const RESET_CODE: VolAddress<usize, Safe, Safe> = unsafe { VolAddress::new(BASE + 0x24) };
@@ -37,7 +37,7 @@ pub struct OS;
 
 pub static MEM1_ALLOCATOR: LockedHeap = LockedHeap::empty();
 
-#[global_allocator]
+#[cfg_attr(target_arch = "powerpc", global_allocator)]
 pub static MEM2_ALLOCATOR: LockedHeap = LockedHeap::empty();
 
 pub static IPC_ALLOCATOR: LockedHeap = LockedHeap::empty();
@@ -91,6 +91,7 @@ impl SerialInterface {
                 .write_three(),
         }
 
+        #[cfg(target_arch = "powerpc")]
         unsafe {
             core::arch::asm!("sync");
             core::arch::asm!("isync");
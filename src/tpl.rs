@@ -0,0 +1,507 @@
+//! Reader for TPL texture palette files.
+//!
+//! A TPL holds a table of images, each with an optional palette. Every offset and size is checked
+//! when the reader is created, after that images can be looked up without failing.
+
+use crate::gx::texture::{
+    TexObj, TextureFilter, TextureFormat, TlutFormat, TlutObj, TlutRegion, WrapMode,
+};
+
+#[derive(Debug)]
+pub enum ParseError {
+    UnexpectedEnd,
+    BadMagic,
+    /// A table entry or data offset points outside of the file.
+    InvalidOffset {
+        offset: usize,
+    },
+    InvalidDimensions {
+        width: u16,
+        height: u16,
+    },
+    UnknownFormat(u32),
+    UnknownTlutFormat(u32),
+    UnknownWrapMode(u32),
+    UnknownFilter(u32),
+    /// Mipmaps only apply when minifying, magnification is either `Near` or `Linear`.
+    MipmapMagFilter(u32),
+    /// Color indexed image without a palette.
+    MissingPalette {
+        image: usize,
+    },
+    /// A color indexed image was turned into a texture without a TLUT region for its palette.
+    MissingTlutRegion,
+    /// Palettes have to be whole lines of 16 entries, no more than the image format can index.
+    InvalidPaletteSize(u16),
+    /// The GP needs image and palette data 32 byte aligned in memory, and so in the file.
+    Misaligned,
+}
+
+pub struct Reader<Data: AsRef<[u8]>> {
+    input: Data,
+    header: Header,
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Header {
+    image_count: u32,
+    image_table_offset: u32,
+}
+
+impl Header {
+    pub const LENGTH: usize = 12;
+    pub const MAGIC: u32 = 0x0020_AF30;
+
+    fn from_bytes(data: [u8; Self::LENGTH]) -> Result<Self, ParseError> {
+        if read_u32(&data, 0)? != Self::MAGIC {
+            return Err(ParseError::BadMagic);
+        }
+
+        Ok(Self {
+            image_count: read_u32(&data, 4)?,
+            image_table_offset: read_u32(&data, 8)?,
+        })
+    }
+
+    pub const fn image_count(&self) -> u32 {
+        self.image_count
+    }
+}
+
+/// How an image wants to be sampled, as stored in the file.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ImageHeader {
+    pub width: u16,
+    pub height: u16,
+    pub format: TextureFormat,
+    pub wrap_s: WrapMode,
+    pub wrap_t: WrapMode,
+    pub min_filter: TextureFilter,
+    pub mag_filter: TextureFilter,
+    pub lod_bias: f32,
+    pub edge_lod: bool,
+    pub min_lod: u8,
+    pub max_lod: u8,
+}
+
+impl ImageHeader {
+    pub const LENGTH: usize = 0x24;
+
+    fn from_bytes(data: &[u8]) -> Result<(Self, usize), ParseError> {
+        let height = read_u16(data, 0x00)?;
+        let width = read_u16(data, 0x02)?;
+        if !(1..=1024).contains(&width) || !(1..=1024).contains(&height) {
+            return Err(ParseError::InvalidDimensions { width, height });
+        }
+
+        let format = read_u32(data, 0x04)?;
+        let wrap_s = read_u32(data, 0x0C)?;
+        let wrap_t = read_u32(data, 0x10)?;
+        let mag_filter = read_u32(data, 0x18)?;
+        let header = Self {
+            width,
+            height,
+            format: format
+                .try_into()
+                .map_err(|_| ParseError::UnknownFormat(format))?,
+            wrap_s: wrap_s
+                .try_into()
+                .map_err(|_| ParseError::UnknownWrapMode(wrap_s))?,
+            wrap_t: wrap_t
+                .try_into()
+                .map_err(|_| ParseError::UnknownWrapMode(wrap_t))?,
+            min_filter: filter_from_file(read_u32(data, 0x14)?)?,
+            mag_filter: match filter_from_file(mag_filter)? {
+                filter @ (TextureFilter::Near | TextureFilter::Linear) => filter,
+                _ => return Err(ParseError::MipmapMagFilter(mag_filter)),
+            },
+            lod_bias: f32::from_bits(read_u32(data, 0x1C)?),
+            edge_lod: read_u8(data, 0x20)? != 0,
+            min_lod: read_u8(data, 0x21)?,
+            max_lod: read_u8(data, 0x22)?,
+        };
+        let data_offset = usize::try_from(read_u32(data, 0x08)?).unwrap();
+
+        Ok((header, data_offset))
+    }
+
+    /// Number of mipmap levels stored after each other in the image data.
+    pub fn levels(&self) -> u8 {
+        self.max_lod.saturating_add(1).min(11)
+    }
+
+    pub fn data_size(&self) -> usize {
+        self.format
+            .mipmap_size(self.width.into(), self.height.into(), self.levels())
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct PaletteHeader {
+    pub entries: u16,
+    pub format: TlutFormat,
+}
+
+impl PaletteHeader {
+    pub const LENGTH: usize = 12;
+
+    fn from_bytes(data: &[u8]) -> Result<(Self, usize), ParseError> {
+        let entries = read_u16(data, 0x00)?;
+        if entries == 0 || entries % 16 != 0 || entries > 16384 {
+            return Err(ParseError::InvalidPaletteSize(entries));
+        }
+
+        let format = read_u32(data, 0x04)?;
+        let header = Self {
+            entries,
+            format: format
+                .try_into()
+                .map_err(|_| ParseError::UnknownTlutFormat(format))?,
+        };
+        let data_offset = usize::try_from(read_u32(data, 0x08)?).unwrap();
+
+        Ok((header, data_offset))
+    }
+}
+
+/// One image of the file, borrowing its data from the reader.
+pub struct Image<'a> {
+    pub header: ImageHeader,
+    pub data: &'a [u8],
+    pub palette: Option<Palette<'a>>,
+}
+
+pub struct Palette<'a> {
+    pub header: PaletteHeader,
+    pub data: &'a [u8],
+}
+
+impl Image<'_> {
+    /// Builds a texture object set up the way the file asks for.
+    ///
+    /// Color indexed images also return their palette, which has to be loaded into
+    /// `tlut_region` before drawing.
+    ///
+    /// # Errors
+    ///
+    /// `Misaligned`: the image or palette data isn't 32 byte aligned in memory
+    /// `MissingTlutRegion`: the image is color indexed and `tlut_region` is `None`
+    pub fn tex_obj(
+        &self,
+        tlut_region: Option<TlutRegion>,
+    ) -> Result<(TexObj, Option<TlutObj>), ParseError> {
+        if self.data.as_ptr().addr() % 32 != 0 {
+            return Err(ParseError::Misaligned);
+        }
+
+        let header = &self.header;
+        let mut texture = TexObj::new(self.data, header.width, header.height, header.format)
+            .with_mipmaps(header.levels())
+            .with_wrap(header.wrap_s, header.wrap_t)
+            .with_filter(header.min_filter, header.mag_filter)
+            .with_lod(
+                header.min_lod.into(),
+                header.max_lod.into(),
+                header.lod_bias,
+            )
+            .with_edge_lod(header.edge_lod);
+
+        let tlut = match &self.palette {
+            Some(palette) if header.format.is_indexed() => {
+                let region = tlut_region.ok_or(ParseError::MissingTlutRegion)?;
+                // Safety: any pair of bytes is a valid u16 and the alignment is checked below.
+                let (prefix, entries, _) = unsafe { palette.data.align_to::<u16>() };
+                if !prefix.is_empty() || palette.data.as_ptr().addr() % 32 != 0 {
+                    return Err(ParseError::Misaligned);
+                }
+
+                let tlut = TlutObj::new(entries, palette.header.format);
+                texture = texture.with_tlut(&tlut, region);
+                Some(tlut)
+            }
+            _ => None,
+        };
+
+        Ok((texture, tlut))
+    }
+}
+
+impl<Data: AsRef<[u8]>> Reader<Data> {
+    /// # Errors
+    /// `UnexpectedEnd`: Data provided is unexpectedly not long enough
+    /// `BadMagic`: the header doesn't have the right magic
+    /// Any other variant: one of the images or palettes is malformed
+    pub fn new(input: Data) -> Result<Self, ParseError> {
+        let bytes = input.as_ref();
+        if bytes.len() < Header::LENGTH {
+            return Err(ParseError::UnexpectedEnd);
+        }
+
+        let header_data = bytes[0..Header::LENGTH].try_into().unwrap();
+        let header = Header::from_bytes(header_data)?;
+
+        let reader = Self { input, header };
+        for index in 0..reader.len() {
+            reader.parse_image(index)?;
+        }
+
+        Ok(reader)
+    }
+
+    pub const fn header(&self) -> Header {
+        self.header
+    }
+
+    pub fn len(&self) -> usize {
+        usize::try_from(self.header.image_count).unwrap()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.header.image_count == 0
+    }
+
+    pub fn image(&self, index: usize) -> Option<Image<'_>> {
+        (index < self.len()).then(|| self.parse_image(index).unwrap())
+    }
+
+    pub fn images(&self) -> impl ExactSizeIterator<Item = Image<'_>> + '_ {
+        (0..self.len()).map(|index| self.parse_image(index).unwrap())
+    }
+
+    fn parse_image(&self, index: usize) -> Result<Image<'_>, ParseError> {
+        let bytes = self.input.as_ref();
+        let entry_offset = usize::try_from(self.header.image_table_offset)
+            .unwrap()
+            .checked_add(index.checked_mul(8).ok_or(ParseError::UnexpectedEnd)?)
+            .ok_or(ParseError::UnexpectedEnd)?;
+        let entry = slice(bytes, entry_offset, 8)?;
+        let image_offset = usize::try_from(read_u32(entry, 0)?).unwrap();
+        let palette_offset = usize::try_from(read_u32(entry, 4)?).unwrap();
+
+        let (header, data_offset) =
+            ImageHeader::from_bytes(slice(bytes, image_offset, ImageHeader::LENGTH)?)?;
+        let data = slice(bytes, aligned(data_offset)?, header.data_size())?;
+
+        let palette = if palette_offset == 0 {
+            None
+        } else {
+            let (palette_header, data_offset) =
+                PaletteHeader::from_bytes(slice(bytes, palette_offset, PaletteHeader::LENGTH)?)?;
+            if palette_header.entries > max_palette_entries(header.format) {
+                return Err(ParseError::InvalidPaletteSize(palette_header.entries));
+            }
+            let data = slice(
+                bytes,
+                aligned(data_offset)?,
+                usize::from(palette_header.entries) * 2,
+            )?;
+            Some(Palette {
+                header: palette_header,
+                data,
+            })
+        };
+
+        if header.format.is_indexed() && palette.is_none() {
+            return Err(ParseError::MissingPalette { image: index });
+        }
+
+        Ok(Image {
+            header,
+            data,
+            palette,
+        })
+    }
+}
+
+/// Filters are stored with the numbering of the C SDK, which differs from the register one.
+const fn filter_from_file(value: u32) -> Result<TextureFilter, ParseError> {
+    match value {
+        0 => Ok(TextureFilter::Near),
+        1 => Ok(TextureFilter::Linear),
+        2 => Ok(TextureFilter::NearMipNear),
+        3 => Ok(TextureFilter::LinearMipNear),
+        4 => Ok(TextureFilter::NearMipLinear),
+        5 => Ok(TextureFilter::LinearMipLinear),
+        _ => Err(ParseError::UnknownFilter(value)),
+    }
+}
+
+/// Most palette entries an image of `format` can index.
+const fn max_palette_entries(format: TextureFormat) -> u16 {
+    match format {
+        TextureFormat::Ci4 => 16,
+        TextureFormat::Ci8 => 256,
+        _ => 16384,
+    }
+}
+
+const fn aligned(offset: usize) -> Result<usize, ParseError> {
+    if offset % 32 == 0 {
+        Ok(offset)
+    } else {
+        Err(ParseError::Misaligned)
+    }
+}
+
+fn slice(data: &[u8], offset: usize, len: usize) -> Result<&[u8], ParseError> {
+    offset
+        .checked_add(len)
+        .and_then(|end| data.get(offset..end))
+        .ok_or(ParseError::InvalidOffset { offset })
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Result<u8, ParseError> {
+    data.get(offset).copied().ok_or(ParseError::UnexpectedEnd)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, ParseError> {
+    let bytes = data
+        .get(offset..offset + 2)
+        .ok_or(ParseError::UnexpectedEnd)?;
+    Ok(u16::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, ParseError> {
+    let bytes = data
+        .get(offset..offset + 4)
+        .ok_or(ParseError::UnexpectedEnd)?;
+    Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{vec, vec::Vec};
+
+    use super::*;
+
+    /// Header of an 8x8 CI4 image with the given filters, data right after it.
+    fn image_header(min_filter: u32, mag_filter: u32) -> [u8; ImageHeader::LENGTH] {
+        let mut data = [0; ImageHeader::LENGTH];
+        data[0x00..0x02].copy_from_slice(&8u16.to_be_bytes());
+        data[0x02..0x04].copy_from_slice(&8u16.to_be_bytes());
+        data[0x04..0x08].copy_from_slice(&8u32.to_be_bytes());
+        data[0x08..0x0C].copy_from_slice(&0x40u32.to_be_bytes());
+        data[0x14..0x18].copy_from_slice(&min_filter.to_be_bytes());
+        data[0x18..0x1C].copy_from_slice(&mag_filter.to_be_bytes());
+        data
+    }
+
+    #[test]
+    fn filters_use_sdk_numbering() {
+        let (header, data_offset) = ImageHeader::from_bytes(&image_header(5, 1)).unwrap();
+        assert_eq!(header.min_filter, TextureFilter::LinearMipLinear);
+        assert_eq!(header.mag_filter, TextureFilter::Linear);
+        assert_eq!(header.format, TextureFormat::Ci4);
+        assert_eq!(data_offset, 0x40);
+    }
+
+    #[test]
+    fn mipmap_mag_filter_is_rejected() {
+        for mag_filter in 2..=5 {
+            let result = ImageHeader::from_bytes(&image_header(0, mag_filter));
+            assert!(
+                matches!(result, Err(ParseError::MipmapMagFilter(value)) if value == mag_filter)
+            );
+        }
+        let result = ImageHeader::from_bytes(&image_header(0, 6));
+        assert!(matches!(result, Err(ParseError::UnknownFilter(6))));
+    }
+
+    fn put(data: &mut [u8], offset: usize, value: u32) {
+        data[offset..offset + 4].copy_from_slice(&value.to_be_bytes());
+    }
+
+    /// A file with one 8x8 image of `format`, and a 16 entry RGB565 palette. The image header is
+    /// at 0x20 with its data at 0x80, the palette header at 0x60 with its data at 0xA0.
+    fn file(format: u32) -> Vec<u8> {
+        let mut data = vec![0; 0xC0];
+        put(&mut data, 0x00, Header::MAGIC);
+        put(&mut data, 0x04, 1);
+        put(&mut data, 0x08, 0x0C);
+        put(&mut data, 0x0C, 0x20);
+        put(&mut data, 0x10, 0x60);
+        data[0x20..0x44].copy_from_slice(&image_header(0, 0));
+        put(&mut data, 0x24, format);
+        put(&mut data, 0x28, 0x80);
+        data[0x60..0x62].copy_from_slice(&16u16.to_be_bytes());
+        put(&mut data, 0x64, 1);
+        put(&mut data, 0x68, 0xA0);
+        data
+    }
+
+    #[test]
+    fn reads_color_indexed_image_with_palette() {
+        let reader = Reader::new(file(8)).unwrap();
+        assert_eq!(reader.len(), 1);
+        let image = reader.image(0).unwrap();
+        assert_eq!(image.header.format, TextureFormat::Ci4);
+        assert_eq!(image.data.len(), 32);
+        let palette = image.palette.unwrap();
+        assert_eq!(palette.header.format, TlutFormat::Rgb565);
+        assert_eq!(palette.data.len(), 32);
+    }
+
+    #[test]
+    fn bad_magic_is_rejected() {
+        let mut data = file(8);
+        data[1] = 0;
+        assert!(matches!(Reader::new(data), Err(ParseError::BadMagic)));
+    }
+
+    #[test]
+    fn truncated_header_is_rejected() {
+        let data = file(8);
+        assert!(matches!(
+            Reader::new(&data[..8]),
+            Err(ParseError::UnexpectedEnd)
+        ));
+    }
+
+    #[test]
+    fn offsets_past_the_end_are_rejected() {
+        for offset in [0x0C, 0x10, 0x28, 0x68] {
+            let mut data = file(8);
+            put(&mut data, offset, 0x1000);
+            assert!(
+                matches!(
+                    Reader::new(data),
+                    Err(ParseError::InvalidOffset { offset: 0x1000 })
+                ),
+                "offset at {offset:#X}"
+            );
+        }
+    }
+
+    #[test]
+    fn color_indexed_image_needs_a_palette() {
+        let mut data = file(8);
+        put(&mut data, 0x10, 0);
+        assert!(matches!(
+            Reader::new(data),
+            Err(ParseError::MissingPalette { image: 0 })
+        ));
+
+        let mut data = file(1);
+        put(&mut data, 0x10, 0);
+        assert!(Reader::new(data).is_ok());
+    }
+
+    #[test]
+    fn palette_larger_than_the_format_indexes_is_rejected() {
+        let mut data = file(8);
+        data[0x60..0x62].copy_from_slice(&32u16.to_be_bytes());
+        assert!(matches!(
+            Reader::new(data),
+            Err(ParseError::InvalidPaletteSize(32))
+        ));
+    }
+
+    #[test]
+    fn misaligned_data_is_rejected() {
+        for offset in [0x28, 0x68] {
+            let mut data = file(8);
+            put(&mut data, offset, 0x84);
+            assert!(matches!(Reader::new(data), Err(ParseError::Misaligned)));
+        }
+    }
+}
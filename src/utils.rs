@@ -3,6 +3,7 @@ use voladdress::{Safe, VolAddress};
 pub struct WriteGatherPipe;
 
 const WRITER_GATHER_PIPE_U8: VolAddress<u8, (), Safe> = unsafe { VolAddress::new(0xCC00_8000) };
+const WRITER_GATHER_PIPE_U16: VolAddress<u16, (), Safe> = unsafe { VolAddress::new(0xCC00_8000) };
 const WRITER_GATHER_PIPE_U32: VolAddress<u32, (), Safe> = unsafe { VolAddress::new(0xCC00_8000) };
 const WRITE_GATHER_PIPE_F32: VolAddress<f32, (), Safe> = unsafe { VolAddress::new(0xCC00_8000) };
 
@@ -22,6 +23,11 @@ impl WriteGatherPipe {
         self
     }
 
+    pub fn write_u16(&mut self, bytes: u16) -> &mut Self {
+        WRITER_GATHER_PIPE_U16.write(bytes);
+        self
+    }
+
     pub fn write_u32(&mut self, bytes: u32) -> &mut Self {
         WRITER_GATHER_PIPE_U32.write(bytes);
         self
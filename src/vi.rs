@@ -29,10 +29,10 @@ pub struct ViFramebuffer {
 
 impl ViFramebuffer {
     pub fn new(width: usize, height: usize) -> Self {
+        let len = width * height * mem::size_of::<u16>();
         let slice = unsafe {
-            let ptr =
-                alloc(Layout::from_size_align(width * height * mem::size_of::<u16>(), 32).unwrap());
-            Box::from_raw(ptr.cast::<[u8; 1]>())
+            let ptr = alloc(Layout::from_size_align(len, 32).unwrap());
+            Box::from_raw(core::ptr::slice_from_raw_parts_mut(ptr, len))
         };
         Self {
             width,
@@ -47,7 +47,7 @@ pub struct VideoSystem {
     pub framebuffer: ViFramebuffer,
 }
 
-static RETRACE_COUNT: AtomicUsize = AtomicUsize::new(0);
+pub(crate) static RETRACE_COUNT: AtomicUsize = AtomicUsize::new(0);
 impl VideoSystem {
     pub fn new(mut framebuffer: ViFramebuffer) -> Self {
         VerticalTiming::new()
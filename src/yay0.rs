@@ -0,0 +1,177 @@
+//! Decoder for Yay0 compressed data.
+//!
+//! Yay0 is the LZ scheme the IPL ROM fonts and many first party files are packed with. After a 16
+//! byte header the data is split into three streams: mask words saying whether the next piece of
+//! output is a literal or a back reference, the 16 bit back references themselves, and the
+//! literal bytes, which also hold the extra length byte of long references.
+
+use alloc::{vec, vec::Vec};
+
+#[derive(Debug)]
+pub enum Error {
+    UnexpectedEnd,
+    BadMagic,
+    /// A back reference points before the start of the output.
+    InvalidReference {
+        distance: usize,
+        position: usize,
+    },
+}
+
+pub const MAGIC: [u8; 4] = *b"Yay0";
+
+/// Size of the data once decompressed, read from the header.
+///
+/// # Errors
+///
+/// Fails when `data` doesn't start with a Yay0 header.
+pub fn decompressed_size(data: &[u8]) -> Result<usize, Error> {
+    if data.get(0..4).ok_or(Error::UnexpectedEnd)? != MAGIC {
+        return Err(Error::BadMagic);
+    }
+    Ok(read_u32(data, 4)?.try_into().unwrap())
+}
+
+/// Decompresses a whole Yay0 file.
+///
+/// # Errors
+///
+/// Fails on a bad header, streams running past the end of `data` and references to output that
+/// doesn't exist yet.
+pub fn decompress(data: &[u8]) -> Result<Vec<u8>, Error> {
+    let size = decompressed_size(data)?;
+    let mut out = vec![0; size];
+    let mut references = usize::try_from(read_u32(data, 8)?).unwrap();
+    let mut literals = usize::try_from(read_u32(data, 12)?).unwrap();
+    let mut masks = 16;
+
+    let mut mask = 0u32;
+    let mut bits = 0;
+    let mut position = 0;
+    while position < size {
+        if bits == 0 {
+            mask = read_u32(data, masks)?;
+            masks += 4;
+            bits = 32;
+        }
+
+        if mask & 0x8000_0000 != 0 {
+            out[position] = read_u8(data, literals)?;
+            literals += 1;
+            position += 1;
+        } else {
+            let reference = read_u16(data, references)?;
+            references += 2;
+
+            let distance = usize::from(reference & 0xFFF) + 1;
+            let length = match reference >> 12 {
+                0 => {
+                    let length = usize::from(read_u8(data, literals)?) + 0x12;
+                    literals += 1;
+                    length
+                }
+                length => usize::from(length) + 2,
+            };
+            let start = position
+                .checked_sub(distance)
+                .ok_or(Error::InvalidReference { distance, position })?;
+
+            // The source can overlap what is being written, so copy byte by byte.
+            for index in 0..length.min(size - position) {
+                out[position + index] = out[start + index];
+            }
+            position += length;
+        }
+
+        mask <<= 1;
+        bits -= 1;
+    }
+
+    Ok(out)
+}
+
+fn read_u8(data: &[u8], offset: usize) -> Result<u8, Error> {
+    data.get(offset).copied().ok_or(Error::UnexpectedEnd)
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Result<u16, Error> {
+    let bytes = data.get(offset..offset + 2).ok_or(Error::UnexpectedEnd)?;
+    Ok(u16::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Result<u32, Error> {
+    let bytes = data.get(offset..offset + 4).ok_or(Error::UnexpectedEnd)?;
+    Ok(u32::from_be_bytes(bytes.try_into().unwrap()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// "ABC" as literals, a short reference repeating it twice, "X" and a long reference
+    /// repeating that 20 times.
+    fn stream() -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&MAGIC);
+        data.extend_from_slice(&30u32.to_be_bytes());
+        data.extend_from_slice(&20u32.to_be_bytes());
+        data.extend_from_slice(&24u32.to_be_bytes());
+        // Literal, literal, literal, reference, literal, reference.
+        data.extend_from_slice(&0xE800_0000u32.to_be_bytes());
+        // Length 6 at distance 3, then length from the literals at distance 1.
+        data.extend_from_slice(&[0x40, 0x02, 0x00, 0x00]);
+        // The long reference takes its length, 0x12 + 2, from after the "X".
+        data.extend_from_slice(b"ABCX\x02");
+        data
+    }
+
+    #[test]
+    fn decompresses_literals_and_references() {
+        let mut expected = b"ABCABCABC".to_vec();
+        expected.extend_from_slice(&[b'X'; 21]);
+        assert_eq!(decompressed_size(&stream()).unwrap(), 30);
+        assert_eq!(decompress(&stream()).unwrap(), expected);
+    }
+
+    #[test]
+    fn truncated_stream_is_an_error() {
+        let data = stream();
+        for len in [2, 12, 18, 22, data.len() - 1] {
+            assert!(
+                matches!(decompress(&data[..len]), Err(Error::UnexpectedEnd)),
+                "cut at {len}"
+            );
+        }
+    }
+
+    #[test]
+    fn output_larger_than_the_stream_is_an_error() {
+        let mut data = stream();
+        data[4..8].copy_from_slice(&64u32.to_be_bytes());
+        // Past the last mask bit used, the next reference is read from the literals.
+        assert!(matches!(
+            decompress(&data),
+            Err(Error::InvalidReference { .. })
+        ));
+    }
+
+    #[test]
+    fn reference_before_the_start_is_an_error() {
+        let mut data = stream();
+        data[16..20].copy_from_slice(&0u32.to_be_bytes());
+        assert!(matches!(
+            decompress(&data),
+            Err(Error::InvalidReference {
+                distance: 3,
+                position: 0
+            })
+        ));
+    }
+
+    #[test]
+    fn bad_magic_is_an_error() {
+        let mut data = stream();
+        data[0] = b'Y' + 1;
+        assert!(matches!(decompress(&data), Err(Error::BadMagic)));
+    }
+}